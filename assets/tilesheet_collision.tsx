@@ -0,0 +1,24 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="tilesheet_collision" tilewidth="32" tileheight="32" tilecount="84" columns="14">
+ <image source="tilesheet.png" width="448" height="192"/>
+ <tile id="0">
+  <objectgroup draworder="index" id="2">
+   <object id="1" x="0" y="16" width="32" height="16"/>
+   <object id="2" x="4" y="4" width="8" height="8">
+    <ellipse/>
+   </object>
+   <object id="3" x="16" y="0">
+    <polygon points="0,0 16,0 16,16"/>
+   </object>
+   <object id="4" x="2" y="30">
+    <point/>
+   </object>
+   <object id="5" x="8" y="8" width="4" height="4" rotation="45"/>
+  </objectgroup>
+ </tile>
+ <tile id="1">
+  <properties>
+   <property name="solid" type="bool" value="false"/>
+  </properties>
+ </tile>
+</tileset>
@@ -0,0 +1,4 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="derived" tilewidth="32" tileheight="32" spacing="2" margin="1">
+ <image source="tilesheet.png" width="448" height="192"/>
+</tileset>
@@ -0,0 +1,10 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="isometric_props" tilewidth="64" tileheight="96" tilecount="2" columns="0">
+ <grid orientation="isometric" width="64" height="32"/>
+ <tile id="0">
+  <image source="folder/small.png" width="16" height="24"/>
+ </tile>
+ <tile id="1">
+  <image source="tilesheet.png" width="448" height="192"/>
+ </tile>
+</tileset>
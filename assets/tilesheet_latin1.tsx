@@ -0,0 +1,7 @@
+<?xml version="1.0" encoding="ISO-8859-1"?>
+<tileset version="1.10" tiledversion="1.10.2" name="Tuiles  l't" tilewidth="32" tileheight="32" tilecount="84" columns="14">
+ <properties>
+  <property name="proprit" value="ber"/>
+ </properties>
+ <image source="tilesheet.png" width="448" height="192"/>
+</tileset>
@@ -0,0 +1,4 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.4" tiledversion="1.4.0" name="tilesheet" tilewidth="32" tileheight="32" tilecount="84" columns="14">
+ <image source="tilesheet.png"/>
+</tileset>
@@ -0,0 +1,5 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="tilesheet_offset" tilewidth="32" tileheight="32" tilecount="84" columns="14">
+ <tileoffset x="-4" y="16"/>
+ <image source="tilesheet.png" width="448" height="192"/>
+</tileset>
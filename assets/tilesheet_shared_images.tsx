@@ -0,0 +1,13 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="shared_images" tilewidth="448" tileheight="192" tilecount="3">
+ <grid orientation="orthogonal" width="1" height="1"/>
+ <tile id="0">
+  <image source="tilesheet.png" width="448" height="192"/>
+ </tile>
+ <tile id="1">
+  <image source="tilesheet.png" width="448" height="192"/>
+ </tile>
+ <tile id="2">
+  <image source="tilesheet.png" width="448" height="192" trans="ff00ff"/>
+ </tile>
+</tileset>
@@ -0,0 +1,17 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="sparse" tilewidth="16" tileheight="16" tilecount="256" columns="16">
+ <image source="tilesheet.png" width="256" height="256"/>
+ <tile id="3">
+  <properties>
+   <property name="solid" type="bool" value="true"/>
+  </properties>
+ </tile>
+ <tile id="17">
+  <animation>
+   <frame tileid="17" duration="100"/>
+   <frame tileid="18" duration="100"/>
+  </animation>
+ </tile>
+ <tile id="255" type="water"/>
+ <tile id="300" probability="0.5"/>
+</tileset>
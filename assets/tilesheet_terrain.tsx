@@ -0,0 +1,20 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="terrain" tilewidth="32" tileheight="32" tilecount="4" columns="2">
+ <image source="tilesheet.png" width="64" height="64"/>
+ <wangsets>
+  <wangset name="paths" type="edge" tile="2">
+   <wangcolor name="dirt" color="#804000" tile="2" probability="1"/>
+   <wangtile tileid="2" wangid="1,0,1,0,0,0,0,0"/>
+  </wangset>
+  <wangset name="cliffs" type="corner" tile="-1">
+   <properties>
+    <property name="height" type="int" value="3"/>
+   </properties>
+   <wangcolor name="rock" color="#808080" tile="0" probability="1"/>
+   <wangcolor name="grass" color="#00ff00" tile="1" probability="0.5"/>
+   <wangtile tileid="0" wangid="0,1,0,1,0,1,0,1"/>
+   <wangtile tileid="1" wangid="0,2,0,2,0,2,0,2"/>
+   <wangtile tileid="3" wangid="0,1,0,2,0,2,0,1" hflip="1"/>
+  </wangset>
+ </wangsets>
+</tileset>
@@ -0,0 +1,44 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.8" tiledversion="1.8.6" name="tile_children" tilewidth="32" tileheight="32" tilecount="84" columns="14">
+ <image source="tilesheet.png" width="448" height="192"/>
+ <tile id="0">
+  <objectgroup draworder="index" id="2">
+   <object id="1" x="0" y="0" width="16" height="16"/>
+  </objectgroup>
+  <properties>
+   <property name="tile" type="int" value="0"/>
+  </properties>
+  <animation>
+   <frame tileid="0" duration="100"/>
+   <frame tileid="1" duration="200"/>
+  </animation>
+ </tile>
+ <tile id="1">
+  <objectgroup draworder="index" id="2">
+   <object id="1" x="0" y="0" width="16" height="16"/>
+  </objectgroup>
+  <animation>
+   <frame tileid="1" duration="100"/>
+   <frame tileid="2" duration="200"/>
+  </animation>
+  <properties>
+   <property name="tile" type="int" value="1"/>
+  </properties>
+ </tile>
+ <tile id="2">
+  <objectgroup draworder="index" id="2"/>
+  <properties/>
+  <animation>
+   <frame tileid="2" duration="100"/>
+   <frame tileid="3" duration="200"/>
+  </animation>
+ </tile>
+ <tile id="3">
+  <objectgroup draworder="index" id="2"/>
+  <animation>
+   <frame tileid="3" duration="100"/>
+   <frame tileid="4" duration="200"/>
+  </animation>
+  <properties/>
+ </tile>
+</tileset>
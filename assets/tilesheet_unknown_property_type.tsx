@@ -0,0 +1,9 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="unknown_property_type" tilewidth="32" tileheight="32" tilecount="84" columns="14">
+ <image source="tilesheet.png" width="448" height="192"/>
+ <tile id="0">
+  <properties>
+   <property name="terrain" type="enum" value="grass"/>
+  </properties>
+ </tile>
+</tileset>
@@ -0,0 +1,16 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.8" tiledversion="1.8.5" name="tilesheet_wangsets_flipped" tilewidth="32" tileheight="32" tilecount="84" columns="14">
+ <image source="tilesheet.png" width="448" height="192"/>
+ <transformations hflip="1" vflip="0" rotate="1"/>
+ <wangsets>
+  <wangset name="Flipped" type="corner" tile="-1">
+   <properties>
+    <property name="flip probability" type="float" value="0.5"/>
+   </properties>
+   <wangcolor name="Grass" color="#00ff00" tile="-1" probability="1"/>
+   <wangtile tileid="0" wangid="0,1,0,1,0,1,0,1"/>
+   <wangtile tileid="1" wangid="0,1,0,0,0,1,0,1" hflip="1"/>
+   <wangtile tileid="2" wangid="0,1,0,1,0,0,0,1" vflip="true" dflip="1"/>
+  </wangset>
+ </wangsets>
+</tileset>
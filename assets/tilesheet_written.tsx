@@ -0,0 +1,45 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="written &amp; read" class="sprites" tilewidth="32" tileheight="32" spacing="2" margin="1" tilecount="65" columns="13">
+ <tileoffset x="4" y="-8"/>
+ <properties>
+  <property name="author" value="&quot;Sprite&quot; sheet &lt;generator&gt;"/>
+ </properties>
+ <image source="tilesheet.png" width="448" height="192"/>
+ <tile id="0" type="wall">
+  <properties>
+   <property name="solid" type="bool" value="true"/>
+  </properties>
+  <objectgroup draworder="index">
+   <object id="1" x="0" y="16" width="32" height="16"/>
+   <object id="2" x="4" y="4" width="8" height="8">
+    <ellipse/>
+   </object>
+   <object id="3" x="16" y="0">
+    <polygon points="0,0 16,0 16,16"/>
+   </object>
+  </objectgroup>
+ </tile>
+ <tile id="3" probability="0.25"/>
+ <tile id="17">
+  <animation>
+   <frame tileid="17" duration="100"/>
+   <frame tileid="18" duration="150"/>
+  </animation>
+ </tile>
+ <wangsets>
+  <wangset name="Terrain" type="corner" tile="16">
+   <properties>
+    <property name="flip probability" type="float" value="0.5"/>
+   </properties>
+   <wangcolor name="Grass" color="#00ff00" tile="-1" probability="1"/>
+   <wangcolor name="Water" class="liquid" color="#0000ff" tile="17" probability="0.5">
+    <properties>
+     <property name="speed" type="int" value="2"/>
+    </properties>
+   </wangcolor>
+   <wangtile tileid="0" wangid="0,1,0,1,0,1,0,1"/>
+   <wangtile tileid="1" wangid="0,1,0,2,0,1,0,2" hflip="1"/>
+   <wangtile tileid="2" wangid="0,2,0,2,0,2,0,2" vflip="1" dflip="1"/>
+  </wangset>
+ </wangsets>
+</tileset>
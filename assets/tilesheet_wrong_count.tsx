@@ -0,0 +1,4 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="wrong count" tilewidth="32" tileheight="32" tilecount="90" columns="14">
+ <image source="tilesheet.png" width="448" height="192"/>
+</tileset>
@@ -0,0 +1,118 @@
+//! Measures how long it takes to load maps with lots of objects and properties, and maps with
+//! large compressed tile layers.
+//!
+//! Run with `cargo bench`.
+
+use std::{fmt::Write, io::Write as _};
+
+use base64::Engine;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use tiled::Loader;
+
+const OBJECT_COUNT: usize = 10_000;
+const LAYER_SIZE: u32 = 512;
+const LAYER_COUNT: usize = 4;
+const PROPERTY_NAMES: [&str; 12] = [
+    "health",
+    "speed",
+    "damage",
+    "armor",
+    "faction",
+    "loot",
+    "respawn",
+    "patrol",
+    "aggressive",
+    "level",
+    "sound",
+    "script",
+];
+
+/// Builds a map with [`OBJECT_COUNT`] objects, all of which share the same names, classes and
+/// property names.
+fn objects_map() -> String {
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" tiledversion="1.10.2" orientation="orthogonal" renderorder="right-down" width="100" height="100" tilewidth="32" tileheight="32" infinite="0" nextlayerid="2" nextobjectid="1">
+ <objectgroup id="1" name="objects">
+"#,
+    );
+    for id in 1..=OBJECT_COUNT {
+        let (x, y) = ((id % 100) * 32, (id / 100) * 32);
+        writeln!(
+            xml,
+            r#"  <object id="{id}" name="enemy" type="npc" x="{x}" y="{y}" width="32" height="32">"#
+        )
+        .unwrap();
+        xml.push_str("   <properties>\n");
+        for (i, name) in PROPERTY_NAMES.iter().enumerate() {
+            writeln!(
+                xml,
+                r#"    <property name="{name}" type="int" value="{}"/>"#,
+                id * i
+            )
+            .unwrap();
+        }
+        xml.push_str("   </properties>\n  </object>\n");
+    }
+    xml.push_str(" </objectgroup>\n</map>\n");
+    xml
+}
+
+fn load_objects_map(c: &mut Criterion) {
+    let xml = objects_map();
+    c.bench_function("load map with 10k objects", |b| {
+        b.iter(|| {
+            Loader::new()
+                .load_tmx_map_from(black_box(xml.as_bytes()), "assets/objects.tmx")
+                .unwrap()
+        })
+    });
+}
+
+/// Builds a map with [`LAYER_COUNT`] tile layers of [`LAYER_SIZE`]x[`LAYER_SIZE`] tiles, stored as
+/// zlib compressed base64.
+fn large_layers_map() -> String {
+    let mut data = Vec::new();
+    for i in 0..LAYER_SIZE * LAYER_SIZE {
+        data.extend_from_slice(&(i % 84 + 1).to_le_bytes());
+    }
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&data).unwrap();
+    let data = base64::engine::general_purpose::STANDARD.encode(encoder.finish().unwrap());
+
+    let mut xml = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" tiledversion="1.10.2" orientation="orthogonal" renderorder="right-down" width="{LAYER_SIZE}" height="{LAYER_SIZE}" tilewidth="32" tileheight="32" infinite="0" nextlayerid="{}" nextobjectid="1">
+ <tileset firstgid="1" source="tilesheet.tsx"/>
+"#,
+        LAYER_COUNT + 1
+    );
+    for id in 1..=LAYER_COUNT {
+        writeln!(
+            xml,
+            r#" <layer id="{id}" name="layer {id}" width="{LAYER_SIZE}" height="{LAYER_SIZE}">
+  <data encoding="base64" compression="zlib">
+   {data}
+  </data>
+ </layer>"#
+        )
+        .unwrap();
+    }
+    xml.push_str("</map>\n");
+    xml
+}
+
+fn load_large_layers_map(c: &mut Criterion) {
+    let xml = large_layers_map();
+    c.bench_function("load map with large zlib layers", |b| {
+        b.iter(|| {
+            Loader::new()
+                .load_tmx_map_from(black_box(xml.as_bytes()), "assets/large_layers.tmx")
+                .unwrap()
+        })
+    });
+}
+
+criterion_group!(benches, load_objects_map, load_large_layers_map);
+criterion_main!(benches);
@@ -266,7 +266,7 @@ impl MapHandler {
         }
 
         if !object.name.is_empty() {
-            let text = graphics::Text::new(object.name.clone());
+            let text = graphics::Text::new(object.name.as_ref());
             canvas.draw(
                 &text,
                 DrawParam::new()
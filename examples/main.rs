@@ -43,6 +43,9 @@ fn main() {
             tiled::LayerType::Group(layer) => {
                 println!("Group layer with {} sublayers", layer.layers().len())
             }
+            tiled::LayerType::Unknown { tag_name, .. } => {
+                println!("Unknown layer of type '{}'", tag_name)
+            }
         }
     }
 }
@@ -33,19 +33,13 @@ impl Tilesheet {
     }
 
     pub fn tile_rect(&self, id: u32) -> FloatRect {
-        let tile_width = self.tileset.tile_width;
-        let tile_height = self.tileset.tile_height;
-        let spacing = self.tileset.spacing;
-        let margin = self.tileset.margin;
-        let tiles_per_row = (self.texture.size().x - margin + spacing) / (tile_width + spacing);
-        let x = id % tiles_per_row * tile_width;
-        let y = id / tiles_per_row * tile_height;
+        let rect = self.tileset.image_rect_for_tile(id).unwrap();
 
         FloatRect {
-            left: x as f32,
-            top: y as f32,
-            width: tile_width as f32,
-            height: tile_height as f32,
+            left: rect.x as f32,
+            top: rect.y as f32,
+            width: rect.width as f32,
+            height: rect.height as f32,
         }
     }
 }
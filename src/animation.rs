@@ -12,7 +12,8 @@ use crate::{
 ///
 /// [frame]: https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#tmx-frame
 /// [TMX tile animation]: https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#animation
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Frame {
     /// The local ID of a tile within the parent tileset.
     pub tile_id: u32,
@@ -23,7 +24,7 @@ pub struct Frame {
 impl Frame {
     pub(crate) fn new(attrs: Vec<Attribute>) -> Result<Frame> {
         let (tile_id, duration) = get_attrs!(
-            for v in attrs {
+            for v in attrs of "frame" {
                 "tileid" => tile_id ?= v.parse::<u32>(),
                 "duration" => duration ?= v.parse::<u32>(),
             }
@@ -33,6 +34,33 @@ impl Frame {
     }
 }
 
+/// Returns how long a whole loop of an animation lasts, in milliseconds. Saturates at
+/// [`u32::MAX`] instead of overflowing.
+pub(crate) fn total_duration(frames: &[Frame]) -> u32 {
+    frames
+        .iter()
+        .fold(0u32, |total, frame| total.saturating_add(frame.duration))
+}
+
+/// Returns the frame of a looping animation shown after `elapsed_ms` milliseconds, along with how
+/// long it has been shown for. Animations whose frames all last zero milliseconds are static.
+pub(crate) fn frame_at(frames: &[Frame], elapsed_ms: u32) -> Option<(&Frame, u32)> {
+    let total = total_duration(frames);
+    if total == 0 {
+        return frames.first().map(|frame| (frame, 0));
+    }
+    let mut remaining = elapsed_ms % total;
+    for frame in frames {
+        // Zero duration frames are never shown
+        if remaining < frame.duration {
+            return Some((frame, remaining));
+        }
+        remaining -= frame.duration;
+    }
+    // The durations add up to at least `total`, so a frame has always been found above
+    None
+}
+
 pub(crate) async fn parse_animation<R: Reader>(parser: &mut Parser<R>) -> Result<Vec<Frame>> {
     let mut animation = Vec::new();
     parse_tag!(parser, "animation", {
@@ -47,6 +47,35 @@ pub trait ResourceCache {
     fn get_template(&self, path: impl AsRef<ResourcePath>) -> Option<Arc<Template>>;
     /// Insert a new template into the cache.
     fn insert_template(&mut self, path: impl AsRef<ResourcePath>, tileset: Arc<Template>);
+    /// Removes a tileset from the cache, returning it if it was present.
+    ///
+    /// This is useful for reloading tilesets that have changed since they were first loaded.
+    ///
+    /// # Example
+    /// ```
+    /// use tiled::{Loader, ResourceCache};
+    /// # use tiled::Result;
+    ///
+    /// # fn main() -> Result<()> {
+    /// let mut loader = Loader::new();
+    /// let map = loader.load_tmx_map("assets/tiled_base64_external.tmx")?;
+    /// assert!(loader.cache().get_tileset("assets/tilesheet.tsx").is_some());
+    ///
+    /// // The next map loaded will read the tileset again instead of reusing the cached one.
+    /// loader.cache_mut().remove_tileset("assets/tilesheet.tsx");
+    /// assert!(loader.cache().get_tileset("assets/tilesheet.tsx").is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn remove_tileset(&mut self, path: impl AsRef<ResourcePath>) -> Option<Arc<Tileset>>;
+    /// Removes a template from the cache, returning it if it was present.
+    fn remove_template(&mut self, path: impl AsRef<ResourcePath>) -> Option<Arc<Template>>;
+    /// Removes all resources from the cache.
+    fn clear(&mut self);
+    /// Returns an iterator over the tilesets stored in the cache along with their paths.
+    fn tilesets(&self) -> impl Iterator<Item = (&ResourcePath, &Arc<Tileset>)>;
+    /// Returns an iterator over the templates stored in the cache along with their paths.
+    fn templates(&self) -> impl Iterator<Item = (&ResourcePath, &Arc<Template>)>;
 }
 
 /// A cache that identifies resources by their path, storing them in a [`HashMap`].
@@ -84,4 +113,29 @@ impl ResourceCache for DefaultResourceCache {
     fn insert_template(&mut self, path: impl AsRef<ResourcePath>, tileset: Arc<Template>) {
         self.templates.insert(path.as_ref().to_path_buf(), tileset);
     }
+
+    fn remove_tileset(&mut self, path: impl AsRef<ResourcePath>) -> Option<Arc<Tileset>> {
+        self.tilesets.remove(path.as_ref())
+    }
+
+    fn remove_template(&mut self, path: impl AsRef<ResourcePath>) -> Option<Arc<Template>> {
+        self.templates.remove(path.as_ref())
+    }
+
+    fn clear(&mut self) {
+        self.tilesets.clear();
+        self.templates.clear();
+    }
+
+    fn tilesets(&self) -> impl Iterator<Item = (&ResourcePath, &Arc<Tileset>)> {
+        self.tilesets
+            .iter()
+            .map(|(path, tileset)| (path.as_path(), tileset))
+    }
+
+    fn templates(&self) -> impl Iterator<Item = (&ResourcePath, &Arc<Template>)> {
+        self.templates
+            .iter()
+            .map(|(path, template)| (path.as_path(), template))
+    }
 }
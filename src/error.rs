@@ -1,6 +1,7 @@
-use crate::InvalidTilesetError::InvalidTileDimensions;
+use crate::InvalidTilesetError::{InvalidTileDimensions, MissingTileCount};
+use crate::TileId;
 use std::num::ParseIntError;
-use std::{fmt, path::PathBuf};
+use std::{fmt, path::PathBuf, sync::Arc};
 
 /// Errors that can occur while decoding csv data.
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -26,6 +27,9 @@ impl std::error::Error for CsvDecodingError {}
 pub enum InvalidTilesetError {
     /// An invalid width or height (0) dimension was found in the input.
     InvalidTileDimensions,
+    /// The `tilecount` or `columns` attribute was missing, and couldn't be derived because the
+    /// dimensions of the tileset image are unknown.
+    MissingTileCount,
 }
 
 impl fmt::Display for InvalidTilesetError {
@@ -35,6 +39,10 @@ impl fmt::Display for InvalidTilesetError {
                 f,
                 "An invalid width or height (0) dimension was found in the input."
             ),
+            MissingTileCount => write!(
+                f,
+                "The tile count or number of columns is missing, and the dimensions of the tileset image are unknown."
+            ),
         }
     }
 }
@@ -45,9 +53,26 @@ impl std::error::Error for InvalidTilesetError {}
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum Error {
-    /// A attribute was missing, had the wrong type of wasn't formated
-    /// correctly.
+    /// An attribute or element had an invalid value, described by the message.
     MalformedAttributes(String),
+    /// The value of an attribute couldn't be parsed.
+    MalformedAttribute {
+        /// The name of the XML element the attribute belongs to, e.g. `map`.
+        element: &'static str,
+        /// The name of the attribute, e.g. `width`.
+        attribute: &'static str,
+        /// The value of the attribute, as found in the file.
+        value: String,
+        /// The error that occurred while parsing the value, if there is one.
+        source: Option<Box<dyn std::error::Error + Send + Sync + 'static>>,
+    },
+    /// A required attribute was missing from an element.
+    MissingAttribute {
+        /// The name of the XML element the attribute belongs to, e.g. `map`.
+        element: &'static str,
+        /// The name of the attribute, e.g. `width`.
+        attribute: &'static str,
+    },
     /// An error occurred when decompressing using the
     /// [flate2](https://github.com/alexcrichton/flate2-rs) crate.
     DecompressingError(std::io::Error),
@@ -78,6 +103,81 @@ pub enum Error {
         /// The `compression` attribute of the tile layer data, if any.
         compression: Option<String>,
     },
+    /// The decoded data of a tile layer or chunk doesn't have the size its dimensions require,
+    /// which usually means it was truncated. Tiles stored as CSV or as `<tile>` elements count
+    /// as four bytes each.
+    InvalidTileDataSize {
+        /// The expected size of the data in bytes, which is four times the number of tiles.
+        expected: usize,
+        /// The actual size of the data in bytes.
+        found: usize,
+    },
+    /// The dimensions of a tile layer or chunk call for more tile data than can be addressed on
+    /// this platform.
+    TileLayerTooLarge {
+        /// The width of the layer or chunk in tiles.
+        width: u32,
+        /// The height of the layer or chunk in tiles.
+        height: u32,
+    },
+    /// The compressed data of a tile layer or chunk decompressed to more than the size its
+    /// dimensions require, or the data of a layer or chunk in any encoding is larger than
+    /// [`LoadSettings::max_decompressed_layer_bytes`](crate::LoadSettings::max_decompressed_layer_bytes).
+    /// Decompression is stopped at that point.
+    DecompressedTileDataTooLarge {
+        /// The maximum size of the decompressed data in bytes.
+        limit: usize,
+    },
+    /// A chunk of a finite tile layer had tiles outside of the layer. Finite layers aren't
+    /// usually split into chunks, but their tiles are kept if they are.
+    ChunkOutOfBounds {
+        /// The rectangle covered by the chunk.
+        chunk: crate::TileBounds,
+        /// The width of the layer in tiles.
+        width: u32,
+        /// The height of the layer in tiles.
+        height: u32,
+    },
+    /// A tile of a tile layer had a GID that doesn't belong to any of the map's tilesets. Loading
+    /// with a lower [`Strictness`](crate::Strictness) leaves such tiles empty instead.
+    UnresolvedTileGid {
+        /// The name of the layer the tile is in.
+        layer_name: String,
+        /// The GID of the tile, without its flip flags.
+        gid: u32,
+    },
+    /// The `tilecount` of a tileset disagreed with the number of tiles that fit in its image. This
+    /// is only an error with [`Strictness::Strict`](crate::Strictness::Strict); Otherwise the
+    /// declared tile count is kept.
+    TileCountMismatch {
+        /// The name of the tileset.
+        tileset_name: String,
+        /// The tile count from the `tilecount` attribute.
+        declared: u32,
+        /// The number of tiles that fit in the tileset image.
+        derived: u32,
+    },
+    /// A list of properties had several properties with the same name. Only returned when
+    /// loading with [`DuplicateHandling::Error`](crate::DuplicateHandling::Error).
+    DuplicateProperty {
+        /// The name of the properties.
+        name: String,
+    },
+    /// Sibling layers had the same name. Only returned when loading with
+    /// [`DuplicateHandling::Error`](crate::DuplicateHandling::Error).
+    DuplicateLayerName {
+        /// The name of the layers.
+        name: String,
+    },
+    /// A map or group layer had a child element that looks like a layer, having both an `id` and a
+    /// `name`, but isn't one of the layer kinds supported by the crate, such as one introduced by
+    /// a newer version of Tiled. This is only an error with
+    /// [`Strictness::Strict`](crate::Strictness::Strict); Otherwise the layer is kept as a
+    /// [`LayerType::Unknown`](crate::LayerType::Unknown) layer.
+    UnknownLayerType {
+        /// The name of the element, e.g. `voxellayer`.
+        tag_name: String,
+    },
     /// There was an error parsing the value of a [`PropertyValue`].
     ///
     /// [`PropertyValue`]: crate::PropertyValue
@@ -107,15 +207,650 @@ pub enum Error {
     },
     /// There was an invalid tileset in the map parsed.
     InvalidTileset(InvalidTilesetError),
+    /// An error that occurred while parsing a file, alongside where it happened.
+    ///
+    /// Errors that happen within a nested file (e.g. an external tileset referenced by a map) point
+    /// to the nested file instead of the one that referenced it.
+    ParseError {
+        /// The path to the file being parsed.
+        path: PathBuf,
+        /// The offset in bytes from the start of the file (not a line or column number) of the
+        /// XML element (or text) being parsed when the error occurred. Always 0 for JSON files,
+        /// which have a `json_path` instead.
+        position: u64,
+        /// For JSON files, the path of the JSON value being parsed when the error occurred, such as
+        /// `$.layers[1].objects[0]`. `None` for XML files.
+        json_path: Option<String>,
+        /// The error that occurred.
+        err: Box<Error>,
+    },
+    /// Some external tilesets of a map had no replacement when remapping them with
+    /// [`Map::remap_tilesets`](crate::Map::remap_tilesets).
+    UnmatchedTilesets {
+        /// The source paths of the tilesets that had no replacement.
+        paths: Vec<PathBuf>,
+    },
+    /// A map loaded with [`LoadSettings::validate`](crate::LoadSettings::validate) set had a
+    /// problem; This is the first one found by [`Map::validate`](crate::Map::validate).
+    ValidationFailed(ValidationIssue),
+    /// An I/O error occurred while writing a map or tileset with
+    /// [`Map::write_to`](crate::Map::write_to) or [`Tileset::write_to`](crate::Tileset::write_to).
+    WritingError(std::io::Error),
+    /// A TMJ map, TSJ tileset or TJ template file wasn't valid JSON, or didn't have the structure
+    /// of the [JSON map format](https://doc.mapeditor.org/en/stable/reference/json-map-format/).
+    #[cfg(feature = "json")]
+    JsonDecodingError(serde_json::Error),
+    /// The JSON of a [`World`](crate::World) file was malformed.
+    #[cfg(feature = "world")]
+    WorldDecodingError(serde_json::Error),
+    /// A pattern in a [`World`](crate::World) file wasn't a valid regular expression.
+    #[cfg(feature = "world")]
+    InvalidWorldPattern(regex::Error),
+    /// The JSON of a [`Project`](crate::Project) file was malformed.
+    #[cfg(feature = "project")]
+    ProjectDecodingError(serde_json::Error),
+}
+
+/// A problem found while loading a file that didn't make loading fail, because of the
+/// [`Strictness`](crate::Strictness) used. Obtained through [`Map::warnings`](crate::Map::warnings).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum ParseWarning {
+    /// A property had a type that isn't supported by the crate. The property is stored as a
+    /// [`PropertyValue::Unknown`](crate::PropertyValue::Unknown).
+    UnknownPropertyType {
+        /// The name of the property.
+        property: String,
+        /// The unsupported type name.
+        type_name: String,
+    },
+    /// A tile of a tile layer had a GID that doesn't belong to any of the map's tilesets, which
+    /// happens when the tilesets it used were removed. Such tiles are left empty. Only the first
+    /// one of each layer or chunk is reported.
+    UnresolvedTileGid {
+        /// The name of the layer the tile is in.
+        layer_name: String,
+        /// The GID of the tile, without its flip flags.
+        gid: u32,
+    },
+    /// The `tilecount` of a tileset disagreed with the number of tiles that fit in its image. The
+    /// declared tile count is kept.
+    TileCountMismatch {
+        /// The name of the tileset.
+        tileset_name: String,
+        /// The tile count from the `tilecount` attribute.
+        declared: u32,
+        /// The number of tiles that fit in the tileset image.
+        derived: u32,
+    },
+    /// A list of properties had several properties with the same name. Which of them is kept
+    /// depends on [`LoadSettings::duplicates`](crate::LoadSettings::duplicates).
+    DuplicateProperty {
+        /// The name of the properties.
+        name: String,
+        /// The file the duplicate property is in.
+        path: PathBuf,
+        /// The byte offset of the duplicate property in that file.
+        position: u64,
+    },
+    /// An external tileset of the map couldn't be read and was replaced by an empty placeholder.
+    /// Only happens with
+    /// [`LoadSettings::placeholder_missing_tilesets`](crate::LoadSettings::placeholder_missing_tilesets).
+    MissingTileset {
+        /// The path the tileset should have been loaded from.
+        path: PathBuf,
+        /// The [`Error::ResourceLoadingError`] that prevented it from being read, shared so that
+        /// warnings can be cloned.
+        #[cfg_attr(feature = "serde", serde(with = "missing_tileset_error"))]
+        error: Arc<Error>,
+    },
+    /// Sibling layers had the same name. All of them are kept.
+    DuplicateLayerName {
+        /// The name of the layers.
+        name: String,
+        /// The file the duplicate layer is in.
+        path: PathBuf,
+        /// The byte offset of the duplicate layer in that file.
+        position: u64,
+    },
+    /// A map or group layer had a child element that looks like a layer but isn't one of the
+    /// layer kinds supported by the crate. It is kept as a
+    /// [`LayerType::Unknown`](crate::LayerType::Unknown) layer.
+    UnknownLayerType {
+        /// The name of the element, e.g. `voxellayer`.
+        tag_name: String,
+        /// The name of the layer.
+        layer_name: String,
+    },
+}
+
+// Errors can't be compared, so those of missing tilesets are considered equal when they are of
+// the same kind and have the same message
+impl PartialEq for ParseWarning {
+    fn eq(&self, other: &Self) -> bool {
+        use ParseWarning::*;
+        match (self, other) {
+            (
+                UnknownPropertyType {
+                    property,
+                    type_name,
+                },
+                UnknownPropertyType {
+                    property: other_property,
+                    type_name: other_type_name,
+                },
+            ) => property == other_property && type_name == other_type_name,
+            (
+                UnresolvedTileGid { layer_name, gid },
+                UnresolvedTileGid {
+                    layer_name: other_layer_name,
+                    gid: other_gid,
+                },
+            ) => layer_name == other_layer_name && gid == other_gid,
+            (
+                TileCountMismatch {
+                    tileset_name,
+                    declared,
+                    derived,
+                },
+                TileCountMismatch {
+                    tileset_name: other_tileset_name,
+                    declared: other_declared,
+                    derived: other_derived,
+                },
+            ) => {
+                tileset_name == other_tileset_name
+                    && declared == other_declared
+                    && derived == other_derived
+            }
+            (
+                DuplicateProperty {
+                    name,
+                    path,
+                    position,
+                },
+                DuplicateProperty {
+                    name: other_name,
+                    path: other_path,
+                    position: other_position,
+                },
+            )
+            | (
+                DuplicateLayerName {
+                    name,
+                    path,
+                    position,
+                },
+                DuplicateLayerName {
+                    name: other_name,
+                    path: other_path,
+                    position: other_position,
+                },
+            ) => name == other_name && path == other_path && position == other_position,
+            (
+                MissingTileset { path, error },
+                MissingTileset {
+                    path: other_path,
+                    error: other_error,
+                },
+            ) => {
+                path == other_path
+                    && (Arc::ptr_eq(error, other_error)
+                        || error.kind() == other_error.kind()
+                            && error.to_string() == other_error.to_string())
+            }
+            (
+                UnknownLayerType {
+                    tag_name,
+                    layer_name,
+                },
+                UnknownLayerType {
+                    tag_name: other_tag_name,
+                    layer_name: other_layer_name,
+                },
+            ) => tag_name == other_tag_name && layer_name == other_layer_name,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for ParseWarning {}
+
+// The errors of missing tilesets are never mutated once stored, so maps stay unwind safe even
+// though the sources of errors in general aren't
+impl std::panic::UnwindSafe for ParseWarning {}
+impl std::panic::RefUnwindSafe for ParseWarning {}
+
+/// Serializes the error of a [`ParseWarning::MissingTileset`] as the path and message of the
+/// [`Error::ResourceLoadingError`] it is. Deserializing it gives back such an error, whose source
+/// only keeps its message.
+#[cfg(feature = "serde")]
+mod missing_tileset_error {
+    use std::{path::PathBuf, sync::Arc};
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::Error;
+
+    #[derive(Serialize, Deserialize)]
+    struct ResourceLoadingError {
+        path: PathBuf,
+        message: String,
+    }
+
+    pub(super) fn serialize<S: Serializer>(
+        error: &Arc<Error>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let error = match &**error {
+            Error::ResourceLoadingError { path, err } => ResourceLoadingError {
+                path: path.clone(),
+                message: err.to_string(),
+            },
+            error => ResourceLoadingError {
+                path: PathBuf::new(),
+                message: error.to_string(),
+            },
+        };
+        error.serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Arc<Error>, D::Error> {
+        let ResourceLoadingError { path, message } = ResourceLoadingError::deserialize(deserializer)?;
+        Ok(Arc::new(Error::ResourceLoadingError {
+            path,
+            err: message.into(),
+        }))
+    }
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseWarning::UnknownPropertyType {
+                property,
+                type_name,
+            } => write!(
+                fmt,
+                "Property '{}' has an unknown type '{}'",
+                property, type_name
+            ),
+            ParseWarning::UnresolvedTileGid { layer_name, gid } => write!(
+                fmt,
+                "Tile GID {} of layer '{}' doesn't belong to any of the map's tilesets",
+                gid, layer_name
+            ),
+            ParseWarning::DuplicateProperty {
+                name,
+                path,
+                position,
+            } => write!(
+                fmt,
+                "Property '{}' is defined more than once (in '{}' at byte offset {})",
+                name,
+                path.to_string_lossy(),
+                position
+            ),
+            ParseWarning::MissingTileset { path, error } => write!(
+                fmt,
+                "Tileset '{}' couldn't be read and was replaced by a placeholder: {}",
+                path.to_string_lossy(),
+                error
+            ),
+            ParseWarning::DuplicateLayerName {
+                name,
+                path,
+                position,
+            } => write!(
+                fmt,
+                "Layer name '{}' is used by more than one sibling layer (in '{}' at byte offset {})",
+                name,
+                path.to_string_lossy(),
+                position
+            ),
+            ParseWarning::UnknownLayerType {
+                tag_name,
+                layer_name,
+            } => write!(
+                fmt,
+                "Layer '{}' has an unknown type '{}'",
+                layer_name, tag_name
+            ),
+            ParseWarning::TileCountMismatch {
+                tileset_name,
+                declared,
+                derived,
+            } => write!(
+                fmt,
+                "Tileset '{}' declares {} tiles, but {} fit in its image",
+                tileset_name, declared, derived
+            ),
+        }
+    }
+}
+
+/// A problem with the contents of an already loaded map, found by
+/// [`Map::validate`](crate::Map::validate).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum ValidationIssue {
+    /// A tile refers to an ID that isn't part of its tileset, usually because its GID points past
+    /// the end of the map's last tileset or into a gap between two of them.
+    TileOutOfRange {
+        /// The ID of the layer the tile is in.
+        layer_id: u32,
+        /// The name of the layer the tile is in.
+        layer_name: String,
+        /// Where the tile is used.
+        location: TileLocation,
+        /// The name of the tileset the tile was resolved to.
+        tileset_name: String,
+        /// The local ID of the tile in that tileset.
+        tile_id: TileId,
+    },
+    /// A tile belongs to an external tileset that could not be loaded. Only happens with maps
+    /// loaded with
+    /// [`LoadSettings::placeholder_missing_tilesets`](crate::LoadSettings::placeholder_missing_tilesets).
+    MissingTileset {
+        /// The ID of the layer the tile is in.
+        layer_id: u32,
+        /// The name of the layer the tile is in.
+        layer_name: String,
+        /// Where the tile is used.
+        location: TileLocation,
+        /// The path the tileset should have been loaded from.
+        tileset_source: Option<PathBuf>,
+    },
+    /// An object has the same ID as another object of the map declared before it.
+    DuplicateObjectId {
+        /// The ID of the layer the object is in.
+        layer_id: u32,
+        /// The name of the layer the object is in.
+        layer_name: String,
+        /// The duplicated object ID.
+        object_id: u32,
+    },
+    /// An object has a position that is NaN or infinite.
+    InvalidObjectCoordinates {
+        /// The ID of the layer the object is in.
+        layer_id: u32,
+        /// The name of the layer the object is in.
+        layer_name: String,
+        /// The ID of the object.
+        object_id: u32,
+    },
+}
+
+/// Where a tile with a [`ValidationIssue`] is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TileLocation {
+    /// In a tile layer, at the given position in tiles.
+    Layer {
+        /// The X position of the tile.
+        x: i32,
+        /// The Y position of the tile.
+        y: i32,
+    },
+    /// As the image of a tile object.
+    Object {
+        /// The ID of the object.
+        object_id: u32,
+    },
+}
+
+impl fmt::Display for TileLocation {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TileLocation::Layer { x, y } => write!(fmt, "at ({}, {})", x, y),
+            TileLocation::Object { object_id } => write!(fmt, "in object {}", object_id),
+        }
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValidationIssue::TileOutOfRange {
+                layer_id,
+                layer_name,
+                location,
+                tileset_name,
+                tile_id,
+            } => write!(
+                fmt,
+                "Tile {} of layer '{}' (ID {}) refers to tile {}, which is not part of tileset '{}'",
+                location, layer_name, layer_id, tile_id, tileset_name
+            ),
+            ValidationIssue::MissingTileset {
+                layer_id,
+                layer_name,
+                location,
+                tileset_source,
+            } => write!(
+                fmt,
+                "Tile {} of layer '{}' (ID {}) belongs to tileset '{}', which could not be loaded",
+                location,
+                layer_name,
+                layer_id,
+                tileset_source
+                    .as_deref()
+                    .map(|path| path.to_string_lossy())
+                    .unwrap_or_default()
+            ),
+            ValidationIssue::DuplicateObjectId {
+                layer_id,
+                layer_name,
+                object_id,
+            } => write!(
+                fmt,
+                "Object ID {} in layer '{}' (ID {}) is already used by another object",
+                object_id, layer_name, layer_id
+            ),
+            ValidationIssue::InvalidObjectCoordinates {
+                layer_id,
+                layer_name,
+                object_id,
+            } => write!(
+                fmt,
+                "Object {} in layer '{}' (ID {}) has a NaN or infinite position",
+                object_id, layer_name, layer_id
+            ),
+        }
+    }
+}
+
+/// The kind of an [`Error`], obtained through [`Error::kind`]. Each kind corresponds to the
+/// [`Error`] variant of the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// See [`Error::MalformedAttributes`].
+    MalformedAttributes,
+    /// See [`Error::MalformedAttribute`].
+    MalformedAttribute,
+    /// See [`Error::MissingAttribute`].
+    MissingAttribute,
+    /// See [`Error::DecompressingError`].
+    DecompressingError,
+    /// See [`Error::Base64DecodingError`].
+    Base64DecodingError,
+    /// See [`Error::CsvDecodingError`].
+    CsvDecodingError,
+    /// See [`Error::XmlDecodingError`].
+    XmlDecodingError,
+    /// See [`Error::PrematureEnd`].
+    PrematureEnd,
+    /// See [`Error::PathIsNotFile`].
+    PathIsNotFile,
+    /// See [`Error::ResourceLoadingError`].
+    ResourceLoadingError,
+    /// See [`Error::InvalidTileFound`].
+    InvalidTileFound,
+    /// See [`Error::InvalidEncodingFormat`].
+    InvalidEncodingFormat,
+    /// See [`Error::InvalidTileDataSize`].
+    InvalidTileDataSize,
+    /// See [`Error::TileLayerTooLarge`].
+    TileLayerTooLarge,
+    /// See [`Error::DecompressedTileDataTooLarge`].
+    DecompressedTileDataTooLarge,
+    /// See [`Error::ChunkOutOfBounds`].
+    ChunkOutOfBounds,
+    /// See [`Error::UnresolvedTileGid`].
+    UnresolvedTileGid,
+    /// See [`Error::TileCountMismatch`].
+    TileCountMismatch,
+    /// See [`Error::DuplicateProperty`].
+    DuplicateProperty,
+    /// See [`Error::DuplicateLayerName`].
+    DuplicateLayerName,
+    /// See [`Error::UnknownLayerType`].
+    UnknownLayerType,
+    /// See [`Error::InvalidPropertyValue`].
+    InvalidPropertyValue,
+    /// See [`Error::UnknownPropertyType`].
+    UnknownPropertyType,
+    /// See [`Error::TemplateHasNoObject`].
+    TemplateHasNoObject,
+    /// See [`Error::InvalidWangIdEncoding`].
+    InvalidWangIdEncoding,
+    /// See [`Error::InvalidObjectData`].
+    InvalidObjectData,
+    /// See [`Error::InvalidTileset`].
+    InvalidTileset,
+    /// See [`Error::UnmatchedTilesets`].
+    UnmatchedTilesets,
+    /// See [`Error::ValidationFailed`].
+    ValidationFailed,
+    /// See [`Error::WritingError`].
+    WritingError,
+    /// See [`Error::JsonDecodingError`].
+    #[cfg(feature = "json")]
+    JsonDecodingError,
+    /// See [`Error::WorldDecodingError`].
+    #[cfg(feature = "world")]
+    WorldDecodingError,
+    /// See [`Error::InvalidWorldPattern`].
+    #[cfg(feature = "world")]
+    InvalidWorldPattern,
+    /// See [`Error::ProjectDecodingError`].
+    #[cfg(feature = "project")]
+    ProjectDecodingError,
 }
 
 /// A result with an error variant of [`crate::Error`].
 pub type Result<T> = std::result::Result<T, Error>;
 
+impl Error {
+    /// Returns the underlying error, skipping the position information added by
+    /// [`Error::ParseError`].
+    pub fn inner(&self) -> &Error {
+        match self {
+            Error::ParseError { err, .. } => err.inner(),
+            err => err,
+        }
+    }
+
+    /// Returns the kind of the underlying error, which allows branching on it without
+    /// destructuring. The position information added by [`Error::ParseError`] is skipped.
+    pub fn kind(&self) -> ErrorKind {
+        match self.inner() {
+            Error::MalformedAttributes(_) => ErrorKind::MalformedAttributes,
+            Error::MalformedAttribute { .. } => ErrorKind::MalformedAttribute,
+            Error::MissingAttribute { .. } => ErrorKind::MissingAttribute,
+            Error::DecompressingError(_) => ErrorKind::DecompressingError,
+            Error::Base64DecodingError(_) => ErrorKind::Base64DecodingError,
+            Error::CsvDecodingError(_) => ErrorKind::CsvDecodingError,
+            Error::XmlDecodingError(_) => ErrorKind::XmlDecodingError,
+            Error::PrematureEnd(_) => ErrorKind::PrematureEnd,
+            Error::PathIsNotFile => ErrorKind::PathIsNotFile,
+            Error::ResourceLoadingError { .. } => ErrorKind::ResourceLoadingError,
+            Error::InvalidTileFound => ErrorKind::InvalidTileFound,
+            Error::InvalidEncodingFormat { .. } => ErrorKind::InvalidEncodingFormat,
+            Error::InvalidTileDataSize { .. } => ErrorKind::InvalidTileDataSize,
+            Error::TileLayerTooLarge { .. } => ErrorKind::TileLayerTooLarge,
+            Error::DecompressedTileDataTooLarge { .. } => ErrorKind::DecompressedTileDataTooLarge,
+            Error::ChunkOutOfBounds { .. } => ErrorKind::ChunkOutOfBounds,
+            Error::UnresolvedTileGid { .. } => ErrorKind::UnresolvedTileGid,
+            Error::TileCountMismatch { .. } => ErrorKind::TileCountMismatch,
+            Error::DuplicateProperty { .. } => ErrorKind::DuplicateProperty,
+            Error::DuplicateLayerName { .. } => ErrorKind::DuplicateLayerName,
+            Error::UnknownLayerType { .. } => ErrorKind::UnknownLayerType,
+            Error::InvalidPropertyValue { .. } => ErrorKind::InvalidPropertyValue,
+            Error::UnknownPropertyType { .. } => ErrorKind::UnknownPropertyType,
+            Error::TemplateHasNoObject => ErrorKind::TemplateHasNoObject,
+            Error::InvalidWangIdEncoding { .. } => ErrorKind::InvalidWangIdEncoding,
+            Error::InvalidObjectData { .. } => ErrorKind::InvalidObjectData,
+            Error::InvalidTileset(_) => ErrorKind::InvalidTileset,
+            Error::ParseError { .. } => unreachable!("inner errors are never parse errors"),
+            Error::UnmatchedTilesets { .. } => ErrorKind::UnmatchedTilesets,
+            Error::ValidationFailed(_) => ErrorKind::ValidationFailed,
+            Error::WritingError(_) => ErrorKind::WritingError,
+            #[cfg(feature = "json")]
+            Error::JsonDecodingError(_) => ErrorKind::JsonDecodingError,
+            #[cfg(feature = "world")]
+            Error::WorldDecodingError(_) => ErrorKind::WorldDecodingError,
+            #[cfg(feature = "world")]
+            Error::InvalidWorldPattern(_) => ErrorKind::InvalidWorldPattern,
+            #[cfg(feature = "project")]
+            Error::ProjectDecodingError(_) => ErrorKind::ProjectDecodingError,
+        }
+    }
+
+    /// Attaches the path and position where this error happened, unless it already has one.
+    pub(crate) fn at_position(self, path: &std::path::Path, position: u64) -> Error {
+        match self {
+            Error::ParseError { .. } => self,
+            err => Error::ParseError {
+                path: path.to_owned(),
+                position,
+                json_path: None,
+                err: Box::new(err),
+            },
+        }
+    }
+
+    /// Attaches the path of the JSON file and of the JSON value where this error happened, unless
+    /// it already has a position.
+    #[cfg(feature = "json")]
+    pub(crate) fn at_json_path(self, path: &std::path::Path, json_path: &str) -> Error {
+        match self {
+            Error::ParseError { .. } => self,
+            err => Error::ParseError {
+                path: path.to_owned(),
+                position: 0,
+                json_path: Some(json_path.to_owned()),
+                err: Box::new(err),
+            },
+        }
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> std::result::Result<(), fmt::Error> {
         match self {
             Error::MalformedAttributes(s) => write!(fmt, "{}", s),
+            Error::MalformedAttribute { element, attribute, value, source } => {
+                write!(
+                    fmt,
+                    "Error parsing attribute '{}' of element '{}' with value \"{}\"",
+                    attribute, element, value
+                )?;
+                match source {
+                    Some(source) => write!(fmt, ": {}", source),
+                    None => Ok(()),
+                }
+            }
+            Error::MissingAttribute { element, attribute } => write!(
+                fmt,
+                "Missing attribute '{}' of element '{}'",
+                attribute, element
+            ),
             Error::DecompressingError(e) => write!(fmt, "{}", e),
             Error::Base64DecodingError(e) => write!(fmt, "{}", e),
             Error::CsvDecodingError(e) => write!(fmt, "{}", e),
@@ -148,6 +883,62 @@ impl fmt::Display for Error {
                     encoding.as_deref().unwrap_or("no"),
                     compression.as_deref().unwrap_or("no")
                 ),
+            Error::InvalidTileDataSize { expected, found } =>
+                write!(
+                    fmt,
+                    "Tile data is {} bytes long instead of the {} bytes expected for the layer's size; It may be truncated or corrupted",
+                    found,
+                    expected
+                ),
+            Error::TileLayerTooLarge { width, height } =>
+                write!(
+                    fmt,
+                    "A layer or chunk of {}x{} tiles is too large to be loaded",
+                    width,
+                    height
+                ),
+            Error::DecompressedTileDataTooLarge { limit } =>
+                write!(
+                    fmt,
+                    "Tile data is larger than the limit of {} bytes once decoded",
+                    limit
+                ),
+            Error::ChunkOutOfBounds { chunk, width, height } =>
+                write!(
+                    fmt,
+                    "Chunk of {}x{} tiles at ({}, {}) has tiles outside of its {}x{} layer",
+                    chunk.width,
+                    chunk.height,
+                    chunk.x,
+                    chunk.y,
+                    width,
+                    height
+                ),
+            Error::UnresolvedTileGid { layer_name, gid } => write!(
+                fmt,
+                "Tile GID {} of layer '{}' doesn't belong to any of the map's tilesets",
+                gid, layer_name
+            ),
+            Error::DuplicateProperty { name } => {
+                write!(fmt, "Property '{}' is defined more than once", name)
+            }
+            Error::DuplicateLayerName { name } => write!(
+                fmt,
+                "Layer name '{}' is used by more than one sibling layer",
+                name
+            ),
+            Error::UnknownLayerType { tag_name } => {
+                write!(fmt, "Unknown layer type '{}'", tag_name)
+            }
+            Error::TileCountMismatch {
+                tileset_name,
+                declared,
+                derived,
+            } => write!(
+                fmt,
+                "Tileset '{}' declares {} tiles, but {} fit in its image",
+                tileset_name, declared, derived
+            ),
             Error::InvalidPropertyValue{description} =>
                 write!(fmt, "Invalid property value: {}", description),
             Error::UnknownPropertyType { type_name } =>
@@ -158,6 +949,40 @@ impl fmt::Display for Error {
             Error::InvalidObjectData{description} =>
                 write!(fmt, "Invalid object data: {}", description),
             Error::InvalidTileset(e) => write!(fmt, "{}", e),
+            Error::ParseError { path, json_path: Some(json_path), err, .. } => write!(
+                fmt,
+                "{} (in '{}' at {})",
+                err,
+                path.to_string_lossy(),
+                json_path
+            ),
+            Error::ParseError { path, position, err, .. } => write!(
+                fmt,
+                "{} (in '{}' at byte offset {})",
+                err,
+                path.to_string_lossy(),
+                position
+            ),
+            Error::ValidationFailed(issue) => write!(fmt, "Map validation failed: {}", issue),
+            Error::WritingError(e) => write!(fmt, "Error while writing: {}", e),
+            #[cfg(feature = "json")]
+            Error::JsonDecodingError(e) => write!(fmt, "Error while decoding JSON: {}", e),
+            Error::UnmatchedTilesets { paths } => {
+                write!(fmt, "No replacement found for tilesets ")?;
+                for (i, path) in paths.iter().enumerate() {
+                    if i > 0 {
+                        write!(fmt, ", ")?;
+                    }
+                    write!(fmt, "'{}'", path.to_string_lossy())?;
+                }
+                Ok(())
+            }
+            #[cfg(feature = "world")]
+            Error::WorldDecodingError(e) => write!(fmt, "{}", e),
+            #[cfg(feature = "world")]
+            Error::InvalidWorldPattern(e) => write!(fmt, "Invalid world pattern: {}", e),
+            #[cfg(feature = "project")]
+            Error::ProjectDecodingError(e) => write!(fmt, "{}", e),
         }
     }
 }
@@ -169,6 +994,20 @@ impl std::error::Error for Error {
             Error::Base64DecodingError(e) => Some(e as &dyn std::error::Error),
             Error::XmlDecodingError(e) => Some(e as &dyn std::error::Error),
             Error::ResourceLoadingError { err, .. } => Some(err.as_ref()),
+            Error::MalformedAttribute {
+                source: Some(source),
+                ..
+            } => Some(source.as_ref()),
+            Error::ParseError { err, .. } => Some(err.as_ref()),
+            Error::WritingError(e) => Some(e as &dyn std::error::Error),
+            #[cfg(feature = "json")]
+            Error::JsonDecodingError(e) => Some(e as &dyn std::error::Error),
+            #[cfg(feature = "world")]
+            Error::WorldDecodingError(e) => Some(e as &dyn std::error::Error),
+            #[cfg(feature = "world")]
+            Error::InvalidWorldPattern(e) => Some(e as &dyn std::error::Error),
+            #[cfg(feature = "project")]
+            Error::ProjectDecodingError(e) => Some(e as &dyn std::error::Error),
             _ => None,
         }
     }
@@ -1,25 +1,31 @@
-use std::path::{Path, PathBuf};
+use std::{
+    convert::{TryFrom, TryInto},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use quick_xml::events::attributes::Attribute;
 
 use crate::{
     error::{Error, Result},
-    parse::xml::{Parser, Reader},
+    layers::parse_base64,
+    parse::xml::{Parser, ReadFrom, Reader},
     properties::Color,
     util::*,
 };
 
-/// A reference to an image stored somewhere within the filesystem.
+/// A reference to an image stored somewhere within the filesystem, or embedded in the file itself.
+///
+/// Images are stored behind an [`Arc`]: All of the identical references to the same file found
+/// while loading a map, including in its tilesets and templates, share a single `Image`.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Image {
     /// The **uncanonicalized** filepath of the image, starting from the path given to load the file
     /// this image is in. See the example for more details.
     ///
     /// ## Note
-    /// The crate does not currently support embedded images (Even though Tiled
-    /// does not allow creating maps with embedded image data, the TMX format does; [source])
-    ///
-    /// [source]: https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#image
+    /// This is empty for [embedded images](Self::embedded_data), which have no file of their own.
     ///
     /// ## Example
     /// ```
@@ -58,13 +64,39 @@ pub struct Image {
     /// ```
     /// Check the assets/tiled_relative_paths.tmx file at the crate root to see the structure of the
     /// file this example is referring to.
-    // TODO: Embedded images
     pub source: PathBuf,
+    /// The `source` attribute of the image exactly as written in the file containing it, or
+    /// [`None`] for [embedded images](Self::embedded_data).
+    ///
+    /// Unlike [`source`](Self::source), this keeps its original separators and `..` segments,
+    /// e.g. `"../tilesheet.png"` in the example above.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub raw_source: Option<String>,
+    /// The format of the image, e.g. `png`. Only set for [embedded images](Self::embedded_data).
+    pub format: Option<String>,
+    /// The decoded contents of the image file, if it was embedded in the TMX/TSX file through a
+    /// `<data>` element instead of being referred to by its [source](Self::source).
+    ///
+    /// Even though Tiled does not allow creating maps with embedded image data, the TMX format
+    /// does; [source].
+    ///
+    /// [source]: https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#image
+    pub embedded_data: Option<Vec<u8>>,
     /// The width in pixels of the image.
+    ///
+    /// This is 0 if the image has no `width` attribute, unless its dimensions were read from the
+    /// image itself; See [`LoadSettings::probe_image_dimensions`](crate::LoadSettings::probe_image_dimensions).
     pub width: i32,
     /// The height in pixels of the image.
+    ///
+    /// This is 0 if the image has no `height` attribute, unless its dimensions were read from the
+    /// image itself; See [`LoadSettings::probe_image_dimensions`](crate::LoadSettings::probe_image_dimensions).
     pub height: i32,
-    /// A color that should be interpreted as transparent (0 alpha), if any.
+    /// A color that should be interpreted as transparent (0 alpha), if any. Pixels of this exact
+    /// color are meant to be made fully transparent when loading the image, which older tilesets
+    /// use instead of an alpha channel.
+    ///
+    /// This is read from the `trans` attribute of the image, with or without a `#` prefix.
     pub transparent_colour: Option<Color>,
 }
 
@@ -73,23 +105,166 @@ impl Image {
         parser: &mut Parser<R>,
         attrs: Vec<Attribute<'_>>,
         path_relative_to: impl AsRef<Path>,
-    ) -> Result<Image> {
-        let (c, (s, w, h)) = get_attrs!(
-            for v in attrs {
+        read_from: &mut impl ReadFrom,
+    ) -> Result<Arc<Image>> {
+        let (c, s, format, (w, h)) = get_attrs!(
+            for v in attrs of "image" {
                 Some("trans") => trans ?= v.parse(),
-                "source" => source = v,
-                "width" => width ?= v.parse::<i32>(),
-                "height" => height ?= v.parse::<i32>(),
+                Some("source") => source = v,
+                Some("format") => format = v.to_owned(),
+                Some("width") => width ?= v.parse::<i32>(),
+                Some("height") => height ?= v.parse::<i32>(),
             }
-            (trans, (source, width, height))
+            (trans, source, format, (width, height))
         );
 
-        parse_tag!(parser, "image", {});
-        Ok(Image {
-            source: path_relative_to.as_ref().join(s),
-            width: w,
-            height: h,
+        let mut embedded_data = None;
+        parse_tag!(parser, "image", {
+            "data" => for attrs {
+                let encoding = get_attrs!(
+                    for v in attrs of "data" {
+                        Some("encoding") => encoding = v,
+                    }
+                    encoding
+                );
+                if encoding != Some("base64") {
+                    return Err(Error::InvalidEncodingFormat {
+                        encoding: encoding.map(ToOwned::to_owned),
+                        compression: None,
+                    });
+                }
+                embedded_data = Some(parse_base64(parser).await?);
+                Ok(())
+            },
+        });
+
+        let source = match (s, &embedded_data) {
+            (Some(s), _) => parser
+                .context
+                .settings
+                .resolve_path(path_relative_to.as_ref(), s),
+            (None, Some(_)) => PathBuf::new(),
+            (None, None) => {
+                return Err(Error::MalformedAttributes(
+                    "image must have either a source or embedded data".to_string(),
+                ))
+            }
+        };
+
+        let (mut width, mut height) = (w.unwrap_or(0), h.unwrap_or(0));
+        if (w.is_none() || h.is_none()) && parser.context.settings.probe_image_dimensions {
+            let probed = match &embedded_data {
+                Some(data) => probe_dimensions(data),
+                None => {
+                    let head = read_from
+                        .read_head(&source, PROBED_HEADER_LEN)
+                        .await
+                        .map_err(|err| Error::ResourceLoadingError {
+                            path: source.clone(),
+                            err,
+                        })?;
+                    probe_dimensions(&head)
+                }
+            };
+            if let Some((probed_width, probed_height)) = probed {
+                width = w.unwrap_or(probed_width);
+                height = h.unwrap_or(probed_height);
+            }
+        }
+
+        Ok(parser.context.state.intern_image(Image {
+            source,
+            raw_source: s.map(ToOwned::to_owned),
+            format,
+            embedded_data,
+            width,
+            height,
             transparent_colour: c,
-        })
+        }))
     }
 }
+
+/// How many bytes of an image file are read to find its dimensions. JPEG files may have metadata
+/// before the frame header, which can't be larger than this.
+const PROBED_HEADER_LEN: usize = 64 * 1024;
+
+/// Reads the dimensions of a PNG, BMP, GIF or JPEG image from the start of its file.
+fn probe_dimensions(head: &[u8]) -> Option<(i32, i32)> {
+    let (width, height) = if head.starts_with(b"\x89PNG\r\n\x1a\n") {
+        // The IHDR chunk always comes first
+        if head.get(12..16)? != b"IHDR" {
+            return None;
+        }
+        (read_u32_be(head, 16)?, read_u32_be(head, 20)?)
+    } else if head.starts_with(b"GIF87a") || head.starts_with(b"GIF89a") {
+        (read_u16_le(head, 6)? as u32, read_u16_le(head, 8)? as u32)
+    } else if head.starts_with(b"BM") {
+        // OS/2 bitmaps have a smaller header with 16-bit dimensions
+        if read_u32_le(head, 14)? == 12 {
+            (read_u16_le(head, 18)? as u32, read_u16_le(head, 20)? as u32)
+        } else {
+            // The height is negative for images stored top to bottom
+            let width = read_u32_le(head, 18)? as i32;
+            let height = read_u32_le(head, 22)? as i32;
+            (width.unsigned_abs(), height.unsigned_abs())
+        }
+    } else if head.starts_with(&[0xFF, 0xD8]) {
+        probe_jpeg_dimensions(head)?
+    } else {
+        return None;
+    };
+    Some((i32::try_from(width).ok()?, i32::try_from(height).ok()?))
+}
+
+/// Finds the dimensions of a JPEG image in its frame header, skipping over the segments before it.
+fn probe_jpeg_dimensions(head: &[u8]) -> Option<(u32, u32)> {
+    let mut position = 2;
+    loop {
+        if *head.get(position)? != 0xFF {
+            return None;
+        }
+        // Markers may be preceded by any number of fill bytes
+        while *head.get(position + 1)? == 0xFF {
+            position += 1;
+        }
+        let marker = *head.get(position + 1)?;
+        position += 2;
+        match marker {
+            // Standalone markers have no length
+            0x01 | 0xD0..=0xD7 => continue,
+            // Start of frame markers, other than DHT, JPG and DAC which share the range
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                let height = read_u16_be(head, position + 3)?;
+                let width = read_u16_be(head, position + 5)?;
+                return Some((width as u32, height as u32));
+            }
+            // Start of scan or end of image, the frame header should have come before
+            0xD9 | 0xDA => return None,
+            _ => position += read_u16_be(head, position)? as usize,
+        }
+    }
+}
+
+fn read_u16_be(bytes: &[u8], position: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        bytes.get(position..position + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_u16_le(bytes: &[u8], position: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        bytes.get(position..position + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_u32_be(bytes: &[u8], position: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        bytes.get(position..position + 4)?.try_into().ok()?,
+    ))
+}
+
+fn read_u32_le(bytes: &[u8], position: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(position..position + 4)?.try_into().ok()?,
+    ))
+}
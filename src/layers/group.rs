@@ -5,14 +5,24 @@ use crate::{
     layers::{LayerData, LayerTag},
     parse::xml::{Parser, ReadFrom, Reader},
     properties::{parse_properties, Properties},
+    stats::HeapSize,
     util::*,
-    Error, Layer, MapTilesetGid, ResourceCache, Tileset,
+    Error, Layer, LayersRecursive, MapTilesetGid, ResourceCache, Tileset,
 };
 
 /// The raw data of a [`GroupLayer`]. Does not include a reference to its parent [`Map`](crate::Map).
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GroupLayerData {
-    layers: Vec<LayerData>,
+    pub(crate) layers: Vec<LayerData>,
+    /// The indices leading to this group from the map's top-level layers.
+    pub(crate) path: Vec<usize>,
+}
+
+impl HeapSize for GroupLayerData {
+    fn heap_size(&self) -> usize {
+        self.layers.heap_size() + self.path.heap_size()
+    }
 }
 
 impl GroupLayerData {
@@ -30,7 +40,8 @@ impl GroupLayerData {
         let mut buffer = Vec::new();
         parse_tag!(parser => &mut buffer, "group", {
             "layer" => for attrs {
-                layers.push(LayerData::new(
+                let position = parser.last_event_position;
+                let layer = LayerData::new(
                     parser,
                     attrs,
                     LayerTag::Tiles,
@@ -40,11 +51,13 @@ impl GroupLayerData {
                     for_tileset.as_ref().cloned(),
                     read_from,
                     cache
-                ).await?);
+                ).await?;
+                LayerData::push_sibling(parser, &mut layers, layer, position)?;
                 Ok(())
             },
             "imagelayer" => for attrs {
-                layers.push(LayerData::new(
+                let position = parser.last_event_position;
+                let layer = LayerData::new(
                     parser,
                     attrs,
                     LayerTag::Image,
@@ -54,11 +67,13 @@ impl GroupLayerData {
                     for_tileset.as_ref().cloned(),
                     read_from,
                     cache
-                ).await?);
+                ).await?;
+                LayerData::push_sibling(parser, &mut layers, layer, position)?;
                 Ok(())
             },
             "objectgroup" => for attrs {
-                layers.push(LayerData::new(
+                let position = parser.last_event_position;
+                let layer = LayerData::new(
                     parser,
                     attrs,
                     LayerTag::Objects,
@@ -68,11 +83,13 @@ impl GroupLayerData {
                     for_tileset.as_ref().cloned(),
                     read_from,
                     cache
-                ).await?);
+                ).await?;
+                LayerData::push_sibling(parser, &mut layers, layer, position)?;
                 Ok(())
             },
             "group" => for attrs {
-                layers.push(LayerData::new(
+                let position = parser.last_event_position;
+                let layer = LayerData::new(
                     parser,
                     attrs,
                     LayerTag::Group,
@@ -82,15 +99,28 @@ impl GroupLayerData {
                     for_tileset.as_ref().cloned(),
                     read_from,
                     cache
-                ).await?);
+                ).await?;
+                LayerData::push_sibling(parser, &mut layers, layer, position)?;
                 Ok(())
             },
             "properties" => {
                 properties = parse_properties(parser).await?;
                 Ok(())
             },
+        }, else start => {
+            let position = parser.last_event_position;
+            if let Some(layer) = LayerData::new_unknown(parser, start).await? {
+                LayerData::push_sibling(parser, &mut layers, layer, position)?;
+            }
+            Ok(())
         });
-        Ok((Self { layers }, properties))
+        Ok((
+            Self {
+                layers,
+                path: Vec::new(),
+            },
+            properties,
+        ))
     }
 }
 
@@ -99,7 +129,10 @@ map_wrapper!(
     #[doc = "\nAlso see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#group)."]
     #[doc = "## Note"]
     #[doc = "In Tiled, the properties of the group layer recursively affect child layers.
-    Implementing this behavior is left up to the user of this library."]
+    The combined offset, parallax factor and tint color of a child layer can be obtained through
+    [`Layer::effective_attributes()`], and its combined opacity and visibility through
+    [`Layer::effective_opacity()`] and [`Layer::effective_visibility()`]; Implementing any other
+    behavior is left up to the user of this library."]
     GroupLayer => GroupLayerData
 );
 
@@ -135,10 +168,42 @@ impl<'map> GroupLayer<'map> {
             .map(move |layer| Layer::new(map, layer))
     }
     /// Gets a specific layer from the group by index.
-    pub fn get_layer(&self, index: usize) -> Option<Layer> {
+    pub fn get_layer(&self, index: usize) -> Option<Layer<'map>> {
         self.data
             .layers
             .get(index)
             .map(|data| Layer::new(self.map, data))
     }
+
+    /// Returns an iterator over all the layers nested inside this group, including the ones inside
+    /// other groups, depth-first in display order.
+    pub fn descendants(&self) -> impl Iterator<Item = Layer<'map>> {
+        LayersRecursive::new(self.map, &self.data.layers).map(|(layer, _)| layer)
+    }
+
+    /// Returns this group as a generic [`Layer`], which gives access to its name, ID, properties
+    /// and other metadata.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// # fn main() {
+    /// # let map = Loader::new()
+    /// #     .load_tmx_map("assets/tiled_group_layers.tmx")
+    /// #     .unwrap();
+    /// let group = map.get_layer(1).unwrap().as_group_layer().unwrap();
+    ///
+    /// assert_eq!(group.as_layer().name, "group-1");
+    /// # }
+    /// ```
+    pub fn as_layer(&self) -> Layer<'map> {
+        let data = LayerData::at_path(self.map, &self.data.path)
+            .expect("group layers should be reachable from their map");
+        Layer::new(self.map, data)
+    }
+
+    /// The custom properties of this group, as arbitrarily set by the user.
+    pub fn properties(&self) -> &'map Properties {
+        &self.as_layer().data.properties
+    }
 }
@@ -1,25 +1,43 @@
-use std::{collections::HashMap, path::Path};
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use quick_xml::events::attributes::Attribute;
 
 use crate::{
-    parse::xml::{Parser, Reader},
+    parse::xml::{Parser, ReadFrom, Reader},
     parse_properties,
-    util::{map_wrapper, parse_tag},
+    util::{get_attrs, map_wrapper, parse_tag},
     Error, Image, Properties, Result,
 };
 
 /// The raw data of an [`ImageLayer`]. Does not include a reference to its parent [`Map`](crate::Map).
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ImageLayerData {
     /// The single image this layer contains, if it exists.
-    pub image: Option<Image>,
+    ///
+    /// Image layers that refer to the same image share it, see [`Image`].
+    pub image: Option<Arc<Image>>,
+    /// Whether the image should be repeated along the X axis.
+    pub repeat_x: bool,
+    /// Whether the image should be repeated along the Y axis.
+    pub repeat_y: bool,
 }
 
 impl ImageLayerData {
     pub(crate) async fn new<R: Reader>(
         parser: &mut Parser<R>,
+        attrs: Vec<Attribute<'_>>,
         map_path: &Path,
+        read_from: &mut impl ReadFrom,
     ) -> Result<(Self, Properties)> {
-        let mut image: Option<Image> = None;
+        let (repeat_x, repeat_y) = get_attrs!(
+            for v in attrs of "imagelayer" {
+                Some("repeatx") => repeat_x ?= v.parse().map(|x: i32| x == 1),
+                Some("repeaty") => repeat_y ?= v.parse().map(|x: i32| x == 1),
+            }
+            (repeat_x, repeat_y)
+        );
+        let mut image: Option<Arc<Image>> = None;
         let mut properties = HashMap::new();
 
         let path_relative_to = map_path.parent().ok_or(Error::PathIsNotFile)?;
@@ -27,7 +45,7 @@ impl ImageLayerData {
         let mut buffer = Vec::new();
         parse_tag!(parser => &mut buffer, "imagelayer", {
             "image" => for attrs {
-                image = Some(Image::new(parser, attrs, path_relative_to).await?);
+                image = Some(Image::new(parser, attrs, path_relative_to, read_from).await?);
                 Ok(())
             },
             "properties" => {
@@ -35,7 +53,14 @@ impl ImageLayerData {
                 Ok(())
             },
         });
-        Ok((ImageLayerData { image }, properties))
+        Ok((
+            ImageLayerData {
+                image,
+                repeat_x: repeat_x.unwrap_or(false),
+                repeat_y: repeat_y.unwrap_or(false),
+            },
+            properties,
+        ))
     }
 }
 
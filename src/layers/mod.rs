@@ -1,13 +1,15 @@
-use std::{path::Path, sync::Arc};
+use std::{collections::HashMap, convert::TryFrom, path::Path, str::FromStr, sync::Arc};
 
-use quick_xml::events::attributes::Attribute;
+use quick_xml::events::{attributes::Attribute, BytesStart};
 
 use crate::{
-    error::Result,
-    parse::xml::{Parser, ReadFrom, Reader},
+    error::{Error, Result},
+    objects::resolve_object_property,
+    parse::xml::{DuplicateKind, Parser, ReadFrom, Reader},
     properties::Properties,
+    stats::HeapSize,
     util::*,
-    Color, Map, MapTilesetGid, ResourceCache, Tileset,
+    Color, Map, MapTilesetGid, Object, ParseWarning, ResourceCache, Strictness, Tileset,
 };
 
 mod image;
@@ -20,11 +22,13 @@ mod group;
 pub use group::*;
 
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) enum LayerDataType {
     Tiles(TileLayerData),
     Objects(ObjectLayerData),
     Image(ImageLayerData),
     Group(GroupLayerData),
+    Unknown(UnknownLayerData),
 }
 
 #[derive(Clone, Copy)]
@@ -35,14 +39,45 @@ pub(crate) enum LayerTag {
     Group,
 }
 
+impl LayerTag {
+    /// The name of the XML element of layers of this kind.
+    pub(crate) fn element_name(self) -> &'static str {
+        match self {
+            LayerTag::Tiles => "layer",
+            LayerTag::Objects => "objectgroup",
+            LayerTag::Image => "imagelayer",
+            LayerTag::Group => "group",
+        }
+    }
+}
+
+/// The element of a layer of a kind the crate doesn't support, of which only the attributes are
+/// kept. See [`LayerType::Unknown`].
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct UnknownLayerData {
+    pub(crate) tag_name: String,
+    pub(crate) attributes: HashMap<String, String>,
+}
+
+impl HeapSize for UnknownLayerData {
+    fn heap_size(&self) -> usize {
+        self.tag_name.heap_size() + self.attributes.heap_size()
+    }
+}
+
 /// The raw data of a [`Layer`]. Does not include a reference to its parent [`Map`](crate::Map).
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LayerData {
     /// The layer's name, set arbitrarily by the user.
     pub name: String,
     id: u32,
     /// Whether this layer should be visible or not.
     pub visible: bool,
+    /// Whether this layer is locked in the editor, which prevents its contents from being
+    /// selected.
+    pub locked: bool,
     /// The layer's x offset (in pixels).
     pub offset_x: f32,
     /// The layer's y offset (in pixels).
@@ -60,6 +95,32 @@ pub struct LayerData {
     /// The layer's type, which is arbitrarily setby the user.
     pub user_type: Option<String>,
     layer_type: LayerDataType,
+    contents_skipped: bool,
+    /// The indices of the group layers containing this layer, starting from the map's top-level
+    /// layers. Empty for top-level layers.
+    parent_path: Vec<usize>,
+}
+
+impl HeapSize for LayerData {
+    fn heap_size(&self) -> usize {
+        self.name.heap_size()
+            + self.properties.heap_size()
+            + self.user_type.heap_size()
+            + self.layer_type.heap_size()
+            + self.parent_path.heap_size()
+    }
+}
+
+impl HeapSize for LayerDataType {
+    fn heap_size(&self) -> usize {
+        match self {
+            LayerDataType::Tiles(data) => data.heap_size(),
+            LayerDataType::Objects(data) => data.heap_size(),
+            LayerDataType::Image(data) => data.heap_size(),
+            LayerDataType::Group(data) => data.heap_size(),
+            LayerDataType::Unknown(data) => data.heap_size(),
+        }
+    }
 }
 
 impl LayerData {
@@ -70,6 +131,14 @@ impl LayerData {
         self.id
     }
 
+    /// Whether the tiles or objects of this layer were skipped while loading the map, because of
+    /// the [`LoadSettings`](crate::LoadSettings) it was loaded with. Skipped layers keep their
+    /// metadata but behave as if they were empty.
+    #[inline]
+    pub fn contents_skipped(&self) -> bool {
+        self.contents_skipped
+    }
+
     // FIXME: fewer arguments?
     #[allow(clippy::too_many_arguments)]
     pub(crate) async fn new<R: Reader>(
@@ -83,10 +152,12 @@ impl LayerData {
         read_from: &mut impl ReadFrom,
         cache: &mut impl ResourceCache,
     ) -> Result<Self> {
+        let element = tag.element_name();
         let (
             opacity,
             tint_color,
             visible,
+            locked,
             offset_x,
             offset_y,
             parallax_x,
@@ -96,10 +167,11 @@ impl LayerData {
             user_type,
             user_class,
         ) = get_attrs!(
-            for v in attrs {
+            for v in attrs of element {
                 Some("opacity") => opacity ?= v.parse(),
                 Some("tintcolor") => tint_color ?= v.parse(),
                 Some("visible") => visible ?= v.parse().map(|x: i32| x == 1),
+                Some("locked") => locked ?= v.parse().map(|x: i32| x == 1),
                 Some("offsetx") => offset_x ?= v.parse(),
                 Some("offsety") => offset_y ?= v.parse(),
                 Some("parallaxx") => parallax_x ?= v.parse(),
@@ -109,9 +181,23 @@ impl LayerData {
                 Some("type") => user_type ?= v.parse(),
                 Some("class") => user_class ?= v.parse(),
             }
-            (opacity, tint_color, visible, offset_x, offset_y, parallax_x, parallax_y, name, id, user_type, user_class)
+            (opacity, tint_color, visible, locked, offset_x, offset_y, parallax_x, parallax_y, name, id, user_type, user_class)
         );
-        let name = name.map(ToOwned::to_owned);
+        let name = name.map(ToOwned::to_owned).unwrap_or_default();
+        let id = id.unwrap_or(0);
+
+        let settings = &parser.context.settings;
+        let filtered_out = settings
+            .layer_name_filter
+            .as_ref()
+            .is_some_and(|filter| !filter.matches(&name));
+        let contents_skipped = match tag {
+            LayerTag::Tiles => settings.skip_tile_layer_data || filtered_out,
+            LayerTag::Objects => filtered_out,
+            LayerTag::Image | LayerTag::Group => false,
+        };
+        parser.skip_layer_contents = contents_skipped;
+        parser.layer_stack.push((name.clone(), id));
 
         let (ty, properties) = match tag {
             LayerTag::Tiles => {
@@ -133,7 +219,8 @@ impl LayerData {
                 (LayerDataType::Objects(ty), properties)
             }
             LayerTag::Image => {
-                let (ty, properties) = ImageLayerData::new(parser, map_path).await?;
+                let (ty, properties) =
+                    ImageLayerData::new(parser, attrs, map_path, read_from).await?;
                 (LayerDataType::Image(ty), properties)
             }
             LayerTag::Group => {
@@ -152,22 +239,191 @@ impl LayerData {
                 (LayerDataType::Group(ty), properties)
             }
         };
+        parser.skip_layer_contents = false;
+        parser.layer_stack.pop();
 
         Ok(Self {
             visible: visible.unwrap_or(true),
+            locked: locked.unwrap_or(false),
             offset_x: offset_x.unwrap_or(0.0),
             offset_y: offset_y.unwrap_or(0.0),
             parallax_x: parallax_x.unwrap_or(1.0),
             parallax_y: parallax_y.unwrap_or(1.0),
             opacity: opacity.unwrap_or(1.0),
             tint_color,
-            name: name.unwrap_or_default().to_string(),
-            id: id.unwrap_or(0),
+            name,
+            id,
             user_type: user_type.or(user_class),
             properties,
             layer_type: ty,
+            contents_skipped,
+            parent_path: Vec::new(),
         })
     }
+
+    /// Parses the element that was just started as a layer of a kind the crate doesn't support if
+    /// it looks like a layer, having both an `id` and a `name` attribute, skipping its contents.
+    /// Returns [`None`] for any other element, which is left as is.
+    ///
+    /// Loading fails instead unless the parser's [`Strictness`] allows it.
+    pub(crate) async fn new_unknown<R: Reader>(
+        parser: &mut Parser<R>,
+        start: &BytesStart<'_>,
+    ) -> Result<Option<Self>> {
+        let attributes: HashMap<String, String> = collect_attributes(start)?
+            .into_iter()
+            .map(|attr| {
+                (
+                    String::from_utf8_lossy(attr.key.into_inner()).into_owned(),
+                    String::from_utf8_lossy(&attr.value).into_owned(),
+                )
+            })
+            .collect();
+        let name = match (attributes.get("name"), attributes.get("id")) {
+            (Some(name), Some(_)) => name.clone(),
+            _ => return Ok(None),
+        };
+        let tag_name = String::from_utf8_lossy(start.name().into_inner()).into_owned();
+        // Tiled skips layers it doesn't know about too, so they only make loading fail when
+        // explicitly asked to
+        match parser.context.settings.strictness {
+            Strictness::Strict => return Err(Error::UnknownLayerType { tag_name }),
+            Strictness::Error | Strictness::Warn => {
+                parser
+                    .context
+                    .state
+                    .warnings
+                    .push(ParseWarning::UnknownLayerType {
+                        tag_name: tag_name.clone(),
+                        layer_name: name.clone(),
+                    })
+            }
+            Strictness::Ignore => {}
+        }
+        parser.skip_element().await?;
+
+        // What the attributes of unknown layers mean isn't known for sure, so the ones shared by
+        // all layers are only used if they are valid
+        fn attr<T: FromStr>(attributes: &HashMap<String, String>, name: &str) -> Option<T> {
+            attributes.get(name)?.parse().ok()
+        }
+        let flag = |name| attr::<i32>(&attributes, name).map(|x| x == 1);
+        Ok(Some(Self {
+            visible: flag("visible").unwrap_or(true),
+            locked: flag("locked").unwrap_or(false),
+            offset_x: attr(&attributes, "offsetx").unwrap_or(0.0),
+            offset_y: attr(&attributes, "offsety").unwrap_or(0.0),
+            parallax_x: attr(&attributes, "parallaxx").unwrap_or(1.0),
+            parallax_y: attr(&attributes, "parallaxy").unwrap_or(1.0),
+            opacity: attr(&attributes, "opacity").unwrap_or(1.0),
+            tint_color: attr(&attributes, "tintcolor"),
+            name,
+            id: attr(&attributes, "id").unwrap_or(0),
+            user_type: attributes
+                .get("type")
+                .or_else(|| attributes.get("class"))
+                .cloned(),
+            properties: Properties::new(),
+            layer_type: LayerDataType::Unknown(UnknownLayerData {
+                tag_name,
+                attributes,
+            }),
+            contents_skipped: false,
+            parent_path: Vec::new(),
+        }))
+    }
+
+    /// Records the location of every object in `layers` by ID, as the path of indices to its
+    /// object layer followed by its index in that layer. Only the first object with a given ID is
+    /// recorded.
+    pub(crate) fn index_objects(
+        layers: &[LayerData],
+        path: &mut Vec<usize>,
+        index: &mut HashMap<u32, (Vec<usize>, usize)>,
+    ) {
+        for (layer_index, layer) in layers.iter().enumerate() {
+            path.push(layer_index);
+            match &layer.layer_type {
+                LayerDataType::Objects(objects) => {
+                    for (object_index, object) in objects.object_data().iter().enumerate() {
+                        if object.id() != 0 {
+                            index
+                                .entry(object.id())
+                                .or_insert_with(|| (path.clone(), object_index));
+                        }
+                    }
+                }
+                LayerDataType::Group(group) => Self::index_objects(&group.layers, path, index),
+                _ => {}
+            }
+            path.pop();
+        }
+    }
+
+    /// Records the position of each of the given layers' parents, recursively. Must be called once
+    /// all the layers of a map have been parsed, since layers don't know their own index while
+    /// they are being parsed.
+    pub(crate) fn set_parent_paths(layers: &mut [LayerData], parent_path: &[usize]) {
+        for (index, layer) in layers.iter_mut().enumerate() {
+            layer.parent_path = parent_path.to_vec();
+            if let LayerDataType::Group(group) = &mut layer.layer_type {
+                let mut path = parent_path.to_vec();
+                path.push(index);
+                Self::set_parent_paths(&mut group.layers, &path);
+                group.path = path;
+            }
+        }
+    }
+
+    /// Returns the layer found by following the given path of indices from the map's top-level
+    /// layers, going through group layers.
+    /// Adds a layer that starts at `position` to the layers parsed before it in the same parent,
+    /// reporting it if one of them has the same name. See
+    /// [`LoadSettings::duplicates`](crate::LoadSettings::duplicates).
+    pub(crate) fn push_sibling<R>(
+        parser: &mut Parser<R>,
+        siblings: &mut Vec<LayerData>,
+        layer: LayerData,
+        position: u64,
+    ) -> Result<()> {
+        if siblings.iter().any(|sibling| sibling.name == layer.name) {
+            parser.report_duplicate(DuplicateKind::LayerName, &layer.name, position)?;
+        }
+        siblings.push(layer);
+        Ok(())
+    }
+
+    /// Returns the indices leading to this layer from the top-level layers of `map`, or [`None`]
+    /// if it isn't one of its layers.
+    pub(crate) fn index_path(&self, map: &Map) -> Option<Vec<usize>> {
+        let index = if self.parent_path.is_empty() {
+            map.layers()
+                .position(|layer| std::ptr::eq(layer.data, self))?
+        } else {
+            match &LayerData::at_path(map, &self.parent_path)?.layer_type {
+                LayerDataType::Group(group) => group
+                    .layers
+                    .iter()
+                    .position(|layer| std::ptr::eq(layer, self))?,
+                _ => return None,
+            }
+        };
+        let mut path = self.parent_path.clone();
+        path.push(index);
+        Some(path)
+    }
+
+    pub(crate) fn at_path<'map>(map: &'map Map, path: &[usize]) -> Option<&'map LayerData> {
+        let (first, rest) = path.split_first()?;
+        let mut layer = map.get_layer(*first)?.data;
+        for &index in rest {
+            match &layer.layer_type {
+                LayerDataType::Group(group) => layer = group.layers.get(index)?,
+                _ => return None,
+            }
+        }
+        Some(layer)
+    }
 }
 
 map_wrapper!(
@@ -249,6 +505,237 @@ impl<'map> Layer<'map> {
             _ => None,
         }
     }
+
+    /// Returns the group layer that contains this layer, or [`None`] if it is a top-level layer of
+    /// its map.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// #
+    /// # fn main() {
+    /// # let map = Loader::new()
+    /// #     .load_tmx_map("assets/tiled_group_layers.tmx")
+    /// #     .unwrap();
+    /// #
+    /// let group = map.get_layer(1).unwrap();
+    /// let nested = group.as_group_layer().unwrap().get_layer(0).unwrap();
+    ///
+    /// assert_eq!(nested.parent().unwrap().name, group.name);
+    /// assert!(group.parent().is_none());
+    /// # }
+    /// ```
+    pub fn parent(&self) -> Option<Layer<'map>> {
+        LayerData::at_path(self.map, &self.data.parent_path).map(|data| Layer::new(self.map, data))
+    }
+
+    /// Returns an iterator over the group layers containing this layer, starting from its direct
+    /// parent and going up to the top-level layers of the map.
+    pub fn ancestors(&self) -> impl Iterator<Item = Layer<'map>> {
+        std::iter::successors(self.parent(), |layer| layer.parent())
+    }
+
+    /// Returns an iterator over this layer followed by all the layers nested inside it if it is a
+    /// group layer, depth-first in display order.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// # fn main() {
+    /// # let map = Loader::new()
+    /// #     .load_tmx_map("assets/tiled_group_layers.tmx")
+    /// #     .unwrap();
+    /// // Visits every layer of the map exactly once
+    /// let layer_count = map
+    ///     .layers()
+    ///     .flat_map(|layer| layer.descendants_and_self())
+    ///     .count();
+    ///
+    /// assert_eq!(layer_count, map.layers_recursive().count());
+    /// # }
+    /// ```
+    pub fn descendants_and_self(&self) -> impl Iterator<Item = Layer<'map>> {
+        LayersRecursive::new(self.map, std::slice::from_ref(self.data)).map(|(layer, _)| layer)
+    }
+
+    /// Reads the `object` property with the given name and returns the object it refers to, if
+    /// both exist. A property set to 0 is considered unset.
+    ///
+    /// See [`Map::get_object_by_id`].
+    pub fn resolve_object_property(&self, name: &str) -> Option<Object<'map>> {
+        resolve_object_property(self.map, &self.data.properties, name)
+    }
+
+    /// Computes the opacity this layer should be rendered with, which is its own opacity
+    /// multiplied by the opacity of all of its parent group layers.
+    pub fn effective_opacity(&self) -> f32 {
+        self.ancestors()
+            .fold(self.data.opacity, |opacity, layer| opacity * layer.opacity)
+    }
+
+    /// Returns whether this layer should be visible, which is only the case if both it and all of
+    /// its parent group layers are visible.
+    pub fn effective_visibility(&self) -> bool {
+        self.data.visible && self.ancestors().all(|layer| layer.visible)
+    }
+
+    /// Computes the tint color this layer should be rendered with, which is the channel-wise
+    /// product of its own tint color and the ones of all of its parent group layers.
+    ///
+    /// Shorthand for the [`tint_color`](EffectiveLayerAttributes::tint_color) of
+    /// [`Layer::effective_attributes()`].
+    pub fn effective_tint(&self) -> Option<Color> {
+        self.effective_attributes().tint_color
+    }
+
+    /// Computes the offset, parallax factor and tint color this layer should be rendered with,
+    /// taking all of its parent group layers into account.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// #
+    /// # fn main() {
+    /// # let map = Loader::new()
+    /// #     .load_tmx_map("assets/tiled_group_layers.tmx")
+    /// #     .unwrap();
+    /// #
+    /// let group = map.get_layer(1).unwrap().as_group_layer().unwrap();
+    /// let nested = group.get_layer(0).unwrap();
+    ///
+    /// let attributes = nested.effective_attributes();
+    /// println!(
+    ///     "Draw '{}' at offset ({}, {})",
+    ///     nested.name, attributes.offset_x, attributes.offset_y
+    /// );
+    /// # }
+    /// ```
+    pub fn effective_attributes(&self) -> EffectiveLayerAttributes {
+        match self.parent() {
+            Some(parent) => parent.effective_attributes().combine(self.data),
+            None => EffectiveLayerAttributes::new(self.data),
+        }
+    }
+}
+
+macro_rules! impl_try_from_layer {
+    ($($layer:ident => $as_layer:ident),* $(,)?) => {
+        $(
+            /// Downcasts a layer, failing with the layer itself if it is of another type.
+            impl<'map> TryFrom<&Layer<'map>> for $layer<'map> {
+                type Error = Layer<'map>;
+
+                #[inline]
+                fn try_from(layer: &Layer<'map>) -> std::result::Result<Self, Self::Error> {
+                    layer.$as_layer().ok_or(*layer)
+                }
+            }
+        )*
+    };
+}
+
+impl_try_from_layer!(
+    TileLayer => as_tile_layer,
+    ObjectLayer => as_object_layer,
+    ImageLayer => as_image_layer,
+    GroupLayer => as_group_layer,
+);
+
+/// An iterator that performs a depth-first traversal of the layers of a map, descending into group
+/// layers. Yields each layer in document order along with its depth, which is 0 for top-level
+/// layers.
+///
+/// Obtained through [`Map::layers_recursive()`].
+#[derive(Debug, Clone)]
+pub struct LayersRecursive<'map> {
+    map: &'map Map,
+    stack: Vec<std::slice::Iter<'map, LayerData>>,
+}
+
+impl<'map> LayersRecursive<'map> {
+    pub(crate) fn new(map: &'map Map, layers: &'map [LayerData]) -> Self {
+        Self {
+            map,
+            stack: vec![layers.iter()],
+        }
+    }
+}
+
+impl<'map> Iterator for LayersRecursive<'map> {
+    type Item = (Layer<'map>, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let depth = self.stack.len().checked_sub(1)?;
+            match self.stack[depth].next() {
+                Some(data) => {
+                    if let LayerDataType::Group(group) = &data.layer_type {
+                        self.stack.push(group.layers.iter());
+                    }
+                    return Some((Layer::new(self.map, data), depth));
+                }
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+/// The offset, parallax factor and tint color of a layer combined with those of all of its parent
+/// group layers, which is how Tiled renders nested layers.
+///
+/// Offsets are added together, while parallax factors and tint colors are multiplied.
+///
+/// Obtained through [`Layer::effective_attributes()`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct EffectiveLayerAttributes {
+    /// The combined x offset (in pixels).
+    pub offset_x: f32,
+    /// The combined y offset (in pixels).
+    pub offset_y: f32,
+    /// The combined x parallax factor.
+    pub parallax_x: f32,
+    /// The combined y parallax factor.
+    pub parallax_y: f32,
+    /// The combined tint color, if the layer or any of its parents has one.
+    pub tint_color: Option<Color>,
+}
+
+impl EffectiveLayerAttributes {
+    fn new(layer: &LayerData) -> Self {
+        Self {
+            offset_x: layer.offset_x,
+            offset_y: layer.offset_y,
+            parallax_x: layer.parallax_x,
+            parallax_y: layer.parallax_y,
+            tint_color: layer.tint_color,
+        }
+    }
+
+    fn combine(self, child: &LayerData) -> Self {
+        let tint_color = match (self.tint_color, child.tint_color) {
+            (Some(parent), Some(child)) => Some(multiply_colors(parent, child)),
+            (parent, child) => parent.or(child),
+        };
+        Self {
+            offset_x: self.offset_x + child.offset_x,
+            offset_y: self.offset_y + child.offset_y,
+            parallax_x: self.parallax_x * child.parallax_x,
+            parallax_y: self.parallax_y * child.parallax_y,
+            tint_color,
+        }
+    }
+}
+
+fn multiply_colors(a: Color, b: Color) -> Color {
+    let mul = |a: u8, b: u8| ((a as u32 * b as u32 + 127) / 255) as u8;
+    Color {
+        alpha: mul(a.alpha, b.alpha),
+        red: mul(a.red, b.red),
+        green: mul(a.green, b.green),
+        blue: mul(a.blue, b.blue),
+    }
 }
 
 /// Represents some kind of map layer.
@@ -262,6 +749,20 @@ pub enum LayerType<'map> {
     Image(ImageLayer<'map>),
     /// A group layer; Also see [`GroupLayer`].
     Group(GroupLayer<'map>),
+    /// A layer of a kind the crate doesn't support, such as one introduced by a newer version of
+    /// Tiled. It keeps its place among the other layers so that their indices don't change, but
+    /// only its attributes are loaded; Its contents are skipped.
+    ///
+    /// Such layers are warned about in [`Map::warnings`](crate::Map::warnings), and only make
+    /// loading fail with [`Strictness::Strict`](crate::Strictness::Strict).
+    /// The attributes shared by all layers, such as its name or visibility, are also available
+    /// on the [`Layer`] itself when they are valid.
+    Unknown {
+        /// The name of the layer's element, e.g. `voxellayer`.
+        tag_name: &'map str,
+        /// All of the attributes of the layer's element, by name.
+        attributes: &'map HashMap<String, String>,
+    },
 }
 
 impl<'map> LayerType<'map> {
@@ -271,6 +772,10 @@ impl<'map> LayerType<'map> {
             LayerDataType::Objects(data) => Self::Objects(ObjectLayer::new(map, data)),
             LayerDataType::Image(data) => Self::Image(ImageLayer::new(map, data)),
             LayerDataType::Group(data) => Self::Group(GroupLayer::new(map, data)),
+            LayerDataType::Unknown(data) => Self::Unknown {
+                tag_name: &data.tag_name,
+                attributes: &data.attributes,
+            },
         }
     }
 }
@@ -1,20 +1,77 @@
-use std::{collections::HashMap, path::Path, sync::Arc};
+use std::{cmp::Ordering, collections::HashMap, fmt, path::Path, str::FromStr, sync::Arc};
 
 use quick_xml::events::attributes::Attribute;
 
 use crate::{
     parse::xml::{Parser, ReadFrom, Reader},
     parse_properties,
+    stats::HeapSize,
     util::{get_attrs, map_wrapper, parse_tag},
-    Color, Error, MapTilesetGid, Object, ObjectData, Properties, ResourceCache, Result, Tileset,
+    Color, Error, MapTilesetGid, Object, ObjectAction, ObjectData, Properties, ResourceCache,
+    Result, Tileset,
 };
 
 /// Raw data referring to a map object layer or tile collision data.
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ObjectLayerData {
     objects: Vec<ObjectData>,
     /// The color used in the editor to display objects in this layer.
     pub colour: Option<Color>,
+    /// The order objects in this layer are drawn in.
+    pub draw_order: DrawOrder,
+}
+
+/// The order the objects of an [`ObjectLayer`] are drawn in. See
+/// [`ObjectLayer::objects_in_draw_order`].
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DrawOrder {
+    /// Objects are drawn sorted by their y coordinate, from top to bottom.
+    #[default]
+    TopDown,
+    /// Objects are drawn in the order they appear in the layer.
+    Index,
+}
+
+#[derive(Debug)]
+/// An error arising from trying to parse a [`DrawOrder`] that is not valid.
+pub struct DrawOrderError {
+    /// The invalid string found.
+    pub str_found: String,
+}
+
+impl fmt::Display for DrawOrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to parse draw order, valid options are `topdown` and `index` but got `{}` \
+            instead",
+            self.str_found
+        )
+    }
+}
+
+impl std::error::Error for DrawOrderError {}
+
+impl FromStr for DrawOrder {
+    type Err = DrawOrderError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "topdown" => Ok(DrawOrder::TopDown),
+            "index" => Ok(DrawOrder::Index),
+            _ => Err(DrawOrderError {
+                str_found: s.to_owned(),
+            }),
+        }
+    }
+}
+
+impl HeapSize for ObjectLayerData {
+    fn heap_size(&self) -> usize {
+        self.objects.heap_size()
+    }
 }
 
 impl ObjectLayerData {
@@ -30,26 +87,34 @@ impl ObjectLayerData {
         read_from: &mut impl ReadFrom,
         cache: &mut impl ResourceCache,
     ) -> Result<(ObjectLayerData, Properties)> {
-        let c = get_attrs!(
-            for v in attrs {
+        let (c, draw_order) = get_attrs!(
+            for v in attrs of "objectgroup" {
                 Some("color") => color ?= v.parse(),
+                Some("draworder") => draw_order ?= v.parse::<DrawOrder>(),
             }
-            color
+            (color, draw_order)
         );
         let mut objects = Vec::new();
         let mut properties = HashMap::new();
         let mut buffer = Vec::new();
         parse_tag!(parser => &mut buffer, "objectgroup", {
             "object" => for attrs {
-                objects.push(ObjectData::new(
-                    parser,
-                    attrs,
-                    tilesets,
-                    for_tileset.as_ref().cloned(),
-                    path_relative_to,
-                    read_from,
-                    cache
-                ).await?);
+                if parser.skip_layer_contents {
+                    parser.skip_element().await?;
+                } else {
+                    let object = ObjectData::new(
+                        parser,
+                        attrs,
+                        tilesets,
+                        for_tileset.as_ref().cloned(),
+                        path_relative_to,
+                        read_from,
+                        cache
+                    ).await?;
+                    if parser.on_object(&object) == ObjectAction::Keep {
+                        objects.push(object);
+                    }
+                }
                 Ok(())
             },
             "properties" => {
@@ -57,7 +122,26 @@ impl ObjectLayerData {
                 Ok(())
             },
         });
-        Ok((ObjectLayerData { objects, colour: c }, properties))
+        Ok((
+            ObjectLayerData {
+                objects,
+                colour: c,
+                draw_order: draw_order.unwrap_or_default(),
+            },
+            properties,
+        ))
+    }
+
+    /// Creates an object layer out of objects built programmatically, such as through
+    /// [`ObjectData::builder()`].
+    ///
+    /// The layer uses the default [`DrawOrder`], which can be changed afterwards.
+    pub fn from_objects(objects: Vec<ObjectData>, colour: Option<Color>) -> Self {
+        Self {
+            objects,
+            colour,
+            draw_order: DrawOrder::default(),
+        }
     }
 
     /// Returns the data belonging to the objects contained within the layer, in the order they were
@@ -81,6 +165,14 @@ impl<'map> ObjectLayer<'map> {
             .map(|data| Object::new(self.map, data))
     }
 
+    /// Returns the first object in this layer with the specified ID, if it exists.
+    ///
+    /// Object IDs are unique within a map when saved by Tiled, but hand-edited files may contain
+    /// duplicates, in which case the object declared first is returned.
+    pub fn get_object_by_id(&self, id: u32) -> Option<Object<'map>> {
+        self.objects().find(|object| object.id() == id)
+    }
+
     /// Returns an iterator over the objects present in this layer, in the order they were declared
     /// in in the TMX file.
     ///
@@ -95,13 +187,9 @@ impl<'map> ObjectLayer<'map> {
     /// #     .unwrap();
     /// #
     /// let spawnpoints: Vec<Object> = map
-    ///     .layers()
-    ///     .filter_map(|layer| match layer.layer_type() {
-    ///         tiled::LayerType::Objects(layer) => Some(layer),
-    ///         _ => None,
-    ///     })
+    ///     .object_layers_recursive()
     ///     .flat_map(|layer| layer.objects())
-    ///     .filter(|object| object.user_type == "spawn")
+    ///     .filter(|object| &*object.user_type == "spawn")
     ///     .collect();
     ///
     /// dbg!(spawnpoints);
@@ -115,4 +203,22 @@ impl<'map> ObjectLayer<'map> {
             .iter()
             .map(move |object| Object::new(map, object))
     }
+
+    /// Returns an iterator over the objects present in this layer, in the order they should be
+    /// drawn in according to the layer's [`DrawOrder`].
+    ///
+    /// With [`DrawOrder::TopDown`], objects are sorted by their y coordinate, objects with the
+    /// same one being kept in the order they were declared in. As in Tiled, that coordinate is
+    /// the bottom edge of tile objects and the top edge of other objects. With
+    /// [`DrawOrder::Index`], objects are returned in the order they were declared in.
+    pub fn objects_in_draw_order(&self) -> impl ExactSizeIterator<Item = Object<'map>> + 'map {
+        let map: &'map crate::Map = self.map;
+        let mut objects: Vec<&'map ObjectData> = self.data.objects.iter().collect();
+        if self.data.draw_order == DrawOrder::TopDown {
+            objects.sort_by(|a, b| a.y.partial_cmp(&b.y).unwrap_or(Ordering::Equal));
+        }
+        objects
+            .into_iter()
+            .map(move |object| Object::new(map, object))
+    }
 }
@@ -1,31 +1,49 @@
+use std::fmt;
+
 use quick_xml::events::attributes::Attribute;
 
 use crate::{
     parse::xml::{Parser, Reader},
+    stats::HeapSize,
     util::{get_attrs, map_wrapper},
-    LayerTile, LayerTileData, MapTilesetGid, Result,
+    Error, LayerTile, LayerTileData, MapTilesetGid, Result, TileBounds,
 };
 
-use super::util::parse_data_line;
+use super::{
+    infinite::InternalChunk,
+    util::{
+        check_data_size, parse_layer_data, tile_count, DebugWith, LayerDataContents, TilesSummary,
+    },
+};
 
 /// The raw data of a [`FiniteTileLayer`]. Does not include a reference to its parent [`Map`](crate::Map).
 #[derive(PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FiniteTileLayerData {
     width: u32,
     height: u32,
-    /// The tiles are arranged in rows.
+    /// The tiles are arranged in rows. Empty if the layer data was skipped while loading.
     tiles: Vec<Option<LayerTileData>>,
 }
 
-impl std::fmt::Debug for FiniteTileLayerData {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+/// Summarizes the tiles as their count and the share of them that isn't empty; Use
+/// [`FiniteTileLayerData::debug_full`] to print every tile.
+impl fmt::Debug for FiniteTileLayerData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("FiniteTileLayerData")
             .field("width", &self.width)
             .field("height", &self.height)
+            .field("tiles", &TilesSummary::new(&self.tiles))
             .finish()
     }
 }
 
+impl HeapSize for FiniteTileLayerData {
+    fn heap_size(&self) -> usize {
+        self.tiles.heap_size()
+    }
+}
+
 impl FiniteTileLayerData {
     /// Get the tile layer's width in tiles.
     #[inline]
@@ -39,6 +57,18 @@ impl FiniteTileLayerData {
         self.height
     }
 
+    /// Returns a value whose [`Debug`](fmt::Debug) output includes every tile of this layer,
+    /// unlike the one of the layer itself, which only summarizes them.
+    pub fn debug_full(&self) -> impl fmt::Debug + '_ {
+        DebugWith(move |f: &mut fmt::Formatter<'_>| {
+            f.debug_struct("FiniteTileLayerData")
+                .field("width", &self.width)
+                .field("height", &self.height)
+                .field("tiles", &self.tiles)
+                .finish()
+        })
+    }
+
     pub(crate) async fn new<R: Reader>(
         parser: &mut Parser<R>,
         attrs: Vec<Attribute<'_>>,
@@ -47,14 +77,30 @@ impl FiniteTileLayerData {
         tilesets: &[MapTilesetGid],
     ) -> Result<Self> {
         let (e, c) = get_attrs!(
-            for v in attrs {
+            for v in attrs of "data" {
                 Some("encoding") => encoding = v,
                 Some("compression") => compression = v,
             }
             (encoding, compression)
         );
 
-        let tiles = parse_data_line(e, c, parser, tilesets).await?;
+        let tile_count = tile_count(width, height)?;
+        let tiles = match parse_layer_data(e, c, parser, tilesets, tile_count).await? {
+            LayerDataContents::Tiles(tiles) => tiles,
+            // Finite layers aren't meant to be split into chunks, but their tiles are kept as long
+            // as they are within the layer
+            LayerDataContents::Chunks(chunks) => {
+                check_data_size(
+                    tile_count,
+                    parser.context.settings.max_decompressed_layer_bytes,
+                )?;
+                let mut tiles = vec![None; tile_count];
+                for chunk in chunks {
+                    Self::place_chunk(&mut tiles, width, height, &chunk)?;
+                }
+                tiles
+            }
+        };
 
         Ok(Self {
             width,
@@ -63,6 +109,45 @@ impl FiniteTileLayerData {
         })
     }
 
+    /// Copies the tiles of a chunk into the tiles of a layer of the given size. The parts of the
+    /// chunk outside of the layer may only have empty tiles.
+    fn place_chunk(
+        tiles: &mut [Option<LayerTileData>],
+        width: u32,
+        height: u32,
+        chunk: &InternalChunk,
+    ) -> Result<()> {
+        for chunk_y in 0..chunk.height {
+            for chunk_x in 0..chunk.width {
+                let tile = *chunk
+                    .tiles
+                    .get(chunk_x as usize + chunk_y as usize * chunk.width as usize)
+                    .ok_or(Error::InvalidTileFound)?;
+                let x = i64::from(chunk.x) + i64::from(chunk_x);
+                let y = i64::from(chunk.y) + i64::from(chunk_y);
+                if (0..i64::from(width)).contains(&x) && (0..i64::from(height)).contains(&y) {
+                    tiles[x as usize + y as usize * width as usize] = tile;
+                } else if tile.is_some() {
+                    return Err(Error::ChunkOutOfBounds {
+                        chunk: chunk.bounds(),
+                        width,
+                        height,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates a layer of the given size with no tiles in it, used when its data is skipped.
+    pub(crate) fn empty(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            tiles: Vec::new(),
+        }
+    }
+
     /// Obtains the tile data present at the position given.
     ///
     /// If the position given is invalid or the position is empty, this function will return [`None`].
@@ -70,7 +155,9 @@ impl FiniteTileLayerData {
     /// If you want to get a [`Tile`](`crate::Tile`) instead, use [`FiniteTileLayer::get_tile()`].
     pub fn get_tile_data(&self, x: i32, y: i32) -> Option<&LayerTileData> {
         if x < self.width as i32 && y < self.height as i32 && x >= 0 && y >= 0 {
-            self.tiles[x as usize + y as usize * self.width as usize].as_ref()
+            self.tiles
+                .get(x as usize + y as usize * self.width as usize)?
+                .as_ref()
         } else {
             None
         }
@@ -91,4 +178,94 @@ impl<'map> FiniteTileLayer<'map> {
             .get_tile_data(x, y)
             .map(|data| LayerTile::new(self.map(), data))
     }
+
+    /// Returns an iterator over the rows of this layer from top to bottom, each of them being an
+    /// iterator over its tiles from left to right. Empty tiles are included as [`None`], so every
+    /// row yields exactly [`width`](FiniteTileLayerData::width) items.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// # let map = Loader::new().load_tmx_map("assets/tiled_csv.tmx").unwrap();
+    /// # let layer = match map.get_layer(0).unwrap().as_tile_layer().unwrap() {
+    /// #     tiled::TileLayer::Finite(layer) => layer,
+    /// #     _ => unreachable!(),
+    /// # };
+    /// for (y, row) in layer.rows().enumerate() {
+    ///     for (x, tile) in row.enumerate() {
+    ///         if let Some(tile) = tile {
+    ///             println!("({}, {}): {}", x, y, tile.id());
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn rows(
+        &self,
+    ) -> impl ExactSizeIterator<Item = impl ExactSizeIterator<Item = Option<LayerTile<'map>>> + 'map>
+           + 'map {
+        let layer = *self;
+        (0..self.data.height as i32)
+            .map(move |y| (0..layer.data.width as i32).map(move |x| layer.get_tile(x, y)))
+    }
+
+    /// Returns an iterator over the non-empty tiles of this layer along with their position, in
+    /// row-major order.
+    pub fn tiles(&self) -> impl Iterator<Item = ((i32, i32), LayerTile<'map>)> + 'map {
+        let map: &'map crate::Map = self.map;
+        let width = self.data.width as usize;
+        // Layers without any width have no positions to put tiles at
+        let tiles = if width == 0 {
+            &[][..]
+        } else {
+            &self.data.tiles[..]
+        };
+        tiles.iter().enumerate().filter_map(move |(index, tile)| {
+            let pos = ((index % width) as i32, (index / width) as i32);
+            tile.as_ref().map(|data| (pos, LayerTile::new(map, data)))
+        })
+    }
+
+    /// Returns the GIDs of this layer's tiles along with their flipping bits, in row-major order
+    /// and exactly as they are stored in map files. Empty tiles are 0.
+    ///
+    /// Tiles whose GID doesn't belong to any of the map's tilesets aren't kept when loading, so
+    /// they are 0 as well. The same goes for every tile of layers whose
+    /// [contents were skipped](crate::LayerData::contents_skipped).
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// # let map = Loader::new().load_tmx_map("assets/tiled_flipped_all.tmx").unwrap();
+    /// # let layer = match map.get_layer(0).unwrap().as_tile_layer().unwrap() {
+    /// #     tiled::TileLayer::Finite(layer) => layer,
+    /// #     _ => unreachable!(),
+    /// # };
+    /// // The second tile is the same as the first one, but flipped horizontally
+    /// let gids = layer.raw_gid_data();
+    /// assert_eq!(gids[1], gids[0] | 0x80000000);
+    /// ```
+    pub fn raw_gid_data(&self) -> Vec<u32> {
+        let tile_count = self.data.width as usize * self.data.height as usize;
+        let first_gids = self.map.first_gids();
+        (0..tile_count)
+            .map(|index| match self.data.tiles.get(index) {
+                Some(Some(tile)) => tile.to_bits_with_first_gid(first_gids[tile.tileset_index()]),
+                _ => 0,
+            })
+            .collect()
+    }
+
+    /// Returns the smallest rectangle containing all of the non-empty tiles of this layer, or
+    /// [`None`] if it has none.
+    pub fn bounds(&self) -> Option<TileBounds> {
+        let width = self.data.width as usize;
+        TileBounds::enclosing(
+            self.data
+                .tiles
+                .iter()
+                .enumerate()
+                .filter(|(_, tile)| tile.is_some())
+                .map(|(index, _)| ((index % width) as i32, (index / width) as i32)),
+        )
+    }
 }
@@ -1,24 +1,54 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
 
 use quick_xml::events::attributes::Attribute;
 
 use crate::{
+    floor_div, floor_mod,
     parse::xml::{Parser, Reader},
-    util::{floor_div, get_attrs, map_wrapper, parse_tag},
-    Error, LayerTile, LayerTileData, MapTilesetGid, Result,
+    stats::HeapSize,
+    util::{get_attrs, map_wrapper},
+    Error, LayerTile, LayerTileData, MapTilesetGid, Result, TileBounds,
 };
 
-use super::util::parse_data_line;
+use super::util::{
+    parse_data_line, parse_layer_data, tile_count, DebugWith, LayerDataContents, TilesSummary,
+};
 
 /// The raw data of a [`InfiniteTileLayer`]. Does not include a reference to its parent [`Map`](crate::Map).
-#[derive(PartialEq, Clone)]
+///
+/// Tiles are stored sparsely in fixed-size [`ChunkData`]s indexed by their chunk position, so only
+/// the parts of the map that contain tiles are allocated, regardless of how far apart they are.
+/// The chunks of the file the layer was loaded from may have any size, which is kept in
+/// [`InfiniteTileLayerData::file_chunks`].
+#[derive(PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InfiniteTileLayerData {
+    #[cfg_attr(feature = "serde", serde(with = "serde_chunks"))]
     chunks: HashMap<(i32, i32), ChunkData>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    file_chunks: Vec<TileBounds>,
 }
 
-impl std::fmt::Debug for InfiniteTileLayerData {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("InfiniteTileLayerData").finish()
+/// Summarizes the tiles of all chunks as their count and the share of them that isn't empty; Use
+/// [`InfiniteTileLayerData::debug_full`] to print every chunk.
+impl fmt::Debug for InfiniteTileLayerData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InfiniteTileLayerData")
+            .field("chunks", &self.chunks.len())
+            .field(
+                "tiles",
+                &TilesSummary::new(self.chunks.values().flat_map(|chunk| chunk.tiles.iter())),
+            )
+            .finish()
+    }
+}
+
+impl HeapSize for InfiniteTileLayerData {
+    fn heap_size(&self) -> usize {
+        self.chunks.heap_size() + self.file_chunks.heap_size()
     }
 }
 
@@ -26,41 +56,61 @@ impl InfiniteTileLayerData {
     pub(crate) async fn new<R: Reader>(
         parser: &mut Parser<R>,
         attrs: Vec<Attribute<'_>>,
+        width: u32,
+        height: u32,
         tilesets: &[MapTilesetGid],
     ) -> Result<Self> {
         let (e, c) = get_attrs!(
-            for v in attrs {
+            for v in attrs of "data" {
                 Some("encoding") => encoding = v,
                 Some("compression") => compression = v,
             }
             (encoding, compression)
         );
 
+        let tile_count = tile_count(width, height)?;
+        let file_chunks = match parse_layer_data(e, c, parser, tilesets, tile_count).await? {
+            LayerDataContents::Chunks(chunks) => chunks,
+            LayerDataContents::Tiles(tiles) if tiles.is_empty() => Vec::new(),
+            // Infinite layers are meant to be split into chunks, but tiles stored in the layer
+            // data itself are kept as a single chunk of the layer's size
+            LayerDataContents::Tiles(tiles) => vec![InternalChunk {
+                x: 0,
+                y: 0,
+                width,
+                height,
+                tiles,
+            }],
+        };
+
         let mut chunks = HashMap::<(i32, i32), ChunkData>::new();
-        let mut buffer = Vec::new();
-        parse_tag!(parser => &mut buffer, "data", {
-            "chunk" => for attrs {
-                let chunk = InternalChunk::new(parser, attrs, e, c, tilesets).await?;
-                for x in chunk.x..chunk.x + chunk.width as i32 {
-                    for y in chunk.y..chunk.y + chunk.height as i32 {
-                        let chunk_pos = ChunkData::tile_to_chunk_pos(x, y);
-                        let relative_pos = (x - chunk_pos.0 * ChunkData::WIDTH as i32, y - chunk_pos.1 * ChunkData::HEIGHT as i32);
-                        let chunk_index = (relative_pos.0 + relative_pos.1 * ChunkData::WIDTH as i32) as usize;
-                        let internal_pos = (x - chunk.x, y - chunk.y);
-                        let internal_index = (internal_pos.0 + internal_pos.1 * chunk.width as i32) as usize;
-
-                        if internal_index >= chunk.tiles.len() {
-                            return Err(Error::InvalidTileFound);
-                        }
-
-                        chunks.entry(chunk_pos).or_insert_with(ChunkData::new).tiles[chunk_index] = chunk.tiles[internal_index];
+        for chunk in &file_chunks {
+            for x in chunk.x..chunk.x + chunk.width as i32 {
+                for y in chunk.y..chunk.y + chunk.height as i32 {
+                    let chunk_pos = ChunkData::tile_to_chunk_pos(x, y);
+                    let chunk_index = ChunkData::tile_index_in_chunk(x, y);
+                    let internal_pos = (x - chunk.x, y - chunk.y);
+                    let internal_index =
+                        (internal_pos.0 + internal_pos.1 * chunk.width as i32) as usize;
+
+                    if internal_index >= chunk.tiles.len() {
+                        return Err(Error::InvalidTileFound);
+                    }
+
+                    // Only allocate chunks that actually contain tiles, so that sparse maps stay cheap
+                    if let Some(tile) = chunk.tiles[internal_index] {
+                        chunks.entry(chunk_pos).or_insert_with(ChunkData::new).tiles[chunk_index] =
+                            Some(tile);
                     }
                 }
-                Ok(())
             }
-        });
+        }
+        let file_chunks = file_chunks.iter().map(InternalChunk::bounds).collect();
 
-        Ok(Self { chunks })
+        Ok(Self {
+            chunks,
+            file_chunks,
+        })
     }
 
     /// Obtains the tile data present at the position given.
@@ -73,18 +123,14 @@ impl InfiniteTileLayerData {
         self.chunks
             .get(&chunk_pos)
             .and_then(|chunk| {
-                let relative_pos = (
-                    x - chunk_pos.0 * ChunkData::WIDTH as i32,
-                    y - chunk_pos.1 * ChunkData::HEIGHT as i32,
-                );
-                let chunk_index =
-                    (relative_pos.0 + relative_pos.1 * ChunkData::WIDTH as i32) as usize;
+                let chunk_index = ChunkData::tile_index_in_chunk(x, y);
                 chunk.tiles.get(chunk_index).map(Option::as_ref)
             })
             .flatten()
     }
 
-    /// Returns an iterator over only the data part of the chunks of this tile layer.
+    /// Returns an iterator over only the data part of the chunks of this tile layer. Chunks that
+    /// don't contain any tiles are not stored and as such won't be returned.
     ///
     /// In 99.99% of cases you'll want to use [`InfiniteTileLayer::chunks()`] instead; Using this method is only
     /// needed if you *only* require the tile data of the chunks (and no other utilities provided by
@@ -106,17 +152,64 @@ impl InfiniteTileLayerData {
     pub fn get_chunk_data(&self, x: i32, y: i32) -> Option<&ChunkData> {
         self.chunks.get(&(x, y))
     }
+
+    /// Returns the rectangles covered by the chunks of the file this layer was loaded from, in the
+    /// order they appear in it.
+    ///
+    /// Each chunk has the size given by its own `width` and `height` attributes, which usually
+    /// matches the chunk size in the map's [editor settings](crate::Map::editor_settings) but
+    /// doesn't have to, nor does it have to be the same for every chunk. Their tiles are stored in
+    /// [`ChunkData`]s of a fixed size regardless.
+    #[inline]
+    pub fn file_chunks(&self) -> &[TileBounds] {
+        &self.file_chunks
+    }
+
+    /// Returns a value whose [`Debug`](fmt::Debug) output includes every tile of this layer,
+    /// unlike the one of the layer itself, which only summarizes them. Chunks are sorted by
+    /// position.
+    pub fn debug_full(&self) -> impl fmt::Debug + '_ {
+        DebugWith(move |f: &mut fmt::Formatter<'_>| {
+            let chunks: BTreeMap<_, _> = self
+                .chunks
+                .iter()
+                .map(|(pos, chunk)| (pos, chunk.debug_full()))
+                .collect();
+            f.debug_struct("InfiniteTileLayerData")
+                .field("chunks", &chunks)
+                .field("file_chunks", &self.file_chunks)
+                .finish()
+        })
+    }
 }
 
 /// Part of an infinite tile layer's data.
 ///
 /// Has only the tile data contained within and not a reference to the map it is part of.
 /// In 99.99% of cases you'll actually want to use [`Chunk`].
-#[derive(Debug, PartialEq, Clone)]
+#[derive(PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ChunkData {
+    #[cfg_attr(feature = "serde", serde(with = "serde_chunk_tiles"))]
     tiles: Box<[Option<LayerTileData>; Self::TILE_COUNT]>,
 }
 
+/// Summarizes the tiles like [`FiniteTileLayerData`](crate::FiniteTileLayerData) does; Use
+/// [`ChunkData::debug_full`] to print every tile.
+impl fmt::Debug for ChunkData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChunkData")
+            .field("tiles", &TilesSummary::new(self.tiles.iter()))
+            .finish()
+    }
+}
+
+impl HeapSize for ChunkData {
+    fn heap_size(&self) -> usize {
+        std::mem::size_of_val(&*self.tiles)
+    }
+}
+
 impl ChunkData {
     /// Infinite layer chunk width. This constant might change between versions, not counting as a
     /// breaking change.
@@ -147,6 +240,16 @@ impl ChunkData {
         }
     }
 
+    /// Returns a value whose [`Debug`](fmt::Debug) output includes every tile of this chunk,
+    /// unlike the one of the chunk itself, which only summarizes them.
+    pub fn debug_full(&self) -> impl fmt::Debug + '_ {
+        DebugWith(move |f: &mut fmt::Formatter<'_>| {
+            f.debug_struct("ChunkData")
+                .field("tiles", &&self.tiles[..])
+                .finish()
+        })
+    }
+
     /// Returns the position of the chunk that contains the given tile position.
     pub fn tile_to_chunk_pos(x: i32, y: i32) -> (i32, i32) {
         (
@@ -154,6 +257,70 @@ impl ChunkData {
             floor_div(y, ChunkData::HEIGHT as i32),
         )
     }
+
+    /// Returns the index in the tiles of a chunk of the given tile position, within the chunk
+    /// given by [`ChunkData::tile_to_chunk_pos`].
+    fn tile_index_in_chunk(x: i32, y: i32) -> usize {
+        let x = floor_mod(x, ChunkData::WIDTH as i32) as usize;
+        let y = floor_mod(y, ChunkData::HEIGHT as i32) as usize;
+        x + y * ChunkData::WIDTH as usize
+    }
+}
+
+/// Stores chunks as a list of position-chunk pairs, since formats such as JSON only allow strings
+/// as map keys.
+#[cfg(feature = "serde")]
+mod serde_chunks {
+    use std::collections::HashMap;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::ChunkData;
+
+    pub(super) fn serialize<S: Serializer>(
+        chunks: &HashMap<(i32, i32), ChunkData>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(chunks)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<(i32, i32), ChunkData>, D::Error> {
+        Vec::<((i32, i32), ChunkData)>::deserialize(deserializer)
+            .map(|chunks| chunks.into_iter().collect())
+    }
+}
+
+/// Stores chunk tiles as a sequence, since serde doesn't support arrays of this size.
+#[cfg(feature = "serde")]
+mod serde_chunk_tiles {
+    use std::convert::TryInto;
+
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use super::ChunkData;
+    use crate::LayerTileData;
+
+    type Tiles = Box<[Option<LayerTileData>; ChunkData::TILE_COUNT]>;
+
+    pub(super) fn serialize<S: Serializer>(
+        tiles: &Tiles,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(tiles.iter())
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Tiles, D::Error> {
+        let tiles = Vec::<Option<LayerTileData>>::deserialize(deserializer)?;
+        let len = tiles.len();
+        tiles
+            .into_boxed_slice()
+            .try_into()
+            .map_err(|_| D::Error::invalid_length(len, &"a chunk's tile count"))
+    }
 }
 
 map_wrapper!(
@@ -170,19 +337,38 @@ impl<'map> Chunk<'map> {
             .get_tile_data(x, y)
             .map(|data| LayerTile::new(self.map(), data))
     }
+
+    /// Returns the GIDs of this chunk's tiles along with their flipping bits, in row-major order
+    /// and exactly as they are stored in map files. Empty tiles are 0.
+    ///
+    /// Chunks are always [`ChunkData::WIDTH`] by [`ChunkData::HEIGHT`] tiles, regardless of the
+    /// chunk size the map was saved with. Tiles whose GID doesn't belong to any of the map's
+    /// tilesets aren't kept when loading, so they are 0 as well.
+    pub fn raw_gid_data(&self) -> Vec<u32> {
+        let first_gids = self.map.first_gids();
+        self.data
+            .tiles
+            .iter()
+            .map(|tile| {
+                tile.as_ref().map_or(0, |tile| {
+                    tile.to_bits_with_first_gid(first_gids[tile.tileset_index()])
+                })
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
-struct InternalChunk {
+pub(crate) struct InternalChunk {
     /// The X coordinate of the top-left-most tile in the chunk.
     /// Corresponds to the `x` attribute in the TMX format.
-    x: i32,
+    pub(super) x: i32,
     /// The Y coordinate of the top-left-most tile in the chunk.
     /// Corresponds to the `y` attribute in the TMX format.
-    y: i32,
-    width: u32,
-    height: u32,
-    tiles: Vec<Option<LayerTileData>>,
+    pub(super) y: i32,
+    pub(super) width: u32,
+    pub(super) height: u32,
+    pub(super) tiles: Vec<Option<LayerTileData>>,
 }
 
 impl InternalChunk {
@@ -194,7 +380,7 @@ impl InternalChunk {
         tilesets: &[MapTilesetGid],
     ) -> Result<Self> {
         let (x, y, width, height) = get_attrs!(
-            for v in attrs {
+            for v in attrs of "chunk" {
                 "x" => x ?= v.parse::<i32>(),
                 "y" => y ?= v.parse::<i32>(),
                 "width" => width ?= v.parse::<u32>(),
@@ -203,7 +389,14 @@ impl InternalChunk {
             (x, y, width, height)
         );
 
-        let tiles = parse_data_line(encoding, compression, parser, tilesets).await?;
+        let tiles = parse_data_line(
+            encoding,
+            compression,
+            parser,
+            tilesets,
+            tile_count(width, height)?,
+        )
+        .await?;
 
         Ok(InternalChunk {
             x,
@@ -213,8 +406,21 @@ impl InternalChunk {
             tiles,
         })
     }
+
+    /// Returns the rectangle covered by the chunk.
+    pub(super) fn bounds(&self) -> TileBounds {
+        TileBounds {
+            x: self.x,
+            y: self.y,
+            width: self.width,
+            height: self.height,
+        }
+    }
 }
 
+/// A chunk returned by [`InfiniteTileLayer::normalize_chunks`]: Its position and its tiles.
+type NormalizedChunk<'map> = ((i32, i32), Vec<Option<LayerTile<'map>>>);
+
 map_wrapper!(
     #[doc = "A [`TileLayer`](super::TileLayer) with no bounds, internally stored using [`Chunk`]s."]
     InfiniteTileLayer => InfiniteTileLayerData
@@ -236,7 +442,8 @@ impl<'map> InfiniteTileLayer<'map> {
     /// implementation defined (see [`ChunkData::WIDTH`], [`ChunkData::HEIGHT`]).
     ///
     /// The iterator item contains the position of the chunk in chunk coordinates along with a
-    /// reference to the actual chunk at that position.
+    /// reference to the actual chunk at that position. Chunks that don't contain any tiles are
+    /// not stored and as such won't be returned.
     ///
     /// This iterator doesn't have any particular order.
     ///
@@ -286,4 +493,110 @@ impl<'map> InfiniteTileLayer<'map> {
             .get_chunk_data(x, y)
             .map(move |data| Chunk::new(map, data))
     }
+
+    /// Returns an iterator over the non-empty tiles of this layer along with their position, in
+    /// row-major order.
+    ///
+    /// Unlike [`InfiniteTileLayer::chunks`], the order is stable, so this is the preferred way of
+    /// going over the tiles of a layer when they need to be processed in a specific order.
+    pub fn tiles(&self) -> impl Iterator<Item = ((i32, i32), LayerTile<'map>)> + 'map {
+        let map: &'map crate::Map = self.map;
+        let mut chunks: Vec<((i32, i32), &'map ChunkData)> = self.data.chunk_data().collect();
+        chunks.sort_by_key(|((x, y), _)| (*y, *x));
+        // Group the chunks by row, so that each row of tiles can go through all of them in order
+        let mut chunk_rows: Vec<(i32, Vec<(i32, &'map ChunkData)>)> = Vec::new();
+        for ((x, y), chunk) in chunks {
+            match chunk_rows.last_mut() {
+                Some((row_y, row)) if *row_y == y => row.push((x, chunk)),
+                _ => chunk_rows.push((y, vec![(x, chunk)])),
+            }
+        }
+
+        chunk_rows.into_iter().flat_map(move |(chunk_y, row)| {
+            (0..ChunkData::HEIGHT as i32).flat_map(move |y| {
+                row.clone().into_iter().flat_map(move |(chunk_x, chunk)| {
+                    (0..ChunkData::WIDTH as i32).filter_map(move |x| {
+                        chunk.get_tile_data(x, y).map(|data| {
+                            let pos = (
+                                chunk_x * ChunkData::WIDTH as i32 + x,
+                                chunk_y * ChunkData::HEIGHT as i32 + y,
+                            );
+                            (pos, LayerTile::new(map, data))
+                        })
+                    })
+                })
+            })
+        })
+    }
+
+    /// Regroups the tiles of this layer into chunks of `width` by `height` tiles, for engines that
+    /// require a specific chunk size. Chunk `(x, y)` starts at tile `(x * width, y * height)`.
+    ///
+    /// Each chunk is returned along with its position in chunk coordinates and its tiles in
+    /// row-major order, empty ones included. Chunks without any tiles are left out, and the rest
+    /// are sorted by row and then by column.
+    ///
+    /// ## Panics
+    /// Panics if `width` or `height` is 0.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = tiled::Loader::new().load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")?;
+    /// let layer = match map.get_layer(0).unwrap().as_tile_layer().unwrap() {
+    ///     tiled::TileLayer::Infinite(layer) => layer,
+    ///     _ => unreachable!(),
+    /// };
+    ///
+    /// for ((chunk_x, chunk_y), tiles) in layer.normalize_chunks(32, 32) {
+    ///     assert_eq!(tiles.len(), 32 * 32);
+    ///     // Upload the chunk...
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn normalize_chunks(&self, width: u32, height: u32) -> Vec<NormalizedChunk<'map>> {
+        assert!(width > 0 && height > 0, "chunks can't be empty");
+        let tile_count = width as usize * height as usize;
+        // Keyed by row first, so that the chunks come out sorted
+        let mut chunks = BTreeMap::<(i32, i32), Vec<Option<LayerTile<'map>>>>::new();
+        for ((x, y), tile) in self.tiles() {
+            let chunk_x = floor_div(x, width as i32);
+            let chunk_y = floor_div(y, height as i32);
+            let index = floor_mod(x, width as i32) as usize
+                + floor_mod(y, height as i32) as usize * width as usize;
+            chunks
+                .entry((chunk_y, chunk_x))
+                .or_insert_with(|| vec![None; tile_count])[index] = Some(tile);
+        }
+        chunks
+            .into_iter()
+            .map(|((chunk_y, chunk_x), tiles)| ((chunk_x, chunk_y), tiles))
+            .collect()
+    }
+
+    /// Returns the smallest rectangle containing all of the non-empty tiles of this layer, or
+    /// [`None`] if it has none.
+    pub fn bounds(&self) -> Option<TileBounds> {
+        TileBounds::enclosing(
+            self.data
+                .chunks
+                .iter()
+                .flat_map(|((chunk_x, chunk_y), chunk)| {
+                    chunk
+                        .tiles
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, tile)| tile.is_some())
+                        .map(move |(index, _)| {
+                            (
+                                chunk_x * ChunkData::WIDTH as i32
+                                    + (index % ChunkData::WIDTH as usize) as i32,
+                                chunk_y * ChunkData::HEIGHT as i32
+                                    + (index / ChunkData::WIDTH as usize) as i32,
+                            )
+                        })
+                }),
+        )
+    }
 }
@@ -1,12 +1,13 @@
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::HashMap};
 
 use quick_xml::events::attributes::Attribute;
 
 use crate::{
     parse::xml::{Parser, Reader},
     parse_properties,
+    stats::HeapSize,
     util::{get_attrs, map_wrapper, parse_tag},
-    Error, Gid, Map, MapTilesetGid, Properties, Result, Tile, TileId, Tileset,
+    Error, Gid, Map, MapTilesetGid, Orientation, Properties, Result, Tile, TileId, Tileset,
 };
 
 mod finite;
@@ -15,9 +16,45 @@ mod util;
 
 pub use finite::*;
 pub use infinite::*;
+pub(crate) use util::parse_base64;
+
+/// A rectangle of tiles in a tile layer, in tile coordinates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileBounds {
+    /// The X position of the left-most column of the rectangle.
+    pub x: i32,
+    /// The Y position of the top-most row of the rectangle.
+    pub y: i32,
+    /// The width of the rectangle in tiles.
+    pub width: u32,
+    /// The height of the rectangle in tiles.
+    pub height: u32,
+}
+
+impl TileBounds {
+    /// Returns the smallest rectangle containing all of the given positions, or [`None`] if there
+    /// are none.
+    pub(crate) fn enclosing(positions: impl Iterator<Item = (i32, i32)>) -> Option<Self> {
+        positions
+            .fold(None, |bounds, (x, y)| match bounds {
+                None => Some((x, y, x, y)),
+                Some((min_x, min_y, max_x, max_y)) => {
+                    Some((min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)))
+                }
+            })
+            .map(|(min_x, min_y, max_x, max_y)| Self {
+                x: min_x,
+                y: min_y,
+                width: (max_x - min_x) as u32 + 1,
+                height: (max_y - min_y) as u32 + 1,
+            })
+    }
+}
 
 /// Stores the internal tile gid about a layer tile, along with how it is flipped.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LayerTileData {
     /// The index of the tileset this tile's in, relative to the tile's map. Guaranteed to be a
     /// valid index of the map tileset container, but **isn't guaranteed to actually contain
@@ -30,7 +67,11 @@ pub struct LayerTileData {
     /// Whether this tile is flipped on its X axis (vertically).
     pub flip_v: bool,
     /// Whether this tile is flipped diagonally.
+    ///
+    /// On hexagonal maps, this instead means the tile is rotated 60 degrees clockwise.
     pub flip_d: bool,
+    /// Whether this tile is rotated 120 degrees clockwise. Only used on hexagonal maps.
+    pub rotate_hex_120: bool,
 }
 
 impl LayerTileData {
@@ -51,36 +92,114 @@ impl LayerTileData {
         self.id
     }
 
+    /// Whether this tile is flipped on its Y axis (horizontally).
+    #[inline]
+    pub fn flip_h(&self) -> bool {
+        self.flip_h
+    }
+
+    /// Whether this tile is flipped on its X axis (vertically).
+    #[inline]
+    pub fn flip_v(&self) -> bool {
+        self.flip_v
+    }
+
+    /// Whether this tile is flipped diagonally.
+    #[inline]
+    pub fn flip_d(&self) -> bool {
+        self.flip_d
+    }
+
+    /// Translates the flip flags of this tile into a clockwise rotation in degrees plus whether
+    /// the tile should be flipped horizontally, which is how most engines expect to draw sprites.
+    ///
+    /// The horizontal flip is meant to be applied to the tile image *before* rotating it.
+    ///
+    /// On [`Orientation::Hexagonal`] maps, the diagonal flag stands for a 60 degree rotation and
+    /// [`rotate_hex_120`](Self::rotate_hex_120) for a 120 degree one, so the rotation returned is
+    /// a multiple of 60. On every other orientation it is a multiple of 90.
+    ///
+    /// Use [`LayerTile::rotation_and_flip`] to use the orientation of the tile's map.
+    pub fn rotation_and_flip(&self, orientation: Orientation) -> (u32, bool) {
+        let (mut rotation, flip_h, flip_v) = if orientation == Orientation::Hexagonal {
+            let rotation =
+                if self.flip_d { 60 } else { 0 } + if self.rotate_hex_120 { 120 } else { 0 };
+            (rotation, self.flip_h, self.flip_v)
+        } else if self.flip_d {
+            (90, self.flip_v, !self.flip_h)
+        } else {
+            (0, self.flip_h, self.flip_v)
+        };
+        // A vertical flip is a horizontal flip plus half a turn.
+        if flip_v {
+            rotation += 180;
+        }
+        (rotation % 360, flip_h != flip_v)
+    }
+
     const FLIPPED_HORIZONTALLY_FLAG: u32 = 0x80000000;
     const FLIPPED_VERTICALLY_FLAG: u32 = 0x40000000;
     const FLIPPED_DIAGONALLY_FLAG: u32 = 0x20000000;
-    const ALL_FLIP_FLAGS: u32 = Self::FLIPPED_HORIZONTALLY_FLAG
+    const ROTATED_HEXAGONAL_120_FLAG: u32 = 0x10000000;
+    pub(crate) const ALL_FLIP_FLAGS: u32 = Self::FLIPPED_HORIZONTALLY_FLAG
         | Self::FLIPPED_VERTICALLY_FLAG
-        | Self::FLIPPED_DIAGONALLY_FLAG;
+        | Self::FLIPPED_DIAGONALLY_FLAG
+        | Self::ROTATED_HEXAGONAL_120_FLAG;
+
+    /// Creates a new [`LayerTileData`] from a [`Gid`] plus its flipping bits, as stored in map
+    /// files, resolving its tileset from `tilesets`.
+    ///
+    /// Returns [`None`] if the GID is empty or doesn't belong to any of the tilesets.
+    pub fn from_bits(bits: u32, tilesets: &[MapTilesetGid]) -> Option<Self> {
+        let gid = Gid(bits & !Self::ALL_FLIP_FLAGS);
+        if gid == Gid::EMPTY {
+            return None;
+        }
+        let (tileset_index, tileset) = crate::get_tileset_for_gid(tilesets, gid)?;
+        Some(Self::from_bits_in(bits, tileset_index, tileset.first_gid))
+    }
 
-    /// Creates a new [`LayerTileData`] from a [`Gid`] plus its flipping bits.
-    pub(crate) fn from_bits(bits: u32, tilesets: &[MapTilesetGid]) -> Option<Self> {
+    /// Creates a new [`LayerTileData`] from a [`Gid`] plus its flipping bits, knowing that it
+    /// belongs to the tileset at `tileset_index`, whose first GID is `first_gid`.
+    pub(crate) fn from_bits_in(bits: u32, tileset_index: usize, first_gid: Gid) -> Self {
         let flags = bits & Self::ALL_FLIP_FLAGS;
         let gid = Gid(bits & !Self::ALL_FLIP_FLAGS);
         let flip_d = flags & Self::FLIPPED_DIAGONALLY_FLAG == Self::FLIPPED_DIAGONALLY_FLAG; // Swap x and y axis (anti-diagonally) [flips over y = -x line]
         let flip_h = flags & Self::FLIPPED_HORIZONTALLY_FLAG == Self::FLIPPED_HORIZONTALLY_FLAG; // Flip tile over y axis
         let flip_v = flags & Self::FLIPPED_VERTICALLY_FLAG == Self::FLIPPED_VERTICALLY_FLAG; // Flip tile over x axis
+        let rotate_hex_120 =
+            flags & Self::ROTATED_HEXAGONAL_120_FLAG == Self::ROTATED_HEXAGONAL_120_FLAG;
 
-        if gid == Gid::EMPTY {
-            None
-        } else {
-            let (tileset_index, tileset) = crate::util::get_tileset_for_gid(tilesets, gid)?;
-            let id = gid.0 - tileset.first_gid.0;
-
-            Some(Self {
-                tileset_index,
-                id,
-                flip_h,
-                flip_v,
-                flip_d,
-            })
+        Self {
+            tileset_index,
+            id: gid.0 - first_gid.0,
+            flip_h,
+            flip_v,
+            flip_d,
+            rotate_hex_120,
         }
     }
+
+    /// Converts this tile back into a [`Gid`] plus its flipping bits, exactly as it was stored in
+    /// the map file. The opposite of [`LayerTileData::from_bits`].
+    ///
+    /// `tilesets` must be the tilesets of the map the tile is from.
+    ///
+    /// # Panics
+    /// Panics if [`tileset_index`](Self::tileset_index) isn't a valid index of `tilesets`.
+    pub fn to_bits(self, tilesets: &[MapTilesetGid]) -> u32 {
+        self.to_bits_with_first_gid(tilesets[self.tileset_index].first_gid)
+    }
+
+    /// Like [`LayerTileData::to_bits`], given the first GID of the tile's tileset.
+    pub(crate) fn to_bits_with_first_gid(self, first_gid: Gid) -> u32 {
+        let flag = |set: bool, flag: u32| if set { flag } else { 0 };
+        (first_gid.0 + self.id)
+            | flag(self.flip_h, Self::FLIPPED_HORIZONTALLY_FLAG)
+            | flag(self.flip_v, Self::FLIPPED_VERTICALLY_FLAG)
+            | flag(self.flip_d, Self::FLIPPED_DIAGONALLY_FLAG)
+            | flag(self.rotate_hex_120, Self::ROTATED_HEXAGONAL_120_FLAG)
+    }
 }
 
 /// The raw data of a [`TileLayer`]. Does not include a reference to its parent [`Map`](crate::Map).
@@ -89,11 +208,21 @@ impl LayerTileData {
 /// dereference [`TileLayer`] into this structure, and even if we could, it wouldn't make much
 /// sense, since we can already deref from the finite/infinite tile layers themselves.
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) enum TileLayerData {
     Finite(FiniteTileLayerData),
     Infinite(InfiniteTileLayerData),
 }
 
+impl HeapSize for TileLayerData {
+    fn heap_size(&self) -> usize {
+        match self {
+            TileLayerData::Finite(data) => data.heap_size(),
+            TileLayerData::Infinite(data) => data.heap_size(),
+        }
+    }
+}
+
 impl TileLayerData {
     pub(crate) async fn new<R: Reader>(
         parser: &mut Parser<R>,
@@ -102,19 +231,31 @@ impl TileLayerData {
         tilesets: &[MapTilesetGid],
     ) -> Result<(Self, Properties)> {
         let (width, height) = get_attrs!(
-            for v in attrs {
+            for v in attrs of "layer" {
                 "width" => width ?= v.parse::<u32>(),
                 "height" => height ?= v.parse::<u32>(),
             }
             (width, height)
         );
-        let mut result = Self::Finite(Default::default());
+        // Layers without any data are empty, but keep their size
+        let mut result = if infinite {
+            Self::Infinite(Default::default())
+        } else {
+            Self::Finite(FiniteTileLayerData::empty(width, height))
+        };
         let mut properties = HashMap::new();
         let mut buffer = Vec::new();
         parse_tag!(parser => &mut buffer, "layer", {
             "data" => for attrs {
-                if infinite {
-                    result = Self::Infinite(InfiniteTileLayerData::new(parser, attrs, tilesets).await?);
+                if parser.skip_layer_contents {
+                    parser.skip_element().await?;
+                    if infinite {
+                        result = Self::Infinite(Default::default());
+                    } else {
+                        result = Self::Finite(FiniteTileLayerData::empty(width, height));
+                    }
+                } else if infinite {
+                    result = Self::Infinite(InfiniteTileLayerData::new(parser, attrs, width, height, tilesets).await?);
                 } else {
                     result = Self::Finite(FiniteTileLayerData::new(parser, attrs, width, height, tilesets).await?);
                 }
@@ -147,6 +288,24 @@ impl<'map> LayerTile<'map> {
         // SAFETY: `tileset_index` is guaranteed to be valid
         &self.map.tilesets()[self.data.tileset_index]
     }
+
+    /// Returns the custom properties of this tile, which are the ones of the tileset tile it
+    /// refers to since tiles placed on layers can't have properties of their own.
+    pub fn properties(&self) -> Cow<'map, Properties> {
+        match self.get_tile() {
+            Some(tile) => Cow::Borrowed(&tile.data.properties),
+            None => Cow::Owned(Properties::new()),
+        }
+    }
+
+    /// Translates the flip flags of this tile into a clockwise rotation in degrees plus a
+    /// horizontal flip, according to the orientation of the parent map.
+    ///
+    /// See [`LayerTileData::rotation_and_flip`] for more details.
+    #[inline]
+    pub fn rotation_and_flip(&self) -> (u32, bool) {
+        self.data.rotation_and_flip(self.map.orientation)
+    }
 }
 
 /// A map layer containing tiles in some way. May be finite or infinite.
@@ -176,6 +335,24 @@ impl<'map> TileLayer<'map> {
         }
     }
 
+    /// Returns an iterator over the non-empty tiles of this layer along with their position, in
+    /// row-major order. See [`FiniteTileLayer::tiles`] and [`InfiniteTileLayer::tiles`].
+    pub fn tiles(&self) -> impl Iterator<Item = ((i32, i32), LayerTile<'map>)> + 'map {
+        match self {
+            TileLayer::Finite(finite) => itertools::Either::Left(finite.tiles()),
+            TileLayer::Infinite(infinite) => itertools::Either::Right(infinite.tiles()),
+        }
+    }
+
+    /// Returns the smallest rectangle containing all of the non-empty tiles of this layer, or
+    /// [`None`] if it has none.
+    pub fn bounds(&self) -> Option<TileBounds> {
+        match self {
+            TileLayer::Finite(finite) => finite.bounds(),
+            TileLayer::Infinite(infinite) => infinite.bounds(),
+        }
+    }
+
     /// The width of this layer, if finite, or `None` if infinite.
     ///
     /// ## Example
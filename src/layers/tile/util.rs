@@ -1,39 +1,154 @@
-use std::{convert::TryInto, io::Read};
+use std::{convert::TryInto, fmt, io::Read};
 
 use base64::Engine;
-use quick_xml::events::Event;
+use quick_xml::events::{BytesStart, Event};
 
 use crate::{
-    parse::xml::{Parser, Reader},
-    CsvDecodingError, Error, LayerTileData, MapTilesetGid, Result,
+    parse::xml::{DataBuffers, Parser, Reader},
+    util::{collect_attributes, get_attrs},
+    CsvDecodingError, Error, LayerTileData, MapTilesetGid, ParseWarning, Result, Strictness,
 };
 
+use super::infinite::InternalChunk;
+
+/// The tiles of the `<data>` element of a tile layer.
+pub(crate) enum LayerDataContents {
+    /// The tiles of the whole layer, stored in the element itself.
+    Tiles(Vec<Option<LayerTileData>>),
+    /// The chunks the tiles are split into, in the order they appear.
+    Chunks(Vec<InternalChunk>),
+}
+
+/// How many times larger than their compressed size tile data is assumed to be at most when
+/// making room for it before decompressing it. This is about the best zlib can achieve; Data that
+/// compresses better is still decompressed, but its buffer grows as it goes.
+const MAX_PREALLOCATION_RATIO: usize = 1032;
+
+/// The most room made for decompressed tile data before decompressing it, whatever the size of
+/// the layer.
+const MAX_PREALLOCATED_BYTES: usize = 16 * 1024 * 1024;
+
+/// Returns the number of tiles of a layer or chunk of the given size, failing if their data
+/// couldn't be addressed.
+pub(crate) fn tile_count(width: u32, height: u32) -> Result<usize> {
+    (width as usize)
+        .checked_mul(height as usize)
+        .filter(|count| count.checked_mul(4).is_some())
+        .ok_or(Error::TileLayerTooLarge { width, height })
+}
+
+/// Parses the `<data>` element of a tile layer that was just started. Its tiles may be split into
+/// `<chunk>` elements or not, whether the map is infinite or not. `tile_count` is the number of
+/// tiles the layer's dimensions call for.
+pub(crate) async fn parse_layer_data<R: Reader>(
+    encoding: Option<&str>,
+    compression: Option<&str>,
+    parser: &mut Parser<R>,
+    tilesets: &[MapTilesetGid],
+    tile_count: usize,
+) -> Result<LayerDataContents> {
+    if parser.last_event_was_empty {
+        return Ok(LayerDataContents::Tiles(Vec::new()));
+    }
+    check_encoding(encoding, compression)?;
+    let mut resolver = GidResolver::new(tilesets);
+    let mut chunks = Vec::new();
+    let mut buffer = Vec::new();
+    loop {
+        buffer.clear();
+        let event = parser
+            .read_event_into(&mut buffer)
+            .await
+            .map_err(Error::XmlDecodingError)?;
+        match event {
+            Event::Start(chunk) | Event::Empty(chunk)
+                if chunk.local_name().into_inner() == b"chunk" =>
+            {
+                let attrs = collect_attributes(&chunk)?;
+                chunks.push(
+                    InternalChunk::new(parser, attrs, encoding, compression, tilesets).await?,
+                );
+            }
+            Event::End(end) if end.local_name().into_inner() == b"data" => break,
+            Event::Eof => return Err(Error::PrematureEnd("Ran out of XML data".to_owned())),
+            // Tiles stored in the element itself are ignored once it turns out to have chunks
+            event if chunks.is_empty() => {
+                let tiles = decode_tiles_at(
+                    &event,
+                    encoding,
+                    compression,
+                    parser,
+                    &mut resolver,
+                    tile_count,
+                )
+                .await?;
+                if let Some(tiles) = tiles {
+                    resolver.report(parser)?;
+                    return Ok(LayerDataContents::Tiles(tiles));
+                }
+            }
+            _ => {}
+        }
+    }
+    if chunks.is_empty() {
+        Ok(LayerDataContents::Tiles(Vec::new()))
+    } else {
+        Ok(LayerDataContents::Chunks(chunks))
+    }
+}
+
+/// Parses the tiles of the `<chunk>` element that was just started. `tile_count` is the number of
+/// tiles its dimensions call for, which the size of binary data is checked against.
 pub(crate) async fn parse_data_line<R: Reader>(
     encoding: Option<&str>,
     compression: Option<&str>,
     parser: &mut Parser<R>,
     tilesets: &[MapTilesetGid],
+    tile_count: usize,
 ) -> Result<Vec<Option<LayerTileData>>> {
-    match (encoding, compression) {
-        (Some("csv"), None) => decode_csv(parser, tilesets).await,
-
-        (Some("base64"), None) => parse_base64(parser)
-            .await
-            .map(|v| convert_to_tiles(&v, tilesets)),
-        (Some("base64"), Some("zlib")) => parse_base64(parser)
-            .await
-            .and_then(|data| process_decoder(Ok(flate2::bufread::ZlibDecoder::new(&data[..]))))
-            .map(|v| convert_to_tiles(&v, tilesets)),
-        (Some("base64"), Some("gzip")) => parse_base64(parser)
-            .await
-            .and_then(|data| process_decoder(Ok(flate2::bufread::GzDecoder::new(&data[..]))))
-            .map(|v| convert_to_tiles(&v, tilesets)),
-        #[cfg(feature = "zstd")]
-        (Some("base64"), Some("zstd")) => parse_base64(parser)
+    // Self-closing elements have no tiles, whatever their encoding
+    if parser.last_event_was_empty {
+        return Ok(Vec::new());
+    }
+    check_encoding(encoding, compression)?;
+    let mut resolver = GidResolver::new(tilesets);
+    let mut buffer = Vec::new();
+    let tiles = loop {
+        buffer.clear();
+        let event = parser
+            .read_event_into(&mut buffer)
             .await
-            .and_then(|data| process_decoder(zstd::stream::read::Decoder::with_buffer(&data[..])))
-            .map(|v| convert_to_tiles(&v, tilesets)),
+            .map_err(Error::XmlDecodingError)?;
+        match event {
+            Event::End(_) => break Vec::new(),
+            Event::Eof => return Err(Error::PrematureEnd("Ran out of XML data".to_owned())),
+            event => {
+                let tiles = decode_tiles_at(
+                    &event,
+                    encoding,
+                    compression,
+                    parser,
+                    &mut resolver,
+                    tile_count,
+                )
+                .await?;
+                if let Some(tiles) = tiles {
+                    break tiles;
+                }
+            }
+        }
+    };
+    resolver.report(parser)?;
+    Ok(tiles)
+}
 
+/// Fails if tile data can't be stored with the given encoding and compression.
+fn check_encoding(encoding: Option<&str>, compression: Option<&str>) -> Result<()> {
+    match (encoding, compression) {
+        (None, None) | (Some("csv"), None) => Ok(()),
+        (Some("base64"), None | Some("zlib") | Some("gzip")) => Ok(()),
+        #[cfg(feature = "zstd")]
+        (Some("base64"), Some("zstd")) => Ok(()),
         _ => Err(Error::InvalidEncodingFormat {
             encoding: encoding.map(ToOwned::to_owned),
             compression: compression.map(ToOwned::to_owned),
@@ -41,72 +156,364 @@ pub(crate) async fn parse_data_line<R: Reader>(
     }
 }
 
-async fn parse_base64<R: Reader>(parser: &mut Parser<R>) -> Result<Vec<u8>> {
+/// Decodes the tiles of a `<data>` or `<chunk>` element if they start at `event`, one of the
+/// events read from its contents. Returns `None` if they don't, such as when `event` is the
+/// whitespace before them.
+async fn decode_tiles_at<R: Reader>(
+    event: &Event<'_>,
+    encoding: Option<&str>,
+    compression: Option<&str>,
+    parser: &mut Parser<R>,
+    resolver: &mut GidResolver<'_>,
+    tile_count: usize,
+) -> Result<Option<Vec<Option<LayerTileData>>>> {
+    let limit = parser.context.settings.max_decompressed_layer_bytes;
+    let tiles = match (encoding, event) {
+        (None, Event::Start(tile) | Event::Empty(tile))
+            if tile.local_name().into_inner() == b"tile" =>
+        {
+            let mut tiles = vec![resolver.resolve(parse_tile_gid(tile)?)];
+            decode_xml_tiles(parser, resolver, &mut tiles, limit).await?;
+            check_tile_count(tiles.len(), tile_count)?;
+            tiles
+        }
+        (Some("csv"), Event::Text(text)) if !is_blank(text) => {
+            let found = text.iter().filter(|&&b| b == b',').count() + 1;
+            check_data_size(found, limit)?;
+            check_tile_count(found, tile_count)?;
+            decode_csv(text, resolver)?
+        }
+        (Some(_), Event::Text(text)) if !is_blank(text) => decode_base64_tiles(
+            text,
+            compression,
+            limit,
+            resolver,
+            tile_count,
+            &mut parser.data_buffers,
+        )?,
+        _ => return Ok(None),
+    };
+    Ok(Some(tiles))
+}
+
+/// Fails if the data of `tile_count` tiles, at four bytes per tile, is larger than `limit`.
+pub(crate) fn check_data_size(tile_count: usize, limit: Option<usize>) -> Result<()> {
+    match limit {
+        Some(limit) if tile_count.saturating_mul(4) > limit => {
+            Err(Error::DecompressedTileDataTooLarge { limit })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Fails if `found` tiles were stored for a layer or chunk that calls for `tile_count` tiles, the
+/// way the size of binary data is checked.
+fn check_tile_count(found: usize, tile_count: usize) -> Result<()> {
+    if found != tile_count {
+        return Err(Error::InvalidTileDataSize {
+            expected: tile_count.saturating_mul(4),
+            found: found.saturating_mul(4),
+        });
+    }
+    Ok(())
+}
+
+fn is_blank(text: &[u8]) -> bool {
+    text.iter().all(u8::is_ascii_whitespace)
+}
+
+/// Resolves the GIDs of the tiles of a layer or chunk, remembering the first one that doesn't
+/// belong to any of the map's tilesets so that it can be reported once the tiles are decoded.
+struct GidResolver<'a> {
+    tilesets: &'a [MapTilesetGid],
+    /// The indices of `tilesets` by ascending first GID, so that each tile is resolved with a
+    /// binary search whatever order the map lists its tilesets in.
+    order: Vec<usize>,
+    unresolved: Option<u32>,
+}
+
+impl<'a> GidResolver<'a> {
+    fn new(tilesets: &'a [MapTilesetGid]) -> Self {
+        let mut order: Vec<usize> = (0..tilesets.len()).collect();
+        // Stable, so that the last of several tilesets with the same first GID wins like in
+        // `get_tileset_for_gid`
+        order.sort_by_key(|&index| tilesets[index].first_gid);
+        Self {
+            tilesets,
+            order,
+            unresolved: None,
+        }
+    }
+
+    fn resolve(&mut self, bits: u32) -> Option<LayerTileData> {
+        let gid = bits & !LayerTileData::ALL_FLIP_FLAGS;
+        if gid == 0 {
+            return None;
+        }
+        let candidates = self
+            .order
+            .partition_point(|&index| self.tilesets[index].first_gid.0 <= gid);
+        match candidates.checked_sub(1) {
+            Some(position) => {
+                let tileset_index = self.order[position];
+                let first_gid = self.tilesets[tileset_index].first_gid;
+                Some(LayerTileData::from_bits_in(bits, tileset_index, first_gid))
+            }
+            None => {
+                self.unresolved.get_or_insert(gid);
+                None
+            }
+        }
+    }
+
+    /// Fails or records a warning if a GID couldn't be resolved, depending on the strictness of
+    /// the parser. Such tiles are left empty otherwise.
+    fn report<R>(self, parser: &mut Parser<R>) -> Result<()> {
+        let gid = match self.unresolved {
+            Some(gid) => gid,
+            None => return Ok(()),
+        };
+        let layer_name = parser
+            .layer_stack
+            .last()
+            .map(|(name, _)| name.clone())
+            .unwrap_or_default();
+        match parser.context.settings.strictness {
+            Strictness::Strict | Strictness::Error => {
+                return Err(Error::UnresolvedTileGid { layer_name, gid })
+            }
+            Strictness::Warn => parser
+                .context
+                .state
+                .warnings
+                .push(ParseWarning::UnresolvedTileGid { layer_name, gid }),
+            Strictness::Ignore => {}
+        }
+        Ok(())
+    }
+}
+
+fn decode_base64_tiles(
+    text: &[u8],
+    compression: Option<&str>,
+    limit: Option<usize>,
+    resolver: &mut GidResolver<'_>,
+    tile_count: usize,
+    buffers: &mut DataBuffers,
+) -> Result<Vec<Option<LayerTileData>>> {
+    // Can't overflow, see `tile_count`
+    let expected = tile_count * 4;
+    // Compressed data is never decompressed past the expected size, and uncompressed data takes
+    // about as much memory as its text
+    check_data_size(tile_count, limit)?;
+    let DataBuffers {
+        decoded,
+        decompressed,
+    } = buffers;
+    decoded.clear();
+    decompressed.clear();
+    decode_base64(text, decoded)?;
+    let data = match compression {
+        None => decoded,
+        Some("zlib") => decompress(
+            flate2::bufread::ZlibDecoder::new(&decoded[..]),
+            decompressed,
+            expected,
+            decoded.len(),
+        )?,
+        Some("gzip") => decompress(
+            flate2::bufread::GzDecoder::new(&decoded[..]),
+            decompressed,
+            expected,
+            decoded.len(),
+        )?,
+        #[cfg(feature = "zstd")]
+        Some("zstd") => decompress(
+            zstd::stream::read::Decoder::with_buffer(&decoded[..])
+                .map_err(Error::DecompressingError)?,
+            decompressed,
+            expected,
+            decoded.len(),
+        )?,
+        _ => {
+            return Err(Error::InvalidEncodingFormat {
+                encoding: Some("base64".to_owned()),
+                compression: compression.map(ToOwned::to_owned),
+            })
+        }
+    };
+    if data.len() != expected {
+        return Err(Error::InvalidTileDataSize {
+            expected,
+            found: data.len(),
+        });
+    }
+    Ok(convert_to_tiles(data, resolver))
+}
+
+/// Decodes the base64 text of the `<data>` element that was just started.
+pub(crate) async fn parse_base64<R: Reader>(parser: &mut Parser<R>) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    if parser.last_event_was_empty {
+        return Ok(data);
+    }
     loop {
         let next = parser.read_event().await.map_err(Error::XmlDecodingError)?;
         match next {
-            Event::Text(mut text) => {
-                text.inplace_trim_start();
-                text.inplace_trim_end();
-                return base64::engine::GeneralPurpose::new(
-                    &base64::alphabet::STANDARD,
-                    base64::engine::general_purpose::PAD,
-                )
-                .decode(&*text)
-                .map_err(Error::Base64DecodingError);
+            Event::Text(text) => {
+                decode_base64(&text, &mut data)?;
+                return Ok(data);
             }
-            Event::End(end) if end.local_name().into_inner() == b"data" => return Ok(Vec::new()),
+            Event::End(end) if end.local_name().into_inner() == b"data" => return Ok(data),
             Event::Eof => return Err(Error::PrematureEnd("Ran out of XML data".to_owned())),
             _ => {}
         }
     }
 }
 
-fn process_decoder(decoder: std::io::Result<impl Read>) -> Result<Vec<u8>> {
+/// Decodes base64 `text`, ignoring the whitespace around it, and appends it to `output`.
+fn decode_base64(text: &[u8], output: &mut Vec<u8>) -> Result<()> {
+    let start = text
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(text.len());
+    let end = text
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .map_or(start, |end| end + 1);
+    base64::engine::GeneralPurpose::new(
+        &base64::alphabet::STANDARD,
+        base64::engine::general_purpose::PAD,
+    )
+    .decode_vec(&text[start..end], output)
+    .map_err(Error::Base64DecodingError)
+}
+
+/// Decompresses the data of `decoder`, which is `compressed_len` bytes long, into `output`.
+/// Decompression stops once the expected size of the data is reached, so that small payloads
+/// which inflate to huge amounts of data can't exhaust memory; Any data left past it is an error.
+///
+/// Room is made for the data beforehand, but no more than it could take given its compressed
+/// size, since the expected size comes from the dimensions the file declares.
+fn decompress(
+    decoder: impl Read,
+    output: &mut Vec<u8>,
+    expected: usize,
+    compressed_len: usize,
+) -> Result<&mut Vec<u8>> {
+    output.reserve(
+        expected
+            .min(compressed_len.saturating_mul(MAX_PREALLOCATION_RATIO))
+            .min(MAX_PREALLOCATED_BYTES),
+    );
+    let mut decoder = decoder.take(expected as u64);
     decoder
-        .and_then(|mut decoder| {
-            let mut data = Vec::new();
-            decoder.read_to_end(&mut data)?;
-            Ok(data)
+        .read_to_end(output)
+        .map_err(Error::DecompressingError)?;
+    let mut rest = [0; 1];
+    let left = decoder
+        .into_inner()
+        .read(&mut rest)
+        .map_err(Error::DecompressingError)?;
+    if left != 0 {
+        return Err(Error::DecompressedTileDataTooLarge { limit: expected });
+    }
+    Ok(output)
+}
+
+fn decode_csv(text: &[u8], resolver: &mut GidResolver<'_>) -> Result<Vec<Option<LayerTileData>>> {
+    let text = std::str::from_utf8(text).map_err(|err| Error::XmlDecodingError(err.into()))?;
+    text.split(',')
+        .map(|v| match v.trim().parse() {
+            Ok(bits) => Ok(resolver.resolve(bits)),
+            Err(e) => Err(Error::CsvDecodingError(
+                CsvDecodingError::TileDataParseError(e),
+            )),
         })
-        .map_err(Error::DecompressingError)
+        .collect()
 }
 
-async fn decode_csv<R: Reader>(
+/// Decodes the rest of the tiles of the `<data>` or `<chunk>` element being read when they are
+/// stored as `<tile gid="..."/>` elements, which is the format used by old versions of Tiled, up
+/// to its end.
+async fn decode_xml_tiles<R: Reader>(
     parser: &mut Parser<R>,
-    tilesets: &[MapTilesetGid],
-) -> Result<Vec<Option<LayerTileData>>> {
+    resolver: &mut GidResolver<'_>,
+    tiles: &mut Vec<Option<LayerTileData>>,
+    limit: Option<usize>,
+) -> Result<()> {
     loop {
         let next = parser.read_event().await.map_err(Error::XmlDecodingError)?;
         match next {
-            Event::Text(text) => {
-                let text = std::str::from_utf8(&text)
-                    .map_err(|err| Error::XmlDecodingError(err.into()))?;
-                let mut tiles = Vec::new();
-                for v in text.split(',') {
-                    match v.trim().parse() {
-                        Ok(bits) => tiles.push(LayerTileData::from_bits(bits, tilesets)),
-                        Err(e) => {
-                            return Err(Error::CsvDecodingError(
-                                CsvDecodingError::TileDataParseError(e),
-                            ))
-                        }
-                    }
-                }
-                return Ok(tiles);
+            Event::Start(tile) | Event::Empty(tile)
+                if tile.local_name().into_inner() == b"tile" =>
+            {
+                tiles.push(resolver.resolve(parse_tile_gid(&tile)?));
+                check_data_size(tiles.len(), limit)?;
             }
-            Event::End(end) if end.local_name().into_inner() == b"data" => return Ok(Vec::new()),
+            Event::End(end) if end.local_name().into_inner() != b"tile" => return Ok(()),
             Event::Eof => return Err(Error::PrematureEnd("Ran out of XML data".to_owned())),
             _ => {}
         }
     }
 }
 
-fn convert_to_tiles(data: &[u8], tilesets: &[MapTilesetGid]) -> Vec<Option<LayerTileData>> {
+/// Returns the GID of a `<tile>` element of tile data, which is empty if it has none.
+fn parse_tile_gid(tile: &BytesStart) -> Result<u32> {
+    let attrs = collect_attributes(tile)?;
+    let gid = get_attrs!(
+        for v in attrs of "tile" {
+            Some("gid") => gid ?= v.parse::<u32>(),
+        }
+        gid
+    );
+    Ok(gid.unwrap_or(0))
+}
+
+fn convert_to_tiles(data: &[u8], resolver: &mut GidResolver) -> Vec<Option<LayerTileData>> {
     data.chunks_exact(4)
         .map(|chunk| {
             let bits = u32::from_le_bytes(chunk.try_into().unwrap());
-            LayerTileData::from_bits(bits, tilesets)
+            resolver.resolve(bits)
         })
         .collect()
 }
+
+/// Summarizes tiles in [`Debug`](fmt::Debug) output as their count and how many aren't empty,
+/// since printing every tile of a large layer produces millions of lines.
+pub(crate) struct TilesSummary {
+    count: usize,
+    non_empty: usize,
+}
+
+impl TilesSummary {
+    pub(crate) fn new<'a>(tiles: impl IntoIterator<Item = &'a Option<LayerTileData>>) -> Self {
+        let (count, non_empty) = tiles.into_iter().fold((0, 0), |(count, non_empty), tile| {
+            (count + 1, non_empty + usize::from(tile.is_some()))
+        });
+        Self { count, non_empty }
+    }
+}
+
+impl fmt::Debug for TilesSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.count == 0 {
+            return f.write_str("[0 tiles]");
+        }
+        write!(
+            f,
+            "[{} tiles, {}% non-empty]",
+            self.count,
+            self.non_empty * 100 / self.count
+        )
+    }
+}
+
+/// Implements [`Debug`](fmt::Debug) with a closure, for the `debug_full` methods of tile data.
+pub(crate) struct DebugWith<F>(pub(crate) F);
+
+impl<F: Fn(&mut fmt::Formatter<'_>) -> fmt::Result> fmt::Debug for DebugWith<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (self.0)(f)
+    }
+}
@@ -12,15 +12,24 @@ mod image;
 mod layers;
 mod loader;
 mod map;
+mod math;
 mod objects;
+mod owned;
 mod parse;
+#[cfg(feature = "project")]
+mod project;
 mod properties;
 mod reader;
+#[cfg(feature = "async-tokio")]
 mod reader_async;
+mod stats;
 mod template;
 mod tile;
 mod tileset;
 mod util;
+#[cfg(feature = "world")]
+mod world;
+mod writer;
 
 pub use animation::*;
 pub use cache::*;
@@ -29,10 +38,19 @@ pub use image::*;
 pub use layers::*;
 pub use loader::*;
 pub use map::*;
+pub use math::*;
 pub use objects::*;
+pub use owned::*;
+#[cfg(feature = "project")]
+pub use project::*;
 pub use properties::*;
 pub use reader::*;
+#[cfg(feature = "async-tokio")]
 pub use reader_async::*;
+pub use stats::*;
 pub use template::*;
 pub use tile::*;
 pub use tileset::*;
+#[cfg(feature = "world")]
+pub use world::*;
+pub use writer::*;
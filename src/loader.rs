@@ -1,13 +1,568 @@
-use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::{
+    io::BufRead,
+    path::{Path, PathBuf},
+};
 
 use futures::FutureExt;
+#[cfg(feature = "async-tokio")]
+use tokio::io::AsyncBufRead;
 
+#[cfg(feature = "project")]
+use crate::Project;
+#[cfg(feature = "async-tokio")]
+use crate::{
+    parse::xml::{AsyncReadFrom, AsyncReader},
+    AsyncResourceReader,
+};
 use crate::{
-    parse::xml::{AsyncReadFrom, SyncReadFrom},
-    AsyncResourceReader, DefaultResourceCache, FilesystemResourceReader, Map, ResourceCache,
-    ResourceReader, Result, Tileset,
+    parse::xml::{LoadContext, SyncReadFrom, SyncReader},
+    util::source_to_path,
+    DefaultResourceCache, FilesystemResourceReader, Map, ObjectData, ResourceCache, ResourceReader,
+    Result, Tileset,
 };
 
+/// A predicate over layer names, used by [`LoadSettings::layer_name_filter`].
+///
+/// Clones share the same function.
+#[derive(Clone)]
+pub struct LayerNameFilter(Arc<dyn Fn(&str) -> bool + Send + Sync>);
+
+impl LayerNameFilter {
+    /// Creates a filter that keeps the contents of the layers whose name `filter` returns `true`
+    /// for.
+    pub fn new(filter: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        Self(Arc::new(filter))
+    }
+
+    pub(crate) fn matches(&self, name: &str) -> bool {
+        (self.0)(name)
+    }
+}
+
+impl std::fmt::Debug for LayerNameFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("LayerNameFilter")
+    }
+}
+
+type ObjectCallbackFn = dyn FnMut(&ObjectData, &LayerContext) -> ObjectAction + Send;
+
+/// A callback invoked with each object of a map as soon as it has been parsed, used by
+/// [`LoadSettings::on_object`].
+///
+/// Clones share the same function, which is only ever called by one load at a time.
+#[derive(Clone)]
+pub struct ObjectCallback(Arc<Mutex<Box<ObjectCallbackFn>>>);
+
+impl ObjectCallback {
+    /// Creates a callback that decides what to do with each object it is given.
+    pub fn new(
+        callback: impl FnMut(&ObjectData, &LayerContext) -> ObjectAction + Send + 'static,
+    ) -> Self {
+        Self(Arc::new(Mutex::new(Box::new(callback))))
+    }
+
+    pub(crate) fn call(&self, object: &ObjectData, layer: &LayerContext) -> ObjectAction {
+        let mut callback = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        callback(object, layer)
+    }
+}
+
+impl std::fmt::Debug for ObjectCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ObjectCallback")
+    }
+}
+
+type PathResolverFn = dyn FnMut(&Path, &str) -> PathBuf + Send;
+
+/// A function that resolves the path of a file referenced by a map, tileset or template, used by
+/// [`LoadSettings::path_resolver`].
+///
+/// It is given the directory of the file containing the reference, and the `source` attribute
+/// exactly as it was written in that file. Clones share the same function, which is only ever
+/// called by one load at a time.
+#[derive(Clone)]
+pub struct PathResolver(Arc<Mutex<Box<PathResolverFn>>>);
+
+impl PathResolver {
+    /// Creates a resolver that returns the path of the file each reference points to.
+    pub fn new(resolver: impl FnMut(&Path, &str) -> PathBuf + Send + 'static) -> Self {
+        Self(Arc::new(Mutex::new(Box::new(resolver))))
+    }
+
+    pub(crate) fn resolve(&self, directory: &Path, source: &str) -> PathBuf {
+        let mut resolver = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        resolver(directory, source)
+    }
+}
+
+impl std::fmt::Debug for PathResolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("PathResolver")
+    }
+}
+
+type ProgressHookFn = dyn FnMut(LoadProgress<'_>) + Send;
+
+/// A function called as loading goes on, used by [`LoadSettings::progress_hook`].
+///
+/// Clones share the same function, which is only ever called by one load at a time.
+#[derive(Clone)]
+pub struct ProgressHook(Arc<Mutex<Box<ProgressHookFn>>>);
+
+impl ProgressHook {
+    /// Creates a hook that is called with each [`LoadProgress`] event.
+    pub fn new(hook: impl FnMut(LoadProgress<'_>) + Send + 'static) -> Self {
+        Self(Arc::new(Mutex::new(Box::new(hook))))
+    }
+
+    pub(crate) fn report(&self, event: LoadProgress<'_>) {
+        let mut hook = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        hook(event)
+    }
+}
+
+impl std::fmt::Debug for ProgressHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ProgressHook")
+    }
+}
+
+/// An event passed to the [`LoadSettings::progress_hook`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LoadProgress<'a> {
+    /// An external tileset is about to be read. Tilesets found in the cache are not read again,
+    /// and don't cause any event.
+    ExternalTilesetStarted(&'a Path),
+    /// An external tileset was read, whether parsing it succeeded or not.
+    ExternalTilesetFinished(&'a Path),
+    /// A top-level layer of the map was parsed, along with all of its children.
+    LayerParsed {
+        /// The name of the layer.
+        name: &'a str,
+        /// The index of the layer in [`Map::layers`](crate::Map::layers).
+        index: usize,
+        /// An estimate of the number of layers of the map, based on its `nextlayerid` attribute.
+        /// It also counts layers nested in groups, as well as deleted ones, so it is usually an
+        /// upper bound of the final index plus one.
+        total_hint: Option<usize>,
+    },
+    /// An external template was read and parsed. Templates found in the cache don't cause any
+    /// event.
+    TemplateLoaded(&'a Path),
+}
+
+/// Settings that control how maps, tilesets and templates are loaded: Which parts of a map are
+/// parsed, how strictly, how the paths of the files they refer to are resolved and where progress
+/// is reported.
+///
+/// Each [`Loader`] holds the settings its load functions use, which can be changed through
+/// [`Loader::settings_mut`]; [`Loader::load_tmx_map_with_settings`] loads a map with other
+/// settings instead. Clones of settings share the same callbacks.
+///
+/// Layers whose contents are skipped still appear in [`Map::layers()`] with all of their metadata
+/// (name, id, properties...), but tile layers have no tiles and object layers have no objects.
+/// Use [`LayerData::contents_skipped`](crate::LayerData::contents_skipped) to tell them apart from
+/// layers that are actually empty.
+///
+/// ## Example
+/// ```
+/// # fn main() -> tiled::Result<()> {
+/// use tiled::{LoadSettings, Loader};
+///
+/// let map = Loader::new().load_tmx_map_with_settings(
+///     "assets/tiled_base64.tmx",
+///     LoadSettings {
+///         skip_tile_layer_data: true,
+///         ..Default::default()
+///     },
+/// )?;
+///
+/// assert!(map.get_layer(0).unwrap().contents_skipped());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct LoadSettings {
+    /// Whether the `<data>` of tile layers should be skipped over without being decoded.
+    pub skip_tile_layer_data: bool,
+    /// If set, only tile and object layers whose name this filter returns `true` for have their
+    /// contents parsed.
+    ///
+    /// Group layers are never filtered themselves; Each of their children is checked separately.
+    pub layer_name_filter: Option<LayerNameFilter>,
+    /// What to do when unexpected contents, such as a property with an unknown type or a layer of
+    /// an unknown kind, are found in the map or any of the tilesets and templates loaded along
+    /// with it. See [`Strictness`].
+    pub strictness: Strictness,
+    /// What to do when a list of properties has several properties with the same name, or
+    /// sibling layers have the same name, in the map or any of the tilesets and templates loaded
+    /// along with it.
+    pub duplicates: DuplicateHandling,
+    /// If set, called with each object of the map's object layers as soon as it has been parsed,
+    /// which can decide whether the object is kept in the resulting map or not.
+    ///
+    /// Discarding objects keeps memory usage low when only some information about them is needed.
+    /// Objects that are part of tiles or templates are not passed to this callback.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// use std::sync::{Arc, Mutex};
+    /// use tiled::{LoadSettings, Loader, ObjectAction, ObjectCallback};
+    ///
+    /// let positions = Arc::new(Mutex::new(Vec::new()));
+    /// let collected = positions.clone();
+    /// let map = Loader::new().load_tmx_map_with_settings(
+    ///     "assets/tiled_nested_objects.tmx",
+    ///     LoadSettings {
+    ///         on_object: Some(ObjectCallback::new(move |object, _layer| {
+    ///             collected.lock().unwrap().push((object.x, object.y));
+    ///             ObjectAction::Discard
+    ///         })),
+    ///         ..Default::default()
+    ///     },
+    /// )?;
+    ///
+    /// assert_eq!(positions.lock().unwrap().len(), 5);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub on_object: Option<ObjectCallback>,
+    /// Whether to run [`Map::validate`] on the map once loaded, making loading fail with an
+    /// [`Error::ValidationFailed`](crate::Error::ValidationFailed) containing the first issue
+    /// found, if any. Useful for checking maps in CI pipelines.
+    pub validate: bool,
+    /// If set, the maximum size in bytes of the tile data of a single tile layer or chunk, at
+    /// four bytes per tile, making loading fail with an
+    /// [`Error::DecompressedTileDataTooLarge`](crate::Error::DecompressedTileDataTooLarge)
+    /// otherwise. This applies to data in every encoding, and compressed data is never
+    /// decompressed past it.
+    ///
+    /// Decompression never produces more data than a layer's dimensions require, but those come
+    /// from the map file too, so this bounds memory usage when loading untrusted maps. Defaults
+    /// to [`LoadSettings::DEFAULT_MAX_DECOMPRESSED_LAYER_BYTES`]; Set it to `None` to load layers
+    /// of any size.
+    pub max_decompressed_layer_bytes: Option<usize>,
+    /// Whether external tilesets of the map that can't be read, e.g. because their file is
+    /// missing, are replaced by empty placeholders instead of making loading fail.
+    ///
+    /// Placeholders have no tiles, and their [`source`](crate::Tileset::source) is the path they
+    /// should have been loaded from; See
+    /// [`Tileset::is_placeholder`](crate::Tileset::is_placeholder). Layer tiles that belong to
+    /// them keep pointing to their index, but
+    /// [`LayerTile::get_tile`](crate::LayerTile::get_tile) returns [`None`] for them. Each of
+    /// them is reported as a [`ParseWarning::MissingTileset`](crate::ParseWarning::MissingTileset)
+    /// in [`Map::warnings`], and they are not stored in the cache.
+    ///
+    /// Tilesets that could be read but are malformed still make loading fail.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// use tiled::{LoadSettings, Loader, ParseWarning};
+    ///
+    /// let map = Loader::new().load_tmx_map_with_settings(
+    ///     "assets/tiled_missing_tileset.tmx",
+    ///     LoadSettings {
+    ///         placeholder_missing_tilesets: true,
+    ///         ..Default::default()
+    ///     },
+    /// )?;
+    ///
+    /// for warning in map.warnings() {
+    ///     if let ParseWarning::MissingTileset { path, error } = warning {
+    ///         eprintln!("Could not load tileset {}: {}", path.display(), error);
+    ///     }
+    /// }
+    /// # assert_eq!(map.tilesets().len(), 2);
+    /// # assert!(map.tilesets()[1].is_placeholder());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub placeholder_missing_tilesets: bool,
+    /// If set, the [`Project`](crate::Project) whose custom property types the properties of the
+    /// loaded files are resolved with: The members of class properties that aren't set are
+    /// filled in with their default values, and enum properties are loaded as
+    /// [`PropertyValue::EnumValue`](crate::PropertyValue::EnumValue)s.
+    ///
+    /// Tilesets and templates are only parsed once before being stored in the cache, so those
+    /// that are already cached keep the properties they were loaded with.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// use std::sync::Arc;
+    ///
+    /// let mut loader = tiled::Loader::new();
+    /// let project = loader.load_project("assets/project/project.tiled-project")?;
+    /// loader.settings_mut().project = Some(Arc::new(project));
+    ///
+    /// let map = loader.load_tmx_map("assets/project/project_map.tmx")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "project")]
+    pub project: Option<Arc<Project>>,
+    /// If set, overrides how the paths of external tilesets, templates and images are resolved.
+    ///
+    /// By default, the `source` attribute of a reference is joined to the directory of the file
+    /// containing it. The path returned by the resolver is used as is instead, both for reading
+    /// the referenced file and for the [`Image::source`](crate::Image::source) of images; As
+    /// such, it is also the path reported by
+    /// [`Error::ResourceLoadingError`](crate::Error::ResourceLoadingError) if reading fails.
+    /// Tilesets and templates are still cached by their resolved path.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// use std::path::Path;
+    /// use tiled::PathResolver;
+    ///
+    /// let mut loader = tiled::Loader::new();
+    /// // All tilesets live in the same directory, regardless of where maps expect them to be
+    /// loader.settings_mut().path_resolver = Some(PathResolver::new(|directory, source| {
+    ///     if source.ends_with(".tsx") {
+    ///         Path::new("assets").join(Path::new(source).file_name().unwrap())
+    ///     } else {
+    ///         directory.join(source)
+    ///     }
+    /// }));
+    ///
+    /// // This map refers to "../art/tilesheet.tsx"
+    /// let map = loader.load_tmx_map("assets/tiled_relocated_tileset.tmx")?;
+    /// assert_eq!(
+    ///     map.tilesets()[0].source.as_deref(),
+    ///     Some(Path::new("assets/tilesheet.tsx"))
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub path_resolver: Option<PathResolver>,
+    /// Replacements for the start of absolute paths in the `source` of tilesets, templates and
+    /// images: Sources starting with the first path of a pair start with the second one instead,
+    /// which helps loading maps saved on a machine whose assets were in another directory.
+    ///
+    /// By default, absolute sources, including `file://` URIs, are used as is instead of being
+    /// joined to the directory of the file containing them, and backslashes are treated as path
+    /// separators on every platform. Prefixes are matched against whole path components, and
+    /// Windows drive letters are matched regardless of their case. When several prefixes match,
+    /// the first one is used.
+    ///
+    /// Prefixes are ignored when a [`path_resolver`](Self::path_resolver) is set.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// use std::path::Path;
+    ///
+    /// let mut loader = tiled::Loader::new();
+    /// loader
+    ///     .settings_mut()
+    ///     .absolute_prefixes
+    ///     .push(("C:\\Users\\artist\\game".into(), "assets".into()));
+    ///
+    /// // This map refers to "C:\Users\artist\game\tilesheet.tsx"
+    /// let map = loader.load_tmx_map("assets/tiled_absolute_tileset.tmx")?;
+    /// assert_eq!(
+    ///     map.tilesets()[0].source.as_deref(),
+    ///     Some(Path::new("assets/tilesheet.tsx"))
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub absolute_prefixes: Vec<(String, PathBuf)>,
+    /// If set, called with a [`LoadProgress`] event as each tileset, template and layer of a map
+    /// is loaded, which can be used to report progress while loading large maps. The same events
+    /// are emitted whether loading is sync or async.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// use std::sync::{Arc, Mutex};
+    /// use tiled::{LoadProgress, ProgressHook};
+    ///
+    /// let layers = Arc::new(Mutex::new(Vec::new()));
+    /// let parsed = layers.clone();
+    /// let mut loader = tiled::Loader::new();
+    /// loader.settings_mut().progress_hook = Some(ProgressHook::new(move |event| {
+    ///     if let LoadProgress::LayerParsed { name, .. } = event {
+    ///         parsed.lock().unwrap().push(name.to_owned());
+    ///     }
+    /// }));
+    ///
+    /// loader.load_tmx_map("assets/tiled_object_template.tmx")?;
+    /// assert_eq!(*layers.lock().unwrap(), ["Tile Layer 1", "Object Layer 1"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub progress_hook: Option<ProgressHook>,
+    /// Whether the dimensions of images that have no `width` and `height` attributes should be
+    /// read from the header of their file. Disabled by default.
+    ///
+    /// Tiled always writes these attributes, but other exporters and hand-written files may omit
+    /// them, leaving [`Image::width`](crate::Image::width) and
+    /// [`Image::height`](crate::Image::height) at 0. When enabled, the first bytes of such images
+    /// are read through the loader's reader, or from their embedded data, and their dimensions are
+    /// taken from the header if the image is a PNG, BMP, GIF or JPEG file. Images in other formats
+    /// are left as is, while images that can't be read make loading fail with an
+    /// [`Error::ResourceLoadingError`](crate::Error::ResourceLoadingError).
+    ///
+    /// Tilesets and templates are only parsed once before being stored in the cache, so those
+    /// that are already cached keep the dimensions they were loaded with.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut loader = tiled::Loader::new();
+    /// loader.settings_mut().probe_image_dimensions = true;
+    ///
+    /// // This tileset's image has no `width` and `height` attributes
+    /// let tileset = loader.load_tsx_tileset("assets/tilesheet_no_image_size.tsx")?;
+    /// let image = tileset.image.as_ref().unwrap();
+    /// assert_eq!((image.width, image.height), (448, 192));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub probe_image_dimensions: bool,
+}
+
+impl LoadSettings {
+    /// The default value of [`LoadSettings::max_decompressed_layer_bytes`], which is 64 MiB, or
+    /// the data of a layer of 4096x4096 tiles.
+    pub const DEFAULT_MAX_DECOMPRESSED_LAYER_BYTES: usize = 64 * 1024 * 1024;
+
+    /// Resolves the `source` attribute of a reference found in a file inside `directory`.
+    pub(crate) fn resolve_path(&self, directory: &Path, source: &str) -> PathBuf {
+        match &self.path_resolver {
+            Some(resolver) => resolver.resolve(directory, source),
+            None => source_to_path(directory, source, &self.absolute_prefixes),
+        }
+    }
+
+    /// Passes an event to the progress hook, if there is one.
+    pub(crate) fn report_progress(&self, event: LoadProgress<'_>) {
+        if let Some(hook) = &self.progress_hook {
+            hook.report(event);
+        }
+    }
+}
+
+impl Default for LoadSettings {
+    fn default() -> Self {
+        Self {
+            skip_tile_layer_data: false,
+            layer_name_filter: None,
+            strictness: Strictness::default(),
+            duplicates: DuplicateHandling::default(),
+            on_object: None,
+            validate: false,
+            max_decompressed_layer_bytes: Some(Self::DEFAULT_MAX_DECOMPRESSED_LAYER_BYTES),
+            placeholder_missing_tilesets: false,
+            #[cfg(feature = "project")]
+            project: None,
+            path_resolver: None,
+            absolute_prefixes: Vec::new(),
+            progress_hook: None,
+            probe_image_dimensions: false,
+        }
+    }
+}
+
+/// What to do with an object once it has been passed to [`LoadSettings::on_object`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ObjectAction {
+    /// Store the object in its layer, as usual.
+    #[default]
+    Keep,
+    /// Drop the object, leaving it out of its layer.
+    Discard,
+}
+
+/// The object layer an object passed to [`LoadSettings::on_object`] is part of.
+#[derive(Debug, Clone, Copy)]
+pub struct LayerContext<'a> {
+    /// The names and IDs of the layers being parsed, from the top-level one to the object layer.
+    layers: &'a [(String, u32)],
+}
+
+impl<'a> LayerContext<'a> {
+    pub(crate) fn new(layers: &'a [(String, u32)]) -> Self {
+        debug_assert!(!layers.is_empty());
+        Self { layers }
+    }
+
+    /// The name of the object layer.
+    #[inline]
+    pub fn name(&self) -> &'a str {
+        &self.layers[self.layers.len() - 1].0
+    }
+
+    /// The ID of the object layer. See [`LayerData::id`](crate::LayerData::id).
+    #[inline]
+    pub fn id(&self) -> u32 {
+        self.layers[self.layers.len() - 1].1
+    }
+
+    /// The names and IDs of the group layers containing the object layer, starting from the
+    /// top-level one.
+    pub fn group_path(&self) -> impl ExactSizeIterator<Item = (&'a str, u32)> {
+        self.layers[..self.layers.len() - 1]
+            .iter()
+            .map(|(name, id)| (name.as_str(), *id))
+    }
+}
+
+/// How strictly unexpected contents are handled while loading, as set in
+/// [`LoadSettings::strictness`].
+///
+/// This applies to properties of unknown types, which are stored as
+/// [`PropertyValue::Unknown`](crate::PropertyValue::Unknown) unless loading fails because of them,
+/// and to tiles whose GID doesn't belong to any tileset, which are left empty.
+///
+/// It also applies to contents that Tiled itself tolerates, which only make loading fail with
+/// [`Strictness::Strict`] and are warned about by default: tilesets whose `tilecount` disagrees
+/// with the size of their image, which keep the declared count, and layers of unknown kinds,
+/// which are kept as [`LayerType::Unknown`](crate::LayerType::Unknown).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Strictness {
+    /// Make loading fail, including for contents that Tiled tolerates.
+    Strict,
+    /// Make loading fail, except for contents that Tiled tolerates, which are warned about like
+    /// with [`Strictness::Warn`]. This is what the other [`Loader`] methods do.
+    #[default]
+    Error,
+    /// Keep loading, but record a [`ParseWarning`](crate::ParseWarning) in
+    /// [`Map::warnings`](crate::Map::warnings).
+    Warn,
+    /// Keep loading without any warning.
+    Ignore,
+}
+
+/// How properties and layers that share their name with a sibling are handled while loading, as
+/// set in [`LoadSettings::duplicates`].
+///
+/// Tiled doesn't allow a property name to be used twice in the same list, but files that were
+/// edited or merged by hand may still contain duplicates. Sibling layers with the same name are
+/// allowed, but make looking layers up by name ambiguous. Both properties and layers are kept by
+/// every variant except [`DuplicateHandling::Error`]; Only which of the duplicate properties ends
+/// up in the [`Properties`](crate::Properties) differs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateHandling {
+    /// Keep the last of the duplicate properties, and record a
+    /// [`ParseWarning`](crate::ParseWarning) in [`Map::warnings`](crate::Map::warnings).
+    #[default]
+    LastWins,
+    /// Keep the first of the duplicate properties, and record a
+    /// [`ParseWarning`](crate::ParseWarning) in [`Map::warnings`](crate::Map::warnings).
+    FirstWins,
+    /// Make loading fail.
+    Error,
+}
+
 /// A type used for loading [`Map`]s and [`Tileset`]s.
 ///
 /// Internally, it holds a [`ResourceCache`] that, as its name implies, caches intermediate loading
@@ -24,6 +579,7 @@ use crate::{
 pub struct Loader<Reader = FilesystemResourceReader, Cache: ResourceCache = DefaultResourceCache> {
     cache: Cache,
     reader: Reader,
+    settings: LoadSettings,
 }
 
 impl Loader {
@@ -33,6 +589,7 @@ impl Loader {
         Self {
             cache: DefaultResourceCache::new(),
             reader: FilesystemResourceReader::new(),
+            settings: LoadSettings::default(),
         }
     }
 }
@@ -79,6 +636,7 @@ impl<Reader> Loader<Reader, DefaultResourceCache> {
         Self {
             cache: DefaultResourceCache::new(),
             reader,
+            settings: LoadSettings::default(),
         }
     }
 }
@@ -124,6 +682,34 @@ impl<Reader, Cache: ResourceCache> Loader<Reader, Cache> {
     ///         _path: impl AsRef<tiled::ResourcePath>,
     ///         _template: Arc<tiled::Template>
     ///     ) {}
+    ///
+    ///     fn remove_tileset(
+    ///         &mut self,
+    ///         _path: impl AsRef<tiled::ResourcePath>,
+    ///     ) -> Option<Arc<tiled::Tileset>> {
+    ///         None
+    ///     }
+    ///
+    ///     fn remove_template(
+    ///         &mut self,
+    ///         _path: impl AsRef<tiled::ResourcePath>,
+    ///     ) -> Option<Arc<tiled::Template>> {
+    ///         None
+    ///     }
+    ///
+    ///     fn clear(&mut self) {}
+    ///
+    ///     fn tilesets(
+    ///         &self,
+    ///     ) -> impl Iterator<Item = (&tiled::ResourcePath, &Arc<tiled::Tileset>)> {
+    ///         std::iter::empty()
+    ///     }
+    ///
+    ///     fn templates(
+    ///         &self,
+    ///     ) -> impl Iterator<Item = (&tiled::ResourcePath, &Arc<tiled::Template>)> {
+    ///         std::iter::empty()
+    ///     }
     /// }
     ///
     /// let mut loader = Loader::with_cache_and_reader(
@@ -156,7 +742,11 @@ impl<Reader, Cache: ResourceCache> Loader<Reader, Cache> {
     /// # }
     /// ```
     pub fn with_cache_and_reader(cache: Cache, reader: Reader) -> Self {
-        Self { cache, reader }
+        Self {
+            cache,
+            reader,
+            settings: LoadSettings::default(),
+        }
     }
 
     /// Returns a reference to the loader's internal [`ResourceCache`].
@@ -183,6 +773,22 @@ impl<Reader, Cache: ResourceCache> Loader<Reader, Cache> {
     pub fn into_inner(self) -> (Cache, Reader) {
         (self.cache, self.reader)
     }
+
+    /// Returns the settings the loader loads files with.
+    pub fn settings(&self) -> &LoadSettings {
+        &self.settings
+    }
+
+    /// Returns a mutable reference to the settings the loader loads files with. Changing them
+    /// affects the files loaded afterwards, but not the ones that are already cached.
+    pub fn settings_mut(&mut self) -> &mut LoadSettings {
+        &mut self.settings
+    }
+
+    /// Creates the context a new load starts with, using the given settings or else the loader's.
+    fn context(&self, settings: Option<LoadSettings>) -> LoadContext {
+        LoadContext::new(settings.unwrap_or_else(|| self.settings.clone()))
+    }
 }
 
 impl<Reader: ResourceReader, Cache: ResourceCache> Loader<Reader, Cache> {
@@ -193,14 +799,123 @@ impl<Reader: ResourceReader, Cache: ResourceCache> Loader<Reader, Cache> {
     ///
     /// [internal loader cache]: Loader::cache()
     pub fn load_tmx_map(&mut self, path: impl AsRef<Path>) -> Result<Map> {
+        let context = self.context(None);
+        let mut read_from = SyncReadFrom(&mut self.reader);
+        crate::parse::xml::parse_map(path.as_ref(), &mut read_from, &mut self.cache, context)
+            .now_or_never()
+            .expect(
+                "synchronously loading a TMX map stayed pending; this is a bug, please report it",
+            )
+    }
+
+    /// Parses a file hopefully containing a Tiled map and tries to parse it, using the given
+    /// [`ResourceCache`] instead of the loader's internal one. All external files will be loaded
+    /// relative to the path given.
+    ///
+    /// All intermediate objects such as map tilesets and templates, including those loaded by
+    /// nested files, will be looked up and stored in `cache`. The [internal loader cache] is left
+    /// untouched.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// use tiled::{DefaultResourceCache, Loader, ResourceCache};
+    ///
+    /// let mut loader = Loader::new();
+    /// let mut preview_cache = DefaultResourceCache::new();
+    ///
+    /// let map = loader.load_tmx_map_with_cache(
+    ///     "assets/tiled_base64_external.tmx",
+    ///     &mut preview_cache,
+    /// )?;
+    ///
+    /// assert!(preview_cache.get_tileset("assets/tilesheet.tsx").is_some());
+    /// assert!(loader.cache().get_tileset("assets/tilesheet.tsx").is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [internal loader cache]: Loader::cache()
+    pub fn load_tmx_map_with_cache(
+        &mut self,
+        path: impl AsRef<Path>,
+        cache: &mut impl ResourceCache,
+    ) -> Result<Map> {
+        let context = self.context(None);
         let mut read_from = SyncReadFrom(&mut self.reader);
-        crate::parse::xml::parse_map(path.as_ref(), &mut read_from, &mut self.cache)
+        crate::parse::xml::parse_map(path.as_ref(), &mut read_from, cache, context)
             .now_or_never()
             .expect(
                 "synchronously loading a TMX map stayed pending; this is a bug, please report it",
             )
     }
 
+    /// Parses a file hopefully containing a Tiled map like [`Loader::load_tmx_map`] does, but
+    /// with the given settings instead of the loader's own, e.g. to skip layer contents that
+    /// aren't needed.
+    ///
+    /// See [`LoadSettings`] for more details. To only change some of the loader's settings, start
+    /// from a clone of [`Loader::settings`].
+    pub fn load_tmx_map_with_settings(
+        &mut self,
+        path: impl AsRef<Path>,
+        settings: LoadSettings,
+    ) -> Result<Map> {
+        let context = self.context(Some(settings));
+        let mut read_from = SyncReadFrom(&mut self.reader);
+        crate::parse::xml::parse_map(path.as_ref(), &mut read_from, &mut self.cache, context)
+            .now_or_never()
+            .expect(
+                "synchronously loading a TMX map stayed pending; this is a bug, please report it",
+            )
+    }
+
+    /// Parses a Tiled map from an already opened reader, such as a map embedded with
+    /// `include_bytes!` or downloaded over the network.
+    ///
+    /// The path given is never read from; It is only used as the base for resolving external
+    /// tilesets and templates, which are still loaded through the loader's [`ResourceReader`].
+    /// As such, it doesn't need to exist, but it must have a parent (e.g. `"map.tmx"` works but
+    /// `""` doesn't).
+    ///
+    /// All intermediate objects such as map tilesets will be stored in the [internal loader cache].
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// use std::io::Cursor;
+    ///
+    /// let mut loader = tiled::Loader::new();
+    /// let map = loader.load_tmx_map_from(
+    ///     Cursor::new(include_bytes!("../assets/tiled_base64_external.tmx")),
+    ///     // External tilesets will be loaded relative to this path.
+    ///     "assets/in_memory.tmx",
+    /// )?;
+    ///
+    /// assert_eq!(map.tilesets().len(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [internal loader cache]: Loader::cache()
+    pub fn load_tmx_map_from(
+        &mut self,
+        reader: impl BufRead,
+        path: impl AsRef<Path>,
+    ) -> Result<Map> {
+        let context = self.context(None);
+        let mut read_from = SyncReadFrom(&mut self.reader);
+        crate::parse::xml::parse_map_from(
+            SyncReader::new(reader),
+            path.as_ref(),
+            &mut read_from,
+            &mut self.cache,
+            context,
+        )
+        .now_or_never()
+        .expect("synchronously loading a TMX map stayed pending; this is a bug, please report it")
+    }
+
     /// Parses a file hopefully containing a Tiled tileset and tries to parse it. All external files
     /// will be loaded relative to the path given.
     ///
@@ -211,15 +926,190 @@ impl<Reader: ResourceReader, Cache: ResourceCache> Loader<Reader, Cache> {
     /// This function will **not** cache the tileset inside the internal [`ResourceCache`], since
     /// in this context it is not an intermediate object.
     pub fn load_tsx_tileset(&mut self, path: impl AsRef<Path>) -> Result<Tileset> {
+        let mut context = self.context(None);
+        let mut read_from = SyncReadFrom(&mut self.reader);
+        crate::parse::xml::parse_tileset(
+            path.as_ref(),
+            &mut read_from,
+            &mut self.cache,
+            &mut context,
+        )
+        .now_or_never()
+        .expect(
+            "synchronously loading a TSX tileset stayed pending; this is a bug, please report it",
+        )
+    }
+
+    /// Parses a Tiled tileset from an already opened reader, such as a tileset embedded with
+    /// `include_bytes!`.
+    ///
+    /// The path given is never read from; It is only used as the base for resolving the
+    /// tileset's images and templates, which are resolved exactly like in
+    /// [`Loader::load_tsx_tileset`]. As such, it doesn't need to exist, but it must have a parent.
+    ///
+    /// ## Note
+    /// This function will **not** cache the tileset inside the internal [`ResourceCache`], since
+    /// in this context it is not an intermediate object.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// use std::{io::Cursor, path::Path};
+    ///
+    /// let mut loader = tiled::Loader::new();
+    /// let tileset = loader.load_tsx_tileset_from(
+    ///     Cursor::new(include_bytes!("../assets/tilesheet.tsx")),
+    ///     "assets/tilesheet.tsx",
+    /// )?;
+    ///
+    /// assert_eq!(
+    ///     tileset.image.unwrap().source,
+    ///     Path::new("assets/tilesheet.png")
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_tsx_tileset_from(
+        &mut self,
+        reader: impl BufRead,
+        path: impl AsRef<Path>,
+    ) -> Result<Tileset> {
+        let mut context = self.context(None);
         let mut read_from = SyncReadFrom(&mut self.reader);
-        crate::parse::xml::parse_tileset(path.as_ref(), &mut read_from, &mut self.cache)
+        crate::parse::xml::parse_tileset_from(
+            SyncReader::new(reader),
+            path.as_ref(),
+            &mut read_from,
+            &mut self.cache,
+            &mut context,
+        )
+        .now_or_never()
+        .expect(
+            "synchronously loading a TSX tileset stayed pending; this is a bug, please report it",
+        )
+    }
+
+    /// Parses a file hopefully containing a Tiled map in the JSON format, usually with a `.tmj`
+    /// extension. All external files will be loaded relative to the path given, and its
+    /// tilesets and templates may be in either the XML or the JSON format, which is told apart by
+    /// their extension.
+    ///
+    /// The map is loaded into the same structures as with [`Loader::load_tmx_map`], and all
+    /// intermediate objects such as map tilesets will be stored in the [internal loader cache].
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut loader = tiled::Loader::new();
+    /// let tmj = loader.load_tmj_map("assets/tiled_json.tmj")?;
+    /// let tmx = loader.load_tmx_map("assets/tiled_json.tmx")?;
+    ///
+    /// assert_eq!(tmj, tmx);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [internal loader cache]: Loader::cache()
+    #[cfg(feature = "json")]
+    pub fn load_tmj_map(&mut self, path: impl AsRef<Path>) -> Result<Map> {
+        let context = self.context(None);
+        let mut read_from = SyncReadFrom(&mut self.reader);
+        crate::parse::json::parse_map(path.as_ref(), &mut read_from, &mut self.cache, context)
             .now_or_never()
             .expect(
-                "synchronously loading a TSX tileset stayed pending; this is a bug, please report it",
+                "synchronously loading a TMJ map stayed pending; this is a bug, please report it",
             )
     }
+
+    /// Parses a file hopefully containing a Tiled tileset in the JSON format, usually with a
+    /// `.tsj` extension. All external files will be loaded relative to the path given.
+    ///
+    /// ## Note
+    /// This function will **not** cache the tileset inside the internal [`ResourceCache`], since
+    /// in this context it is not an intermediate object.
+    #[cfg(feature = "json")]
+    pub fn load_tsj_tileset(&mut self, path: impl AsRef<Path>) -> Result<Tileset> {
+        let mut context = self.context(None);
+        let mut read_from = SyncReadFrom(&mut self.reader);
+        crate::parse::json::parse_tileset(
+            path.as_ref(),
+            &mut read_from,
+            &mut self.cache,
+            &mut context,
+        )
+        .now_or_never()
+        .expect(
+            "synchronously loading a TSJ tileset stayed pending; this is a bug, please report it",
+        )
+    }
+
+    /// Parses a file containing a Tiled map in either the TMX or the TMJ format, which is told
+    /// apart by the first character of the file that isn't whitespace. Everything else works like
+    /// [`Loader::load_tmx_map`] and [`Loader::load_tmj_map`].
+    #[cfg(feature = "json")]
+    pub fn load_map(&mut self, path: impl AsRef<Path>) -> Result<Map> {
+        let path = path.as_ref();
+        let is_json =
+            crate::parse::json::is_json_document(path, &mut SyncReadFrom(&mut self.reader))
+                .now_or_never()
+                .expect(
+                    "synchronously loading a map stayed pending; this is a bug, please report it",
+                )?;
+        if is_json {
+            self.load_tmj_map(path)
+        } else {
+            self.load_tmx_map(path)
+        }
+    }
+
+    /// Parses a Tiled world file. The paths of its maps are resolved relative to the path given.
+    ///
+    /// The maps placed by the world's patterns are found by matching them against the files
+    /// listed by [`ResourceReader::list_files`]. The maps themselves aren't loaded; Use
+    /// [`World::load_maps`](crate::World::load_maps) for that.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut loader = tiled::Loader::new();
+    /// let world = loader.load_world("assets/world/world_list.world")?;
+    ///
+    /// for (map_ref, map) in world.load_maps(&mut loader)? {
+    ///     println!("{:?} at ({}, {})", map_ref.path, map_ref.x, map_ref.y);
+    ///     assert_eq!(map.width, 2);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "world")]
+    pub fn load_world(&mut self, path: impl AsRef<Path>) -> Result<crate::World> {
+        crate::world::parse_world(path.as_ref(), &mut self.reader)
+    }
+
+    /// Parses a Tiled project file, which holds the custom property types used by its maps.
+    ///
+    /// The project isn't attached to the loader; Set it as [`LoadSettings::project`] for that.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// use std::sync::Arc;
+    ///
+    /// let mut loader = tiled::Loader::new();
+    /// let project = loader.load_project("assets/project/project.tiled-project")?;
+    /// loader.settings_mut().project = Some(Arc::new(project));
+    ///
+    /// let map = loader.load_tmx_map("assets/project/project_map.tmx")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "project")]
+    pub fn load_project(&mut self, path: impl AsRef<Path>) -> Result<Project> {
+        crate::project::parse_project(path.as_ref(), &mut self.reader)
+    }
 }
 
+#[cfg(feature = "async-tokio")]
 impl<Reader: AsyncResourceReader, Cache: ResourceCache> Loader<Reader, Cache> {
     /// Parses a file hopefully containing a Tiled map and tries to parse it. All external files
     /// will be loaded relative to the path given.
@@ -228,8 +1118,67 @@ impl<Reader: AsyncResourceReader, Cache: ResourceCache> Loader<Reader, Cache> {
     ///
     /// [internal loader cache]: Loader::cache()
     pub async fn load_tmx_map_async(&mut self, path: impl AsRef<Path>) -> Result<Map> {
+        let context = self.context(None);
         let mut read_from = AsyncReadFrom(&mut self.reader);
-        crate::parse::xml::parse_map(path.as_ref(), &mut read_from, &mut self.cache).await
+        crate::parse::xml::parse_map(path.as_ref(), &mut read_from, &mut self.cache, context).await
+    }
+
+    /// Parses a file hopefully containing a Tiled map and tries to parse it, using the given
+    /// [`ResourceCache`] instead of the loader's internal one. This is the async version of
+    /// [`Loader::load_tmx_map_with_cache`].
+    ///
+    /// All intermediate objects such as map tilesets and templates, including those loaded by
+    /// nested files, will be looked up and stored in `cache`. The [internal loader cache] is left
+    /// untouched.
+    ///
+    /// [internal loader cache]: Loader::cache()
+    pub async fn load_tmx_map_with_cache_async(
+        &mut self,
+        path: impl AsRef<Path>,
+        cache: &mut impl ResourceCache,
+    ) -> Result<Map> {
+        let context = self.context(None);
+        let mut read_from = AsyncReadFrom(&mut self.reader);
+        crate::parse::xml::parse_map(path.as_ref(), &mut read_from, cache, context).await
+    }
+
+    /// Parses a file hopefully containing a Tiled map with the given settings instead of the
+    /// loader's own. This is the async version of [`Loader::load_tmx_map_with_settings`].
+    pub async fn load_tmx_map_with_settings_async(
+        &mut self,
+        path: impl AsRef<Path>,
+        settings: LoadSettings,
+    ) -> Result<Map> {
+        let context = self.context(Some(settings));
+        let mut read_from = AsyncReadFrom(&mut self.reader);
+        crate::parse::xml::parse_map(path.as_ref(), &mut read_from, &mut self.cache, context).await
+    }
+
+    /// Parses a Tiled map from an already opened asynchronous reader. This is the async version of
+    /// [`Loader::load_tmx_map_from`].
+    ///
+    /// The path given is never read from; It is only used as the base for resolving external
+    /// tilesets and templates, which are still loaded through the loader's
+    /// [`AsyncResourceReader`].
+    ///
+    /// All intermediate objects such as map tilesets will be stored in the [internal loader cache].
+    ///
+    /// [internal loader cache]: Loader::cache()
+    pub async fn load_tmx_map_from_async(
+        &mut self,
+        reader: impl AsyncBufRead + Unpin,
+        path: impl AsRef<Path>,
+    ) -> Result<Map> {
+        let context = self.context(None);
+        let mut read_from = AsyncReadFrom(&mut self.reader);
+        crate::parse::xml::parse_map_from(
+            AsyncReader::new(reader),
+            path.as_ref(),
+            &mut read_from,
+            &mut self.cache,
+            context,
+        )
+        .await
     }
 
     /// Parses a file hopefully containing a Tiled tileset and tries to parse it. All external files
@@ -242,7 +1191,148 @@ impl<Reader: AsyncResourceReader, Cache: ResourceCache> Loader<Reader, Cache> {
     /// This function will **not** cache the tileset inside the internal [`ResourceCache`], since
     /// in this context it is not an intermediate object.
     pub async fn load_tsx_tileset_async(&mut self, path: impl AsRef<Path>) -> Result<Tileset> {
+        let mut context = self.context(None);
+        let mut read_from = AsyncReadFrom(&mut self.reader);
+        crate::parse::xml::parse_tileset(
+            path.as_ref(),
+            &mut read_from,
+            &mut self.cache,
+            &mut context,
+        )
+        .await
+    }
+
+    /// Parses a Tiled tileset from an already opened asynchronous reader. This is the async
+    /// version of [`Loader::load_tsx_tileset_from`].
+    ///
+    /// The path given is never read from; It is only used as the base for resolving the
+    /// tileset's images and templates.
+    ///
+    /// ## Note
+    /// This function will **not** cache the tileset inside the internal [`ResourceCache`], since
+    /// in this context it is not an intermediate object.
+    pub async fn load_tsx_tileset_from_async(
+        &mut self,
+        reader: impl AsyncBufRead + Unpin,
+        path: impl AsRef<Path>,
+    ) -> Result<Tileset> {
+        let mut context = self.context(None);
         let mut read_from = AsyncReadFrom(&mut self.reader);
-        crate::parse::xml::parse_tileset(path.as_ref(), &mut read_from, &mut self.cache).await
+        crate::parse::xml::parse_tileset_from(
+            AsyncReader::new(reader),
+            path.as_ref(),
+            &mut read_from,
+            &mut self.cache,
+            &mut context,
+        )
+        .await
     }
+
+    /// Parses a file hopefully containing a Tiled map in the JSON format. This is the async
+    /// version of [`Loader::load_tmj_map`].
+    ///
+    /// All intermediate objects such as map tilesets will be stored in the [internal loader cache].
+    ///
+    /// [internal loader cache]: Loader::cache()
+    #[cfg(feature = "json")]
+    pub async fn load_tmj_map_async(&mut self, path: impl AsRef<Path>) -> Result<Map> {
+        let context = self.context(None);
+        let mut read_from = AsyncReadFrom(&mut self.reader);
+        crate::parse::json::parse_map(path.as_ref(), &mut read_from, &mut self.cache, context).await
+    }
+
+    /// Parses a file hopefully containing a Tiled tileset in the JSON format. This is the async
+    /// version of [`Loader::load_tsj_tileset`].
+    ///
+    /// ## Note
+    /// This function will **not** cache the tileset inside the internal [`ResourceCache`], since
+    /// in this context it is not an intermediate object.
+    #[cfg(feature = "json")]
+    pub async fn load_tsj_tileset_async(&mut self, path: impl AsRef<Path>) -> Result<Tileset> {
+        let mut context = self.context(None);
+        let mut read_from = AsyncReadFrom(&mut self.reader);
+        crate::parse::json::parse_tileset(
+            path.as_ref(),
+            &mut read_from,
+            &mut self.cache,
+            &mut context,
+        )
+        .await
+    }
+
+    /// Parses a file containing a Tiled map in either the TMX or the TMJ format. This is the
+    /// async version of [`Loader::load_map`].
+    #[cfg(feature = "json")]
+    pub async fn load_map_async(&mut self, path: impl AsRef<Path>) -> Result<Map> {
+        let path = path.as_ref();
+        let is_json =
+            crate::parse::json::is_json_document(path, &mut AsyncReadFrom(&mut self.reader))
+                .await?;
+        if is_json {
+            self.load_tmj_map_async(path).await
+        } else {
+            self.load_tmx_map_async(path).await
+        }
+    }
+}
+
+/// Loads a Tiled map from the filesystem using a new [`Loader`], without having to construct one.
+///
+/// Since a new cache is created on each call, tilesets shared between maps will be loaded again
+/// every time. Use a [`Loader`] instead if you need to load more than one map.
+///
+/// With the `json` feature, maps in the TMJ format are loaded as well, like with
+/// [`Loader::load_map`].
+///
+/// ## Example
+/// ```
+/// # fn main() -> tiled::Result<()> {
+/// let map = tiled::load_map("assets/tiled_base64_external.tmx")?;
+/// # assert_eq!(map.tilesets().len(), 1);
+/// # Ok(())
+/// # }
+/// ```
+pub fn load_map(path: impl AsRef<Path>) -> Result<Map> {
+    #[cfg(feature = "json")]
+    {
+        Loader::new().load_map(path)
+    }
+    #[cfg(not(feature = "json"))]
+    {
+        Loader::new().load_tmx_map(path)
+    }
+}
+
+/// Loads a Tiled tileset from the filesystem using a new [`Loader`], without having to construct
+/// one.
+pub fn load_tileset(path: impl AsRef<Path>) -> Result<Tileset> {
+    Loader::new().load_tsx_tileset(path)
+}
+
+/// Asynchronously loads a Tiled map from the filesystem using a new [`Loader`], without having to
+/// construct one. This is the async version of [`load_map`], and requires a [`tokio`] runtime.
+///
+/// The loader and its cache are owned by the returned future, which is [`Send`] as long as the
+/// path given is.
+#[cfg(feature = "async-tokio")]
+pub async fn load_map_async(path: impl AsRef<Path>) -> Result<Map> {
+    #[cfg(feature = "json")]
+    {
+        Loader::new().load_map_async(path).await
+    }
+    #[cfg(not(feature = "json"))]
+    {
+        Loader::new().load_tmx_map_async(path).await
+    }
+}
+
+/// Asynchronously loads a Tiled tileset from the filesystem using a new [`Loader`], without having
+/// to construct one. This is the async version of [`load_tileset`], and requires a [`tokio`]
+/// runtime.
+///
+/// The loader and its cache are owned by the returned future, which is [`Send`] as long as the
+/// path given is.
+#[cfg(feature = "async-tokio")]
+pub async fn load_tileset_async(path: impl AsRef<Path>) -> Result<Tileset> {
+    Loader::new().load_tsx_tileset_async(path).await
 }
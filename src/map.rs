@@ -1,28 +1,67 @@
 //! Structures related to Tiled maps.
 
-use std::{collections::HashMap, fmt, path::Path, str::FromStr, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    ops::Range,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
 
 use quick_xml::events::attributes::Attribute;
 
 use crate::{
     error::{Error, Result},
-    layers::{LayerData, LayerTag},
+    layers::{LayerData, LayerTag, LayersRecursive},
     parse::xml::{Parser, ReadFrom, Reader},
     properties::{parse_properties, Color, Properties},
+    stats::{HeapSize, MapStats},
     tileset::Tileset,
-    util::{get_attrs, parse_tag},
-    EmbeddedParseResultType, Layer, ResourceCache,
+    util::{get_attrs, normalize_path, parse_tag},
+    ChunkData, EmbeddedParseResultType, ImageLayer, Layer, LayerType, LoadProgress, Object,
+    ObjectLayer, ParseWarning, ResourceCache, TileId, TileLayer, TileLocation, TilesetLocation,
+    ValidationIssue, WangSet,
 };
 
-pub(crate) struct MapTilesetGid {
+/// A tileset along with the first [`Gid`] it is assigned to in a map.
+///
+/// Maps only keep their tilesets once loaded, so lists of these are mostly useful to resolve GIDs
+/// that refer to the tilesets of a map from outside of it, for instance when a world or a custom
+/// format references tiles of several maps. See [`get_tileset_for_gid`] and
+/// [`LayerTileData::from_bits`](crate::LayerTileData::from_bits).
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapTilesetGid {
+    /// The first GID of the tileset. The tile with local ID 0 is assigned to it.
     pub first_gid: Gid,
+    /// The tileset the GIDs refer to.
     pub tileset: Arc<Tileset>,
 }
 
+/// Finds the tileset a [`Gid`] belongs to, returning both the tileset and its index in the slice.
+///
+/// This is the tileset with the highest first GID that isn't greater than `gid`; `tilesets` don't
+/// need to be sorted, since map files may list them in any order. Returns [`None`] if `gid` is
+/// lower than the first GID of every tileset. Note that the flip flags must have been removed from
+/// the GID beforehand. Use [`LayerTileData::from_bits`](crate::LayerTileData::from_bits) to resolve
+/// raw values as they are stored in a file.
+pub fn get_tileset_for_gid(
+    tilesets: &[MapTilesetGid],
+    gid: Gid,
+) -> Option<(usize, &MapTilesetGid)> {
+    tilesets
+        .iter()
+        .enumerate()
+        .filter(|(_idx, ts)| ts.first_gid <= gid)
+        .max_by_key(|(_idx, ts)| ts.first_gid)
+}
+
 /// All Tiled map files will be parsed into this. Holds all the layers and tilesets.
 #[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Map {
     version: String,
+    tiled_version: Option<String>,
     /// The way tiles are laid out in the map.
     pub orientation: Orientation,
     /// Width of the map, in tiles.
@@ -53,8 +92,17 @@ pub struct Map {
     pub stagger_axis: StaggerAxis,
     /// The stagger index of Hexagonal/Staggered map.
     pub stagger_index: StaggerIndex,
+    /// The length in pixels of the sides of the tiles of a Hexagonal map that are parallel to
+    /// its [stagger axis](Self::stagger_axis), or 0 if not set.
+    pub hex_side_length: u32,
     /// The tilesets present on this map.
     tilesets: Vec<Arc<Tileset>>,
+    /// The first GID of each of the tilesets, in the same order.
+    first_gids: Vec<Gid>,
+    /// The `source` attribute of each of the tilesets as written in the map file, in the same
+    /// order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    tileset_raw_sources: Vec<Option<String>>,
     /// The layers present in this map.
     layers: Vec<LayerData>,
     /// The custom properties of this map.
@@ -64,6 +112,67 @@ pub struct Map {
     infinite: bool,
     /// The type of the map, which is arbitrary and set by the user.
     pub user_type: Option<String>,
+    /// The editor-specific settings saved alongside this map, if any.
+    pub editor_settings: Option<EditorSettings>,
+    /// The ID that Tiled will give to the next layer created in this map, if present. Used to
+    /// keep layer IDs unique.
+    pub next_layer_id: Option<u32>,
+    /// The ID that Tiled will give to the next object created in this map, if present. Used to
+    /// keep object IDs unique.
+    pub next_object_id: Option<u32>,
+    warnings: Vec<ParseWarning>,
+    /// The location of each object by ID, as the path of indices to its object layer followed by
+    /// its index in that layer.
+    object_index: HashMap<u32, (Vec<usize>, usize)>,
+}
+
+/// Settings used by the Tiled editor when working with a map, such as where it gets exported to.
+/// They don't affect how the map looks.
+///
+/// Also see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#editorsettings).
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EditorSettings {
+    /// The width and height (in tiles) of the chunks used for infinite maps when saving, if set.
+    /// Tiled uses 16x16 chunks by default.
+    pub chunk_size: Option<(u32, u32)>,
+    /// The last file the map was exported to, if any.
+    pub export_target: Option<String>,
+    /// The last format the map was exported with, if any.
+    pub export_format: Option<String>,
+}
+
+impl EditorSettings {
+    async fn parse_xml<R: Reader>(parser: &mut Parser<R>) -> Result<EditorSettings> {
+        let mut settings = EditorSettings::default();
+        let mut buffer = Vec::new();
+        parse_tag!(parser => &mut buffer, "editorsettings", {
+            "chunksize" => for attrs {
+                let (width, height) = get_attrs!(
+                    for v in attrs of "chunksize" {
+                        Some("width") => width ?= v.parse::<u32>(),
+                        Some("height") => height ?= v.parse::<u32>(),
+                    }
+                    (width, height)
+                );
+                settings.chunk_size = Some((width.unwrap_or(16), height.unwrap_or(16)));
+                Ok(())
+            },
+            "export" => for attrs {
+                let (target, format) = get_attrs!(
+                    for v in attrs of "export" {
+                        Some("target") => target = v.to_owned(),
+                        Some("format") => format = v.to_owned(),
+                    }
+                    (target, format)
+                );
+                settings.export_target = target;
+                settings.export_format = format;
+                Ok(())
+            },
+        });
+        Ok(settings)
+    }
 }
 
 impl Map {
@@ -73,21 +182,336 @@ impl Map {
         self.version.as_ref()
     }
 
+    /// The version of Tiled this map was saved with, if present. Equivalent to the map file's
+    /// `tiledversion` attribute.
+    ///
+    /// Both versions can be parsed into a [`TiledVersion`] for comparing them.
+    pub fn tiled_version(&self) -> Option<&str> {
+        self.tiled_version.as_deref()
+    }
+
     /// Whether this map is infinite. An infinite map has no fixed size and can grow in all
     /// directions. Its layer data is stored in chunks. This value determines whether the map's
     /// tile layers are [`FiniteTileLayer`](crate::FiniteTileLayer)s or [`crate::InfiniteTileLayer`](crate::InfiniteTileLayer)s.
     pub fn infinite(&self) -> bool {
         self.infinite
     }
+
+    /// The problems found while loading this map that didn't make loading fail, including the
+    /// ones from the tilesets and templates loaded along with it.
+    ///
+    /// Duplicate property and layer names are reported according to
+    /// [`LoadSettings::duplicates`](crate::LoadSettings::duplicates), and tilesets that couldn't
+    /// be read according to
+    /// [`LoadSettings::placeholder_missing_tilesets`](crate::LoadSettings::placeholder_missing_tilesets).
+    /// The other problems depend on [`LoadSettings::strictness`](crate::LoadSettings::strictness):
+    /// Contents that Tiled itself tolerates, such as layers of unknown kinds and tilesets whose
+    /// `tilecount` disagrees with their image, are recorded under the default
+    /// [`Strictness::Error`] as well as [`Strictness::Warn`], which also records the problems that
+    /// make loading fail otherwise. None of them are recorded under [`Strictness::Ignore`].
+    ///
+    /// Tilesets and templates that were already cached don't report their warnings again.
+    ///
+    /// [`Strictness::Error`]: crate::Strictness::Error
+    /// [`Strictness::Warn`]: crate::Strictness::Warn
+    /// [`Strictness::Ignore`]: crate::Strictness::Ignore
+    pub fn warnings(&self) -> &[ParseWarning] {
+        &self.warnings
+    }
+
+    /// Counts the contents of this map, including the layers nested in groups, and estimates the
+    /// memory it uses along with its tilesets.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_group_layers.tmx")
+    ///     .unwrap();
+    /// let stats = map.statistics();
+    ///
+    /// println!(
+    ///     "{} tile layers, {} objects, about {} bytes",
+    ///     stats.tile_layers, stats.objects, stats.heap_size
+    /// );
+    /// # }
+    /// ```
+    pub fn statistics(&self) -> MapStats {
+        let mut stats = MapStats {
+            heap_size: self.heap_size(),
+            ..Default::default()
+        };
+        for (layer, _) in self.layers_recursive() {
+            match layer.layer_type() {
+                LayerType::Tiles(TileLayer::Finite(layer)) => {
+                    stats.tile_layers += 1;
+                    stats.tile_cells += layer.width() as usize * layer.height() as usize;
+                }
+                LayerType::Tiles(TileLayer::Infinite(layer)) => {
+                    stats.tile_layers += 1;
+                    stats.tile_cells += layer.chunks().len() * ChunkData::TILE_COUNT;
+                }
+                LayerType::Objects(layer) => {
+                    stats.object_layers += 1;
+                    stats.objects += layer.object_data().len();
+                }
+                LayerType::Image(_) => stats.image_layers += 1,
+                LayerType::Group(_) => stats.group_layers += 1,
+                LayerType::Unknown { .. } => stats.unknown_layers += 1,
+            }
+        }
+        // The same tileset may be used more than once
+        let mut tilesets: Vec<&Arc<Tileset>> = Vec::new();
+        for tileset in &self.tilesets {
+            if !tilesets.iter().any(|other| Arc::ptr_eq(other, tileset)) {
+                tilesets.push(tileset);
+            }
+        }
+        stats.tilesets = tilesets.len();
+        for tileset in tilesets {
+            let tileset_stats = tileset.statistics();
+            stats.animated_tiles += tileset_stats.animated_tiles;
+            // Tilesets are stored in their own allocation along with their reference counts
+            stats.heap_size += std::mem::size_of::<Tileset>()
+                + 2 * std::mem::size_of::<usize>()
+                + tileset_stats.heap_size;
+        }
+        stats
+    }
+}
+
+/// Only covers the memory owned by the map itself, not the contents of its tilesets.
+impl HeapSize for Map {
+    fn heap_size(&self) -> usize {
+        self.version.heap_size()
+            + self.tiled_version.heap_size()
+            + self.tilesets.capacity() * std::mem::size_of::<Arc<Tileset>>()
+            + self.first_gids.heap_size()
+            + self.tileset_raw_sources.heap_size()
+            + self.layers.heap_size()
+            + self.properties.heap_size()
+            + self.user_type.heap_size()
+            + self.editor_settings.heap_size()
+            + self.warnings.heap_size()
+            + self.object_index.heap_size()
+    }
 }
 
 impl Map {
     /// Get a reference to the map's tilesets.
+    ///
+    /// They are in the order the map file lists them in, which is usually but not necessarily
+    /// ascending first GID order.
     #[inline]
     pub fn tilesets(&self) -> &[Arc<Tileset>] {
         self.tilesets.as_ref()
     }
 
+    /// Returns the tilesets that tile objects based on templates get their tile from, but that
+    /// aren't one of [`Map::tilesets`], such as a template's embedded tileset. Each is returned
+    /// once, in the order the objects using them are found in.
+    ///
+    /// Along with [`Map::tilesets`], these are all the tilesets drawing this map can require, so
+    /// this can be used to load their images up front.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_object_template_gid.tmx")
+    ///     .unwrap();
+    ///
+    /// for tileset in map.tilesets().iter().chain(map.template_tilesets()) {
+    ///     println!("{:?}", tileset.image.as_ref().map(|image| &image.source));
+    /// }
+    /// # }
+    /// ```
+    pub fn template_tilesets(&self) -> Vec<&Arc<Tileset>> {
+        let mut tilesets: Vec<&Arc<Tileset>> = Vec::new();
+        for layer in self.object_layers_recursive() {
+            for object in layer.objects() {
+                let location = object.get_tile().map(|tile| tile.data.tileset_location());
+                if let Some(TilesetLocation::Template(tileset)) = location {
+                    let is_known = |known: &Arc<Tileset>| Arc::ptr_eq(known, tileset);
+                    if !self.tilesets.iter().any(is_known)
+                        && !tilesets.iter().any(|known| is_known(known))
+                    {
+                        tilesets.push(tileset);
+                    }
+                }
+            }
+        }
+        tilesets
+    }
+
+    /// Returns the first GID of each of the map's tilesets, in the same order as
+    /// [`Map::tilesets`].
+    #[inline]
+    pub fn first_gids(&self) -> &[Gid] {
+        self.first_gids.as_ref()
+    }
+
+    /// Returns the `source` attribute of each of the map's tilesets exactly as written in the map
+    /// file, in the same order as [`Map::tilesets`]. Embedded tilesets have no source and are
+    /// [`None`].
+    ///
+    /// Unlike [`Tileset::source`], which is the resolved path the tileset was loaded from, these
+    /// keep their original separators and `..` segments, which makes them suitable for writing
+    /// the map back out unchanged.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// # use std::path::Path;
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/folder/tiled_relative_paths.tmx")?;
+    ///
+    /// assert_eq!(map.tileset_raw_sources()[0].as_deref(), Some("../tilesheet.tsx"));
+    /// assert_eq!(
+    ///     map.tilesets()[0].source.as_deref(),
+    ///     Some(Path::new("assets/tilesheet.tsx"))
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn tileset_raw_sources(&self) -> &[Option<String>] {
+        self.tileset_raw_sources.as_ref()
+    }
+
+    /// Iterates through the map's tilesets along with their first GID, in the same order as
+    /// [`Map::tilesets`].
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_csv_infinite_flipped.tmx")
+    ///     .unwrap();
+    ///
+    /// for (index, (first_gid, tileset)) in map.tilesets_with_first_gids().enumerate() {
+    ///     let gids = map.gid_range_of(index);
+    ///     assert_eq!(gids.start, first_gid.0);
+    ///     println!("{}: GIDs {} to {}", tileset.name, gids.start, gids.end - 1);
+    /// }
+    /// # }
+    /// ```
+    pub fn tilesets_with_first_gids(
+        &self,
+    ) -> impl ExactSizeIterator<Item = (Gid, &Arc<Tileset>)> + '_ {
+        self.first_gids.iter().copied().zip(self.tilesets.iter())
+    }
+
+    /// Returns the tileset loaded from the TSX file at `path`, along with its index in
+    /// [`Map::tilesets`], by comparing it against [`Tileset::source`].
+    ///
+    /// Both paths are normalized lexically first, so `./tilesets/terrain.tsx` and
+    /// `tilesets/terrain.tsx` match, but no filesystem access is made to resolve them otherwise:
+    /// `path` should be relative to the same directory as the path the map was loaded from. If the
+    /// map uses the same file more than once, the first tileset is returned.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/folder/tiled_relative_paths.tmx")?;
+    ///
+    /// let (index, tileset) = map.tileset_by_source("./assets/tilesheet.tsx").unwrap();
+    /// assert_eq!(index, 0);
+    /// assert_eq!(tileset.name, "tilesheet");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tileset_by_source(&self, path: impl AsRef<Path>) -> Option<(usize, &Arc<Tileset>)> {
+        let path = normalize_path(path.as_ref());
+        self.tilesets.iter().enumerate().find(|(_, tileset)| {
+            tileset
+                .source
+                .as_deref()
+                .is_some_and(|source| normalize_path(source) == path)
+        })
+    }
+
+    /// Returns the first of the map's tilesets named `name`, along with its index in
+    /// [`Map::tilesets`]. This is mostly useful for embedded tilesets, which have no
+    /// [source](Tileset::source) to look them up by; Names aren't required to be unique.
+    pub fn tileset_by_name(&self, name: &str) -> Option<(usize, &Arc<Tileset>)> {
+        self.tilesets
+            .iter()
+            .enumerate()
+            .find(|(_, tileset)| tileset.name == name)
+    }
+
+    /// Returns the index in [`Map::tilesets`] of `tileset`, which is compared by identity with
+    /// [`Arc::ptr_eq`] rather than by value, or [`None`] if it isn't one of the map's tilesets.
+    pub fn tileset_index_of(&self, tileset: &Arc<Tileset>) -> Option<usize> {
+        self.tilesets
+            .iter()
+            .position(|known| Arc::ptr_eq(known, tileset))
+    }
+
+    /// Returns the GIDs assigned to the tileset at the given index of [`Map::tilesets`].
+    ///
+    /// The range ends at the next first GID of the map's tilesets, or, for the tileset with the
+    /// highest first GID, after its last tile ID. Tile IDs above [`Tileset::tilecount`] are taken
+    /// into account, as those of image collections may be.
+    ///
+    /// # Panics
+    /// Panics if `tileset_index` is out of bounds.
+    pub fn gid_range_of(&self, tileset_index: usize) -> Range<u32> {
+        let start = self.first_gids[tileset_index].0;
+        let next_first_gid = self
+            .first_gids
+            .iter()
+            .map(|gid| gid.0)
+            .filter(|&gid| gid > start)
+            .min();
+        let end = match next_first_gid {
+            Some(end) => end,
+            None => {
+                let tileset = &self.tilesets[tileset_index];
+                let id_count = tileset
+                    .defined_tiles()
+                    .map(|(id, _)| id + 1)
+                    .fold(tileset.tilecount, u32::max);
+                start.saturating_add(id_count)
+            }
+        };
+        start..end
+    }
+
+    /// Replaces the external tilesets of this map with the ones in `mapping` that have the same
+    /// source path, e.g. to make several maps of a world share tilesets loaded separately.
+    ///
+    /// Tilesets keep their position in [`Map::tilesets`], so the tileset indices stored in the
+    /// map's tiles stay valid. Embedded tilesets are left untouched.
+    ///
+    /// ## Errors
+    /// Returns [`Error::UnmatchedTilesets`] with the source paths of every external tileset that
+    /// has no replacement in `mapping`. The map is not modified in that case.
+    pub fn remap_tilesets(&mut self, mapping: &HashMap<PathBuf, Arc<Tileset>>) -> Result<()> {
+        let paths: Vec<PathBuf> = self
+            .tilesets
+            .iter()
+            .filter_map(|tileset| tileset.source.as_ref())
+            .filter(|source| !mapping.contains_key(*source))
+            .cloned()
+            .collect();
+        if !paths.is_empty() {
+            return Err(Error::UnmatchedTilesets { paths });
+        }
+
+        for tileset in self.tilesets.iter_mut() {
+            if let Some(replacement) = tileset.source.as_ref().and_then(|s| mapping.get(s)) {
+                *tileset = replacement.clone();
+            }
+        }
+        Ok(())
+    }
+
     /// Get an iterator over all the layers in the map in ascending order of their layer index.
     ///
     /// ## Example
@@ -119,10 +543,576 @@ impl Map {
         self.layers.iter().map(move |layer| Layer::new(self, layer))
     }
 
+    /// Returns an iterator over the top-level tile layers of this map, in document order.
+    ///
+    /// Tile layers nested inside group layers are skipped; Use [`Map::tile_layers_recursive`] to
+    /// include them.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// #
+    /// # fn main() {
+    /// # struct Renderer;
+    /// # impl Renderer {
+    /// #     fn render(&self, _: tiled::TileLayer) {}
+    /// # }
+    /// # let my_renderer = Renderer;
+    /// # let map = Loader::new()
+    /// #     .load_tmx_map("assets/tiled_group_layers.tmx")
+    /// #     .unwrap();
+    /// #
+    /// for layer in map.tile_layers() {
+    ///     my_renderer.render(layer);
+    /// }
+    /// # }
+    /// ```
+    pub fn tile_layers(&self) -> impl Iterator<Item = TileLayer<'_>> {
+        self.layers().filter_map(Layer::as_tile_layer)
+    }
+
+    /// Returns an iterator over the top-level object layers of this map, in document order.
+    ///
+    /// Object layers nested inside group layers are skipped; Use
+    /// [`Map::object_layers_recursive`] to include them.
+    pub fn object_layers(&self) -> impl Iterator<Item = ObjectLayer<'_>> {
+        self.layers().filter_map(Layer::as_object_layer)
+    }
+
+    /// Returns an iterator over the top-level image layers of this map, in document order.
+    ///
+    /// Image layers nested inside group layers are skipped; Use [`Map::image_layers_recursive`]
+    /// to include them.
+    pub fn image_layers(&self) -> impl Iterator<Item = ImageLayer<'_>> {
+        self.layers().filter_map(Layer::as_image_layer)
+    }
+
+    /// Returns an iterator over all the tile layers of this map, including the ones nested inside
+    /// group layers, in document order. Group layers themselves are never yielded.
+    pub fn tile_layers_recursive(&self) -> impl Iterator<Item = TileLayer<'_>> {
+        self.layers_recursive()
+            .filter_map(|(layer, _)| layer.as_tile_layer())
+    }
+
+    /// Returns an iterator over all the object layers of this map, including the ones nested
+    /// inside group layers, in document order. Group layers themselves are never yielded.
+    pub fn object_layers_recursive(&self) -> impl Iterator<Item = ObjectLayer<'_>> {
+        self.layers_recursive()
+            .filter_map(|(layer, _)| layer.as_object_layer())
+    }
+
+    /// Returns an iterator over all the image layers of this map, including the ones nested
+    /// inside group layers, in document order. Group layers themselves are never yielded.
+    pub fn image_layers_recursive(&self) -> impl Iterator<Item = ImageLayer<'_>> {
+        self.layers_recursive()
+            .filter_map(|(layer, _)| layer.as_image_layer())
+    }
+
     /// Returns the layer that has the specified index, if it exists.
     pub fn get_layer(&self, index: usize) -> Option<Layer> {
         self.layers.get(index).map(|data| Layer::new(self, data))
     }
+
+    /// Returns the first top-level layer with the specified name, if it exists.
+    ///
+    /// Layers nested inside group layers are not searched; Use [`Map::find_layer`] for that.
+    pub fn get_layer_by_name(&self, name: &str) -> Option<Layer<'_>> {
+        self.layers().find(|layer| layer.name == name)
+    }
+
+    /// Returns the layer with the specified ID, including the ones nested inside group layers, if
+    /// it exists.
+    ///
+    /// Unlike names and indices, IDs stay the same when layers are renamed or reordered. If
+    /// several layers share the same ID, which can happen in hand-edited files, the first one in
+    /// the order of [`Map::layers_recursive`] is returned. Layers without an ID are never
+    /// returned, since their [`id`](crate::LayerData::id) is 0.
+    pub fn get_layer_by_id(&self, id: u32) -> Option<Layer<'_>> {
+        if id == 0 {
+            return None;
+        }
+        self.find_layer(|layer| layer.id() == id)
+    }
+
+    /// Returns an iterator over all the layers of this map, including the ones nested inside group
+    /// layers, alongside their depth in the layer hierarchy.
+    ///
+    /// Layers are visited depth-first in document order, so a group layer is always yielded right
+    /// before its children.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// # fn main() {
+    /// # let map = Loader::new()
+    /// #     .load_tmx_map("assets/tiled_group_layers.tmx")
+    /// #     .unwrap();
+    /// for (layer, depth) in map.layers_recursive() {
+    ///     println!("{}{}", "  ".repeat(depth), layer.name);
+    /// }
+    /// # }
+    /// ```
+    #[inline]
+    pub fn layers_recursive(&self) -> LayersRecursive<'_> {
+        LayersRecursive::new(self, &self.layers)
+    }
+
+    /// Returns the highest ID among the objects of this map's object layers, including the ones
+    /// nested inside group layers, or [`None`] if the map has no objects.
+    ///
+    /// All object IDs should be lower than [`Map::next_object_id`]; This can be used to check
+    /// that is the case.
+    pub fn max_used_object_id(&self) -> Option<u32> {
+        self.object_layers_recursive()
+            .flat_map(|layer| layer.objects().map(|object| object.id()))
+            .max()
+    }
+
+    /// Returns the object with the specified ID, searching the object layers nested inside group
+    /// layers as well, if it exists.
+    ///
+    /// Object IDs are unique within a map, so this is how `object` properties
+    /// ([`PropertyValue::ObjectValue`](crate::PropertyValue::ObjectValue)) refer to objects; Also
+    /// see [`Object::resolve_object_property`]. If several objects share the same ID, which can
+    /// happen in hand-edited files, the first one in the order of [`Map::layers_recursive`] is
+    /// returned.
+    ///
+    /// Objects are indexed when the map is loaded, so lookups don't go through all of its layers.
+    pub fn get_object_by_id(&self, id: u32) -> Option<Object<'_>> {
+        let (path, object_index) = self.object_index.get(&id)?;
+        let (first, rest) = path.split_first()?;
+        let mut layer = self.get_layer(*first)?;
+        for &index in rest {
+            layer = layer.as_group_layer()?.get_layer(index)?;
+        }
+        layer.as_object_layer()?.get_object(*object_index)
+    }
+
+    /// Checks the contents of this map for problems that don't make loading fail but are likely to
+    /// cause issues further down the line, such as tiles that don't exist or duplicate object
+    /// IDs. Returns all of the problems found, in the order of [`Map::layers_recursive`].
+    ///
+    /// This goes through every tile and object of the map, so it isn't done when loading a map
+    /// unless [`LoadSettings::validate`](crate::LoadSettings::validate) is set.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = tiled::Loader::new().load_tmx_map("assets/tiled_base64_external.tmx")?;
+    ///
+    /// for issue in map.validate() {
+    ///     eprintln!("{}", issue);
+    /// }
+    /// # assert!(map.validate().is_empty());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        let mut object_ids = HashSet::new();
+        for (layer, _) in self.layers_recursive() {
+            let check_tile = |issues: &mut Vec<ValidationIssue>,
+                              tileset: &Tileset,
+                              tile_id: TileId,
+                              location: TileLocation| {
+                if tileset.is_placeholder() {
+                    issues.push(ValidationIssue::MissingTileset {
+                        layer_id: layer.id(),
+                        layer_name: layer.name.clone(),
+                        location,
+                        tileset_source: tileset.source.clone(),
+                    });
+                } else if !tileset.contains_tile(tile_id) {
+                    issues.push(ValidationIssue::TileOutOfRange {
+                        layer_id: layer.id(),
+                        layer_name: layer.name.clone(),
+                        location,
+                        tileset_name: tileset.name.clone(),
+                        tile_id,
+                    });
+                }
+            };
+
+            match layer.layer_type() {
+                LayerType::Tiles(tiles) => {
+                    for ((x, y), tile) in tiles.tiles() {
+                        check_tile(
+                            &mut issues,
+                            tile.get_tileset(),
+                            tile.id(),
+                            TileLocation::Layer { x, y },
+                        );
+                    }
+                }
+                LayerType::Objects(objects) => {
+                    for object in objects.objects() {
+                        let object_id = object.id();
+                        if object_id != 0 && !object_ids.insert(object_id) {
+                            issues.push(ValidationIssue::DuplicateObjectId {
+                                layer_id: layer.id(),
+                                layer_name: layer.name.clone(),
+                                object_id,
+                            });
+                        }
+                        if !object.x.is_finite() || !object.y.is_finite() {
+                            issues.push(ValidationIssue::InvalidObjectCoordinates {
+                                layer_id: layer.id(),
+                                layer_name: layer.name.clone(),
+                                object_id,
+                            });
+                        }
+                        if let Some(tile) = object.get_tile() {
+                            check_tile(
+                                &mut issues,
+                                tile.get_tileset(),
+                                tile.id(),
+                                TileLocation::Object { object_id },
+                            );
+                        }
+                    }
+                }
+                LayerType::Image(_) | LayerType::Group(_) | LayerType::Unknown { .. } => {}
+            }
+        }
+        issues
+    }
+
+    /// Returns the first layer, including the ones nested inside group layers, that matches the
+    /// given predicate. Layers are searched in the same order as [`Map::layers_recursive`].
+    pub fn find_layer(&self, mut predicate: impl FnMut(&Layer) -> bool) -> Option<Layer<'_>> {
+        self.layers_recursive()
+            .map(|(layer, _)| layer)
+            .find(|layer| predicate(layer))
+    }
+
+    /// Returns the first Wang set with the given name among the map's tilesets, alongside the
+    /// tileset it belongs to. Tilesets are searched in the same order as [`Map::tilesets`], and
+    /// may be either embedded in the map or external.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_embedded_wangsets.tmx")?;
+    ///
+    /// let (tileset, cliffs) = map.find_wang_set("cliffs").unwrap();
+    /// assert_eq!(tileset.name, "terrain");
+    /// assert_eq!(cliffs.color("rock").unwrap().0, 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn find_wang_set(&self, name: &str) -> Option<(&Arc<Tileset>, &WangSet)> {
+        self.tilesets
+            .iter()
+            .find_map(|tileset| Some((tileset, tileset.wang_set(name)?)))
+    }
+}
+
+impl Map {
+    /// Returns the pixel position of the top-left corner of the bounding rectangle of the tile at
+    /// the given position, according to the map's [orientation](Self::orientation).
+    ///
+    /// The projections are the same ones Tiled uses to render maps, so for all orientations but
+    /// [`Orientation::Orthogonal`] tiles overlap each others' bounding rectangles. Tile positions
+    /// may be negative, which happens in infinite maps.
+    ///
+    /// Isometric maps are laid out so that the leftmost tile of the map's declared
+    /// [width](Self::width) and [height](Self::height) touches the left edge of the map.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_base64.tmx")
+    ///     .unwrap();
+    ///
+    /// let (x, y) = map.tile_to_pixel(3, 2);
+    /// let center = (x + map.tile_width as f32 / 2.0, y + map.tile_height as f32 / 2.0);
+    /// assert_eq!(map.pixel_to_tile(center.0, center.1), (3, 2));
+    /// # }
+    /// ```
+    pub fn tile_to_pixel(&self, x: i32, y: i32) -> (f32, f32) {
+        match self.orientation {
+            Orientation::Orthogonal => (
+                x as f32 * self.tile_width as f32,
+                y as f32 * self.tile_height as f32,
+            ),
+            Orientation::Isometric => {
+                let half_width = self.tile_width as f32 / 2.0;
+                let half_height = self.tile_height as f32 / 2.0;
+                (
+                    (x - y) as f32 * half_width + self.isometric_origin_x() - half_width,
+                    (x + y) as f32 * half_height,
+                )
+            }
+            Orientation::Staggered | Orientation::Hexagonal => {
+                let p = StaggerParams::new(self);
+                if p.stagger_x {
+                    let mut pixel_y = y * (p.tile_height + p.side_length_y);
+                    if p.is_staggered(x) {
+                        pixel_y += p.row_height;
+                    }
+                    ((x * p.column_width) as f32, pixel_y as f32)
+                } else {
+                    let mut pixel_x = x * (p.tile_width + p.side_length_x);
+                    if p.is_staggered(y) {
+                        pixel_x += p.column_width;
+                    }
+                    (pixel_x as f32, (y * p.row_height) as f32)
+                }
+            }
+        }
+    }
+
+    /// Returns the position of the tile that contains the given pixel position, according to the
+    /// map's [orientation](Self::orientation). This is the inverse of [`Map::tile_to_pixel`].
+    ///
+    /// The position returned may be outside of the map's bounds, including negative positions.
+    ///
+    /// Like in Tiled, pixels on [`Orientation::Hexagonal`] maps belong to the tile whose center is
+    /// the closest, which only exactly matches the outline of the tiles if their proportions are
+    /// those of a regular hexagon.
+    pub fn pixel_to_tile(&self, px: f32, py: f32) -> (i32, i32) {
+        match self.orientation {
+            Orientation::Orthogonal => (
+                (px / self.tile_width as f32).floor() as i32,
+                (py / self.tile_height as f32).floor() as i32,
+            ),
+            Orientation::Isometric => {
+                let tile_x = (px - self.isometric_origin_x()) / self.tile_width as f32;
+                let tile_y = py / self.tile_height as f32;
+                (
+                    (tile_y + tile_x).floor() as i32,
+                    (tile_y - tile_x).floor() as i32,
+                )
+            }
+            Orientation::Staggered => StaggerParams::new(self).staggered_pixel_to_tile(px, py),
+            Orientation::Hexagonal => StaggerParams::new(self).hexagonal_pixel_to_tile(px, py),
+        }
+    }
+
+    /// The horizontal pixel position of the top corner of the isometric tile at (0, 0).
+    fn isometric_origin_x(&self) -> f32 {
+        self.height as f32 * self.tile_width as f32 / 2.0
+    }
+}
+
+/// The measurements used to lay out the tiles of staggered and hexagonal maps, computed the same
+/// way Tiled does.
+struct StaggerParams {
+    tile_width: i32,
+    tile_height: i32,
+    side_length_x: i32,
+    side_length_y: i32,
+    side_offset_x: i32,
+    side_offset_y: i32,
+    column_width: i32,
+    row_height: i32,
+    stagger_x: bool,
+    stagger_even: bool,
+}
+
+impl StaggerParams {
+    fn new(map: &Map) -> Self {
+        // Tiled only works with even tile sizes for these orientations
+        let tile_width = map.tile_width as i32 & !1;
+        let tile_height = map.tile_height as i32 & !1;
+        let stagger_x = map.stagger_axis == StaggerAxis::X;
+        let side_length = match map.orientation {
+            Orientation::Hexagonal => map.hex_side_length as i32,
+            _ => 0,
+        };
+        let side_length_x = if stagger_x { side_length } else { 0 };
+        let side_length_y = if stagger_x { 0 } else { side_length };
+        let side_offset_x = (tile_width - side_length_x) / 2;
+        let side_offset_y = (tile_height - side_length_y) / 2;
+        Self {
+            tile_width,
+            tile_height,
+            side_length_x,
+            side_length_y,
+            side_offset_x,
+            side_offset_y,
+            column_width: side_offset_x + side_length_x,
+            row_height: side_offset_y + side_length_y,
+            stagger_x,
+            stagger_even: map.stagger_index == StaggerIndex::Even,
+        }
+    }
+
+    /// Whether the given column (if staggering along X) or row (if staggering along Y) is shifted.
+    fn is_staggered(&self, index: i32) -> bool {
+        ((index & 1) != 0) ^ self.stagger_even
+    }
+
+    fn staggered_pixel_to_tile(&self, mut x: f32, mut y: f32) -> (i32, i32) {
+        if self.stagger_even {
+            if self.stagger_x {
+                x -= self.side_offset_x as f32;
+            } else {
+                y -= self.side_offset_y as f32;
+            }
+        }
+
+        // Start with the tile whose bounding rectangle is aligned with the pixel grid
+        let mut reference = (
+            (x / self.tile_width as f32).floor() as i32,
+            (y / self.tile_height as f32).floor() as i32,
+        );
+        let rel_x = x - (reference.0 * self.tile_width) as f32;
+        let rel_y = y - (reference.1 * self.tile_height) as f32;
+        self.to_staggered_index(&mut reference);
+
+        // Check whether the position is in one of the corners of the rectangle, which belong to
+        // neighboring tiles
+        let y_pos = rel_x * (self.tile_height as f32 / self.tile_width as f32);
+        let side_offset_y = self.side_offset_y as f32;
+        let (x, y) = reference;
+        if side_offset_y - y_pos > rel_y {
+            self.top_left(x, y)
+        } else if -side_offset_y + y_pos > rel_y {
+            self.top_right(x, y)
+        } else if side_offset_y + y_pos < rel_y {
+            self.bottom_left(x, y)
+        } else if side_offset_y * 3.0 - y_pos < rel_y {
+            self.bottom_right(x, y)
+        } else {
+            reference
+        }
+    }
+
+    fn hexagonal_pixel_to_tile(&self, mut x: f32, mut y: f32) -> (i32, i32) {
+        if self.stagger_x {
+            x -= if self.stagger_even {
+                self.tile_width
+            } else {
+                self.side_offset_x
+            } as f32;
+        } else {
+            y -= if self.stagger_even {
+                self.tile_height
+            } else {
+                self.side_offset_y
+            } as f32;
+        }
+
+        // Start with the coordinates of a grid-aligned tile
+        let block_width = (self.column_width * 2) as f32;
+        let block_height = (self.row_height * 2) as f32;
+        let mut reference = (
+            (x / block_width).floor() as i32,
+            (y / block_height).floor() as i32,
+        );
+        let rel_x = x - reference.0 as f32 * block_width;
+        let rel_y = y - reference.1 as f32 * block_height;
+        self.to_staggered_index(&mut reference);
+
+        // The position belongs to the tile with the nearest center
+        let (centers, offsets) = if self.stagger_x {
+            let left = (self.side_length_x / 2) as f32;
+            let center_x = left + self.column_width as f32;
+            let center_y = (self.tile_height / 2) as f32;
+            let row_height = self.row_height as f32;
+            (
+                [
+                    (left, center_y),
+                    (center_x, center_y - row_height),
+                    (center_x, center_y + row_height),
+                    (center_x + self.column_width as f32, center_y),
+                ],
+                [(0, 0), (1, -1), (1, 0), (2, 0)],
+            )
+        } else {
+            let top = (self.side_length_y / 2) as f32;
+            let center_x = (self.tile_width / 2) as f32;
+            let center_y = top + self.row_height as f32;
+            let column_width = self.column_width as f32;
+            (
+                [
+                    (center_x, top),
+                    (center_x - column_width, center_y),
+                    (center_x + column_width, center_y),
+                    (center_x, center_y + self.row_height as f32),
+                ],
+                [(0, 0), (-1, 1), (0, 1), (0, 2)],
+            )
+        };
+        let distance = |(cx, cy): (f32, f32)| (cx - rel_x).powi(2) + (cy - rel_y).powi(2);
+        let nearest = (1..4).fold(0, |nearest, i| {
+            if distance(centers[i]) < distance(centers[nearest]) {
+                i
+            } else {
+                nearest
+            }
+        });
+        (
+            reference.0 + offsets[nearest].0,
+            reference.1 + offsets[nearest].1,
+        )
+    }
+
+    /// Converts the position of a rectangle-aligned tile into the actual tile position along the
+    /// stagger axis.
+    fn to_staggered_index(&self, reference: &mut (i32, i32)) {
+        let index = if self.stagger_x {
+            &mut reference.0
+        } else {
+            &mut reference.1
+        };
+        *index *= 2;
+        if self.stagger_even {
+            *index += 1;
+        }
+    }
+
+    fn top_left(&self, x: i32, y: i32) -> (i32, i32) {
+        match (
+            self.stagger_x,
+            self.is_staggered(if self.stagger_x { x } else { y }),
+        ) {
+            (false, true) => (x, y - 1),
+            (false, false) => (x - 1, y - 1),
+            (true, true) => (x - 1, y),
+            (true, false) => (x - 1, y - 1),
+        }
+    }
+
+    fn top_right(&self, x: i32, y: i32) -> (i32, i32) {
+        match (
+            self.stagger_x,
+            self.is_staggered(if self.stagger_x { x } else { y }),
+        ) {
+            (false, true) => (x + 1, y - 1),
+            (false, false) => (x, y - 1),
+            (true, true) => (x + 1, y),
+            (true, false) => (x + 1, y - 1),
+        }
+    }
+
+    fn bottom_left(&self, x: i32, y: i32) -> (i32, i32) {
+        match (
+            self.stagger_x,
+            self.is_staggered(if self.stagger_x { x } else { y }),
+        ) {
+            (false, true) => (x, y + 1),
+            (false, false) => (x - 1, y + 1),
+            (true, true) => (x - 1, y + 1),
+            (true, false) => (x - 1, y),
+        }
+    }
+
+    fn bottom_right(&self, x: i32, y: i32) -> (i32, i32) {
+        match (
+            self.stagger_x,
+            self.is_staggered(if self.stagger_x { x } else { y }),
+        ) {
+            (false, true) => (x + 1, y + 1),
+            (false, false) => (x, y + 1),
+            (true, true) => (x + 1, y + 1),
+            (true, false) => (x + 1, y),
+        }
+    }
 }
 
 impl Map {
@@ -134,16 +1124,21 @@ impl Map {
         cache: &mut impl ResourceCache,
     ) -> Result<Map> {
         let (
-            (c, infinite, user_type, user_class, stagger_axis, stagger_index),
+            (c, infinite, user_type, user_class, stagger_axis, stagger_index, hex_side_length),
+            (next_layer_id, next_object_id, tiled_version),
             (v, o, w, h, tw, th),
         ) = get_attrs!(
-            for v in attrs {
+            for v in attrs of "map" {
                 Some("backgroundcolor") => colour ?= v.parse(),
                 Some("infinite") => infinite = v == "1",
                 Some("type") => user_type ?= v.parse(),
                 Some("class") => user_class ?= v.parse(),
                 Some("staggeraxis") => stagger_axis ?= v.parse::<StaggerAxis>(),
                 Some("staggerindex") => stagger_index ?= v.parse::<StaggerIndex>(),
+                Some("hexsidelength") => hex_side_length ?= v.parse::<u32>(),
+                Some("nextlayerid") => next_layer_id ?= v.parse::<u32>(),
+                Some("nextobjectid") => next_object_id ?= v.parse::<u32>(),
+                Some("tiledversion") => tiled_version = v.to_owned(),
                 "version" => version = v,
                 "orientation" => orientation ?= v.parse::<Orientation>(),
                 "width" => width ?= v.parse::<u32>(),
@@ -151,7 +1146,7 @@ impl Map {
                 "tilewidth" => tile_width ?= v.parse::<u32>(),
                 "tileheight" => tile_height ?= v.parse::<u32>(),
             }
-            ((colour, infinite, user_type, user_class, stagger_axis, stagger_index), (version, orientation, width, height, tile_width, tile_height))
+            ((colour, infinite, user_type, user_class, stagger_axis, stagger_index, hex_side_length), (next_layer_id, next_object_id, tiled_version), (version, orientation, width, height, tile_width, tile_height))
         );
 
         let infinite = infinite.unwrap_or(false);
@@ -159,37 +1154,66 @@ impl Map {
         let stagger_axis = stagger_axis.unwrap_or_default();
         let stagger_index = stagger_index.unwrap_or_default();
 
-        // We can only parse sequentally, but tilesets are guaranteed to appear before layers.
-        // So we can pass in tileset data to layer construction without worrying about unfinished
-        // data usage.
+        // Tilesets are guaranteed to appear before layers, so the external ones are only
+        // collected at first and then loaded all at once before the first layer, which allows
+        // their files to be read concurrently. Layers can then be constructed with complete
+        // tileset data.
         let mut layers = Vec::new();
         let mut properties = HashMap::new();
         let mut tilesets = Vec::new();
+        let mut tileset_raw_sources = Vec::new();
+        let mut editor_settings = None;
+        let mut loaded_tilesets: HashMap<PathBuf, Arc<Tileset>> = HashMap::new();
+        let mut pending_tilesets = PendingTilesets::default();
+        let layer_count_hint = next_layer_id.map(|id| id.saturating_sub(1) as usize);
 
         let mut buffer = Vec::new();
         parse_tag!(parser => &mut buffer, "map", {
+            "editorsettings" => {
+                editor_settings = Some(EditorSettings::parse_xml(parser).await?);
+                Ok(())
+            },
             "tileset" => for attrs {
-                let res = Tileset::parse_xml_in_map(parser, &attrs, map_path,  read_from, cache).await?;
+                // Tilesets and layers are parsed behind boxed futures so that their state machines
+                // don't inflate the size of the map's own future
+                let res = Box::pin(Tileset::parse_xml_in_map(parser, &attrs, map_path,  read_from, cache)).await?;
                 match res.result_type {
-                    EmbeddedParseResultType::ExternalReference { tileset_path } => {
-                        let tileset = if let Some(ts) = cache.get_tileset(&tileset_path) {
+                    EmbeddedParseResultType::ExternalReference { tileset_path, raw_source } => {
+                        // The same tileset may be referenced more than once; Make sure it is only
+                        // read once per load even if the cache doesn't keep it around.
+                        let tileset = if let Some(ts) = loaded_tilesets.get(&tileset_path) {
+                            ts.clone()
+                        } else if let Some(ts) = cache.get_tileset(&tileset_path) {
+                            loaded_tilesets.insert(tileset_path, ts.clone());
                             ts
                         } else {
-                            let tileset = Arc::new(crate::parse::xml::parse_tileset(&tileset_path, read_from, cache).await?);
-                            cache.insert_tileset(tileset_path.clone(), tileset.clone());
-                            tileset
+                            // Stands in for the tileset until it's loaded
+                            let placeholder = Arc::new(Tileset::placeholder(&tileset_path, tw, th));
+                            pending_tilesets.push(tileset_path, tilesets.len());
+                            placeholder
                         };
 
                         tilesets.push(MapTilesetGid { first_gid: res.first_gid, tileset } );
+                        tileset_raw_sources.push(Some(raw_source));
                     }
                     EmbeddedParseResultType::Embedded { tileset } => {
                         tilesets.push(MapTilesetGid { first_gid: res.first_gid, tileset: Arc::new(tileset) });
+                        tileset_raw_sources.push(None);
                     },
                 };
                 Ok(())
             },
             "layer" => for attrs {
-                layers.push(LayerData::new(
+                let position = parser.last_event_position;
+                Box::pin(pending_tilesets.load(
+                    &mut tilesets,
+                    &mut loaded_tilesets,
+                    (tw, th),
+                    parser,
+                    read_from,
+                    cache,
+                )).await?;
+                let layer = Box::pin(LayerData::new(
                     parser,
                     attrs,
                     LayerTag::Tiles,
@@ -199,11 +1223,22 @@ impl Map {
                     None,
                     read_from,
                     cache
-                ).await?);
+                )).await?;
+                LayerData::push_sibling(parser, &mut layers, layer, position)?;
+                report_layer_parsed(parser, &layers, layer_count_hint);
                 Ok(())
             },
             "imagelayer" => for attrs {
-                layers.push(LayerData::new(
+                let position = parser.last_event_position;
+                Box::pin(pending_tilesets.load(
+                    &mut tilesets,
+                    &mut loaded_tilesets,
+                    (tw, th),
+                    parser,
+                    read_from,
+                    cache,
+                )).await?;
+                let layer = Box::pin(LayerData::new(
                     parser,
                     attrs,
                     LayerTag::Image,
@@ -213,11 +1248,22 @@ impl Map {
                     None,
                     read_from,
                     cache
-                ).await?);
+                )).await?;
+                LayerData::push_sibling(parser, &mut layers, layer, position)?;
+                report_layer_parsed(parser, &layers, layer_count_hint);
                 Ok(())
             },
             "objectgroup" => for attrs {
-                layers.push(LayerData::new(
+                let position = parser.last_event_position;
+                Box::pin(pending_tilesets.load(
+                    &mut tilesets,
+                    &mut loaded_tilesets,
+                    (tw, th),
+                    parser,
+                    read_from,
+                    cache,
+                )).await?;
+                let layer = Box::pin(LayerData::new(
                     parser,
                     attrs,
                     LayerTag::Objects,
@@ -227,11 +1273,22 @@ impl Map {
                     None,
                     read_from,
                     cache
-                ).await?);
+                )).await?;
+                LayerData::push_sibling(parser, &mut layers, layer, position)?;
+                report_layer_parsed(parser, &layers, layer_count_hint);
                 Ok(())
             },
             "group" => for attrs {
-                layers.push(LayerData::new(
+                let position = parser.last_event_position;
+                Box::pin(pending_tilesets.load(
+                    &mut tilesets,
+                    &mut loaded_tilesets,
+                    (tw, th),
+                    parser,
+                    read_from,
+                    cache,
+                )).await?;
+                let layer = Box::pin(LayerData::new(
                     parser,
                     attrs,
                     LayerTag::Group,
@@ -241,20 +1298,45 @@ impl Map {
                     None,
                     read_from,
                     cache
-                ).await?);
+                )).await?;
+                LayerData::push_sibling(parser, &mut layers, layer, position)?;
+                report_layer_parsed(parser, &layers, layer_count_hint);
                 Ok(())
             },
             "properties" => {
                 properties = parse_properties(parser).await?;
                 Ok(())
             },
+        }, else start => {
+            let position = parser.last_event_position;
+            if let Some(layer) = LayerData::new_unknown(parser, start).await? {
+                LayerData::push_sibling(parser, &mut layers, layer, position)?;
+                report_layer_parsed(parser, &layers, layer_count_hint);
+            }
+            Ok(())
         });
+        Box::pin(pending_tilesets.load(
+            &mut tilesets,
+            &mut loaded_tilesets,
+            (tw, th),
+            parser,
+            read_from,
+            cache,
+        ))
+        .await?;
 
-        // We do not need first GIDs any more
-        let tilesets = tilesets.into_iter().map(|ts| ts.tileset).collect();
+        LayerData::set_parent_paths(&mut layers, &[]);
+        let mut object_index = HashMap::new();
+        LayerData::index_objects(&layers, &mut Vec::new(), &mut object_index);
+
+        let (first_gids, tilesets) = tilesets
+            .into_iter()
+            .map(|ts| (ts.first_gid, ts.tileset))
+            .unzip();
 
         Ok(Map {
             version: v.to_owned(),
+            tiled_version,
             orientation: o,
             width: w,
             height: h,
@@ -262,19 +1344,118 @@ impl Map {
             tile_height: th,
             stagger_axis,
             stagger_index,
+            hex_side_length: hex_side_length.unwrap_or(0),
             tilesets,
+            first_gids,
+            tileset_raw_sources,
             layers,
             properties,
             background_color: c,
             infinite,
             user_type,
+            editor_settings,
+            next_layer_id,
+            next_object_id,
+            warnings: std::mem::take(&mut parser.context.state.warnings),
+            object_index,
         })
     }
 }
 
+/// The external tilesets of a map that is being parsed which haven't been loaded yet.
+#[derive(Default)]
+struct PendingTilesets {
+    /// The path of each tileset, without duplicates.
+    paths: Vec<PathBuf>,
+    /// The index of each reference to them in the map's tilesets, along with the index of the path.
+    references: Vec<(usize, usize)>,
+}
+
+impl PendingTilesets {
+    fn push(&mut self, path: PathBuf, index: usize) {
+        let path_index = match self.paths.iter().position(|pending| *pending == path) {
+            Some(path_index) => path_index,
+            None => {
+                self.paths.push(path);
+                self.paths.len() - 1
+            }
+        };
+        self.references.push((index, path_index));
+    }
+
+    /// Loads the pending tilesets, replacing the placeholders that stand in for them in
+    /// `tilesets`. Tilesets that can't be read keep their placeholder if
+    /// [`LoadSettings::placeholder_missing_tilesets`](crate::LoadSettings::placeholder_missing_tilesets)
+    /// is set, with a warning about it.
+    async fn load<R: Reader>(
+        &mut self,
+        tilesets: &mut [MapTilesetGid],
+        loaded_tilesets: &mut HashMap<PathBuf, Arc<Tileset>>,
+        (tile_width, tile_height): (u32, u32),
+        parser: &mut Parser<R>,
+        read_from: &mut impl ReadFrom,
+        cache: &mut impl ResourceCache,
+    ) -> Result<()> {
+        if self.paths.is_empty() {
+            return Ok(());
+        }
+        let paths = std::mem::take(&mut self.paths);
+        let results =
+            crate::parse::parse_tilesets(&paths, read_from, cache, &mut parser.context).await;
+        let mut loaded = Vec::with_capacity(paths.len());
+        for (path, result) in paths.into_iter().zip(results) {
+            let tileset = match result {
+                Ok(tileset) => {
+                    let tileset = Arc::new(tileset);
+                    cache.insert_tileset(path.clone(), tileset.clone());
+                    tileset
+                }
+                // Only tilesets that couldn't be read are replaced; One that was read but is
+                // malformed still makes the map fail. Placeholders are not cached so that the
+                // tileset is loaded again once it's available.
+                Err(err @ Error::ResourceLoadingError { .. })
+                    if parser.context.settings.placeholder_missing_tilesets =>
+                {
+                    parser
+                        .context
+                        .state
+                        .warnings
+                        .push(ParseWarning::MissingTileset {
+                            path: path.clone(),
+                            error: Arc::new(err),
+                        });
+                    Arc::new(Tileset::placeholder(&path, tile_width, tile_height))
+                }
+                Err(err) => return Err(err),
+            };
+            loaded_tilesets.insert(path, tileset.clone());
+            loaded.push(tileset);
+        }
+        for (index, path_index) in self.references.drain(..) {
+            tilesets[index].tileset = loaded[path_index].clone();
+        }
+        Ok(())
+    }
+}
+
+/// Reports the last top-level layer of a map that is being parsed to the progress hook.
+fn report_layer_parsed<R>(parser: &Parser<R>, layers: &[LayerData], total_hint: Option<usize>) {
+    if let Some(layer) = layers.last() {
+        parser
+            .context
+            .settings
+            .report_progress(LoadProgress::LayerParsed {
+                name: &layer.name,
+                index: layers.len() - 1,
+                total_hint,
+            });
+    }
+}
+
 // Specifies whether the odd or even rows/columns are shifted half a tile
 // right/down. Only applies to Staggered and Hexagonal map orientations.
-#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum StaggerIndex {
     Even,
@@ -299,6 +1480,8 @@ impl std::fmt::Display for StaggerIndexError {
     }
 }
 
+impl std::error::Error for StaggerIndexError {}
+
 impl FromStr for StaggerIndex {
     type Err = StaggerIndexError;
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
@@ -314,7 +1497,8 @@ impl FromStr for StaggerIndex {
 
 // Specifies which axis is staggered. Only applies to Staggered and Hexagonal
 // map orientations.
-#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum StaggerAxis {
     X,
@@ -339,6 +1523,8 @@ impl std::fmt::Display for StaggerAxisError {
     }
 }
 
+impl std::error::Error for StaggerAxisError {}
+
 impl FromStr for StaggerAxis {
     type Err = StaggerAxisError;
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
@@ -352,8 +1538,95 @@ impl FromStr for StaggerAxis {
     }
 }
 
+/// A version number of the TMX format or of the Tiled editor, such as the ones returned by
+/// [`Map::version`] and [`Map::tiled_version`].
+///
+/// Versions are ordered by their major, minor and patch numbers, so they can be compared to check
+/// for features that were introduced in a specific version.
+///
+/// ## Example
+/// ```
+/// use tiled::TiledVersion;
+///
+/// let version: TiledVersion = "1.10.2".parse().unwrap();
+/// assert!(version >= TiledVersion::new(1, 9, 0));
+/// assert_eq!("1.0".parse(), Ok(TiledVersion::new(1, 0, 0)));
+/// ```
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TiledVersion {
+    /// The major version number.
+    pub major: u32,
+    /// The minor version number.
+    pub minor: u32,
+    /// The patch version number, which is 0 if the version didn't have one.
+    pub patch: u32,
+}
+
+impl TiledVersion {
+    /// Creates a new version from its numbers.
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+/// An error arising from trying to parse a [`TiledVersion`] that is not valid.
+pub struct TiledVersionParseError {
+    /// The invalid string found.
+    pub str_found: String,
+}
+
+impl std::fmt::Display for TiledVersionParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!(
+            "failed to parse version, expected a version such as `1.10` or `1.10.2` \
+        but got `{}` instead",
+            self.str_found
+        ))
+    }
+}
+
+impl std::error::Error for TiledVersionParseError {}
+
+impl FromStr for TiledVersion {
+    type Err = TiledVersionParseError;
+
+    /// Parses versions of the form `major.minor` or `major.minor.patch`. Anything after the
+    /// version numbers, such as the `-beta` of `1.11.0-beta`, is ignored.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let err = || TiledVersionParseError {
+            str_found: s.to_owned(),
+        };
+        let numbers = s
+            .trim()
+            .split(|c: char| !c.is_ascii_digit() && c != '.')
+            .next()
+            .unwrap_or_default();
+        let mut parts = numbers.split('.').map(|part| part.parse::<u32>().ok());
+        let major = parts.next().flatten().ok_or_else(err)?;
+        let minor = parts.next().flatten().ok_or_else(err)?;
+        let patch = match parts.next() {
+            Some(patch) => patch.ok_or_else(err)?,
+            None => 0,
+        };
+        Ok(Self::new(major, minor, patch))
+    }
+}
+
+impl fmt::Display for TiledVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
 /// Represents the way tiles are laid out in a map.
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum Orientation {
     Orthogonal,
@@ -415,11 +1688,11 @@ impl fmt::Display for Orientation {
 /// a starting GID of 1.
 ///
 /// See also: <https://doc.mapeditor.org/en/latest/reference/global-tile-ids/>
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub(crate) struct Gid(pub u32);
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Gid(pub u32);
 
 impl Gid {
     /// The GID representing an empty tile in the map.
-    #[allow(dead_code)]
     pub const EMPTY: Gid = Gid(0);
 }
@@ -0,0 +1,81 @@
+//! Integer division that rounds towards negative infinity, which is what converting between tile
+//! and chunk coordinates requires: Tile `-1` belongs to chunk `-1`, at position `15` within it.
+//! The `/` and `%` operators round towards zero instead, which gives the wrong result for negative
+//! coordinates.
+
+/// Divides `a` by `b`, rounding the result towards negative infinity.
+///
+/// ## Panics
+/// Panics if `b` is 0, or if the division overflows.
+///
+/// ## Example
+/// ```
+/// use tiled::floor_div;
+///
+/// assert_eq!(floor_div(7, 2), 3);
+/// assert_eq!(floor_div(-7, 2), -4);
+/// assert_eq!(floor_div(7, -2), -4);
+/// assert_eq!(floor_div(-7, -2), 3);
+/// ```
+#[inline]
+pub fn floor_div(a: i32, b: i32) -> i32 {
+    let d = a / b;
+    let r = a % b;
+
+    if r == 0 {
+        d
+    } else {
+        d - ((a < 0) ^ (b < 0)) as i32
+    }
+}
+
+/// Returns the remainder of [`floor_div`], which has the same sign as `b` so that
+/// `floor_div(a, b) * b + floor_mod(a, b) == a`.
+///
+/// ## Panics
+/// Panics if `b` is 0, or if the division overflows.
+///
+/// ## Example
+/// ```
+/// use tiled::floor_mod;
+///
+/// assert_eq!(floor_mod(7, 2), 1);
+/// assert_eq!(floor_mod(-7, 2), 1);
+/// assert_eq!(floor_mod(7, -2), -1);
+/// assert_eq!(floor_mod(-7, -2), -1);
+/// ```
+#[inline]
+pub fn floor_mod(a: i32, b: i32) -> i32 {
+    let r = a % b;
+
+    if r != 0 && ((r < 0) ^ (b < 0)) {
+        r + b
+    } else {
+        r
+    }
+}
+
+/// The [`i64`] version of [`floor_div`].
+#[inline]
+pub fn floor_div_i64(a: i64, b: i64) -> i64 {
+    let d = a / b;
+    let r = a % b;
+
+    if r == 0 {
+        d
+    } else {
+        d - ((a < 0) ^ (b < 0)) as i64
+    }
+}
+
+/// The [`i64`] version of [`floor_mod`].
+#[inline]
+pub fn floor_mod_i64(a: i64, b: i64) -> i64 {
+    let r = a % b;
+
+    if r != 0 && ((r < 0) ^ (b < 0)) {
+        r + b
+    } else {
+        r
+    }
+}
@@ -1,13 +1,19 @@
-use std::{collections::HashMap, path::Path, sync::Arc};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use quick_xml::events::{attributes::Attribute, Event};
+use quick_xml::events::attributes::Attribute;
 
 use crate::{
     error::{Error, Result},
     parse::xml::{Parser, ReadFrom, Reader},
-    properties::{parse_properties, Properties},
+    properties::{merge_properties, parse_properties, Properties, PropertiesExt},
+    stats::HeapSize,
     template::Template,
-    util::{get_attrs, map_wrapper, parse_tag},
+    util::{get_attrs, map_wrapper, normalize_path, parse_tag},
     Color, Gid, MapTilesetGid, ResourceCache, Tile, TileId, Tileset,
 };
 
@@ -15,6 +21,7 @@ use crate::{
 ///
 /// Tilesets can be contained within either a map or a template.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TilesetLocation {
     /// Index into the Map's tileset list, guaranteed to be a valid index of the map tileset container.
     Map(usize),
@@ -24,6 +31,7 @@ pub enum TilesetLocation {
 
 /// Stores the internal tile gid about a layer tile, along with how it is flipped.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ObjectTileData {
     /// A valid TilesetLocation that points to a tileset that **may or may not contain** this tile.
     tileset_location: TilesetLocation,
@@ -51,6 +59,24 @@ impl ObjectTileData {
         &self.tileset_location
     }
 
+    /// Whether this tile is flipped on its Y axis (horizontally).
+    #[inline]
+    pub fn flip_h(&self) -> bool {
+        self.flip_h
+    }
+
+    /// Whether this tile is flipped on its X axis (vertically).
+    #[inline]
+    pub fn flip_v(&self) -> bool {
+        self.flip_v
+    }
+
+    /// Whether this tile is flipped diagonally.
+    #[inline]
+    pub fn flip_d(&self) -> bool {
+        self.flip_d
+    }
+
     const FLIPPED_HORIZONTALLY_FLAG: u32 = 0x80000000;
     const FLIPPED_VERTICALLY_FLAG: u32 = 0x40000000;
     const FLIPPED_DIAGONALLY_FLAG: u32 = 0x20000000;
@@ -74,9 +100,14 @@ impl ObjectTileData {
             None
         } else {
             let (tileset_location, id) = match for_tileset {
-                Some(tileset) => (TilesetLocation::Template(tileset), gid.0 - 1),
+                Some(tileset) => {
+                    // Templates only hold a single tileset, but it doesn't need to start at GID 1
+                    let first_gid = crate::get_tileset_for_gid(tilesets, gid)
+                        .map_or(1, |(_, tileset)| tileset.first_gid.0);
+                    (TilesetLocation::Template(tileset), gid.0 - first_gid)
+                }
                 None => {
-                    let (tileset_index, tileset) = crate::util::get_tileset_for_gid(tilesets, gid)?;
+                    let (tileset_index, tileset) = crate::get_tileset_for_gid(tilesets, gid)?;
                     let id = gid.0 - tileset.first_gid.0;
                     (TilesetLocation::Map(tileset_index), id)
                 }
@@ -91,6 +122,16 @@ impl ObjectTileData {
             })
         }
     }
+
+    /// The GID of this tile plus its flipping bits, given the first GID of its tileset. The
+    /// opposite of [`ObjectTileData::from_bits`].
+    pub(crate) fn to_bits_with_first_gid(&self, first_gid: Gid) -> u32 {
+        let flag = |set: bool, flag: u32| if set { flag } else { 0 };
+        (first_gid.0 + self.id)
+            | flag(self.flip_h, Self::FLIPPED_HORIZONTALLY_FLAG)
+            | flag(self.flip_v, Self::FLIPPED_VERTICALLY_FLAG)
+            | flag(self.flip_d, Self::FLIPPED_DIAGONALLY_FLAG)
+    }
 }
 
 map_wrapper!(
@@ -119,6 +160,7 @@ impl<'map> ObjectTile<'map> {
 ///
 /// Also see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#tmx-object).
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum ObjectShape {
     Rect {
@@ -155,8 +197,40 @@ pub enum ObjectShape {
     },
 }
 
+impl ObjectShape {
+    /// The size of the shape, if it has one.
+    fn size(&self) -> Option<(f32, f32)> {
+        match *self {
+            ObjectShape::Rect { width, height }
+            | ObjectShape::Ellipse { width, height }
+            | ObjectShape::Text { width, height, .. } => Some((width, height)),
+            _ => None,
+        }
+    }
+
+    /// Returns the shape of a template's object, as used by an instance of it placed at `(x, y)`
+    /// with the given size.
+    fn instantiated(mut self, x: f32, y: f32, new_width: f32, new_height: f32) -> Self {
+        match &mut self {
+            ObjectShape::Rect { width, height }
+            | ObjectShape::Ellipse { width, height }
+            | ObjectShape::Text { width, height, .. } => {
+                *width = new_width;
+                *height = new_height;
+            }
+            ObjectShape::Point(point_x, point_y) => {
+                *point_x = x;
+                *point_y = y;
+            }
+            ObjectShape::Polyline { .. } | ObjectShape::Polygon { .. } => {}
+        }
+        self
+    }
+}
+
 /// The horizontal alignment of an [`ObjectShape::Text`].
-#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum HorizontalAlignment {
     #[default]
@@ -167,7 +241,8 @@ pub enum HorizontalAlignment {
 }
 
 /// The vertical alignment of an [`ObjectShape::Text`].
-#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum VerticalAlignment {
     #[default]
@@ -180,13 +255,18 @@ pub enum VerticalAlignment {
 ///
 /// Also see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#tmx-object).
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ObjectData {
     id: u32,
     tile: Option<ObjectTileData>,
     /// The name of the object, which is arbitrary and set by the user.
-    pub name: String,
+    ///
+    /// Objects with the same name share the same allocation.
+    pub name: Arc<str>,
     /// The type of the object, which is arbitrary and set by the user.
-    pub user_type: String,
+    ///
+    /// Objects with the same type share the same allocation.
+    pub user_type: Arc<str>,
     /// The X coordinate of this object in pixels.
     pub x: f32,
     /// The Y coordinate of this object in pixels.
@@ -195,13 +275,53 @@ pub struct ObjectData {
     pub rotation: f32,
     /// Whether the object is shown or hidden.
     pub visible: bool,
+    /// Whether the object is locked in the editor, which prevents it from being selected.
+    pub locked: bool,
     /// The object's shape.
     pub shape: ObjectShape,
     /// The object's custom properties as set by the user.
     pub properties: Properties,
+    /// The path of the template this object is an instance of, as resolved to load it, or
+    /// [`None`] if it isn't based on a template.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub template_source: Option<PathBuf>,
+    /// The `template` attribute of this object exactly as written in the file containing it, or
+    /// [`None`] if it isn't based on a template.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub template_raw_source: Option<String>,
+}
+
+impl HeapSize for ObjectData {
+    fn heap_size(&self) -> usize {
+        // The tileset of a template's tile object is shared with the template
+        self.name.heap_size()
+            + self.user_type.heap_size()
+            + self.shape.heap_size()
+            + self.properties.heap_size()
+            + self.template_source.heap_size()
+            + self.template_raw_source.heap_size()
+    }
 }
 
 impl ObjectData {
+    /// Returns a builder for creating objects programmatically. See [`ObjectDataBuilder`].
+    pub fn builder() -> ObjectDataBuilder {
+        ObjectDataBuilder {
+            id: 0,
+            name: Arc::from(""),
+            user_type: Arc::from(""),
+            x: 0.0,
+            y: 0.0,
+            width: 0.0,
+            height: 0.0,
+            rotation: 0.0,
+            visible: true,
+            locked: false,
+            shape: None,
+            properties: Properties::new(),
+        }
+    }
+
     /// ID of the object, which is unique per map since Tiled 0.11.
     ///
     /// On older versions this value is defaulted to 0.
@@ -215,6 +335,368 @@ impl ObjectData {
     pub fn tile_data(&self) -> Option<ObjectTileData> {
         self.tile.clone()
     }
+
+    /// Returns the vertices of this object's shape in map (pixel) coordinates, taking its
+    /// position and rotation into account.
+    ///
+    /// Objects are rotated clockwise around their top-left corner, or around their bottom-left
+    /// corner in the case of tile objects. Tile flipping doesn't change an object's outline and is
+    /// as such ignored.
+    ///
+    /// - Rectangles, text and tile objects return their four corners, in clockwise order starting
+    ///   from the top-left one.
+    /// - Polygons and polylines return their points.
+    /// - Points return the point itself.
+    /// - Ellipses return an approximation with [`Self::DEFAULT_ELLIPSE_SEGMENTS`] vertices; Use
+    ///   [`Self::world_ellipse()`] to choose the amount of vertices.
+    pub fn world_vertices(&self) -> Vec<(f32, f32)> {
+        match &self.shape {
+            ObjectShape::Rect { width, height } | ObjectShape::Text { width, height, .. } => {
+                self.rect_world_vertices(*width, *height)
+            }
+            ObjectShape::Polyline { points } | ObjectShape::Polygon { points } => {
+                points.iter().map(|&(x, y)| self.to_world(x, y)).collect()
+            }
+            ObjectShape::Point(_, _) => vec![(self.x, self.y)],
+            ObjectShape::Ellipse { .. } => self
+                .world_ellipse(Self::DEFAULT_ELLIPSE_SEGMENTS)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The amount of vertices used by [`Self::world_vertices()`] to approximate ellipses.
+    pub const DEFAULT_ELLIPSE_SEGMENTS: usize = 32;
+
+    /// Approximates this object's ellipse as a polygon with `segments` vertices in map (pixel)
+    /// coordinates, taking its position and rotation into account.
+    ///
+    /// Returns [`None`] if the object is not an [`ObjectShape::Ellipse`].
+    pub fn world_ellipse(&self, segments: usize) -> Option<Vec<(f32, f32)>> {
+        match self.shape {
+            ObjectShape::Ellipse { width, height } => {
+                let (rx, ry) = (width / 2., height / 2.);
+                Some(
+                    (0..segments)
+                        .map(|i| {
+                            let angle = std::f32::consts::TAU * i as f32 / segments as f32;
+                            self.to_world(rx + rx * angle.cos(), ry + ry * angle.sin())
+                        })
+                        .collect(),
+                )
+            }
+            _ => None,
+        }
+    }
+
+    /// Checks whether a point in map (pixel) coordinates is inside this object's shape, taking
+    /// the object's position and rotation into account. Points on the edge of a shape are inside
+    /// of it.
+    ///
+    /// - Rectangles, text and tile objects check against their rectangle.
+    /// - Ellipses check against the exact ellipse rather than an approximation.
+    /// - Polygons use the even-odd rule, so the parts of self-intersecting polygons that overlap
+    ///   an even amount of times are outside of them.
+    /// - Points only contain points closer to them than [`Self::POINT_EPSILON`].
+    /// - Polylines have no area and never contain any point.
+    ///
+    /// Shapes with no area, such as rectangles with a width or height of 0 or polygons with less
+    /// than 3 points, contain no points except for the ones on their outline in the case of
+    /// rectangles.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// use tiled::ObjectData;
+    ///
+    /// // A 20x10 trigger zone, rotated to stand upright
+    /// let trigger = ObjectData::builder()
+    ///     .position(100.0, 100.0)
+    ///     .size(20.0, 10.0)
+    ///     .rotation(90.0)
+    ///     .build()?;
+    ///
+    /// assert!(trigger.contains_point(95.0, 110.0));
+    /// assert!(!trigger.contains_point(110.0, 105.0));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn contains_point(&self, x: f32, y: f32) -> bool {
+        let (x, y) = self.to_local(x, y);
+        match &self.shape {
+            ObjectShape::Rect { width, height } | ObjectShape::Text { width, height, .. } => {
+                let top = if self.tile.is_some() { -height } else { 0. };
+                let (min_x, max_x) = (width.min(0.), width.max(0.));
+                let (min_y, max_y) = (top.min(top + height), top.max(top + height));
+                (min_x..=max_x).contains(&x) && (min_y..=max_y).contains(&y)
+            }
+            ObjectShape::Ellipse { width, height } => {
+                let (rx, ry) = (width / 2., height / 2.);
+                if rx == 0. || ry == 0. {
+                    return false;
+                }
+                let (dx, dy) = ((x - rx) / rx, (y - ry) / ry);
+                dx * dx + dy * dy <= 1.
+            }
+            ObjectShape::Polygon { points } => polygon_contains_point(points, x, y),
+            ObjectShape::Point(_, _) => x * x + y * y <= Self::POINT_EPSILON * Self::POINT_EPSILON,
+            ObjectShape::Polyline { .. } => false,
+        }
+    }
+
+    /// How far in pixels a point may be from a point object for
+    /// [`contains_point()`](Self::contains_point) to consider it contained.
+    pub const POINT_EPSILON: f32 = 1e-3;
+
+    /// Returns the axis-aligned bounding box of this object's shape in map (pixel) coordinates,
+    /// taking its position and rotation into account, as `(min_x, min_y, max_x, max_y)`.
+    ///
+    /// The bounding box of ellipses is exact rather than the one of their approximation. Point
+    /// objects and polygons or polylines without points have an empty box at the object's
+    /// position.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// use tiled::ObjectData;
+    ///
+    /// let object = ObjectData::builder()
+    ///     .position(100.0, 100.0)
+    ///     .size(20.0, 10.0)
+    ///     .rotation(90.0)
+    ///     .build()?;
+    ///
+    /// let (min_x, min_y, max_x, max_y) = object.aabb();
+    /// assert!((min_x - 90.0).abs() < 1e-4 && (max_x - 100.0).abs() < 1e-4);
+    /// assert!((min_y - 100.0).abs() < 1e-4 && (max_y - 120.0).abs() < 1e-4);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn aabb(&self) -> (f32, f32, f32, f32) {
+        if let ObjectShape::Ellipse { width, height } = self.shape {
+            let (rx, ry) = (width / 2., height / 2.);
+            let (center_x, center_y) = self.to_world(rx, ry);
+            let (sin, cos) = self.rotation.to_radians().sin_cos();
+            let extent_x = (rx * cos).hypot(ry * sin);
+            let extent_y = (rx * sin).hypot(ry * cos);
+            return (
+                center_x - extent_x,
+                center_y - extent_y,
+                center_x + extent_x,
+                center_y + extent_y,
+            );
+        }
+        self.world_vertices().into_iter().fold(
+            (self.x, self.y, self.x, self.y),
+            |(min_x, min_y, max_x, max_y), (x, y)| {
+                (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+            },
+        )
+    }
+
+    fn rect_world_vertices(&self, width: f32, height: f32) -> Vec<(f32, f32)> {
+        // Tile objects are anchored at their bottom-left corner
+        let top = if self.tile.is_some() { -height } else { 0. };
+        vec![
+            self.to_world(0., top),
+            self.to_world(width, top),
+            self.to_world(width, top + height),
+            self.to_world(0., top + height),
+        ]
+    }
+
+    /// Converts a point relative to the object's position into map coordinates.
+    fn to_world(&self, x: f32, y: f32) -> (f32, f32) {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        (self.x + x * cos - y * sin, self.y + x * sin + y * cos)
+    }
+
+    /// Converts a point in map coordinates into one relative to the object's position, undoing
+    /// its rotation. The inverse of [`Self::to_world`].
+    fn to_local(&self, x: f32, y: f32) -> (f32, f32) {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        let (dx, dy) = (x - self.x, y - self.y);
+        (dx * cos + dy * sin, dy * cos - dx * sin)
+    }
+}
+
+/// Checks whether a point is inside a polygon using the even-odd rule.
+fn polygon_contains_point(points: &[(f32, f32)], x: f32, y: f32) -> bool {
+    if points.len() < 3 {
+        return false;
+    }
+    let mut inside = false;
+    let mut previous = points[points.len() - 1];
+    for &current in points {
+        let ((x1, y1), (x2, y2)) = (previous, current);
+        // Points on an edge are inside, regardless of how many crossings they add up to
+        let cross = (x2 - x1) * (y - y1) - (y2 - y1) * (x - x1);
+        if cross == 0.
+            && (x1.min(x2)..=x1.max(x2)).contains(&x)
+            && (y1.min(y2)..=y1.max(y2)).contains(&y)
+        {
+            return true;
+        }
+        if (y1 > y) != (y2 > y) && x < x1 + (y - y1) * (x2 - x1) / (y2 - y1) {
+            inside = !inside;
+        }
+        previous = current;
+    }
+    inside
+}
+
+/// Builds [`ObjectData`] programmatically, such as for objects generated at runtime that should
+/// sit alongside the ones of a loaded map.
+///
+/// Obtained through [`ObjectData::builder()`].
+///
+/// ## Example
+/// ```
+/// # fn main() -> tiled::Result<()> {
+/// use tiled::{ObjectData, ObjectLayerData, ObjectShape};
+///
+/// let wall = ObjectData::builder()
+///     .id(1)
+///     .name("wall")
+///     .position(32.0, 64.0)
+///     .size(16.0, 48.0)
+///     .build()?;
+/// let slope = ObjectData::builder()
+///     .shape(ObjectShape::Polygon {
+///         points: vec![(0.0, 0.0), (16.0, 0.0), (16.0, -16.0)],
+///     })
+///     .build()?;
+///
+/// let layer = ObjectLayerData::from_objects(vec![wall, slope], None);
+/// assert_eq!(layer.object_data().len(), 2);
+/// assert_eq!(
+///     layer.object_data()[0].shape,
+///     ObjectShape::Rect {
+///         width: 16.0,
+///         height: 48.0
+///     }
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ObjectDataBuilder {
+    id: u32,
+    name: Arc<str>,
+    user_type: Arc<str>,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    rotation: f32,
+    visible: bool,
+    locked: bool,
+    shape: Option<ObjectShape>,
+    properties: Properties,
+}
+
+impl ObjectDataBuilder {
+    /// Sets the ID of the object. Defaults to 0, which Tiled never assigns to map objects.
+    pub fn id(mut self, id: u32) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// Sets the name of the object. Defaults to an empty name.
+    pub fn name(mut self, name: impl Into<Arc<str>>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Sets the type of the object. Defaults to an empty type.
+    pub fn user_type(mut self, user_type: impl Into<Arc<str>>) -> Self {
+        self.user_type = user_type.into();
+        self
+    }
+
+    /// Sets the position of the object in pixels. Defaults to `(0, 0)`.
+    pub fn position(mut self, x: f32, y: f32) -> Self {
+        self.x = x;
+        self.y = y;
+        self
+    }
+
+    /// Sets the size of the rectangle the object is if no [`shape`](Self::shape) is given.
+    /// Defaults to 0 by 0, like objects without a size in TMX files.
+    pub fn size(mut self, width: f32, height: f32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Sets the clockwise rotation of the object in degrees. Defaults to 0.
+    pub fn rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Sets whether the object is shown. Defaults to `true`.
+    pub fn visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    /// Sets whether the object is locked in the editor. Defaults to `false`.
+    pub fn locked(mut self, locked: bool) -> Self {
+        self.locked = locked;
+        self
+    }
+
+    /// Sets the shape of the object, which is used as is instead of the rectangle given by
+    /// [`size`](Self::size).
+    pub fn shape(mut self, shape: ObjectShape) -> Self {
+        self.shape = Some(shape);
+        self
+    }
+
+    /// Sets the custom properties of the object. Defaults to no properties.
+    pub fn properties(mut self, properties: Properties) -> Self {
+        self.properties = properties;
+        self
+    }
+
+    /// Creates the object.
+    ///
+    /// Fails with [`Error::InvalidObjectData`] if the object is a polygon with less than 3 points
+    /// or a polyline with less than 2 points.
+    pub fn build(self) -> Result<ObjectData> {
+        let shape = self.shape.unwrap_or(ObjectShape::Rect {
+            width: self.width,
+            height: self.height,
+        });
+        let (kind, minimum, points) = match &shape {
+            ObjectShape::Polygon { points } => ("polygon", 3, points.len()),
+            ObjectShape::Polyline { points } => ("polyline", 2, points.len()),
+            _ => ("", 0, 0),
+        };
+        if points < minimum {
+            return Err(Error::InvalidObjectData {
+                description: format!(
+                    "a {} needs at least {} points, but {} were given",
+                    kind, minimum, points
+                ),
+            });
+        }
+        Ok(ObjectData {
+            id: self.id,
+            tile: None,
+            name: self.name,
+            user_type: self.user_type,
+            x: self.x,
+            y: self.y,
+            rotation: self.rotation,
+            visible: self.visible,
+            locked: self.locked,
+            shape,
+            properties: self.properties,
+            template_source: None,
+            template_raw_source: None,
+        })
+    }
 }
 
 impl ObjectData {
@@ -230,32 +712,43 @@ impl ObjectData {
         read_from: &mut impl ReadFrom,
         cache: &mut impl ResourceCache,
     ) -> Result<ObjectData> {
-        let (id, tile, mut n, mut t, c, w, h, mut v, mut r, template, x, y) = get_attrs!(
-            for v in attrs {
+        let (id, tile, mut n, t, c, mut w, mut h, mut v, locked, mut r, template, x, y) = get_attrs!(
+            for v in attrs of "object" {
                 Some("id") => id ?= v.parse(),
                 Some("gid") => tile ?= v.parse::<u32>(),
-                Some("name") => name ?= v.parse(),
-                Some("type") => user_type ?= v.parse(),
-                Some("class") => user_class ?= v.parse(),
+                Some("name") => name = parser.interner.intern(v),
+                Some("type") => user_type = parser.interner.intern(v),
+                Some("class") => user_class = parser.interner.intern(v),
                 Some("width") => width ?= v.parse(),
                 Some("height") => height ?= v.parse(),
                 Some("visible") => visible ?= v.parse().map(|x:i32| x == 1),
+                Some("locked") => locked ?= v.parse().map(|x:i32| x == 1),
                 Some("rotation") => rotation ?= v.parse(),
                 Some("template") => template = v.to_string(),
                 Some("x") => x ?= v.parse::<f32>(),
                 Some("y") => y ?= v.parse::<f32>(),
             }
-            (id, tile, name, user_type, user_class, width, height, visible, rotation, template, x, y)
+            (id, tile, name, user_type, user_class, width, height, visible, locked, rotation, template, x, y)
         );
+        // The `class` attribute replaced `type` in Tiled 1.9; Older maps will still use the latter
+        let mut t = t.or(c);
         let x = x.unwrap_or(0.);
         let y = y.unwrap_or(0.);
         let mut tile = tile.and_then(|bits| {
             ObjectTileData::from_bits(bits, tilesets?, for_tileset.as_ref().cloned())
         });
         // If the template attribute is there, we need to go fetch the template file
-        let template: Option<Arc<Template>> = match template {
+        let template_raw_source = template;
+        let mut template_source = None;
+        let template: Option<Arc<Template>> = match &template_raw_source {
             Some(template_path) => {
-                let template_path = base_path.join(Path::new(&template_path));
+                let template_path = normalize_path(
+                    &parser
+                        .context
+                        .settings
+                        .resolve_path(base_path, template_path),
+                );
+                template_source = Some(template_path.clone());
 
                 // Check the cache to see if this template exists
                 let template = if let Some(templ) = cache.get_template(&template_path) {
@@ -263,20 +756,29 @@ impl ObjectData {
                 } else {
                     // add indirection because the returned async state machine is a recursive data structure
                     // (`Template::parse_template` eventually calls this function)
-                    let template =
-                        Box::pin(Template::parse_template(&template_path, read_from, cache))
-                            .await?;
+                    let template = Box::pin(Template::parse_template(
+                        &template_path,
+                        read_from,
+                        cache,
+                        &mut parser.context,
+                    ))
+                    .await?;
                     // Insert it into the cache
                     cache.insert_template(&template_path, template.clone());
                     template
                 };
 
-                // The template sets the default values for the object
+                // The template sets the default values for the object; Tiled only writes the
+                // attributes that differ from it, even if they are set to an empty string
                 let obj = &template.object;
                 v.get_or_insert(obj.visible);
                 r.get_or_insert(obj.rotation);
                 n.get_or_insert_with(|| obj.name.clone());
                 t.get_or_insert_with(|| obj.user_type.clone());
+                if let Some((width, height)) = obj.shape.size() {
+                    w.get_or_insert(width);
+                    h.get_or_insert(height);
+                }
                 if let Some(templ_tile) = &obj.tile {
                     tile.get_or_insert_with(|| templ_tile.clone());
                 }
@@ -286,12 +788,13 @@ impl ObjectData {
         };
 
         let visible = v.unwrap_or(true);
+        let locked = locked.unwrap_or(false);
         let width = w.unwrap_or(0f32);
         let height = h.unwrap_or(0f32);
         let rotation = r.unwrap_or(0f32);
         let id = id.unwrap_or(0u32);
-        let name = n.unwrap_or_default();
-        let user_type: String = t.or(c).unwrap_or_default();
+        let name = n.unwrap_or_else(|| parser.interner.intern(""));
+        let user_type = t.unwrap_or_else(|| parser.interner.intern(""));
         let mut shape = None;
         let mut properties = HashMap::new();
 
@@ -329,7 +832,11 @@ impl ObjectData {
         // Possibly copy properties from the template into the object
         // Any that already exist in the object's map don't get copied over
         if let Some(templ) = template {
-            shape.get_or_insert(templ.object.shape.clone());
+            // A shape declared by the object replaces the template's one entirely, but the
+            // template's shape still has to be moved and resized like the object
+            shape.get_or_insert_with(|| {
+                templ.object.shape.clone().instantiated(x, y, width, height)
+            });
 
             for (k, v) in &templ.object.properties {
                 if !properties.contains_key(k) {
@@ -349,8 +856,11 @@ impl ObjectData {
             y,
             rotation,
             visible,
+            locked,
             shape,
             properties,
+            template_source,
+            template_raw_source,
         })
     }
 }
@@ -358,7 +868,7 @@ impl ObjectData {
 impl ObjectData {
     fn new_polyline(attrs: Vec<Attribute>) -> Result<ObjectShape> {
         let points = get_attrs!(
-            for v in attrs {
+            for v in attrs of "polyline" {
                 "points" => points ?= ObjectData::parse_points(v.to_string()),
             }
             points
@@ -368,7 +878,7 @@ impl ObjectData {
 
     fn new_polygon(attrs: Vec<Attribute>) -> Result<ObjectShape> {
         let points = get_attrs!(
-            for v in attrs {
+            for v in attrs of "polygon" {
                 "points" => points ?= ObjectData::parse_points(v.to_string()),
             }
             points
@@ -395,7 +905,7 @@ impl ObjectData {
             halign,
             valign,
         ) = get_attrs!(
-            for v in attrs {
+            for v in attrs of "text" {
                 Some("fontfamily") => font_family = v,
                 Some("pixelsize") => pixel_size ?= v.parse(),
                 Some("wrap") => wrap ?= v.parse(),
@@ -452,23 +962,15 @@ impl ObjectData {
         let kerning = kerning.map_or(true, |k| k == 1);
         let halign = halign.unwrap_or_default();
         let valign = valign.unwrap_or_default();
-        let contents = match parser.read_event().await.map_err(Error::XmlDecodingError)? {
-            Event::Eof => {
-                return Err(Error::PrematureEnd(
-                    "XML stream ended when trying to parse text contents".to_owned(),
-                ))
-            }
-
-            Event::Text(contents) => std::str::from_utf8(&contents)
-                .map_err(|err| Error::XmlDecodingError(err.into()))?
-                .to_string(),
-            _ => {
-                return Err(Error::InvalidObjectData {
+        // The contents are kept verbatim, including newlines and unescaped entities
+        let contents =
+            parser
+                .read_text(b"text")
+                .await?
+                .ok_or_else(|| Error::InvalidObjectData {
                     description: "Text attribute contained anything but characters as content"
                         .into(),
-                })
-            }
-        };
+                })?;
 
         Ok(ObjectShape::Text {
             font_family,
@@ -526,4 +1028,66 @@ impl<'map> Object<'map> {
             .as_ref()
             .map(|tile| ObjectTile::new(self.map, tile))
     }
+
+    /// Returns the custom properties of this object, including the ones it inherits from its tile
+    /// if it is a tile object.
+    ///
+    /// The object's own properties take precedence over the tile's, and class properties set on
+    /// both are merged member by member.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::PropertiesExt;
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = tiled::Loader::new().load_tmx_map("assets/tiled_inherited_properties.tmx")?;
+    /// let object = map.get_object_by_id(1).unwrap();
+    ///
+    /// // Set on the object
+    /// assert_eq!(object.effective_properties().get_int("health"), Some(5));
+    /// // Inherited from the tile
+    /// assert_eq!(object.effective_properties().get_bool("solid"), Some(true));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn effective_properties(&self) -> Cow<'map, Properties> {
+        let tile = self.get_tile().and_then(|tile| tile.get_tile());
+        match tile {
+            Some(tile) if !tile.data.properties.is_empty() => Cow::Owned(merge_properties(
+                &tile.data.properties,
+                &self.data.properties,
+            )),
+            _ => Cow::Borrowed(&self.data.properties),
+        }
+    }
+
+    /// Reads the `object` property with the given name and returns the object it refers to, if
+    /// both exist. A property set to 0 is considered unset.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = tiled::Loader::new().load_tmx_map("assets/tiled_object_references.tmx")?;
+    /// let switch = map.get_object_by_id(1).unwrap();
+    ///
+    /// let door = switch.resolve_object_property("target").unwrap();
+    /// assert_eq!(&*door.name, "door");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resolve_object_property(&self, name: &str) -> Option<Object<'map>> {
+        resolve_object_property(self.map, &self.data.properties, name)
+    }
+}
+
+/// Resolves the `object` property with the given name in `properties` into the object of `map`
+/// it refers to.
+pub(crate) fn resolve_object_property<'map>(
+    map: &'map crate::Map,
+    properties: &Properties,
+    name: &str,
+) -> Option<Object<'map>> {
+    match properties.get_object(name)? {
+        0 => None,
+        id => map.get_object_by_id(id),
+    }
 }
@@ -0,0 +1,250 @@
+//! Counterparts of the map wrapper types that own a reference to their map instead of borrowing
+//! it, so that they can be stored for later use.
+
+use std::{ops::Deref, sync::Arc};
+
+use crate::{Layer, LayerData, LayerTile, LayerTileData, Map, Object, ObjectData};
+
+/// A [`Layer`] that holds an [`Arc`] to its map rather than borrowing it, which makes it
+/// `'static`. Obtained through [`Layer::to_owned_layer`].
+///
+/// It only stores where the layer is in the map, and finds its data again whenever it is accessed.
+/// Cloning it is cheap. Either dereference it for the layer's data or use [`OwnedLayer::get`]
+/// for the methods of [`Layer`].
+///
+/// ## Example
+/// ```
+/// # use std::sync::Arc;
+/// # use tiled::{Loader, OwnedLayer};
+/// # fn main() {
+/// let map = Arc::new(
+///     Loader::new()
+///         .load_tmx_map("assets/tiled_group_layers.tmx")
+///         .unwrap(),
+/// );
+/// let layer: OwnedLayer = map.get_layer(0).unwrap().to_owned_layer(&map);
+/// drop(map);
+///
+/// // The layer keeps the map alive
+/// assert_eq!(layer.name, "tile-1");
+/// assert!(layer.get().as_tile_layer().is_some());
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct OwnedLayer {
+    map: Arc<Map>,
+    /// The indices leading to the layer from the map's top-level layers.
+    path: Arc<[usize]>,
+}
+
+impl OwnedLayer {
+    /// Get the map this layer is from.
+    #[inline]
+    pub fn map(&self) -> &Arc<Map> {
+        &self.map
+    }
+
+    /// Returns the indices leading to this layer: Its index among the top-level layers of the
+    /// map, followed by its index in each of the group layers containing it.
+    #[inline]
+    pub fn index_path(&self) -> &[usize] {
+        &self.path
+    }
+
+    /// Returns the borrowed [`Layer`] this refers to.
+    pub fn get(&self) -> Layer<'_> {
+        Layer::new(&self.map, self.data())
+    }
+
+    /// Returns the tile at the given position if this is a tile layer and there is one. See
+    /// [`TileLayer::get_tile`](crate::TileLayer::get_tile).
+    pub fn get_tile(&self, x: i32, y: i32) -> Option<OwnedLayerTile> {
+        self.get().as_tile_layer()?.get_tile(x, y)?;
+        Some(OwnedLayerTile {
+            layer: self.clone(),
+            x,
+            y,
+        })
+    }
+
+    fn data(&self) -> &LayerData {
+        // The map can't change while it is shared, so the layer is still there
+        LayerData::at_path(&self.map, &self.path).expect("owned layer is missing from its map")
+    }
+}
+
+impl Deref for OwnedLayer {
+    type Target = LayerData;
+
+    #[inline]
+    fn deref(&self) -> &LayerData {
+        self.data()
+    }
+}
+
+impl<'map> Layer<'map> {
+    /// Converts this layer into an [`OwnedLayer`], which holds onto `map` instead of borrowing it.
+    ///
+    /// ## Panics
+    /// Panics if `map` is not the map this layer is from.
+    pub fn to_owned_layer(&self, map: &Arc<Map>) -> OwnedLayer {
+        assert!(
+            std::ptr::eq(self.map, Arc::as_ptr(map)),
+            "the layer is not from the given map"
+        );
+        let path = self
+            .data
+            .index_path(map)
+            .expect("a layer is always part of its map");
+        OwnedLayer {
+            map: map.clone(),
+            path: path.into(),
+        }
+    }
+}
+
+/// An [`Object`] that holds an [`Arc`] to its map rather than borrowing it, which makes it
+/// `'static`. Obtained through [`Object::to_owned_object`].
+///
+/// Like [`OwnedLayer`], it only stores where the object is and is cheap to clone. Either
+/// dereference it for the object's data or use [`OwnedObject::get`] for the methods of [`Object`].
+///
+/// ## Example
+/// ```
+/// # use std::sync::Arc;
+/// # use tiled::{Loader, ObjectShape, OwnedObject};
+/// # fn main() {
+/// let map = Arc::new(
+///     Loader::new()
+///         .load_tmx_map("assets/tiled_inherited_properties.tmx")
+///         .unwrap(),
+/// );
+/// let object: OwnedObject = map.get_object_by_id(3).unwrap().to_owned_object(&map);
+///
+/// assert!(matches!(object.shape, ObjectShape::Rect { width, .. } if width == 16.0));
+/// assert_eq!(object.layer().name, "Object Layer 1");
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct OwnedObject {
+    layer: OwnedLayer,
+    index: usize,
+}
+
+impl OwnedObject {
+    /// Get the map this object is from.
+    #[inline]
+    pub fn map(&self) -> &Arc<Map> {
+        self.layer.map()
+    }
+
+    /// Returns the object layer this object is in.
+    #[inline]
+    pub fn layer(&self) -> &OwnedLayer {
+        &self.layer
+    }
+
+    /// Returns the index of this object in its layer, as used by
+    /// [`ObjectLayer::get_object`](crate::ObjectLayer::get_object).
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the borrowed [`Object`] this refers to.
+    pub fn get(&self) -> Object<'_> {
+        self.layer
+            .get()
+            .as_object_layer()
+            .and_then(|layer| layer.get_object(self.index))
+            .expect("owned object is missing from its map")
+    }
+}
+
+impl Deref for OwnedObject {
+    type Target = ObjectData;
+
+    #[inline]
+    fn deref(&self) -> &ObjectData {
+        self.get().data
+    }
+}
+
+impl<'map> Object<'map> {
+    /// Converts this object into an [`OwnedObject`], which holds onto `map` instead of borrowing
+    /// it.
+    ///
+    /// ## Panics
+    /// Panics if `map` is not the map this object is from, or if the object isn't in one of its
+    /// object layers, such as the objects of a tile's collision shapes.
+    pub fn to_owned_object(&self, map: &Arc<Map>) -> OwnedObject {
+        assert!(
+            std::ptr::eq(self.map, Arc::as_ptr(map)),
+            "the object is not from the given map"
+        );
+        map.layers_recursive()
+            .find_map(|(layer, _)| {
+                let index = layer
+                    .as_object_layer()?
+                    .object_data()
+                    .iter()
+                    .position(|object| std::ptr::eq(object, self.data))?;
+                Some(OwnedObject {
+                    layer: layer.to_owned_layer(map),
+                    index,
+                })
+            })
+            .expect("the object is not in any of the map's object layers")
+    }
+}
+
+/// A [`LayerTile`] that holds an [`Arc`] to its map rather than borrowing it, which makes it
+/// `'static`. Obtained through [`OwnedLayer::get_tile`].
+///
+/// Like [`OwnedLayer`], it only stores where the tile is and is cheap to clone. Either
+/// dereference it for the tile's data or use [`OwnedLayerTile::get`] for the methods of
+/// [`LayerTile`].
+#[derive(Clone, Debug)]
+pub struct OwnedLayerTile {
+    layer: OwnedLayer,
+    x: i32,
+    y: i32,
+}
+
+impl OwnedLayerTile {
+    /// Get the map this tile is from.
+    #[inline]
+    pub fn map(&self) -> &Arc<Map> {
+        self.layer.map()
+    }
+
+    /// Returns the tile layer this tile is in.
+    #[inline]
+    pub fn layer(&self) -> &OwnedLayer {
+        &self.layer
+    }
+
+    /// Returns the position of this tile in its layer, in tiles.
+    #[inline]
+    pub fn position(&self) -> (i32, i32) {
+        (self.x, self.y)
+    }
+
+    /// Returns the borrowed [`LayerTile`] this refers to.
+    pub fn get(&self) -> LayerTile<'_> {
+        self.layer
+            .get()
+            .as_tile_layer()
+            .and_then(|layer| layer.get_tile(self.x, self.y))
+            .expect("owned tile is missing from its map")
+    }
+}
+
+impl Deref for OwnedLayerTile {
+    type Target = LayerTileData;
+
+    #[inline]
+    fn deref(&self) -> &LayerTileData {
+        self.get().data
+    }
+}
@@ -0,0 +1,348 @@
+use std::{convert::TryFrom, path::Path};
+
+use serde_json::{Map as JsonObject, Value};
+
+use super::{
+    as_object, attribute_value, copy_attributes, malformed, objects, read_json,
+    tileset::write_tileset, with_json_path, write_image, write_properties, JsonSources, Writer,
+};
+use crate::{
+    parse::xml::{LoadContext, ReadFrom, SyncReader},
+    writer::Element,
+    Error, LoadSettings, Map, ResourceCache, Result,
+};
+
+/// Parses the TMJ map at `path`. This is the JSON counterpart of
+/// [`parse_map`](crate::parse::xml::parse_map), and takes the same arguments.
+pub(crate) async fn parse_map(
+    path: &Path,
+    read_from: &mut impl ReadFrom,
+    cache: &mut impl ResourceCache,
+    context: LoadContext,
+) -> Result<Map> {
+    let json = read_json(path, read_from).await?;
+    let (tmx, sources) =
+        map_to_tmx(&json, path, &context.settings).map_err(|err| err.at_json_path(path, "$"))?;
+    crate::parse::xml::parse_map_from(SyncReader::new(&tmx[..]), path, read_from, cache, context)
+        .await
+        .map_err(|err| with_json_path(err, path, &sources))
+}
+
+/// Converts the TMJ map at `path` into the equivalent TMX document.
+fn map_to_tmx(
+    map: &JsonObject<String, Value>,
+    path: &Path,
+    settings: &LoadSettings,
+) -> Result<(Vec<u8>, JsonSources)> {
+    let mut tmx = Vec::new();
+    let mut w = Writer::new(&mut tmx, path);
+    w.declaration()?;
+    // `type` is always "map" here; The class of the map is in `class`
+    w.start(copy_attributes(
+        Element::new("map"),
+        map,
+        &[
+            "version",
+            "tiledversion",
+            "class",
+            "orientation",
+            "renderorder",
+            "width",
+            "height",
+            "tilewidth",
+            "tileheight",
+            "hexsidelength",
+            "staggeraxis",
+            "staggerindex",
+            "backgroundcolor",
+            "infinite",
+            "nextlayerid",
+            "nextobjectid",
+        ],
+    ))?;
+    if let Some(settings) = map.get("editorsettings") {
+        w.member("editorsettings", |w| {
+            write_editor_settings(w, as_object(settings, "map", "editorsettings")?)
+        })?;
+    }
+    write_properties(&mut w, "map", map, settings)?;
+    for (index, tileset) in objects(map, "map", "tilesets")?.into_iter().enumerate() {
+        w.element("tilesets", index, |w| {
+            if tileset.contains_key("source") {
+                w.empty(copy_attributes(
+                    Element::new("tileset"),
+                    tileset,
+                    &["firstgid", "source"],
+                ))
+            } else {
+                write_tileset(w, tileset, true, settings)
+            }
+        })?;
+    }
+    for (index, layer) in objects(map, "map", "layers")?.into_iter().enumerate() {
+        w.element("layers", index, |w| write_layer(w, layer, settings))?;
+    }
+    w.end("map")?;
+    let sources = w.finish()?;
+    Ok((tmx, sources))
+}
+
+fn write_editor_settings(w: &mut Writer, settings: &JsonObject<String, Value>) -> Result<()> {
+    w.start(Element::new("editorsettings"))?;
+    if let Some(chunk_size) = settings.get("chunksize") {
+        w.empty(copy_attributes(
+            Element::new("chunksize"),
+            as_object(chunk_size, "editorsettings", "chunksize")?,
+            &["width", "height"],
+        ))?;
+    }
+    if let Some(export) = settings.get("export") {
+        w.empty(copy_attributes(
+            Element::new("export"),
+            as_object(export, "editorsettings", "export")?,
+            &["target", "format"],
+        ))?;
+    }
+    w.end("editorsettings")
+}
+
+fn write_layer(
+    w: &mut Writer,
+    layer: &JsonObject<String, Value>,
+    settings: &LoadSettings,
+) -> Result<()> {
+    let layer_type = match layer.get("type") {
+        Some(Value::String(layer_type)) => layer_type.as_str(),
+        Some(value) => {
+            return Err(Error::MalformedAttribute {
+                element: "layer",
+                attribute: "type",
+                value: value.to_string(),
+                source: None,
+            })
+        }
+        None => {
+            return Err(Error::MissingAttribute {
+                element: "layer",
+                attribute: "type",
+            })
+        }
+    };
+    let (name, keys): (_, &[_]) = match layer_type {
+        "tilelayer" => ("layer", &["width", "height"]),
+        "objectgroup" => ("objectgroup", &["color", "draworder"]),
+        "imagelayer" => ("imagelayer", &["repeatx", "repeaty"]),
+        "group" => ("group", &[]),
+        _ => return write_unknown_layer(w, layer, layer_type),
+    };
+    let element = copy_attributes(
+        Element::new(name),
+        layer,
+        &[
+            "id",
+            "name",
+            "class",
+            "visible",
+            "locked",
+            "opacity",
+            "tintcolor",
+            "offsetx",
+            "offsety",
+            "parallaxx",
+            "parallaxy",
+        ],
+    );
+    w.start(copy_attributes(element, layer, keys))?;
+    write_properties(w, name, layer, settings)?;
+    match layer_type {
+        "tilelayer" => write_tile_data(w, layer)?,
+        "objectgroup" => {
+            for (index, object) in objects(layer, "objectgroup", "objects")?
+                .into_iter()
+                .enumerate()
+            {
+                w.element("objects", index, |w| write_object(w, object, settings))?;
+            }
+        }
+        "imagelayer" => write_image(w, layer, "image", layer.get("transparentcolor"))?,
+        _ => {
+            for (index, child) in objects(layer, "group", "layers")?.into_iter().enumerate() {
+                w.element("layers", index, |w| write_layer(w, child, settings))?;
+            }
+        }
+    }
+    w.end(name)
+}
+
+/// Writes a layer of a type the crate doesn't support as an empty element named after its type,
+/// with its other members as attributes, so that it's parsed like the unknown layers of TMX maps.
+/// Members that aren't numbers, strings or booleans, such as its properties, are left out.
+fn write_unknown_layer(
+    w: &mut Writer,
+    layer: &JsonObject<String, Value>,
+    layer_type: &str,
+) -> Result<()> {
+    if !is_xml_name(layer_type) {
+        return Err(Error::MalformedAttribute {
+            element: "layer",
+            attribute: "type",
+            value: layer_type.to_owned(),
+            source: None,
+        });
+    }
+    let mut element = Element::new(layer_type.to_owned());
+    for (key, value) in layer {
+        if key == "type" || !is_xml_name(key) {
+            continue;
+        }
+        if let Some(value) = attribute_value(value) {
+            element = element.attr(key.clone(), value);
+        }
+    }
+    w.empty(element)
+}
+
+/// Whether `name` can be used as the name of an XML element or attribute as is. Only ASCII names
+/// are accepted, which is all Tiled uses.
+fn is_xml_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// Writes the `<data>` element of a tile layer. Tiles are stored either as an array of GIDs, or
+/// as a base64 string, in which case the layer's `encoding` and `compression` apply.
+fn write_tile_data(w: &mut Writer, layer: &JsonObject<String, Value>) -> Result<()> {
+    let compression = layer
+        .get("compression")
+        .and_then(Value::as_str)
+        .filter(|compression| !compression.is_empty());
+    let data = Element::new("data");
+    let data = match layer.get("encoding").and_then(Value::as_str) {
+        Some("base64") => data
+            .attr("encoding", "base64")
+            .attr_opt("compression", compression),
+        _ => data.attr("encoding", "csv"),
+    };
+    if let Some(chunks) = layer.get("chunks") {
+        w.start(data)?;
+        for (index, chunk) in chunks.as_array().into_iter().flatten().enumerate() {
+            w.element("chunks", index, |w| {
+                let chunk = as_object(chunk, "layer", "chunks")?;
+                let element =
+                    copy_attributes(Element::new("chunk"), chunk, &["x", "y", "width", "height"]);
+                w.text(element, &tile_data_text("chunk", chunk.get("data"))?)
+            })?;
+        }
+        w.end("data")
+    } else {
+        w.text(data, &tile_data_text("layer", layer.get("data"))?)
+    }
+}
+
+/// Converts the tiles of a layer or chunk, which is converted into the XML element `element`, into
+/// the text of the XML element holding them: A comma separated list of GIDs for arrays, and the
+/// string itself for base64 data.
+fn tile_data_text(element: &'static str, data: Option<&Value>) -> Result<String> {
+    match data {
+        None | Some(Value::Null) => Ok(String::new()),
+        Some(Value::String(base64)) => Ok(base64.clone()),
+        Some(Value::Array(gids)) => gids
+            .iter()
+            .map(|gid| {
+                gid.as_u64()
+                    .filter(|&gid| u32::try_from(gid).is_ok())
+                    .map(|gid| gid.to_string())
+                    .ok_or_else(|| malformed(element, "data", gid, "a tile GID"))
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(|gids| gids.join(",")),
+        Some(data) => Err(malformed(
+            element,
+            "data",
+            data,
+            "an array of GIDs or a base64 string",
+        )),
+    }
+}
+
+pub(super) fn write_object(
+    w: &mut Writer,
+    object: &JsonObject<String, Value>,
+    settings: &LoadSettings,
+) -> Result<()> {
+    w.start(copy_attributes(
+        Element::new("object"),
+        object,
+        &[
+            "id", "template", "name", "type", "class", "gid", "x", "y", "width", "height",
+            "rotation", "visible", "locked",
+        ],
+    ))?;
+    write_properties(w, "object", object, settings)?;
+    if object.get("ellipse") == Some(&Value::Bool(true)) {
+        w.empty(Element::new("ellipse"))?;
+    }
+    if object.get("point") == Some(&Value::Bool(true)) {
+        w.empty(Element::new("point"))?;
+    }
+    for shape in ["polygon", "polyline"] {
+        if let Some(points) = object.get(shape) {
+            w.member(shape, |w| {
+                w.empty(Element::new(shape).attr("points", points_text(shape, points)?))
+            })?;
+        }
+    }
+    if let Some(text) = object.get("text") {
+        w.member("text", |w| {
+            let text = as_object(text, "object", "text")?;
+            let element = copy_attributes(
+                Element::new("text"),
+                text,
+                &[
+                    "fontfamily",
+                    "pixelsize",
+                    "wrap",
+                    "color",
+                    "bold",
+                    "italic",
+                    "underline",
+                    "strikeout",
+                    "kerning",
+                    "halign",
+                    "valign",
+                ],
+            );
+            let contents = text.get("text").and_then(Value::as_str).unwrap_or_default();
+            w.text(element, contents)
+        })?;
+    }
+    w.end("object")
+}
+
+/// Converts the points of a polygon or polyline, `shape` being which of them, into the
+/// `x,y x,y ...` format of TMX files.
+fn points_text(shape: &'static str, points: &Value) -> Result<String> {
+    let points = points
+        .as_array()
+        .ok_or_else(|| malformed("object", shape, points, "an array of points"))?;
+    let points = points
+        .iter()
+        .map(|point| {
+            let point = as_object(point, "object", shape)?;
+            let coordinate = |attribute| {
+                point
+                    .get(attribute)
+                    .and_then(attribute_value)
+                    .ok_or(Error::MissingAttribute {
+                        element: "point",
+                        attribute,
+                    })
+            };
+            Ok(format!("{},{}", coordinate("x")?, coordinate("y")?))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(points.join(" "))
+}
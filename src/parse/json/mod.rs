@@ -0,0 +1,426 @@
+//! Support for Tiled's JSON formats: TMJ maps, TSJ tilesets and TJ templates.
+//!
+//! JSON documents are converted into their XML counterparts, which then go through the same parser
+//! as TMX, TSX and TX files. This way both formats are loaded into exactly the same structures, and
+//! share everything from tile data decoding to the resolution of external files. Errors found while
+//! parsing the XML are reported at the path of the JSON value it was converted from.
+
+use std::{
+    ops::{Deref, DerefMut},
+    path::Path,
+};
+
+use serde_json::{Map as JsonObject, Value};
+
+use super::xml::ReadFrom;
+use crate::{
+    writer::{Element, XmlWriter},
+    Error, LoadSettings, Result, WriteOptions,
+};
+
+mod map;
+mod template;
+mod tileset;
+
+pub(crate) use map::*;
+pub(crate) use template::*;
+pub(crate) use tileset::*;
+
+/// Writes the XML a JSON document is converted into, keeping track of the JSON value each part of
+/// it is converted from.
+struct Writer<'a> {
+    xml: XmlWriter<&'a mut Vec<u8>>,
+    /// The path of the JSON file being converted.
+    path: &'a Path,
+    /// The path of the JSON value being converted, such as `$.layers[0]`.
+    json_path: String,
+    sources: JsonSources,
+}
+
+impl<'a> Writer<'a> {
+    fn new(out: &'a mut Vec<u8>, path: &'a Path) -> Self {
+        Self {
+            xml: XmlWriter::new(out, WriteOptions::default()),
+            path,
+            json_path: "$".to_owned(),
+            sources: JsonSources(vec![(0, "$".to_owned())]),
+        }
+    }
+
+    /// Converts the member `key` of the current JSON value with `convert`.
+    fn member<T>(&mut self, key: &str, convert: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        self.nested(format!(".{}", key), convert)
+    }
+
+    /// Converts the element at `index` of the array that is the member `key` of the current JSON
+    /// value with `convert`.
+    fn element<T>(
+        &mut self,
+        key: &str,
+        index: usize,
+        convert: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        self.nested(format!(".{}[{}]", key, index), convert)
+    }
+
+    /// Converts the JSON value at `segment` from the current one with `convert`. The XML written
+    /// meanwhile is recorded as coming from that value, and errors are reported at its path.
+    fn nested<T>(
+        &mut self,
+        segment: String,
+        convert: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        let parent_len = self.json_path.len();
+        self.json_path.push_str(&segment);
+        self.mark();
+        let result = convert(self).map_err(|err| err.at_json_path(self.path, &self.json_path));
+        self.json_path.truncate(parent_len);
+        self.mark();
+        result
+    }
+
+    /// Records that the XML written from now on comes from the current JSON value.
+    fn mark(&mut self) {
+        let position = self.xml.position();
+        self.sources.0.push((position, self.json_path.clone()));
+    }
+
+    /// Finishes writing the XML, returning where each part of it was converted from.
+    fn finish(self) -> Result<JsonSources> {
+        self.xml.finish()?;
+        Ok(self.sources)
+    }
+}
+
+impl<'a> Deref for Writer<'a> {
+    type Target = XmlWriter<&'a mut Vec<u8>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.xml
+    }
+}
+
+impl DerefMut for Writer<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.xml
+    }
+}
+
+/// The path of the JSON value each part of the XML a JSON document was converted into comes from,
+/// as the byte offset from which on it does, in order.
+struct JsonSources(Vec<(u64, String)>);
+
+impl JsonSources {
+    /// Returns the path of the JSON value the XML at `position` was converted from.
+    fn json_path_at(&self, position: u64) -> &str {
+        let index = self.0.partition_point(|(offset, _)| *offset <= position);
+        self.0[..index]
+            .last()
+            .map_or("$", |(_, json_path)| json_path)
+    }
+}
+
+/// Whether the file at `path` is expected to be in a JSON format, judging by its extension:
+/// `.tmj`, `.tsj`, `.tj` or `.json`, optionally followed by `.gz` or `.zst` for compressed files.
+pub(crate) fn is_json_path(path: &Path) -> bool {
+    let is_compressed = path
+        .extension()
+        .is_some_and(|extension| extension == "gz" || extension == "zst");
+    let path = if is_compressed {
+        Path::new(path.file_stem().unwrap_or_default())
+    } else {
+        path
+    };
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            ["tmj", "tsj", "tj", "json"]
+                .iter()
+                .any(|json| extension.eq_ignore_ascii_case(json))
+        })
+}
+
+/// Whether the file at `path` holds a JSON document rather than an XML one, judging by its first
+/// character that isn't whitespace.
+pub(crate) async fn is_json_document(path: &Path, read_from: &mut impl ReadFrom) -> Result<bool> {
+    let head =
+        read_from
+            .read_head(path, 1024)
+            .await
+            .map_err(|err| Error::ResourceLoadingError {
+                path: path.to_owned(),
+                err,
+            })?;
+    let head = head.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&head);
+    Ok(head
+        .iter()
+        .find(|byte| !byte.is_ascii_whitespace())
+        .is_some_and(|&byte| byte == b'{'))
+}
+
+/// Reads and decodes the JSON document at `path`, which must be an object.
+async fn read_json(
+    path: &Path,
+    read_from: &mut impl ReadFrom,
+) -> Result<JsonObject<String, Value>> {
+    let contents = read_from
+        .read_all(path)
+        .await
+        .map_err(|err| Error::ResourceLoadingError {
+            path: path.to_owned(),
+            err,
+        })?;
+    decode_json(&contents, path)
+}
+
+/// Decodes `contents`, the JSON document at `path`, which must be an object.
+fn decode_json(contents: &[u8], path: &Path) -> Result<JsonObject<String, Value>> {
+    serde_json::from_slice(contents)
+        .map_err(|err| Error::JsonDecodingError(err).at_json_path(path, "$"))
+}
+
+/// Replaces the position of errors that happened while parsing the XML the document at `path` was
+/// converted into, which doesn't mean anything in the JSON document, with the path of the JSON
+/// value the XML at that position was converted from. Errors in other files it refers to are left
+/// alone.
+fn with_json_path(err: Error, path: &Path, sources: &JsonSources) -> Error {
+    match err {
+        Error::ParseError {
+            path: err_path,
+            position,
+            json_path: None,
+            err,
+        } if err_path == path => Error::ParseError {
+            path: err_path,
+            position: 0,
+            json_path: Some(sources.json_path_at(position).to_owned()),
+            err,
+        },
+        err => err,
+    }
+}
+
+/// Returns the error for `value`, the member `attribute` of a JSON object converted into the XML
+/// element `element`, not being `expected`.
+fn malformed(
+    element: &'static str,
+    attribute: &'static str,
+    value: &Value,
+    expected: &str,
+) -> Error {
+    Error::MalformedAttribute {
+        element,
+        attribute,
+        value: value.to_string(),
+        source: Some(format!("expected {}", expected).into()),
+    }
+}
+
+/// Gets `value`, the member `attribute` of a JSON object converted into the XML element
+/// `element`, as a JSON object.
+fn as_object<'a>(
+    value: &'a Value,
+    element: &'static str,
+    attribute: &'static str,
+) -> Result<&'a JsonObject<String, Value>> {
+    value
+        .as_object()
+        .ok_or_else(|| malformed(element, attribute, value, "a JSON object"))
+}
+
+/// Gets the member `key` of `object`, which is converted into the XML element `element`, as an
+/// array of JSON objects. It is empty if there is no such member.
+fn objects<'a>(
+    object: &'a JsonObject<String, Value>,
+    element: &'static str,
+    key: &'static str,
+) -> Result<Vec<&'a JsonObject<String, Value>>> {
+    match object.get(key) {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::Array(values)) => values
+            .iter()
+            .map(|value| as_object(value, element, key))
+            .collect(),
+        Some(value) => Err(malformed(element, key, value, "a JSON array")),
+    }
+}
+
+/// Converts a JSON value into the text of an XML attribute. Booleans are written as `1` or `0`,
+/// like Tiled does in XML files.
+fn attribute_value(value: &Value) -> Option<String> {
+    match value {
+        Value::String(string) => Some(string.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(value) => Some(if *value { "1" } else { "0" }.to_owned()),
+        _ => None,
+    }
+}
+
+/// Copies the members of `object` named by `keys` into attributes of the same name.
+fn copy_attributes(
+    mut element: Element,
+    object: &JsonObject<String, Value>,
+    keys: &[&'static str],
+) -> Element {
+    for key in keys {
+        element = element.attr_opt(key, object.get(*key).and_then(attribute_value));
+    }
+    element
+}
+
+/// Writes an `<image>` element for the image whose source is the member `key` of `object`, with
+/// the size given by its `imagewidth` and `imageheight` members. Nothing is written if there is no
+/// image.
+fn write_image(
+    w: &mut Writer,
+    object: &JsonObject<String, Value>,
+    key: &str,
+    transparent_color: Option<&Value>,
+) -> Result<()> {
+    let source = match object.get(key).and_then(Value::as_str) {
+        Some(source) if !source.is_empty() => source,
+        _ => return Ok(()),
+    };
+    let trans = transparent_color
+        .and_then(Value::as_str)
+        .map(|color| color.trim_start_matches('#'));
+    w.empty(
+        Element::new("image")
+            .attr("source", source)
+            .attr_opt("trans", trans)
+            .attr_opt("width", object.get("imagewidth").and_then(attribute_value))
+            .attr_opt(
+                "height",
+                object.get("imageheight").and_then(attribute_value),
+            ),
+    )
+}
+
+/// Writes the `properties` member of `object`, which is converted into the XML element `element`,
+/// as a `<properties>` element, if there are any.
+fn write_properties(
+    w: &mut Writer,
+    element: &'static str,
+    object: &JsonObject<String, Value>,
+    settings: &LoadSettings,
+) -> Result<()> {
+    let properties = objects(object, element, "properties")?;
+    if properties.is_empty() {
+        return Ok(());
+    }
+    w.start(Element::new("properties"))?;
+    for (index, property) in properties.into_iter().enumerate() {
+        w.element("properties", index, |w| {
+            let name =
+                property
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .ok_or(Error::MissingAttribute {
+                        element: "property",
+                        attribute: "name",
+                    })?;
+            let property_type = property.get("type").and_then(Value::as_str);
+            write_property(
+                w,
+                name,
+                property_type.unwrap_or("string"),
+                property.get("propertytype").and_then(Value::as_str),
+                property.get("value").unwrap_or(&Value::Null),
+                settings,
+            )
+        })?;
+    }
+    w.end("properties")
+}
+
+fn write_property(
+    w: &mut Writer,
+    name: &str,
+    property_type: &str,
+    custom_type: Option<&str>,
+    value: &Value,
+    settings: &LoadSettings,
+) -> Result<()> {
+    let element = Element::new("property")
+        .attr("name", name)
+        .attr("type", property_type)
+        .attr_opt("propertytype", custom_type);
+    if property_type != "class" {
+        let value = match value {
+            Value::String(string) => string.clone(),
+            Value::Null => String::new(),
+            value => value.to_string(),
+        };
+        return w.empty(element.attr("value", value));
+    }
+
+    // Class values only hold the members that were set, without their types
+    let members = match value.as_object() {
+        Some(members) if !members.is_empty() => members,
+        _ => return w.empty(element),
+    };
+    w.start(element)?;
+    w.start(Element::new("properties"))?;
+    for (member, value) in members {
+        let (member_type, member_custom_type) = member_type(custom_type, member, value, settings);
+        write_property(
+            w,
+            member,
+            member_type,
+            member_custom_type.as_deref(),
+            value,
+            settings,
+        )?;
+    }
+    w.end("properties")?;
+    w.end("property")
+}
+
+/// Finds the type and custom type of the member `member` of a class value, whose class is
+/// `class`. They're taken from the class definition if the loader has a project that defines it,
+/// and guessed from the JSON value otherwise.
+#[cfg_attr(not(feature = "project"), allow(unused_variables))]
+fn member_type(
+    class: Option<&str>,
+    member: &str,
+    value: &Value,
+    settings: &LoadSettings,
+) -> (&'static str, Option<String>) {
+    #[cfg(feature = "project")]
+    {
+        use crate::PropertyValue;
+
+        let default = settings
+            .project
+            .as_ref()
+            .zip(class)
+            .and_then(|(project, class)| project.get_class(class))
+            .and_then(|class| class.members.iter().find(|(name, _)| name == member))
+            .map(|(_, default)| default);
+        match default {
+            Some(PropertyValue::BoolValue(_)) => return ("bool", None),
+            Some(PropertyValue::FloatValue(_)) => return ("float", None),
+            Some(PropertyValue::IntValue(_)) => return ("int", None),
+            Some(PropertyValue::ColorValue(_)) => return ("color", None),
+            Some(PropertyValue::StringValue(_)) => return ("string", None),
+            Some(PropertyValue::FileValue(_)) => return ("file", None),
+            Some(PropertyValue::ObjectValue(_)) => return ("object", None),
+            Some(PropertyValue::ClassValue { property_type, .. }) => {
+                return ("class", Some(property_type.clone()))
+            }
+            Some(PropertyValue::EnumValue { name, .. }) => {
+                let storage = if value.is_number() { "int" } else { "string" };
+                return (storage, Some(name.clone()));
+            }
+            Some(PropertyValue::Unknown { .. }) | None => {}
+        }
+    }
+    let member_type = match value {
+        Value::Bool(_) => "bool",
+        Value::Number(number) if number.is_f64() => "float",
+        Value::Number(_) => "int",
+        Value::Object(_) => "class",
+        _ => "string",
+    };
+    (member_type, None)
+}
@@ -0,0 +1,63 @@
+use std::{path::Path, sync::Arc};
+
+use serde_json::{Map as JsonObject, Value};
+
+use super::{
+    as_object, copy_attributes, map::write_object, read_json, tileset::write_tileset,
+    with_json_path, JsonSources, Writer,
+};
+use crate::{
+    parse::xml::{LoadContext, ReadFrom, SyncReader},
+    writer::Element,
+    LoadSettings, ResourceCache, Result, Template,
+};
+
+/// Parses the TJ template at `path`. This is the JSON counterpart of
+/// [`Template::parse_template`], and takes the same arguments.
+pub(crate) async fn parse_template(
+    path: &Path,
+    read_from: &mut impl ReadFrom,
+    cache: &mut impl ResourceCache,
+    context: &mut LoadContext,
+) -> Result<Arc<Template>> {
+    let json = read_json(path, read_from).await?;
+    let (tx, sources) = template_to_tx(&json, path, &context.settings)
+        .map_err(|err| err.at_json_path(path, "$"))?;
+    Template::parse_template_from(SyncReader::new(&tx[..]), path, read_from, cache, context)
+        .await
+        .map_err(|err| with_json_path(err, path, &sources))
+}
+
+/// Converts the TJ template at `path` into the equivalent TX document.
+fn template_to_tx(
+    template: &JsonObject<String, Value>,
+    path: &Path,
+    settings: &LoadSettings,
+) -> Result<(Vec<u8>, JsonSources)> {
+    let mut tx = Vec::new();
+    let mut w = Writer::new(&mut tx, path);
+    w.declaration()?;
+    w.start(Element::new("template"))?;
+    if let Some(tileset) = template.get("tileset") {
+        w.member("tileset", |w| {
+            let tileset = as_object(tileset, "template", "tileset")?;
+            if tileset.contains_key("source") {
+                w.empty(copy_attributes(
+                    Element::new("tileset"),
+                    tileset,
+                    &["firstgid", "source"],
+                ))
+            } else {
+                write_tileset(w, tileset, true, settings)
+            }
+        })?;
+    }
+    if let Some(object) = template.get("object") {
+        w.member("object", |w| {
+            write_object(w, as_object(object, "template", "object")?, settings)
+        })?;
+    }
+    w.end("template")?;
+    let sources = w.finish()?;
+    Ok((tx, sources))
+}
@@ -0,0 +1,256 @@
+use std::path::Path;
+
+use serde_json::{Map as JsonObject, Value};
+
+use super::{
+    as_object, copy_attributes, decode_json, map::write_object, objects, read_json, with_json_path,
+    write_image, write_properties, Writer,
+};
+use crate::{
+    parse::xml::{LoadContext, ReadFrom, SyncReader},
+    writer::Element,
+    LoadProgress, LoadSettings, ResourceCache, Result, Tileset,
+};
+
+/// Parses the TSJ tileset at `path`. This is the JSON counterpart of
+/// [`parse_tileset`](crate::parse::xml::parse_tileset), and takes the same arguments.
+pub(crate) async fn parse_tileset(
+    path: &Path,
+    read_from: &mut impl ReadFrom,
+    cache: &mut impl ResourceCache,
+    context: &mut LoadContext,
+) -> Result<Tileset> {
+    context
+        .settings
+        .report_progress(LoadProgress::ExternalTilesetStarted(path));
+    let tileset = parse_tileset_inner(path, read_from, cache, context).await;
+    context
+        .settings
+        .report_progress(LoadProgress::ExternalTilesetFinished(path));
+    tileset
+}
+
+async fn parse_tileset_inner(
+    path: &Path,
+    read_from: &mut impl ReadFrom,
+    cache: &mut impl ResourceCache,
+    context: &mut LoadContext,
+) -> Result<Tileset> {
+    let tileset = read_json(path, read_from).await?;
+    parse_tileset_json(&tileset, path, read_from, cache, context).await
+}
+
+/// Parses a TSJ tileset from the already read `contents` of its file. `path` is only used to
+/// resolve the external files the tileset refers to.
+pub(crate) async fn parse_tileset_from(
+    contents: &[u8],
+    path: &Path,
+    read_from: &mut impl ReadFrom,
+    cache: &mut impl ResourceCache,
+    context: &mut LoadContext,
+) -> Result<Tileset> {
+    let tileset = decode_json(contents, path)?;
+    parse_tileset_json(&tileset, path, read_from, cache, context).await
+}
+
+async fn parse_tileset_json(
+    tileset: &JsonObject<String, Value>,
+    path: &Path,
+    read_from: &mut impl ReadFrom,
+    cache: &mut impl ResourceCache,
+    context: &mut LoadContext,
+) -> Result<Tileset> {
+    let mut tsx = Vec::new();
+    let mut w = Writer::new(&mut tsx, path);
+    let sources = w
+        .declaration()
+        .and_then(|_| write_tileset(&mut w, tileset, false, &context.settings))
+        .and_then(|_| w.finish())
+        .map_err(|err| err.at_json_path(path, "$"))?;
+    crate::parse::xml::parse_tileset_from(
+        SyncReader::new(&tsx[..]),
+        path,
+        read_from,
+        cache,
+        context,
+    )
+    .await
+    .map_err(|err| with_json_path(err, path, &sources))
+}
+
+/// Writes a JSON tileset as a `<tileset>` element. Tilesets embedded in a map keep their
+/// `firstgid`, while external ones keep their format version instead.
+pub(super) fn write_tileset(
+    w: &mut Writer,
+    tileset: &JsonObject<String, Value>,
+    embedded: bool,
+    settings: &LoadSettings,
+) -> Result<()> {
+    let element = Element::new("tileset");
+    let element = if embedded {
+        copy_attributes(element, tileset, &["firstgid"])
+    } else {
+        copy_attributes(element, tileset, &["version", "tiledversion"])
+    };
+    // `type` is always "tileset" here; The class of the tileset is in `class`
+    w.start(copy_attributes(
+        element,
+        tileset,
+        &[
+            "name",
+            "class",
+            "tilewidth",
+            "tileheight",
+            "spacing",
+            "margin",
+            "tilecount",
+            "columns",
+            "tilerendersize",
+            "fillmode",
+        ],
+    ))?;
+
+    if let Some(offset) = tileset.get("tileoffset") {
+        w.member("tileoffset", |w| {
+            w.empty(copy_attributes(
+                Element::new("tileoffset"),
+                as_object(offset, "tileset", "tileoffset")?,
+                &["x", "y"],
+            ))
+        })?;
+    }
+    if let Some(grid) = tileset.get("grid") {
+        w.member("grid", |w| {
+            w.empty(copy_attributes(
+                Element::new("grid"),
+                as_object(grid, "tileset", "grid")?,
+                &["orientation", "width", "height"],
+            ))
+        })?;
+    }
+    write_properties(w, "tileset", tileset, settings)?;
+    write_image(w, tileset, "image", tileset.get("transparentcolor"))?;
+    if let Some(transformations) = tileset.get("transformations") {
+        w.member("transformations", |w| {
+            w.empty(copy_attributes(
+                Element::new("transformations"),
+                as_object(transformations, "tileset", "transformations")?,
+                &["hflip", "vflip", "rotate", "preferuntransformed"],
+            ))
+        })?;
+    }
+    for (index, tile) in objects(tileset, "tileset", "tiles")?
+        .into_iter()
+        .enumerate()
+    {
+        w.element("tiles", index, |w| write_tile(w, tile, settings))?;
+    }
+
+    let wang_sets = objects(tileset, "tileset", "wangsets")?;
+    if !wang_sets.is_empty() {
+        w.start(Element::new("wangsets"))?;
+        for (index, wang_set) in wang_sets.into_iter().enumerate() {
+            w.element("wangsets", index, |w| write_wang_set(w, wang_set, settings))?;
+        }
+        w.end("wangsets")?;
+    }
+    w.end("tileset")
+}
+
+fn write_tile(
+    w: &mut Writer,
+    tile: &JsonObject<String, Value>,
+    settings: &LoadSettings,
+) -> Result<()> {
+    w.start(copy_attributes(
+        Element::new("tile"),
+        tile,
+        &["id", "type", "class", "probability"],
+    ))?;
+    write_properties(w, "tile", tile, settings)?;
+    write_image(w, tile, "image", None)?;
+    if let Some(collision) = tile.get("objectgroup") {
+        w.member("objectgroup", |w| {
+            let collision = as_object(collision, "tile", "objectgroup")?;
+            w.start(copy_attributes(
+                Element::new("objectgroup"),
+                collision,
+                &["id", "name", "color", "draworder"],
+            ))?;
+            for (index, object) in objects(collision, "objectgroup", "objects")?
+                .into_iter()
+                .enumerate()
+            {
+                w.element("objects", index, |w| write_object(w, object, settings))?;
+            }
+            w.end("objectgroup")
+        })?;
+    }
+    if tile.contains_key("animation") {
+        w.start(Element::new("animation"))?;
+        for (index, frame) in objects(tile, "tile", "animation")?.into_iter().enumerate() {
+            w.element("animation", index, |w| {
+                w.empty(copy_attributes(
+                    Element::new("frame"),
+                    frame,
+                    &["tileid", "duration"],
+                ))
+            })?;
+        }
+        w.end("animation")?;
+    }
+    w.end("tile")
+}
+
+fn write_wang_set(
+    w: &mut Writer,
+    wang_set: &JsonObject<String, Value>,
+    settings: &LoadSettings,
+) -> Result<()> {
+    w.start(copy_attributes(
+        Element::new("wangset"),
+        wang_set,
+        &["name", "class", "type", "tile"],
+    ))?;
+    write_properties(w, "wangset", wang_set, settings)?;
+    for (index, wang_color) in objects(wang_set, "wangset", "colors")?
+        .into_iter()
+        .enumerate()
+    {
+        w.element("colors", index, |w| {
+            w.start(copy_attributes(
+                Element::new("wangcolor"),
+                wang_color,
+                &["name", "class", "color", "tile", "probability"],
+            ))?;
+            write_properties(w, "wangcolor", wang_color, settings)?;
+            w.end("wangcolor")
+        })?;
+    }
+    for (index, wang_tile) in objects(wang_set, "wangset", "wangtiles")?
+        .into_iter()
+        .enumerate()
+    {
+        let wang_id = wang_tile
+            .get("wangid")
+            .and_then(Value::as_array)
+            .map(|colors| {
+                colors
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            });
+        w.element("wangtiles", index, |w| {
+            w.empty(
+                copy_attributes(
+                    Element::new("wangtile"),
+                    wang_tile,
+                    &["tileid", "hflip", "vflip", "dflip"],
+                )
+                .attr_opt("wangid", wang_id),
+            )
+        })?;
+    }
+    w.end("wangset")
+}
@@ -1 +1,67 @@
+#[cfg(feature = "json")]
+pub mod json;
 pub mod xml;
+
+use std::path::{Path, PathBuf};
+
+use crate::{Error, LoadProgress, ResourceCache, Result, Tileset};
+use xml::{LoadContext, ReadFrom, SyncReader};
+
+/// Parses the external tileset at `path`, which is a TSJ file if its extension says so and a TSX
+/// file otherwise.
+pub(crate) async fn parse_tileset(
+    path: &Path,
+    read_from: &mut impl ReadFrom,
+    cache: &mut impl ResourceCache,
+    context: &mut LoadContext,
+) -> Result<Tileset> {
+    #[cfg(feature = "json")]
+    if json::is_json_path(path) {
+        return json::parse_tileset(path, read_from, cache, context).await;
+    }
+    xml::parse_tileset(path, read_from, cache, context).await
+}
+
+/// Parses the external tilesets at `paths` like [`parse_tileset`] does, except that all of their
+/// files are read up front, concurrently if `read_from` supports it. The results are in the same
+/// order as `paths`.
+pub(crate) async fn parse_tilesets(
+    paths: &[PathBuf],
+    read_from: &mut impl ReadFrom,
+    cache: &mut impl ResourceCache,
+    context: &mut LoadContext,
+) -> Vec<Result<Tileset>> {
+    let contents = read_from.read_all_of(paths).await;
+    let mut tilesets = Vec::with_capacity(paths.len());
+    for (path, contents) in paths.iter().zip(contents) {
+        context
+            .settings
+            .report_progress(LoadProgress::ExternalTilesetStarted(path));
+        let tileset = match contents {
+            Ok(contents) => parse_tileset_from(&contents, path, read_from, cache, context).await,
+            Err(err) => Err(Error::ResourceLoadingError {
+                path: path.to_owned(),
+                err,
+            }),
+        };
+        context
+            .settings
+            .report_progress(LoadProgress::ExternalTilesetFinished(path));
+        tilesets.push(tileset);
+    }
+    tilesets
+}
+
+async fn parse_tileset_from(
+    contents: &[u8],
+    path: &Path,
+    read_from: &mut impl ReadFrom,
+    cache: &mut impl ResourceCache,
+    context: &mut LoadContext,
+) -> Result<Tileset> {
+    #[cfg(feature = "json")]
+    if json::is_json_path(path) {
+        return json::parse_tileset_from(contents, path, read_from, cache, context).await;
+    }
+    xml::parse_tileset_from(SyncReader::new(contents), path, read_from, cache, context).await
+}
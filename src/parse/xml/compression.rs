@@ -0,0 +1,397 @@
+//! Support for whole documents compressed with gzip, or with zstd when the `zstd` feature is
+//! enabled, such as `map.tmx.gz`.
+//!
+//! The format is detected from the first bytes of a resource, so compressed documents don't need
+//! a particular extension and are read like any other.
+
+use std::io::{self, BufRead, BufReader, Read};
+#[cfg(feature = "async-tokio")]
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+#[cfg(feature = "async-tokio")]
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncReadExt, ReadBuf};
+
+/// The number of bytes needed to detect every supported format.
+const MAGIC_LEN: usize = 4;
+
+/// The format a document is compressed with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compression {
+    Gzip,
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl Compression {
+    /// Returns the format of a document starting with `head`, or `None` if it isn't compressed.
+    fn detect(head: &[u8]) -> Option<Self> {
+        match head {
+            [0x1f, 0x8b, ..] => Some(Self::Gzip),
+            #[cfg(feature = "zstd")]
+            [0x28, 0xb5, 0x2f, 0xfd, ..] => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// A reader that can look at the bytes coming up without consuming them.
+pub(crate) struct Peekable<R> {
+    inner: R,
+    /// The bytes that were peeked at; Those from `pos` onwards haven't been read yet.
+    peeked: Vec<u8>,
+    pos: usize,
+}
+
+impl<R> Peekable<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        Self {
+            inner,
+            peeked: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    fn unread(&self) -> &[u8] {
+        &self.peeked[self.pos..]
+    }
+
+    /// Takes up to `len` of the peeked bytes that haven't been read yet.
+    fn take_unread(&mut self, len: usize) -> &[u8] {
+        let start = self.pos;
+        self.pos = self.peeked.len().min(start + len);
+        &self.peeked[start..self.pos]
+    }
+}
+
+impl<R: Read> Peekable<R> {
+    /// Returns the next `len` bytes without consuming them. Fewer are returned only if the end of
+    /// the stream is reached first.
+    pub(crate) fn peek(&mut self, len: usize) -> io::Result<&[u8]> {
+        let mut buf = [0; MAGIC_LEN];
+        while self.unread().len() < len {
+            let wanted = (len - self.unread().len()).min(buf.len());
+            match self.inner.read(&mut buf[..wanted]) {
+                Ok(0) => break,
+                Ok(read) => self.peeked.extend_from_slice(&buf[..read]),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        let unread = self.unread();
+        Ok(&unread[..len.min(unread.len())])
+    }
+}
+
+#[cfg(feature = "async-tokio")]
+impl<R: AsyncRead + Unpin> Peekable<R> {
+    /// The asynchronous counterpart of [`Peekable::peek`].
+    pub(crate) async fn peek_async(&mut self, len: usize) -> io::Result<&[u8]> {
+        let mut buf = [0; MAGIC_LEN];
+        while self.unread().len() < len {
+            let wanted = (len - self.unread().len()).min(buf.len());
+            match self.inner.read(&mut buf[..wanted]).await? {
+                0 => break,
+                read => self.peeked.extend_from_slice(&buf[..read]),
+            }
+        }
+        let unread = self.unread();
+        Ok(&unread[..len.min(unread.len())])
+    }
+}
+
+impl<R: Read> Read for Peekable<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.unread().is_empty() {
+            return self.inner.read(buf);
+        }
+        let unread = self.take_unread(buf.len());
+        buf[..unread.len()].copy_from_slice(unread);
+        Ok(unread.len())
+    }
+}
+
+impl<R: BufRead> BufRead for Peekable<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos < self.peeked.len() {
+            return Ok(&self.peeked[self.pos..]);
+        }
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if self.pos < self.peeked.len() {
+            self.take_unread(amt);
+        } else {
+            self.inner.consume(amt);
+        }
+    }
+}
+
+#[cfg(feature = "async-tokio")]
+impl<R: AsyncRead + Unpin> AsyncRead for Peekable<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if this.unread().is_empty() {
+            return Pin::new(&mut this.inner).poll_read(cx, buf);
+        }
+        buf.put_slice(this.take_unread(buf.remaining()));
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "async-tokio")]
+impl<R: AsyncBufRead + Unpin> AsyncBufRead for Peekable<R> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        if this.pos < this.peeked.len() {
+            return Poll::Ready(Ok(&this.peeked[this.pos..]));
+        }
+        Pin::new(&mut this.inner).poll_fill_buf(cx)
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let this = self.get_mut();
+        if this.pos < this.peeked.len() {
+            this.take_unread(amt);
+        } else {
+            Pin::new(&mut this.inner).consume(amt);
+        }
+    }
+}
+
+/// A resource that is decompressed as it is read if it turns out to be compressed.
+pub(crate) enum Decompressed<R> {
+    Plain(Peekable<R>),
+    Gzip(BufReader<flate2::bufread::GzDecoder<Peekable<R>>>),
+    #[cfg(feature = "zstd")]
+    Zstd(BufReader<zstd::stream::read::Decoder<'static, Peekable<R>>>),
+}
+
+impl<R: BufRead> Decompressed<R> {
+    /// Detects whether `reader` is compressed by peeking at its first bytes.
+    pub(crate) fn new(reader: R) -> io::Result<Self> {
+        let mut reader = Peekable::new(reader);
+        let compression = Compression::detect(reader.peek(MAGIC_LEN)?);
+        Self::with_compression(reader, compression)
+    }
+
+    fn with_compression(reader: Peekable<R>, compression: Option<Compression>) -> io::Result<Self> {
+        Ok(match compression {
+            None => Self::Plain(reader),
+            Some(Compression::Gzip) => {
+                Self::Gzip(BufReader::new(flate2::bufread::GzDecoder::new(reader)))
+            }
+            #[cfg(feature = "zstd")]
+            Some(Compression::Zstd) => Self::Zstd(BufReader::new(
+                zstd::stream::read::Decoder::with_buffer(reader)?,
+            )),
+        })
+    }
+
+    /// Returns the reader the compressed bytes are read from.
+    #[cfg(feature = "async-tokio")]
+    fn source_mut(&mut self) -> &mut R {
+        match self {
+            Self::Plain(reader) => &mut reader.inner,
+            Self::Gzip(reader) => &mut reader.get_mut().get_mut().inner,
+            #[cfg(feature = "zstd")]
+            Self::Zstd(reader) => &mut reader.get_mut().get_mut().inner,
+        }
+    }
+}
+
+impl<R: BufRead> Read for Decompressed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(reader) => reader.read(buf),
+            Self::Gzip(reader) => reader.read(buf),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(reader) => reader.read(buf),
+        }
+    }
+}
+
+impl<R: BufRead> BufRead for Decompressed<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match self {
+            Self::Plain(reader) => reader.fill_buf(),
+            Self::Gzip(reader) => reader.fill_buf(),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(reader) => reader.fill_buf(),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        match self {
+            Self::Plain(reader) => reader.consume(amt),
+            Self::Gzip(reader) => reader.consume(amt),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(reader) => reader.consume(amt),
+        }
+    }
+}
+
+/// Compressed bytes read asynchronously, from which a [`Decompressed`] reader decodes.
+///
+/// Once its bytes are used up, reading from it fails with [`io::ErrorKind::WouldBlock`] until more
+/// are pushed. The decoders used leave their state untouched when that happens, so they can resume
+/// once the next bytes arrive.
+#[cfg(feature = "async-tokio")]
+pub(crate) struct Feed {
+    bytes: Vec<u8>,
+    pos: usize,
+    eof: bool,
+}
+
+#[cfg(feature = "async-tokio")]
+impl Feed {
+    fn new() -> Self {
+        Self {
+            bytes: Vec::new(),
+            pos: 0,
+            eof: false,
+        }
+    }
+
+    /// Replaces the used up bytes with `bytes`; Pushing no bytes marks the end of the stream.
+    fn push(&mut self, bytes: &[u8]) {
+        self.bytes.clear();
+        self.bytes.extend_from_slice(bytes);
+        self.pos = 0;
+        self.eof = bytes.is_empty();
+    }
+}
+
+#[cfg(feature = "async-tokio")]
+impl Read for Feed {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+#[cfg(feature = "async-tokio")]
+impl BufRead for Feed {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.bytes.len() && !self.eof {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+        Ok(&self.bytes[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = self.bytes.len().min(self.pos + amt);
+    }
+}
+
+/// The asynchronous counterpart of [`Decompressed`].
+///
+/// Compressed resources are decoded as they are read, one buffer at a time, so neither the
+/// compressed nor the decompressed document is ever held in memory as a whole.
+#[cfg(feature = "async-tokio")]
+pub(crate) enum AsyncDecompressed<R> {
+    Plain(Peekable<R>),
+    Compressed {
+        source: Peekable<R>,
+        decoder: Box<Decompressed<Feed>>,
+    },
+}
+
+#[cfg(feature = "async-tokio")]
+impl<R: AsyncBufRead + Unpin> AsyncDecompressed<R> {
+    /// Detects whether `reader` is compressed by peeking at its first bytes.
+    pub(crate) async fn new(reader: R) -> io::Result<Self> {
+        let mut reader = Peekable::new(reader);
+        Ok(
+            match Compression::detect(reader.peek_async(MAGIC_LEN).await?) {
+                None => Self::Plain(reader),
+                compression => Self::Compressed {
+                    source: reader,
+                    decoder: Box::new(Decompressed::with_compression(
+                        Peekable::new(Feed::new()),
+                        compression,
+                    )?),
+                },
+            },
+        )
+    }
+}
+
+/// Reads compressed bytes from `source` into `decoder` until it has decoded bytes available.
+#[cfg(feature = "async-tokio")]
+fn poll_decode<R: AsyncBufRead + Unpin>(
+    source: &mut Peekable<R>,
+    decoder: &mut Decompressed<Feed>,
+    cx: &mut Context<'_>,
+) -> Poll<io::Result<()>> {
+    loop {
+        match decoder.fill_buf() {
+            Ok(_) => return Poll::Ready(Ok(())),
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+            Err(err) => return Poll::Ready(Err(err)),
+        }
+        let compressed = match Pin::new(&mut *source).poll_fill_buf(cx) {
+            Poll::Ready(Ok(compressed)) => compressed,
+            Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+            Poll::Pending => return Poll::Pending,
+        };
+        decoder.source_mut().push(compressed);
+        let len = compressed.len();
+        Pin::new(&mut *source).consume(len);
+    }
+}
+
+#[cfg(feature = "async-tokio")]
+impl<R: AsyncBufRead + Unpin> AsyncRead for AsyncDecompressed<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Plain(reader) => Pin::new(reader).poll_read(cx, buf),
+            Self::Compressed { source, decoder } => {
+                match poll_decode(source, decoder, cx) {
+                    Poll::Ready(Ok(())) => {}
+                    poll => return poll,
+                }
+                let decoded = decoder.fill_buf()?;
+                let len = decoded.len().min(buf.remaining());
+                buf.put_slice(&decoded[..len]);
+                decoder.consume(len);
+                Poll::Ready(Ok(()))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async-tokio")]
+impl<R: AsyncBufRead + Unpin> AsyncBufRead for AsyncDecompressed<R> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        match self.get_mut() {
+            Self::Plain(reader) => Pin::new(reader).poll_fill_buf(cx),
+            Self::Compressed { source, decoder } => match poll_decode(source, decoder, cx) {
+                Poll::Ready(Ok(())) => Poll::Ready(decoder.fill_buf()),
+                Poll::Ready(Err(err)) => Poll::Ready(Err(err)),
+                Poll::Pending => Poll::Pending,
+            },
+        }
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        match self.get_mut() {
+            Self::Plain(reader) => Pin::new(reader).consume(amt),
+            Self::Compressed { decoder, .. } => decoder.consume(amt),
+        }
+    }
+}
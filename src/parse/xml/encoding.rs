@@ -0,0 +1,48 @@
+//! Support for documents that declare another encoding than UTF-8 in their XML declaration, such
+//! as `<?xml version="1.0" encoding="ISO-8859-1"?>`.
+//!
+//! The encoding is detected by quick-xml, which decodes any ASCII-compatible encoding known to
+//! `encoding_rs`. The rest of the parser expects UTF-8, so the events read from such documents are
+//! transcoded to UTF-8 with the reader's [`Decoder`] as they are read.
+
+use std::borrow::Cow;
+
+use quick_xml::{
+    events::{BytesCData, BytesEnd, BytesStart, BytesText, Event},
+    Decoder,
+};
+
+use super::ReadResult;
+
+/// Transcodes text decoded by `decoder` to UTF-8, keeping the lifetime of borrowed bytes.
+fn decode<'a>(decoder: Decoder, bytes: Cow<'a, [u8]>) -> ReadResult<Cow<'a, str>> {
+    Ok(match bytes {
+        Cow::Borrowed(bytes) => decoder.decode(bytes)?,
+        Cow::Owned(bytes) => Cow::Owned(decoder.decode(&bytes)?.into_owned()),
+    })
+}
+
+/// Transcodes the contents of an event read from a document decoded by `decoder` to UTF-8.
+/// Events of UTF-8 documents are returned as is.
+pub(crate) fn decode_event(decoder: Decoder, event: Event<'_>) -> ReadResult<Event<'_>> {
+    if decoder.encoding() == encoding_rs::UTF_8 {
+        return Ok(event);
+    }
+    let decode_start = |start: BytesStart<'_>| -> ReadResult<BytesStart<'_>> {
+        let name_len = decoder.decode(start.name().into_inner())?.len();
+        Ok(BytesStart::from_content(
+            decoder.decode(&start)?.into_owned(),
+            name_len,
+        ))
+    };
+    Ok(match event {
+        Event::Start(start) => Event::Start(decode_start(start)?),
+        Event::Empty(start) => Event::Empty(decode_start(start)?),
+        Event::End(end) => Event::End(BytesEnd::new(decoder.decode(&end)?.into_owned())),
+        Event::Text(text) => {
+            Event::Text(BytesText::from_escaped(decode(decoder, text.into_inner())?))
+        }
+        Event::CData(data) => Event::CData(BytesCData::new(decode(decoder, data.into_inner())?)),
+        event => event,
+    })
+}
@@ -1,38 +1,64 @@
 use std::path::Path;
 
-use itertools::Itertools;
 use quick_xml::events::Event;
 
-use super::{Parser, ReadFrom, Reader};
-use crate::{Error, Map, ResourceCache, Result};
+use super::{LoadContext, Parser, ReadFrom, Reader};
+use crate::{util::collect_attributes, Error, Map, ResourceCache, Result};
 
 pub async fn parse_map(
     path: &Path,
     read_from: &mut impl ReadFrom,
     cache: &mut impl ResourceCache,
+    context: LoadContext,
+) -> Result<Map> {
+    let reader = read_from
+        .read_from(path)
+        .await
+        .map_err(|err| Error::ResourceLoadingError {
+            path: path.to_owned(),
+            err,
+        })?;
+    parse_map_from(reader, path, read_from, cache, context).await
+}
+
+/// Parses a map from an already opened reader. `path` is only used to resolve the external files
+/// the map refers to.
+///
+/// `context` holds the settings the map is loaded with, such as which layer contents are skipped
+/// and whether the map is validated once loaded, along with the state shared with the rest of the
+/// load.
+pub async fn parse_map_from<R: Reader>(
+    mut reader: R,
+    path: &Path,
+    read_from: &mut impl ReadFrom,
+    cache: &mut impl ResourceCache,
+    context: LoadContext,
 ) -> Result<Map> {
-    let mut reader =
-        read_from
-            .read_from(path)
-            .await
-            .map_err(|err| Error::ResourceLoadingError {
-                path: path.to_owned(),
-                err: Box::new(err),
-            })?;
     let mut buffer = Vec::new();
     loop {
+        let position = reader.buffer_position();
         match reader
             .read_event_into(&mut buffer)
             .await
-            .map_err(Error::XmlDecodingError)?
+            .map_err(|err| Error::XmlDecodingError(err).at_position(path, position))?
         {
             Event::Start(start) if start.local_name().into_inner() == b"map" => {
-                let attributes = start
-                    .attributes()
-                    .try_collect()
-                    .map_err(|err| Error::XmlDecodingError(err.into()))?;
+                let attributes =
+                    collect_attributes(&start).map_err(|err| err.at_position(path, position))?;
                 let mut parser = Parser::with_reader(reader);
-                return Map::parse_xml(&mut parser, attributes, path, read_from, cache).await;
+                parser.last_event_position = position;
+                parser.context = context;
+                parser.path = path.to_owned();
+                let validate = parser.context.settings.validate;
+                let map = Map::parse_xml(&mut parser, attributes, path, read_from, cache)
+                    .await
+                    .map_err(|err| err.at_position(path, parser.last_event_position))?;
+                if validate {
+                    if let Some(issue) = map.validate().into_iter().next() {
+                        return Err(Error::ValidationFailed(issue));
+                    }
+                }
+                return Ok(map);
             }
             Event::Eof => {
                 return Err(Error::PrematureEnd(
@@ -1,85 +1,298 @@
+mod compression;
+mod encoding;
 mod map;
-use std::io::BufRead;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 pub(crate) use map::*;
 mod tileset;
+#[cfg(feature = "async-tokio")]
+use compression::AsyncDecompressed;
+use compression::Decompressed;
+use encoding::decode_event;
 pub(crate) use quick_xml::events::Event;
 pub(crate) use quick_xml::Reader as RawReader;
 pub(crate) use quick_xml::Result as ReadResult;
 pub(crate) use tileset::*;
+#[cfg(feature = "async-tokio")]
 use tokio::io::AsyncBufRead;
 
+#[cfg(feature = "async-tokio")]
 use crate::AsyncResourceReader;
-use crate::ResourceReader;
+use crate::{
+    DuplicateHandling, Error, Image, LayerContext, LoadSettings, ObjectAction, ObjectData,
+    ParseWarning, ResourceReader,
+};
 
-/// An abstraction of [`RawReader`] that comes in two flavors: [`SyncEventReader`] and
-/// [`AsyncEventReader`].
+/// The kinds of elements whose names are checked for duplicates, see [`Parser::report_duplicate`].
+#[derive(Clone, Copy)]
+pub(crate) enum DuplicateKind {
+    /// A property with the same name as another one in the same list.
+    Property,
+    /// A layer with the same name as one of its siblings.
+    LayerName,
+}
+
+/// An abstraction of [`RawReader`] that comes in two flavors: [`SyncReader`] and
+/// [`AsyncReader`], the latter only being available with the `async-tokio` feature.
 pub(crate) trait Reader {
     /// Delegates to either [`RawReader::read_event_into`] or [`RawReader::read_event_into_async`],
     /// depending on the implementor.
     async fn read_event_into<'b>(&mut self, buf: &'b mut Vec<u8>) -> ReadResult<Event<'b>>;
+
+    /// Delegates to [`RawReader::buffer_position`].
+    fn buffer_position(&self) -> u64;
 }
 
 /// A [`RawReader`] in 'sync' mode, i.e. that will delegate to [`RawReader::read_event_into`].
-pub(crate) struct SyncReader<R>(pub(crate) RawReader<R>);
+pub(crate) struct SyncReader<R> {
+    reader: RawReader<R>,
+}
+
+impl<R: BufRead> SyncReader<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self {
+            reader: RawReader::from_reader(reader),
+        }
+    }
+}
 
 impl<R: BufRead> Reader for SyncReader<R> {
     /// Will immediately return the next event on the first poll.
     async fn read_event_into<'b>(&mut self, buf: &'b mut Vec<u8>) -> ReadResult<Event<'b>> {
-        self.0.read_event_into(buf)
+        let event = self.reader.read_event_into(buf)?;
+        decode_event(self.reader.decoder(), event)
+    }
+
+    fn buffer_position(&self) -> u64 {
+        self.reader.buffer_position()
     }
 }
 
 /// A [`RawReader`] in 'async' mode, i.e. that will delegate to [`RawReader::read_event_into_async`].
-pub(crate) struct AsyncReader<R>(pub(crate) RawReader<R>);
+#[cfg(feature = "async-tokio")]
+pub(crate) struct AsyncReader<R> {
+    reader: RawReader<R>,
+}
+
+#[cfg(feature = "async-tokio")]
+impl<R: AsyncBufRead + Unpin> AsyncReader<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self {
+            reader: RawReader::from_reader(reader),
+        }
+    }
+}
 
+#[cfg(feature = "async-tokio")]
 impl<R: AsyncBufRead + Unpin> Reader for AsyncReader<R> {
     async fn read_event_into<'b>(&mut self, buf: &'b mut Vec<u8>) -> ReadResult<Event<'b>> {
-        self.0.read_event_into_async(buf).await
+        let event = self.reader.read_event_into_async(buf).await?;
+        decode_event(self.reader.decoder(), event)
+    }
+
+    fn buffer_position(&self) -> u64 {
+        self.reader.buffer_position()
     }
 }
 
 /// An abstraction of the [`ResourceReader`] and [`AsyncResourceReader`] traits that comes in two
 /// flavors: [`SyncReadFrom`] and [`AsyncReadFrom`].
+///
+/// Resources compressed as a whole, such as `map.tmx.gz`, are decompressed as they are read.
 pub(crate) trait ReadFrom {
     type Reader: Reader;
-    type Error: std::error::Error + Send + Sync + 'static;
-    async fn read_from(&mut self, path: &Path) -> Result<Self::Reader, Self::Error>;
+    async fn read_from(&mut self, path: &Path) -> Result<Self::Reader, BoxedError>;
+
+    /// Reads up to the first `len` bytes of a resource, such as the header of an image.
+    async fn read_head(&mut self, path: &Path, len: usize) -> Result<Vec<u8>, BoxedError>;
+
+    /// Reads the whole contents of a resource, such as a JSON file.
+    async fn read_all(&mut self, path: &Path) -> Result<Vec<u8>, BoxedError>;
+
+    /// Reads the whole contents of several resources, concurrently if possible. The results are
+    /// in the same order as `paths`.
+    async fn read_all_of(&mut self, paths: &[PathBuf]) -> Vec<Result<Vec<u8>, BoxedError>>;
 }
 
+type BoxedError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
 /// Wraps a [`ResourceReader`].
 pub(crate) struct SyncReadFrom<'r, R>(pub(crate) &'r mut R);
 
 impl<R: ResourceReader> ReadFrom for SyncReadFrom<'_, R> {
-    type Reader = SyncReader<R::Resource>;
-    type Error = R::Error;
+    type Reader = SyncReader<Decompressed<R::Resource>>;
+
+    /// Returns on the first poll.
+    async fn read_from(&mut self, path: &Path) -> Result<Self::Reader, BoxedError> {
+        let resource = Decompressed::new(self.0.read_from(path)?)?;
+        Ok(SyncReader::new(resource))
+    }
 
     /// Returns on the first poll.
-    async fn read_from(&mut self, path: &Path) -> Result<Self::Reader, Self::Error> {
-        let resource = self.0.read_from(path)?;
-        Ok(SyncReader(RawReader::from_reader(resource)))
+    async fn read_head(&mut self, path: &Path, len: usize) -> Result<Vec<u8>, BoxedError> {
+        let resource = Decompressed::new(self.0.read_from(path)?)?;
+        let mut head = Vec::new();
+        resource.take(len as u64).read_to_end(&mut head)?;
+        Ok(head)
+    }
+
+    /// Returns on the first poll.
+    async fn read_all(&mut self, path: &Path) -> Result<Vec<u8>, BoxedError> {
+        let mut resource = Decompressed::new(self.0.read_from(path)?)?;
+        let mut contents = Vec::new();
+        resource.read_to_end(&mut contents)?;
+        Ok(contents)
+    }
+
+    /// Reads the resources one after another. Returns on the first poll.
+    async fn read_all_of(&mut self, paths: &[PathBuf]) -> Vec<Result<Vec<u8>, BoxedError>> {
+        let mut contents = Vec::with_capacity(paths.len());
+        for path in paths {
+            contents.push(self.read_all(path).await);
+        }
+        contents
     }
 }
 
 /// Wraps an [`AsyncResourceReader`].
+#[cfg(feature = "async-tokio")]
 pub(crate) struct AsyncReadFrom<'r, R>(pub(crate) &'r mut R);
 
+#[cfg(feature = "async-tokio")]
 impl<R: AsyncResourceReader> ReadFrom for AsyncReadFrom<'_, R> {
-    type Reader = AsyncReader<R::Resource>;
-    type Error = R::Error;
+    type Reader = AsyncReader<AsyncDecompressed<R::Resource>>;
+
+    async fn read_from(&mut self, path: &Path) -> Result<Self::Reader, BoxedError> {
+        let resource = AsyncDecompressed::new(self.0.read_from(path).await?).await?;
+        Ok(AsyncReader::new(resource))
+    }
+
+    async fn read_head(&mut self, path: &Path, len: usize) -> Result<Vec<u8>, BoxedError> {
+        use tokio::io::AsyncReadExt;
+
+        let resource = AsyncDecompressed::new(self.0.read_from(path).await?).await?;
+        let mut head = Vec::new();
+        resource.take(len as u64).read_to_end(&mut head).await?;
+        Ok(head)
+    }
+
+    async fn read_all(&mut self, path: &Path) -> Result<Vec<u8>, BoxedError> {
+        use tokio::io::AsyncReadExt;
+
+        let mut resource = AsyncDecompressed::new(self.0.read_from(path).await?).await?;
+        let mut contents = Vec::new();
+        resource.read_to_end(&mut contents).await?;
+        Ok(contents)
+    }
+
+    /// Opens the resources through [`AsyncResourceReader::read_from_many`], which does so
+    /// concurrently if the reader supports it, then reads them all concurrently.
+    async fn read_all_of(&mut self, paths: &[PathBuf]) -> Vec<Result<Vec<u8>, BoxedError>> {
+        use tokio::io::AsyncReadExt;
+
+        let resources = self.0.read_from_many(paths).await;
+        futures::future::join_all(resources.into_iter().map(|resource| async move {
+            let mut resource = AsyncDecompressed::new(resource?).await?;
+            let mut contents = Vec::new();
+            resource.read_to_end(&mut contents).await?;
+            Ok::<_, BoxedError>(contents)
+        }))
+        .await
+    }
+}
+
+/// Everything a single load shares between the parsers of a map and of the files it loads.
+#[derive(Debug, Default)]
+pub(crate) struct LoadContext {
+    /// How the load is configured, which doesn't change until it is over.
+    pub(crate) settings: LoadSettings,
+    /// What the load has found so far.
+    pub(crate) state: LoadState,
+}
+
+impl LoadContext {
+    pub(crate) fn new(settings: LoadSettings) -> Self {
+        Self {
+            settings,
+            state: LoadState::default(),
+        }
+    }
+}
 
-    async fn read_from(&mut self, path: &Path) -> Result<Self::Reader, Self::Error> {
-        let resource = self.0.read_from(path).await?;
-        Ok(AsyncReader(RawReader::from_reader(resource)))
+/// The results of a load that are gathered as it goes on: Warnings, and the images loaded so far.
+#[derive(Debug, Default)]
+pub(crate) struct LoadState {
+    pub(crate) warnings: Vec<ParseWarning>,
+    /// The images referenced so far, by resolved source path. See [`LoadState::intern_image`].
+    images: HashMap<PathBuf, Vec<Arc<Image>>>,
+}
+
+impl LoadState {
+    /// Returns a shared copy of the given image, so that all of the identical images referenced
+    /// during a load share a single allocation. Embedded images are never shared.
+    pub(crate) fn intern_image(&mut self, image: Image) -> Arc<Image> {
+        if image.embedded_data.is_some() {
+            return Arc::new(image);
+        }
+        let images = self.images.entry(image.source.clone()).or_default();
+        if let Some(interned) = images.iter().find(|interned| ***interned == image) {
+            return interned.clone();
+        }
+        let interned = Arc::new(image);
+        images.push(interned.clone());
+        interned
     }
 }
 
+/// Deduplicates strings that tend to be repeated throughout a file, such as property names, so that
+/// all of their occurrences share a single allocation.
+#[derive(Debug, Default)]
+pub(crate) struct Interner {
+    strings: HashSet<Arc<str>>,
+}
+
+impl Interner {
+    /// Returns a shared copy of the given string, allocating it only if it wasn't seen before.
+    pub(crate) fn intern(&mut self, string: &str) -> Arc<str> {
+        if let Some(interned) = self.strings.get(string) {
+            return interned.clone();
+        }
+        let interned: Arc<str> = Arc::from(string);
+        self.strings.insert(interned.clone());
+        interned
+    }
+}
+
+/// Scratch buffers used to decode the tile data of layers, kept around so that their allocations
+/// can be reused by every layer and chunk of a map.
+#[derive(Debug, Default)]
+pub(crate) struct DataBuffers {
+    /// The bytes decoded from base64.
+    pub(crate) decoded: Vec<u8>,
+    /// The bytes obtained by decompressing [`DataBuffers::decoded`].
+    pub(crate) decompressed: Vec<u8>,
+}
+
 /// A [`Reader`]-buffer pair.
 pub(crate) struct Parser<R> {
     reader: R,
     pub(crate) buffer: Vec<u8>,
     pub(crate) last_event_was_empty: bool,
+    /// The byte offset at which the last event read starts.
+    pub(crate) last_event_position: u64,
+    /// The file being parsed, which warnings point to.
+    pub(crate) path: PathBuf,
+    /// Whether the contents of the layer currently being parsed should be skipped.
+    pub(crate) skip_layer_contents: bool,
+    pub(crate) context: LoadContext,
+    pub(crate) interner: Interner,
+    /// The names and IDs of the map layers currently being parsed, from the top-level one to the
+    /// innermost one.
+    pub(crate) layer_stack: Vec<(String, u32)>,
+    pub(crate) data_buffers: DataBuffers,
 }
 
 impl<R> Parser<R> {
@@ -89,12 +302,65 @@ impl<R> Parser<R> {
             reader,
             buffer: Vec::new(),
             last_event_was_empty: false,
+            last_event_position: 0,
+            path: PathBuf::new(),
+            skip_layer_contents: false,
+            context: LoadContext::default(),
+            interner: Interner::default(),
+            layer_stack: Vec::new(),
+            data_buffers: DataBuffers::default(),
+        }
+    }
+
+    /// Handles an element named `name` that starts at `position` and has the same name as an
+    /// element before it, according to [`LoadSettings::duplicates`]. Returns whether it should
+    /// take the place of the element before it.
+    pub(crate) fn report_duplicate(
+        &mut self,
+        kind: DuplicateKind,
+        name: &str,
+        position: u64,
+    ) -> crate::Result<bool> {
+        let duplicates = self.context.settings.duplicates;
+        if duplicates == DuplicateHandling::Error {
+            let name = name.to_owned();
+            let err = match kind {
+                DuplicateKind::Property => Error::DuplicateProperty { name },
+                DuplicateKind::LayerName => Error::DuplicateLayerName { name },
+            };
+            return Err(err.at_position(&self.path, position));
+        }
+        let (name, path) = (name.to_owned(), self.path.clone());
+        self.context.state.warnings.push(match kind {
+            DuplicateKind::Property => ParseWarning::DuplicateProperty {
+                name,
+                path,
+                position,
+            },
+            DuplicateKind::LayerName => ParseWarning::DuplicateLayerName {
+                name,
+                path,
+                position,
+            },
+        });
+        Ok(duplicates == DuplicateHandling::LastWins)
+    }
+
+    /// Passes an object that was just parsed to [`LoadSettings::on_object`] if it belongs to one of
+    /// the map's layers, and returns whether it should be kept.
+    pub(crate) fn on_object(&self, object: &ObjectData) -> ObjectAction {
+        match &self.context.settings.on_object {
+            Some(callback) if !self.layer_stack.is_empty() => {
+                callback.call(object, &LayerContext::new(&self.layer_stack))
+            }
+            _ => ObjectAction::Keep,
         }
     }
 }
 
 impl<R: Reader> Parser<R> {
     pub(crate) async fn read_event(&mut self) -> ReadResult<Event> {
+        self.last_event_position = self.reader.buffer_position();
         let event = self.reader.read_event_into(&mut self.buffer).await?;
         self.last_event_was_empty = matches!(event, Event::Empty(_));
         Ok(event)
@@ -104,8 +370,77 @@ impl<R: Reader> Parser<R> {
         &mut self,
         buf: &'a mut Vec<u8>,
     ) -> ReadResult<Event<'a>> {
+        self.last_event_position = self.reader.buffer_position();
         let event = self.reader.read_event_into(buf).await?;
         self.last_event_was_empty = matches!(event, Event::Empty(_));
         Ok(event)
     }
+
+    /// Reads the text contents of the element named `element` that was just started, up to its
+    /// end. Entities are unescaped and CDATA sections are kept verbatim, newlines included, as
+    /// quick-xml splits text around the latter. Comments are skipped.
+    ///
+    /// Returns `None` if the element contains anything but text, such as child elements.
+    pub(crate) async fn read_text(&mut self, element: &[u8]) -> crate::Result<Option<String>> {
+        let mut contents = String::new();
+        if self.last_event_was_empty {
+            return Ok(Some(contents));
+        }
+        let mut buffer = Vec::new();
+        loop {
+            match self
+                .read_event_into(&mut buffer)
+                .await
+                .map_err(Error::XmlDecodingError)?
+            {
+                Event::Text(text) => {
+                    contents.push_str(&text.unescape().map_err(Error::XmlDecodingError)?)
+                }
+                Event::CData(data) => contents.push_str(
+                    std::str::from_utf8(&data)
+                        .map_err(|err| Error::XmlDecodingError(err.into()))?,
+                ),
+                Event::Comment(_) => {}
+                Event::End(end) if end.local_name().into_inner() == element => {
+                    return Ok(Some(contents))
+                }
+                Event::Eof => {
+                    return Err(Error::PrematureEnd(
+                        "Document ended before the text contents of an element were read"
+                            .to_owned(),
+                    ))
+                }
+                _ => return Ok(None),
+            }
+            buffer.clear();
+        }
+    }
+
+    /// Reads and discards everything up to the end of the element that was just started, without
+    /// decoding its contents.
+    pub(crate) async fn skip_element(&mut self) -> crate::Result<()> {
+        if self.last_event_was_empty {
+            return Ok(());
+        }
+        let mut buffer = Vec::new();
+        let mut depth = 0usize;
+        loop {
+            match self
+                .read_event_into(&mut buffer)
+                .await
+                .map_err(Error::XmlDecodingError)?
+            {
+                Event::Start(_) => depth += 1,
+                Event::End(_) if depth == 0 => return Ok(()),
+                Event::End(_) => depth -= 1,
+                Event::Eof => {
+                    return Err(Error::PrematureEnd(
+                        "Document ended before the skipped element was closed".to_owned(),
+                    ))
+                }
+                _ => {}
+            }
+            buffer.clear();
+        }
+    }
 }
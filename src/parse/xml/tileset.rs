@@ -1,48 +1,73 @@
 use std::path::Path;
 
-use itertools::Itertools;
 use quick_xml::events::Event;
 
-use crate::{Error, ResourceCache, Result, Tileset};
+use crate::{util::collect_attributes, Error, LoadProgress, ResourceCache, Result, Tileset};
 
-use super::{Parser, ReadFrom, Reader};
+use super::{LoadContext, Parser, ReadFrom, Reader};
 
 pub async fn parse_tileset(
     path: &Path,
     read_from: &mut impl ReadFrom,
     cache: &mut impl ResourceCache,
+    context: &mut LoadContext,
+) -> Result<Tileset> {
+    context
+        .settings
+        .report_progress(LoadProgress::ExternalTilesetStarted(path));
+    let tileset = match read_from.read_from(path).await {
+        Ok(reader) => parse_tileset_from(reader, path, read_from, cache, context).await,
+        Err(err) => Err(Error::ResourceLoadingError {
+            path: path.to_owned(),
+            err,
+        }),
+    };
+    context
+        .settings
+        .report_progress(LoadProgress::ExternalTilesetFinished(path));
+    tileset
+}
+
+/// Parses a tileset from an already opened reader. `path` is only used to resolve the external
+/// files the tileset refers to.
+///
+/// Warnings found while parsing are added to `context`.
+pub async fn parse_tileset_from<R: Reader>(
+    mut reader: R,
+    path: &Path,
+    read_from: &mut impl ReadFrom,
+    cache: &mut impl ResourceCache,
+    context: &mut LoadContext,
 ) -> Result<Tileset> {
-    let mut reader =
-        read_from
-            .read_from(path)
-            .await
-            .map_err(|err| Error::ResourceLoadingError {
-                path: path.to_owned(),
-                err: Box::new(err),
-            })?;
     let mut buffer = Vec::new();
     loop {
+        let position = reader.buffer_position();
         match reader
             .read_event_into(&mut buffer)
             .await
-            .map_err(Error::XmlDecodingError)?
+            .map_err(|err| Error::XmlDecodingError(err).at_position(path, position))?
         {
             Event::Start(start) | Event::Empty(start)
                 if start.local_name().into_inner() == b"tileset" =>
             {
-                let attributes: Vec<_> = start
-                    .attributes()
-                    .try_collect()
-                    .map_err(|err| Error::XmlDecodingError(err.into()))?;
+                let attributes: Vec<_> =
+                    collect_attributes(&start).map_err(|err| err.at_position(path, position))?;
 
-                return Tileset::parse_external_tileset(
-                    &mut Parser::with_reader(reader),
+                let mut parser = Parser::with_reader(reader);
+                parser.last_event_position = position;
+                parser.context = std::mem::take(context);
+                parser.path = path.to_owned();
+                let tileset = Tileset::parse_external_tileset(
+                    &mut parser,
                     &attributes,
                     path,
                     read_from,
                     cache,
                 )
-                .await;
+                .await
+                .map_err(|err| err.at_position(path, parser.last_event_position));
+                *context = parser.context;
+                return tileset;
             }
 
             Event::Eof => {
@@ -0,0 +1,377 @@
+//! Structures related to Tiled project files.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    properties::merge_properties, Color, Error, Properties, PropertyValue, ResourceReader, Result,
+};
+
+/// A Tiled project, which holds the custom property types shared by the maps and tilesets it
+/// contains.
+///
+/// Projects are loaded from `.tiled-project` files through [`Loader::load_project`]. Once set as
+/// the [`LoadSettings::project`] of a loader, the files it loads get the default values of their
+/// class properties filled in, and their enum properties are turned into
+/// [`PropertyValue::EnumValue`]s.
+///
+/// Also see the [Tiled docs](https://doc.mapeditor.org/en/stable/manual/custom-properties/#custom-types).
+///
+/// [`Loader::load_project`]: crate::Loader::load_project
+/// [`LoadSettings::project`]: crate::LoadSettings::project
+#[derive(Debug, PartialEq, Clone)]
+pub struct Project {
+    /// The path the project was loaded from.
+    pub source: PathBuf,
+    /// The custom classes defined by the project.
+    pub classes: Vec<CustomClass>,
+    /// The custom enums defined by the project.
+    pub enums: Vec<CustomEnum>,
+}
+
+impl Project {
+    /// Gets the custom class with the given name, if the project defines it.
+    pub fn get_class(&self, name: &str) -> Option<&CustomClass> {
+        self.classes.iter().find(|class| class.name == name)
+    }
+
+    /// Gets the custom enum with the given name, if the project defines it.
+    pub fn get_enum(&self, name: &str) -> Option<&CustomEnum> {
+        self.enums
+            .iter()
+            .find(|custom_enum| custom_enum.name == name)
+    }
+
+    /// Fills in the members that aren't set in the class values of `properties`, including nested
+    /// ones, with the defaults of their classes.
+    pub(crate) fn with_class_defaults(&self, properties: Properties) -> Properties {
+        properties
+            .into_iter()
+            .map(|(name, value)| {
+                let value = match value {
+                    PropertyValue::ClassValue {
+                        property_type,
+                        properties,
+                    } => {
+                        let properties = match self.get_class(&property_type) {
+                            Some(class) => {
+                                merge_properties(&class.default_properties(), &properties)
+                            }
+                            None => properties,
+                        };
+                        PropertyValue::ClassValue {
+                            property_type,
+                            properties: self.with_class_defaults(properties),
+                        }
+                    }
+                    value => value,
+                };
+                (name, value)
+            })
+            .collect()
+    }
+}
+
+/// A custom class defined by a [`Project`], which groups several typed members.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CustomClass {
+    /// The name of the class, which properties refer to through their property type.
+    pub name: String,
+    /// The members of the class along with their default values, in the order they were defined.
+    pub members: Vec<(String, PropertyValue)>,
+    /// What the class can be used for, such as `"property"`, `"map"` or `"object"`.
+    pub use_as: Vec<String>,
+}
+
+impl CustomClass {
+    /// Returns the members of this class along with their default values, as [`Properties`].
+    pub fn default_properties(&self) -> Properties {
+        self.members
+            .iter()
+            .map(|(name, value)| (Arc::from(name.as_str()), value.clone()))
+            .collect()
+    }
+}
+
+/// A custom enum defined by a [`Project`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CustomEnum {
+    /// The name of the enum, which properties refer to through their property type.
+    pub name: String,
+    /// The possible values of the enum.
+    pub values: Vec<String>,
+    /// How the values of the enum are stored in map files.
+    pub storage: EnumStorage,
+    /// Whether several values can be set at once, like flags.
+    pub values_as_flags: bool,
+}
+
+impl CustomEnum {
+    /// Converts the stored value of a property of this enum into the name of its value. Values
+    /// of flag enums are joined by commas, like Tiled does when storing them as strings.
+    ///
+    /// Returns [`None`] if the stored value isn't valid for this enum.
+    pub fn value_name(&self, stored: &str) -> Option<String> {
+        match (self.storage, self.values_as_flags) {
+            (EnumStorage::String, false) => self
+                .values
+                .iter()
+                .any(|value| value == stored)
+                .then(|| stored.to_owned()),
+            (EnumStorage::String, true) => stored
+                .split(',')
+                .filter(|value| !value.is_empty())
+                .all(|value| self.values.iter().any(|known| known == value))
+                .then(|| stored.to_owned()),
+            (EnumStorage::Int, false) => {
+                let index: usize = stored.parse().ok()?;
+                self.values.get(index).cloned()
+            }
+            (EnumStorage::Int, true) => {
+                let bits: u32 = stored.parse().ok()?;
+                if self.values.len() < 32 && bits >> self.values.len() != 0 {
+                    return None;
+                }
+                let names: Vec<&str> = self
+                    .values
+                    .iter()
+                    .take(32)
+                    .enumerate()
+                    .filter(|(index, _)| bits & (1 << index) != 0)
+                    .map(|(_, value)| value.as_str())
+                    .collect();
+                Some(names.join(","))
+            }
+        }
+    }
+}
+
+/// How the values of a [`CustomEnum`] are stored in map files.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EnumStorage {
+    /// Values are stored by name, in `string` properties.
+    String,
+    /// Values are stored by index in `int` properties, or as bit flags if the enum's values can
+    /// be combined.
+    Int,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawProject {
+    #[serde(default)]
+    property_types: Vec<RawPropertyType>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum RawPropertyType {
+    #[serde(rename_all = "camelCase")]
+    Class {
+        name: String,
+        #[serde(default)]
+        use_as: Vec<String>,
+        #[serde(default)]
+        members: Vec<RawMember>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Enum {
+        name: String,
+        storage_type: String,
+        #[serde(default)]
+        values: Vec<String>,
+        #[serde(default)]
+        values_as_flags: bool,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawMember {
+    name: String,
+    #[serde(rename = "type")]
+    member_type: String,
+    property_type: Option<String>,
+    #[serde(default)]
+    value: Value,
+}
+
+pub(crate) fn parse_project(path: &Path, reader: &mut impl ResourceReader) -> Result<Project> {
+    let resource = reader
+        .read_from(path)
+        .map_err(|err| Error::ResourceLoadingError {
+            path: path.to_owned(),
+            err: Box::new(err),
+        })?;
+    let raw: RawProject = serde_json::from_reader(resource).map_err(Error::ProjectDecodingError)?;
+
+    let mut enums = Vec::new();
+    let mut class_names = Vec::new();
+    let mut raw_classes = HashMap::new();
+    for property_type in raw.property_types {
+        match property_type {
+            RawPropertyType::Class {
+                name,
+                use_as,
+                members,
+            } => {
+                class_names.push(name.clone());
+                raw_classes.insert(name, (use_as, members));
+            }
+            RawPropertyType::Enum {
+                name,
+                storage_type,
+                values,
+                values_as_flags,
+            } => enums.push(CustomEnum {
+                name,
+                values,
+                storage: match storage_type.as_str() {
+                    "int" => EnumStorage::Int,
+                    _ => EnumStorage::String,
+                },
+                values_as_flags,
+            }),
+        }
+    }
+
+    let mut project = Project {
+        source: path.to_owned(),
+        classes: Vec::new(),
+        enums,
+    };
+    for name in &class_names {
+        let class = class_from_raw(name, &raw_classes, &project, &mut Vec::new())?;
+        project.classes.push(class);
+    }
+    Ok(project)
+}
+
+type RawClasses = HashMap<String, (Vec<String>, Vec<RawMember>)>;
+
+/// Builds the class with the given name, resolving the default values of its members. `parents`
+/// holds the classes currently being built, to detect classes that contain themselves.
+fn class_from_raw(
+    name: &str,
+    raw_classes: &RawClasses,
+    project: &Project,
+    parents: &mut Vec<String>,
+) -> Result<CustomClass> {
+    let (use_as, raw_members) =
+        raw_classes
+            .get(name)
+            .ok_or_else(|| Error::InvalidPropertyValue {
+                description: format!("unknown class '{}'", name),
+            })?;
+    if parents.iter().any(|parent| parent == name) {
+        return Err(Error::InvalidPropertyValue {
+            description: format!("class '{}' contains itself", name),
+        });
+    }
+    parents.push(name.to_owned());
+    let members = raw_members
+        .iter()
+        .map(|member| {
+            let value = member_value(member, raw_classes, project, parents)?;
+            Ok((member.name.clone(), value))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    parents.pop();
+    Ok(CustomClass {
+        name: name.to_owned(),
+        members,
+        use_as: use_as.clone(),
+    })
+}
+
+fn member_value(
+    member: &RawMember,
+    raw_classes: &RawClasses,
+    project: &Project,
+    parents: &mut Vec<String>,
+) -> Result<PropertyValue> {
+    let invalid = || Error::InvalidPropertyValue {
+        description: format!(
+            "invalid value for member '{}' of type '{}'",
+            member.name, member.member_type
+        ),
+    };
+    let value = &member.value;
+    Ok(match member.member_type.as_str() {
+        "class" => {
+            let property_type = member.property_type.clone().unwrap_or_default();
+            let class = class_from_raw(&property_type, raw_classes, project, parents)?;
+            // Members of nested classes that are set on this member override the class defaults
+            let overrides = match value {
+                Value::Object(members) => members
+                    .iter()
+                    .map(|(name, value)| {
+                        let raw = raw_classes[&property_type]
+                            .1
+                            .iter()
+                            .find(|member| member.name == *name)
+                            .ok_or_else(invalid)?;
+                        let member = RawMember {
+                            name: name.clone(),
+                            member_type: raw.member_type.clone(),
+                            property_type: raw.property_type.clone(),
+                            value: value.clone(),
+                        };
+                        let value = member_value(&member, raw_classes, project, parents)?;
+                        Ok((Arc::from(name.as_str()), value))
+                    })
+                    .collect::<Result<Properties>>()?,
+                _ => Properties::new(),
+            };
+            PropertyValue::ClassValue {
+                property_type,
+                properties: merge_properties(&class.default_properties(), &overrides),
+            }
+        }
+        "bool" => PropertyValue::BoolValue(value.as_bool().unwrap_or_default()),
+        "float" => PropertyValue::FloatValue(value.as_f64().unwrap_or_default() as f32),
+        "int" | "string" if member.property_type.is_some() => {
+            let enum_name = member.property_type.clone().unwrap_or_default();
+            let stored = match value {
+                Value::String(value) => value.clone(),
+                Value::Number(value) => value.to_string(),
+                _ => String::new(),
+            };
+            let value = match project.get_enum(&enum_name) {
+                Some(custom_enum) => custom_enum.value_name(&stored).ok_or_else(invalid)?,
+                None => stored,
+            };
+            PropertyValue::EnumValue {
+                name: enum_name,
+                value,
+            }
+        }
+        "int" => PropertyValue::IntValue(value.as_i64().unwrap_or_default() as i32),
+        "color" => match value.as_str() {
+            Some("") | None => PropertyValue::ColorValue(Color {
+                alpha: 0,
+                red: 0,
+                green: 0,
+                blue: 0,
+            }),
+            Some(color) => {
+                PropertyValue::ColorValue(Color::from_str(color).map_err(|_| invalid())?)
+            }
+        },
+        "string" => PropertyValue::StringValue(value.as_str().unwrap_or_default().to_owned()),
+        "file" => PropertyValue::FileValue(value.as_str().unwrap_or_default().to_owned()),
+        "object" => PropertyValue::ObjectValue(value.as_u64().unwrap_or_default() as u32),
+        _ => {
+            return Err(Error::UnknownPropertyType {
+                type_name: member.member_type.clone(),
+            })
+        }
+    })
+}
@@ -1,15 +1,17 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{collections::HashMap, convert::TryFrom, str::FromStr, sync::Arc};
 
 use quick_xml::events::{attributes::Attribute, Event};
 
 use crate::{
     error::{Error, Result},
-    parse::xml::{Parser, Reader},
+    parse::xml::{DuplicateKind, Parser, Reader},
     util::{get_attrs, parse_tag},
+    ParseWarning, Strictness,
 };
 
 /// Represents a RGBA color with 8-bit depth on each channel.
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub struct Color {
     pub alpha: u8,
@@ -18,45 +20,96 @@ pub struct Color {
     pub blue: u8,
 }
 
+impl Color {
+    /// Returns the channels of this color as floats between 0 and 1, in RGBA order.
+    pub fn to_rgba_f32(&self) -> [f32; 4] {
+        [self.red, self.green, self.blue, self.alpha].map(|channel| channel as f32 / 255.0)
+    }
+
+    /// Creates a color from floats between 0 and 1 in RGBA order. Values outside of that range are
+    /// clamped.
+    pub fn from_rgba_f32(rgba: [f32; 4]) -> Self {
+        let [red, green, blue, alpha] =
+            rgba.map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8);
+        Self {
+            alpha,
+            red,
+            green,
+            blue,
+        }
+    }
+}
+
+impl From<Color> for [u8; 4] {
+    /// Returns the channels of the color in RGBA order.
+    fn from(color: Color) -> Self {
+        [color.red, color.green, color.blue, color.alpha]
+    }
+}
+
+impl From<[u8; 4]> for Color {
+    /// Creates a color from channels in RGBA order.
+    fn from([red, green, blue, alpha]: [u8; 4]) -> Self {
+        Self {
+            alpha,
+            red,
+            green,
+            blue,
+        }
+    }
+}
+
+impl std::fmt::Display for Color {
+    /// Formats the color as TMX files do, in the `#aarrggbb` form.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "#{:02x}{:02x}{:02x}{:02x}",
+            self.alpha, self.red, self.green, self.blue
+        )
+    }
+}
+
 impl FromStr for Color {
     type Err = ();
 
+    /// Parses a color in the `#rrggbb` or `#aarrggbb` forms, or their `#rgb` and `#argb`
+    /// shorthands. The `#` is optional.
     fn from_str(s: &str) -> std::result::Result<Color, Self::Err> {
         let s = if let Some(stripped) = s.strip_prefix('#') {
             stripped
         } else {
             s
         };
-        match s.len() {
-            6 if s.is_ascii() => {
-                let r = u8::from_str_radix(&s[0..2], 16);
-                let g = u8::from_str_radix(&s[2..4], 16);
-                let b = u8::from_str_radix(&s[4..6], 16);
-                match (r, g, b) {
-                    (Ok(red), Ok(green), Ok(blue)) => Ok(Color {
-                        alpha: 0xFF,
-                        red,
-                        green,
-                        blue,
-                    }),
-                    _ => Err(()),
-                }
-            }
-            8 if s.is_ascii() => {
-                let a = u8::from_str_radix(&s[0..2], 16);
-                let r = u8::from_str_radix(&s[2..4], 16);
-                let g = u8::from_str_radix(&s[4..6], 16);
-                let b = u8::from_str_radix(&s[6..8], 16);
-                match (a, r, g, b) {
-                    (Ok(alpha), Ok(red), Ok(green), Ok(blue)) => Ok(Color {
-                        alpha,
-                        red,
-                        green,
-                        blue,
-                    }),
-                    _ => Err(()),
-                }
-            }
+        if !s.is_ascii() {
+            return Err(());
+        }
+        let channels = match s.len() {
+            // Each digit of the shorthand forms is repeated, so that `f` becomes `ff`
+            3 | 4 => s
+                .chars()
+                .map(|digit| digit.to_digit(16).map(|value| value as u8 * 0x11))
+                .collect::<Option<Vec<_>>>(),
+            6 | 8 => (0..s.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+                .collect::<Option<Vec<_>>>(),
+            _ => None,
+        }
+        .ok_or(())?;
+        match channels[..] {
+            [red, green, blue] => Ok(Color {
+                alpha: 0xFF,
+                red,
+                green,
+                blue,
+            }),
+            [alpha, red, green, blue] => Ok(Color {
+                alpha,
+                red,
+                green,
+                blue,
+            }),
             _ => Err(()),
         }
     }
@@ -66,6 +119,7 @@ impl FromStr for Color {
 ///
 /// Also read the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#tmx-properties).
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PropertyValue {
     /// A boolean value. Corresponds to the `bool` property type.
     BoolValue(bool),
@@ -91,6 +145,26 @@ pub enum PropertyValue {
         /// A set of properties.
         properties: Properties,
     },
+    /// A value of a custom enum. Only created when loading with a [`Project`] that defines the
+    /// enum; Otherwise, enum properties are loaded as the `string` or `int` properties they are
+    /// stored as.
+    ///
+    /// [`Project`]: https://docs.rs/tiled/latest/tiled/struct.Project.html
+    EnumValue {
+        /// The name of the enum.
+        name: String,
+        /// The name of the value. The values of enums whose values can be combined are joined by
+        /// commas.
+        value: String,
+    },
+    /// A value of a property type that isn't supported by the crate. Only created when loading
+    /// with a lenient [`Strictness`](crate::Strictness).
+    Unknown {
+        /// The name of the property type.
+        type_name: String,
+        /// The value of the property, as written in the file.
+        raw_value: String,
+    },
 }
 
 impl PropertyValue {
@@ -135,16 +209,242 @@ impl PropertyValue {
     }
 }
 
+macro_rules! impl_try_from_property_value {
+    ($($ty:ty => $variant:ident ($value:ident) => $expr:expr, $name:literal;)*) => {
+        $(
+            impl TryFrom<&PropertyValue> for $ty {
+                type Error = Error;
+
+                fn try_from(value: &PropertyValue) -> Result<Self> {
+                    match value {
+                        PropertyValue::$variant($value) => Ok($expr),
+                        _ => Err(Error::InvalidPropertyValue {
+                            description: concat!("expected a property of type `", $name, "`")
+                                .to_owned(),
+                        }),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_try_from_property_value! {
+    bool => BoolValue(v) => *v, "bool";
+    f32 => FloatValue(v) => *v, "float";
+    i32 => IntValue(v) => *v, "int";
+    Color => ColorValue(v) => *v, "color";
+    String => StringValue(v) => v.clone(), "string";
+}
+
 /// A custom property container.
-pub type Properties = HashMap<String, PropertyValue>;
+///
+/// Property names are shared between all the properties of a file that have the same name.
+pub type Properties = HashMap<Arc<str>, PropertyValue>;
+
+/// Returns the properties of `base` overridden by the ones of `overlay`, as Tiled does when an
+/// instance inherits the properties of its tile. Class properties present in both are merged
+/// member by member.
+pub(crate) fn merge_properties(base: &Properties, overlay: &Properties) -> Properties {
+    let mut merged = base.clone();
+    for (name, value) in overlay {
+        let value = match (merged.get(name), value) {
+            (
+                Some(PropertyValue::ClassValue {
+                    properties: base_members,
+                    ..
+                }),
+                PropertyValue::ClassValue {
+                    property_type,
+                    properties,
+                },
+            ) => PropertyValue::ClassValue {
+                property_type: property_type.clone(),
+                properties: merge_properties(base_members, properties),
+            },
+            _ => value.clone(),
+        };
+        merged.insert(name.clone(), value);
+    }
+    merged
+}
+
+/// Typed accessors for [`Properties`].
+///
+/// All of these return [`None`] if the property doesn't exist or if it is of a different type
+/// than the one requested.
+///
+/// ## Example
+/// ```
+/// use tiled::{PropertiesExt, Properties, PropertyValue};
+///
+/// let mut properties = Properties::new();
+/// properties.insert("speed".into(), PropertyValue::FloatValue(2.5));
+///
+/// assert_eq!(properties.get_float("speed"), Some(2.5));
+/// assert_eq!(properties.get_int("speed"), None);
+/// assert_eq!(properties.get_as::<f32>("speed"), Some(2.5));
+/// ```
+pub trait PropertiesExt {
+    /// Obtains the value of a `bool` property.
+    fn get_bool(&self, name: &str) -> Option<bool>;
+    /// Obtains the value of a `float` property.
+    fn get_float(&self, name: &str) -> Option<f32>;
+    /// Obtains the value of an `int` property.
+    fn get_int(&self, name: &str) -> Option<i32>;
+    /// Obtains the value of a `color` property.
+    fn get_color(&self, name: &str) -> Option<Color>;
+    /// Obtains the value of a `string` property.
+    fn get_string(&self, name: &str) -> Option<&str>;
+    /// Obtains the value of a `file` property, relative to the map or tileset.
+    fn get_file(&self, name: &str) -> Option<&str>;
+    /// Obtains the object ID stored in an `object` property.
+    fn get_object(&self, name: &str) -> Option<u32>;
+    /// Obtains the type name and members of a `class` property.
+    fn get_class(&self, name: &str) -> Option<(&str, &Properties)>;
+    /// Obtains a property converted into any type that implements `TryFrom<&PropertyValue>`.
+    fn get_as<'a, T: TryFrom<&'a PropertyValue>>(&'a self, name: &str) -> Option<T>;
+}
+
+impl PropertiesExt for Properties {
+    fn get_bool(&self, name: &str) -> Option<bool> {
+        self.get_as(name)
+    }
+
+    fn get_float(&self, name: &str) -> Option<f32> {
+        self.get_as(name)
+    }
+
+    fn get_int(&self, name: &str) -> Option<i32> {
+        self.get_as(name)
+    }
+
+    fn get_color(&self, name: &str) -> Option<Color> {
+        self.get_as(name)
+    }
+
+    fn get_string(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            PropertyValue::StringValue(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    fn get_file(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            PropertyValue::FileValue(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    fn get_object(&self, name: &str) -> Option<u32> {
+        match self.get(name)? {
+            PropertyValue::ObjectValue(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    fn get_class(&self, name: &str) -> Option<(&str, &Properties)> {
+        match self.get(name)? {
+            PropertyValue::ClassValue {
+                property_type,
+                properties,
+            } => Some((property_type, properties)),
+            _ => None,
+        }
+    }
+
+    fn get_as<'a, T: TryFrom<&'a PropertyValue>>(&'a self, name: &str) -> Option<T> {
+        self.get(name).and_then(|v| T::try_from(v).ok())
+    }
+}
+
+/// Anything that has custom properties, such as maps, tilesets, layers and objects. Allows reading
+/// the properties of different kinds of entities through the same interface, including as a
+/// `&dyn HasProperties`.
+///
+/// ## Example
+/// ```
+/// # fn main() -> tiled::Result<()> {
+/// use tiled::{HasProperties, PropertyValue};
+///
+/// let map = tiled::Loader::new().load_tmx_map("assets/tiled_base64.tmx")?;
+///
+/// let mut entities: Vec<&dyn HasProperties> = vec![&map];
+/// entities.extend(map.tilesets().iter().map(|tileset| tileset.as_ref() as &dyn HasProperties));
+///
+/// let tileset = entities[1];
+/// assert_eq!(
+///     tileset.get_property("tileset property"),
+///     Some(&PropertyValue::StringValue("tsp".to_owned()))
+/// );
+/// # Ok(())
+/// # }
+/// ```
+pub trait HasProperties {
+    /// The custom properties of this entity, as arbitrarily set by the user.
+    fn properties(&self) -> &Properties;
+
+    /// Obtains the value of the property with the given name, if there is one.
+    ///
+    /// Use [`PropertiesExt`] on [`HasProperties::properties`] to obtain values of a specific type.
+    fn get_property(&self, name: &str) -> Option<&PropertyValue> {
+        self.properties().get(name)
+    }
+}
+
+macro_rules! impl_has_properties {
+    ($($ty:ty),*) => {
+        $(
+            impl HasProperties for $ty {
+                #[inline]
+                fn properties(&self) -> &Properties {
+                    &self.properties
+                }
+            }
+        )*
+    };
+}
+
+impl_has_properties!(
+    crate::Map,
+    crate::Tileset,
+    crate::LayerData,
+    crate::ObjectData,
+    crate::TileData,
+    crate::WangSet,
+    crate::WangColor,
+    crate::Layer<'_>,
+    crate::Object<'_>,
+    crate::Tile<'_>
+);
+
+impl HasProperties for crate::GroupLayer<'_> {
+    #[inline]
+    fn properties(&self) -> &Properties {
+        crate::GroupLayer::properties(self)
+    }
+}
 
 pub(crate) async fn parse_properties<R: Reader>(parser: &mut Parser<R>) -> Result<Properties> {
+    let properties = parse_property_list(parser).await?;
+    // Defaults are filled in once the whole list has been read, since the defaults of nested class
+    // members come from the class that contains them rather than from their own class
+    #[cfg(feature = "project")]
+    let properties = match &parser.context.settings.project {
+        Some(project) => project.with_class_defaults(properties),
+        None => properties,
+    };
+    Ok(properties)
+}
+
+async fn parse_property_list<R: Reader>(parser: &mut Parser<R>) -> Result<Properties> {
     let mut p = HashMap::new();
     let mut buffer = Vec::new();
     parse_tag!(parser => &mut buffer, "properties", {
         "property" => for attrs {
             // add indirection because the returned async state machine is a recursive data structure
-            // (`parse_properties_inner` calls `parse_properties` again)
+            // (`parse_properties_inner` calls `parse_property_list` again)
             Box::pin(parse_properties_inner(parser, &mut p, attrs)).await
         },
     });
@@ -153,11 +453,12 @@ pub(crate) async fn parse_properties<R: Reader>(parser: &mut Parser<R>) -> Resul
 
 async fn parse_properties_inner<R: Reader>(
     parser: &mut Parser<R>,
-    p: &mut HashMap<String, PropertyValue>,
+    p: &mut Properties,
     attrs: Vec<Attribute<'_>>,
 ) -> Result<()> {
+    let position = parser.last_event_position;
     let (t, v_attr, k, p_t) = get_attrs!(
-        for attr in attrs {
+        for attr in attrs of "property" {
             Some("type") => obj_type = attr,
             Some("value") => value = attr,
             Some("propertytype") => propertytype = attr,
@@ -171,41 +472,88 @@ async fn parse_properties_inner<R: Reader>(
         // element. Only the actually set members are saved. When no members have been set
         // the properties element is left out entirely.
         let properties = if has_properties_tag_next(parser).await {
-            parse_properties(parser).await?
+            parse_property_list(parser).await?
         } else {
             HashMap::new()
         };
-        p.insert(
-            k.to_string(),
-            PropertyValue::ClassValue {
-                property_type: p_t.unwrap_or_default().to_string(),
-                properties,
-            },
-        );
-        return Ok(());
+        let value = PropertyValue::ClassValue {
+            property_type: p_t.unwrap_or_default().to_string(),
+            properties,
+        };
+        return insert_property(parser, p, k, value, position);
     }
 
     let v: String = match v_attr {
         Some(val) => val.to_string(),
-        None => {
-            // if the "value" attribute was missing, might be a multiline string
-            match parser.read_event().await {
-                Ok(Event::Text(text)) => {
-                    let text = text.into_inner();
-                    let text = std::str::from_utf8(&text)
-                        .map_err(|err| Error::XmlDecodingError(err.into()))?;
-                    Ok(text.to_string())
-                }
-                Err(err) => Err(Error::XmlDecodingError(err)),
-                _ => Err(Error::MalformedAttributes(format!(
-                    "property '{}' is missing a value",
-                    k
-                ))),
-            }?
+        // A self-closing property without a value is empty
+        None if parser.last_event_was_empty => String::new(),
+        // If the "value" attribute is missing, the value might be a multiline string stored as
+        // the element's text
+        None => parser.read_text(b"property").await?.ok_or_else(|| {
+            Error::MalformedAttributes(format!("property '{}' is missing a value", k))
+        })?,
+    };
+
+    #[cfg(feature = "project")]
+    if let (Some(project), Some(property_type)) = (&parser.context.settings.project, p_t) {
+        if let Some(custom_enum) = project.get_enum(property_type) {
+            let value = custom_enum
+                .value_name(&v)
+                .ok_or_else(|| Error::InvalidPropertyValue {
+                    description: format!(
+                        "'{}' is not a valid value of enum '{}' for property '{}'",
+                        v, property_type, k
+                    ),
+                })?;
+            let value = PropertyValue::EnumValue {
+                name: property_type.to_owned(),
+                value,
+            };
+            return insert_property(parser, p, k, value, position);
         }
+    }
+
+    let value = match parser.context.settings.strictness {
+        Strictness::Strict | Strictness::Error => PropertyValue::new(t, v)?,
+        strictness => match PropertyValue::new(t, v.clone()) {
+            Err(Error::UnknownPropertyType { type_name }) => {
+                if strictness == Strictness::Warn {
+                    parser
+                        .context
+                        .state
+                        .warnings
+                        .push(ParseWarning::UnknownPropertyType {
+                            property: k.to_string(),
+                            type_name: type_name.clone(),
+                        });
+                }
+                PropertyValue::Unknown {
+                    type_name,
+                    raw_value: v,
+                }
+            }
+            value => value?,
+        },
     };
+    insert_property(parser, p, k, value, position)
+}
 
-    p.insert(k.to_string(), PropertyValue::new(t, v)?);
+/// Adds a property that starts at `position` to the list it was found in, unless the list already
+/// has a property with the same name that should be kept instead. See
+/// [`LoadSettings::duplicates`](crate::LoadSettings::duplicates).
+fn insert_property<R>(
+    parser: &mut Parser<R>,
+    properties: &mut Properties,
+    name: &str,
+    value: PropertyValue,
+    position: u64,
+) -> Result<()> {
+    if properties.contains_key(name)
+        && !parser.report_duplicate(DuplicateKind::Property, name, position)?
+    {
+        return Ok(());
+    }
+    properties.insert(parser.interner.intern(name), value);
     Ok(())
 }
 
@@ -1,5 +1,13 @@
-use std::io::{BufRead, BufReader};
-use std::{fs::File, path::Path};
+use std::io::{BufRead, BufReader, Cursor, Read};
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::File,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::util::normalize_path;
 
 /// A trait defining types that can load data from a [`ResourcePath`](crate::ResourcePath).
 ///
@@ -36,6 +44,19 @@ pub trait ResourceReader {
 
     /// Try to return a reader object from a path into the resources filesystem.
     fn read_from(&mut self, path: &Path) -> std::result::Result<Self::Resource, Self::Error>;
+
+    /// Lists the files in a directory of the resources filesystem.
+    ///
+    /// This is used to find the maps matched by the patterns of a [`World`](crate::World). The
+    /// default implementation returns no files, in which case those maps are left unresolved and
+    /// only the patterns themselves are available.
+    #[cfg(feature = "world")]
+    fn list_files(
+        &mut self,
+        _dir: &Path,
+    ) -> std::result::Result<Vec<std::path::PathBuf>, Self::Error> {
+        Ok(Vec::new())
+    }
 }
 
 /// A [`ResourceReader`] that reads from [`File`] handles.
@@ -57,6 +78,21 @@ impl ResourceReader for FilesystemResourceReader {
         let file = File::open(path)?;
         Ok(BufReader::new(file))
     }
+
+    #[cfg(feature = "world")]
+    fn list_files(
+        &mut self,
+        dir: &Path,
+    ) -> std::result::Result<Vec<std::path::PathBuf>, Self::Error> {
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                files.push(entry.path());
+            }
+        }
+        Ok(files)
+    }
 }
 
 impl<T, R, E> ResourceReader for T
@@ -72,3 +108,328 @@ where
         self(path)
     }
 }
+
+/// A [`ResourceReader`] that reads resources from a primary reader, falling back to a second one
+/// for the resources the first one fails to provide. Useful for overlay filesystems, e.g. to let
+/// mods override some of a game's base assets.
+///
+/// Also implements [`AsyncResourceReader`](crate::AsyncResourceReader) when both readers do.
+/// Readers can be chained further by using another [`ChainedReader`] as the fallback.
+///
+/// ## Example
+/// ```
+/// # fn main() -> tiled::Result<()> {
+/// use std::{io::Cursor, path::Path};
+/// use tiled::{ChainedReader, FilesystemResourceReader, Loader};
+///
+/// // Provides an overridden map, and nothing else
+/// let overrides = |path: &Path| -> std::io::Result<_> {
+///     if path == Path::new("assets/tiled_csv.tmx") {
+///         Ok(Cursor::new(include_bytes!("../assets/tiled_base64.tmx").as_slice()))
+///     } else {
+///         Err(std::io::ErrorKind::NotFound.into())
+///     }
+/// };
+/// let mut loader = Loader::with_reader(ChainedReader::new(overrides, FilesystemResourceReader));
+///
+/// // The tileset isn't overridden, so it's read from the filesystem
+/// let map = loader.load_tmx_map("assets/tiled_csv.tmx")?;
+/// assert_eq!(map.tilesets()[0].name, "tilesheet");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ChainedReader<A, B> {
+    /// The reader that is tried first.
+    pub primary: A,
+    /// The reader that is used when the primary one fails.
+    pub fallback: B,
+}
+
+impl<A, B> ChainedReader<A, B> {
+    /// Creates a reader that tries `primary` first, and `fallback` if it fails.
+    pub fn new(primary: A, fallback: B) -> Self {
+        Self { primary, fallback }
+    }
+}
+
+impl<A: ResourceReader, B: ResourceReader> ResourceReader for ChainedReader<A, B> {
+    type Resource = ChainedResource<A::Resource, B::Resource>;
+    type Error = ChainedReaderError<A::Error, B::Error>;
+
+    fn read_from(&mut self, path: &Path) -> std::result::Result<Self::Resource, Self::Error> {
+        match self.primary.read_from(path) {
+            Ok(resource) => Ok(ChainedResource::Primary(resource)),
+            Err(primary) => match self.fallback.read_from(path) {
+                Ok(resource) => Ok(ChainedResource::Fallback(resource)),
+                Err(fallback) => Err(ChainedReaderError { primary, fallback }),
+            },
+        }
+    }
+
+    /// Lists the files of both readers, without duplicates. Only fails if both readers do.
+    #[cfg(feature = "world")]
+    fn list_files(
+        &mut self,
+        dir: &Path,
+    ) -> std::result::Result<Vec<std::path::PathBuf>, Self::Error> {
+        match (self.primary.list_files(dir), self.fallback.list_files(dir)) {
+            (Ok(mut files), Ok(fallback_files)) => {
+                for file in fallback_files {
+                    if !files.contains(&file) {
+                        files.push(file);
+                    }
+                }
+                Ok(files)
+            }
+            (Ok(files), Err(_)) | (Err(_), Ok(files)) => Ok(files),
+            (Err(primary), Err(fallback)) => Err(ChainedReaderError { primary, fallback }),
+        }
+    }
+}
+
+/// A resource read by a [`ChainedReader`], from either of its readers.
+#[derive(Debug)]
+pub enum ChainedResource<A, B> {
+    /// A resource read by the primary reader.
+    Primary(A),
+    /// A resource read by the fallback reader.
+    Fallback(B),
+}
+
+impl<A: Read, B: Read> Read for ChainedResource<A, B> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            ChainedResource::Primary(resource) => resource.read(buf),
+            ChainedResource::Fallback(resource) => resource.read(buf),
+        }
+    }
+}
+
+impl<A: BufRead, B: BufRead> BufRead for ChainedResource<A, B> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        match self {
+            ChainedResource::Primary(resource) => resource.fill_buf(),
+            ChainedResource::Fallback(resource) => resource.fill_buf(),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        match self {
+            ChainedResource::Primary(resource) => resource.consume(amt),
+            ChainedResource::Fallback(resource) => resource.consume(amt),
+        }
+    }
+}
+
+/// The error returned by a [`ChainedReader`] when neither of its readers could provide a
+/// resource, holding both of their errors.
+#[derive(Debug)]
+pub struct ChainedReaderError<A, B> {
+    /// The error of the primary reader.
+    pub primary: A,
+    /// The error of the fallback reader.
+    pub fallback: B,
+}
+
+impl<A: fmt::Display, B: fmt::Display> fmt::Display for ChainedReaderError<A, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (fallback: {})", self.primary, self.fallback)
+    }
+}
+
+impl<A, B> std::error::Error for ChainedReaderError<A, B>
+where
+    A: std::error::Error + 'static,
+    B: std::error::Error,
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.primary)
+    }
+}
+
+/// A [`ResourceReader`] that serves files kept in memory, e.g. for tests, WASM builds or assets
+/// embedded in the executable.
+///
+/// Paths are normalized when inserting and reading files, so `maps/a.tmx`, `./maps/a.tmx` and
+/// `maps/../maps/a.tmx` all refer to the same file. Backslashes are treated as separators like in
+/// the files Tiled writes, and paths are compared case-insensitively on Windows.
+///
+/// Also implements [`AsyncResourceReader`](crate::AsyncResourceReader). Reading a file only
+/// clones a reference to its contents.
+///
+/// ## Example
+/// ```
+/// # fn main() -> tiled::Result<()> {
+/// use tiled::{Loader, MemoryResourceReader};
+///
+/// let mut reader = MemoryResourceReader::new();
+/// reader.insert("maps/map.tmx", include_bytes!("../assets/tiled_base64_external.tmx").to_vec());
+/// reader.insert_str("maps/tilesheet.tsx", include_str!("../assets/tilesheet.tsx"));
+///
+/// let mut loader = Loader::with_reader(reader);
+/// let map = loader.load_tmx_map("maps/map.tmx")?;
+/// assert_eq!(map.tilesets()[0].name, "tilesheet");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryResourceReader {
+    files: HashMap<PathBuf, Arc<[u8]>>,
+}
+
+impl MemoryResourceReader {
+    /// Creates a new [`MemoryResourceReader`] without any files.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file, replacing the one at the same path if there is one.
+    pub fn insert(&mut self, path: impl AsRef<Path>, contents: impl Into<Arc<[u8]>>) {
+        self.files
+            .insert(normalize_key(path.as_ref()), contents.into());
+    }
+
+    /// Adds a text file, replacing the one at the same path if there is one.
+    pub fn insert_str(&mut self, path: impl AsRef<Path>, contents: &str) {
+        self.insert(path, contents.as_bytes());
+    }
+
+    /// Removes a file, returning its contents if it was there.
+    pub fn remove(&mut self, path: impl AsRef<Path>) -> Option<Arc<[u8]>> {
+        let key = self.find(path.as_ref())?.to_owned();
+        self.files.remove(&key)
+    }
+
+    /// Returns the contents of a file, if it is there.
+    pub fn get(&self, path: impl AsRef<Path>) -> Option<&Arc<[u8]>> {
+        self.find(path.as_ref()).map(|key| &self.files[key])
+    }
+
+    /// Returns the normalized paths of all the files, in no particular order.
+    pub fn paths(&self) -> impl ExactSizeIterator<Item = &Path> {
+        self.files.keys().map(PathBuf::as_path)
+    }
+
+    /// Returns the key of the file at `path`.
+    fn find(&self, path: &Path) -> Option<&Path> {
+        let key = normalize_key(path);
+        if let Some((key, _)) = self.files.get_key_value(&key) {
+            return Some(key);
+        }
+        if cfg!(windows) {
+            let key = key.to_string_lossy();
+            return self
+                .files
+                .keys()
+                .find(|other| other.to_string_lossy().eq_ignore_ascii_case(&key))
+                .map(PathBuf::as_path);
+        }
+        None
+    }
+
+    /// Returns the error for a file that isn't there, listing the paths that look alike.
+    fn not_found(&self, path: &Path) -> MemoryResourceError {
+        let key = normalize_key(path).to_string_lossy().to_lowercase();
+        let file_name = key.rsplit('/').next().unwrap_or_default().to_owned();
+        let mut near_misses: Vec<(usize, PathBuf)> = self
+            .files
+            .keys()
+            .filter_map(|other| {
+                let other_key = other.to_string_lossy().to_lowercase();
+                let distance = edit_distance(&key, &other_key);
+                let same_name = other_key.rsplit('/').next() == Some(&file_name);
+                (same_name || distance <= 3).then(|| (distance, other.clone()))
+            })
+            .collect();
+        near_misses.sort();
+        MemoryResourceError {
+            path: path.to_owned(),
+            near_misses: near_misses
+                .into_iter()
+                .take(MemoryResourceError::MAX_NEAR_MISSES)
+                .map(|(_, path)| path)
+                .collect(),
+        }
+    }
+
+    pub(crate) fn open(&self, path: &Path) -> Result<Cursor<Arc<[u8]>>, MemoryResourceError> {
+        match self.find(path) {
+            Some(key) => Ok(Cursor::new(self.files[key].clone())),
+            None => Err(self.not_found(path)),
+        }
+    }
+}
+
+impl ResourceReader for MemoryResourceReader {
+    type Resource = Cursor<Arc<[u8]>>;
+    type Error = MemoryResourceError;
+
+    fn read_from(&mut self, path: &Path) -> std::result::Result<Self::Resource, Self::Error> {
+        self.open(path)
+    }
+
+    /// Lists the files directly inside `dir`.
+    #[cfg(feature = "world")]
+    fn list_files(&mut self, dir: &Path) -> std::result::Result<Vec<PathBuf>, Self::Error> {
+        let dir = normalize_key(dir);
+        Ok(self
+            .files
+            .keys()
+            .filter(|path| path.parent() == Some(dir.as_path()))
+            .cloned()
+            .collect())
+    }
+}
+
+/// Normalizes a path to be used as a key of [`MemoryResourceReader`].
+fn normalize_key(path: &Path) -> PathBuf {
+    match path.to_str() {
+        Some(path) if path.contains('\\') => normalize_path(Path::new(&path.replace('\\', "/"))),
+        _ => normalize_path(path),
+    }
+}
+
+/// The number of single character insertions, deletions or substitutions needed to turn `a` into
+/// `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, &b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// The error returned by a [`MemoryResourceReader`] when it doesn't have a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryResourceError {
+    /// The path of the file, as given to the reader.
+    pub path: PathBuf,
+    /// The paths of the files the reader does have that are similar to [`path`](Self::path),
+    /// closest first, which usually point to a typo or wrong directory.
+    pub near_misses: Vec<PathBuf>,
+}
+
+impl MemoryResourceError {
+    /// The most near misses listed.
+    const MAX_NEAR_MISSES: usize = 5;
+}
+
+impl fmt::Display for MemoryResourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' is not in memory", self.path.display())?;
+        for (i, path) in self.near_misses.iter().enumerate() {
+            let separator = if i == 0 { "; Did you mean" } else { " or" };
+            write!(f, "{} '{}'", separator, path.display())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for MemoryResourceError {}
@@ -1,7 +1,20 @@
-use std::{future::Future, path::Path};
+use std::{
+    future::Future,
+    io::Cursor,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
 
 use tokio::{
-    io::{AsyncBufRead, BufReader},
+    fs::File,
+    io::{AsyncBufRead, AsyncRead, BufReader, ReadBuf},
+};
+
+use crate::{
+    ChainedReader, ChainedReaderError, ChainedResource, FilesystemResourceReader,
+    MemoryResourceError, MemoryResourceReader,
 };
 
 /// A trait defining types that can asynchronously load data from a
@@ -9,6 +22,17 @@ use tokio::{
 ///
 /// This is the async version of [`ResourceReader`](crate::reader::ResourceReader). It should be
 /// implemented if you wish to asynchronously load data from a virtual filesystem.
+///
+/// ## `Send` futures
+/// The futures returned by the async [`Loader`](crate::Loader) methods are [`Send`] (and can as
+/// such be passed to e.g. `tokio::spawn`) as long as the reader, its [`Resource`](Self::Resource),
+/// the futures returned by [`read_from()`](Self::read_from()) and
+/// [`read_from_many()`](Self::read_from_many()) and the loader's
+/// [`ResourceCache`](crate::ResourceCache) are all [`Send`], which is the case for
+/// [`FilesystemResourceReader`] and [`DefaultResourceCache`](crate::DefaultResourceCache).
+///
+/// Code that is generic over this trait can't rely on [`read_from()`](Self::read_from())
+/// returning a [`Send`] future, so prefer using concrete reader types in that case.
 pub trait AsyncResourceReader {
     /// The type of the resource that the reader provides. For example, for
     /// [`FilesystemResourceReader`], this is defined as [`File`].
@@ -23,4 +47,128 @@ pub trait AsyncResourceReader {
         &mut self,
         path: &Path,
     ) -> impl Future<Output = Result<Self::Resource, Self::Error>>;
+
+    /// Tries to return a reader object for each of several paths, in the same order. This is used
+    /// to open all the external tilesets of a map at once.
+    ///
+    /// The default implementation calls [`read_from()`](Self::read_from()) for each path in turn,
+    /// since it borrows the reader mutably. Readers that can open several resources concurrently,
+    /// such as [`FilesystemResourceReader`], should override it to do so.
+    fn read_from_many(
+        &mut self,
+        paths: &[PathBuf],
+    ) -> impl Future<Output = Vec<Result<Self::Resource, Self::Error>>> {
+        async move {
+            let mut resources = Vec::with_capacity(paths.len());
+            for path in paths {
+                resources.push(self.read_from(path).await);
+            }
+            resources
+        }
+    }
+}
+
+/// Reads from [`tokio`] [`File`] handles. Requires a tokio runtime.
+impl AsyncResourceReader for FilesystemResourceReader {
+    type Resource = BufReader<File>;
+    type Error = std::io::Error;
+
+    async fn read_from(&mut self, path: &Path) -> Result<Self::Resource, Self::Error> {
+        let file = File::open(path).await?;
+        Ok(BufReader::new(file))
+    }
+
+    /// Opens all the files concurrently.
+    async fn read_from_many(
+        &mut self,
+        paths: &[PathBuf],
+    ) -> Vec<Result<Self::Resource, Self::Error>> {
+        futures::future::join_all(
+            paths
+                .iter()
+                .map(|path| async move { Ok(BufReader::new(File::open(path).await?)) }),
+        )
+        .await
+    }
+}
+
+/// Returns on the first poll.
+impl AsyncResourceReader for MemoryResourceReader {
+    type Resource = Cursor<Arc<[u8]>>;
+    type Error = MemoryResourceError;
+
+    async fn read_from(&mut self, path: &Path) -> Result<Self::Resource, Self::Error> {
+        self.open(path)
+    }
+}
+
+impl<A: AsyncResourceReader, B: AsyncResourceReader> AsyncResourceReader for ChainedReader<A, B> {
+    type Resource = ChainedResource<A::Resource, B::Resource>;
+    type Error = ChainedReaderError<A::Error, B::Error>;
+
+    async fn read_from(&mut self, path: &Path) -> Result<Self::Resource, Self::Error> {
+        match self.primary.read_from(path).await {
+            Ok(resource) => Ok(ChainedResource::Primary(resource)),
+            Err(primary) => match self.fallback.read_from(path).await {
+                Ok(resource) => Ok(ChainedResource::Fallback(resource)),
+                Err(fallback) => Err(ChainedReaderError { primary, fallback }),
+            },
+        }
+    }
+
+    /// Opens the resources with the primary reader's [`read_from_many()`](
+    /// AsyncResourceReader::read_from_many()), then the ones it failed to open with the fallback
+    /// reader's.
+    async fn read_from_many(
+        &mut self,
+        paths: &[PathBuf],
+    ) -> Vec<Result<Self::Resource, Self::Error>> {
+        let primary = self.primary.read_from_many(paths).await;
+        let missing: Vec<_> = paths
+            .iter()
+            .zip(&primary)
+            .filter(|(_, resource)| resource.is_err())
+            .map(|(path, _)| path.clone())
+            .collect();
+        let mut fallback = self.fallback.read_from_many(&missing).await.into_iter();
+        primary
+            .into_iter()
+            .map(|resource| match resource {
+                Ok(resource) => Ok(ChainedResource::Primary(resource)),
+                Err(primary) => match fallback.next().expect("one result per path") {
+                    Ok(resource) => Ok(ChainedResource::Fallback(resource)),
+                    Err(fallback) => Err(ChainedReaderError { primary, fallback }),
+                },
+            })
+            .collect()
+    }
+}
+
+impl<A: AsyncRead + Unpin, B: AsyncRead + Unpin> AsyncRead for ChainedResource<A, B> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            ChainedResource::Primary(resource) => Pin::new(resource).poll_read(cx, buf),
+            ChainedResource::Fallback(resource) => Pin::new(resource).poll_read(cx, buf),
+        }
+    }
+}
+
+impl<A: AsyncBufRead + Unpin, B: AsyncBufRead + Unpin> AsyncBufRead for ChainedResource<A, B> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<&[u8]>> {
+        match self.get_mut() {
+            ChainedResource::Primary(resource) => Pin::new(resource).poll_fill_buf(cx),
+            ChainedResource::Fallback(resource) => Pin::new(resource).poll_fill_buf(cx),
+        }
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        match self.get_mut() {
+            ChainedResource::Primary(resource) => Pin::new(resource).consume(amt),
+            ChainedResource::Fallback(resource) => Pin::new(resource).consume(amt),
+        }
+    }
 }
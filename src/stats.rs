@@ -0,0 +1,256 @@
+use std::{collections::HashMap, mem::size_of, path::PathBuf, sync::Arc};
+
+use crate::{
+    Color, EditorSettings, Frame, Gid, Image, ImageLayerData, LayerTileData, ObjectShape,
+    ParseWarning, PropertyValue, TileBounds, TileData, WangColor, WangSet, WangTile,
+};
+
+/// Counts of the contents of a [`Map`](crate::Map), obtained through
+/// [`Map::statistics`](crate::Map::statistics).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MapStats {
+    /// The number of tile layers, including the ones inside groups.
+    pub tile_layers: usize,
+    /// The number of object layers, including the ones inside groups.
+    pub object_layers: usize,
+    /// The number of image layers, including the ones inside groups.
+    pub image_layers: usize,
+    /// The number of group layers, including the ones inside other groups.
+    pub group_layers: usize,
+    /// The number of layers of kinds the crate doesn't support, including the ones inside groups.
+    /// See [`LayerType::Unknown`](crate::LayerType::Unknown).
+    pub unknown_layers: usize,
+    /// The number of tile cells stored by tile layers, empty ones included. Finite layers store
+    /// one per tile of their size, while infinite ones store a whole chunk's worth of cells for
+    /// each of their chunks.
+    pub tile_cells: usize,
+    /// The number of objects in object layers. Objects describing tile collisions aren't counted.
+    pub objects: usize,
+    /// The number of distinct tilesets used by the map.
+    pub tilesets: usize,
+    /// The number of tiles that have an animation in the map's tilesets.
+    pub animated_tiles: usize,
+    /// An estimate of the heap memory used by the map in bytes, including its tilesets.
+    ///
+    /// It is computed from the capacities of the containers of the map, so it doesn't account
+    /// for allocator overhead. Strings shared between several objects and properties, and images
+    /// shared between several tiles or layers, are counted once per use.
+    pub heap_size: usize,
+}
+
+/// Counts of the contents of a [`Tileset`](crate::Tileset), obtained through
+/// [`Tileset::statistics`](crate::Tileset::statistics).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TilesetStats {
+    /// The number of tiles with data of their own, such as properties or an animation. Other
+    /// tiles of the tileset take no memory.
+    pub tiles: usize,
+    /// The number of tiles that have an animation.
+    pub animated_tiles: usize,
+    /// The total number of frames of the animations of the tiles.
+    pub animation_frames: usize,
+    /// The total number of collision objects of the tiles.
+    pub collision_objects: usize,
+    /// The number of Wang sets.
+    pub wang_sets: usize,
+    /// An estimate of the heap memory used by the tileset in bytes. See
+    /// [`MapStats::heap_size`].
+    pub heap_size: usize,
+}
+
+/// Estimates the memory a value owns on the heap, not counting its own size.
+pub(crate) trait HeapSize {
+    fn heap_size(&self) -> usize;
+}
+
+macro_rules! impl_no_heap_size {
+    ($($ty:ty),*) => {
+        $(
+            impl HeapSize for $ty {
+                #[inline]
+                fn heap_size(&self) -> usize {
+                    0
+                }
+            }
+        )*
+    };
+}
+
+impl_no_heap_size!(
+    bool,
+    u8,
+    u32,
+    i32,
+    f32,
+    usize,
+    Color,
+    Frame,
+    Gid,
+    LayerTileData,
+    TileBounds,
+    WangTile
+);
+
+impl HeapSize for String {
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl HeapSize for PathBuf {
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl HeapSize for Arc<str> {
+    fn heap_size(&self) -> usize {
+        // The reference counts are stored alongside the string
+        2 * size_of::<usize>() + self.len()
+    }
+}
+
+impl<A: HeapSize, B: HeapSize> HeapSize for (A, B) {
+    fn heap_size(&self) -> usize {
+        self.0.heap_size() + self.1.heap_size()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Option<T> {
+    fn heap_size(&self) -> usize {
+        self.as_ref().map_or(0, HeapSize::heap_size)
+    }
+}
+
+impl<T: HeapSize> HeapSize for Vec<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<T>() + self.iter().map(HeapSize::heap_size).sum::<usize>()
+    }
+}
+
+impl<K: HeapSize, V: HeapSize> HeapSize for HashMap<K, V> {
+    fn heap_size(&self) -> usize {
+        // Each bucket also has a control byte
+        self.capacity() * (size_of::<(K, V)>() + 1)
+            + self
+                .iter()
+                .map(|(k, v)| k.heap_size() + v.heap_size())
+                .sum::<usize>()
+    }
+}
+
+impl HeapSize for PropertyValue {
+    fn heap_size(&self) -> usize {
+        match self {
+            PropertyValue::StringValue(value) | PropertyValue::FileValue(value) => {
+                value.heap_size()
+            }
+            PropertyValue::ClassValue {
+                property_type,
+                properties,
+            } => property_type.heap_size() + properties.heap_size(),
+            PropertyValue::EnumValue { name, value } => name.heap_size() + value.heap_size(),
+            PropertyValue::Unknown {
+                type_name,
+                raw_value,
+            } => type_name.heap_size() + raw_value.heap_size(),
+            PropertyValue::BoolValue(_)
+            | PropertyValue::FloatValue(_)
+            | PropertyValue::IntValue(_)
+            | PropertyValue::ColorValue(_)
+            | PropertyValue::ObjectValue(_) => 0,
+        }
+    }
+}
+
+impl HeapSize for Image {
+    fn heap_size(&self) -> usize {
+        self.source.heap_size()
+            + self.raw_source.heap_size()
+            + self.format.heap_size()
+            + self.embedded_data.heap_size()
+    }
+}
+
+impl HeapSize for Arc<Image> {
+    fn heap_size(&self) -> usize {
+        // The reference counts are stored alongside the image
+        2 * size_of::<usize>() + size_of::<Image>() + (**self).heap_size()
+    }
+}
+
+impl HeapSize for ImageLayerData {
+    fn heap_size(&self) -> usize {
+        self.image.heap_size()
+    }
+}
+
+impl HeapSize for ObjectShape {
+    fn heap_size(&self) -> usize {
+        match self {
+            ObjectShape::Polyline { points } | ObjectShape::Polygon { points } => {
+                points.heap_size()
+            }
+            ObjectShape::Text {
+                font_family, text, ..
+            } => font_family.heap_size() + text.heap_size(),
+            ObjectShape::Rect { .. } | ObjectShape::Ellipse { .. } | ObjectShape::Point(..) => 0,
+        }
+    }
+}
+
+impl HeapSize for TileData {
+    fn heap_size(&self) -> usize {
+        self.image.heap_size()
+            + self.properties.heap_size()
+            + self.collision.heap_size()
+            + self.animation.heap_size()
+            + self.user_type.heap_size()
+    }
+}
+
+impl HeapSize for WangSet {
+    fn heap_size(&self) -> usize {
+        self.name.heap_size()
+            + self.wang_colors.heap_size()
+            + self.wang_tiles.heap_size()
+            + self.properties.heap_size()
+            + self.user_type.heap_size()
+    }
+}
+
+impl HeapSize for WangColor {
+    fn heap_size(&self) -> usize {
+        self.name.heap_size() + self.properties.heap_size() + self.user_type.heap_size()
+    }
+}
+
+impl HeapSize for EditorSettings {
+    fn heap_size(&self) -> usize {
+        self.export_target.heap_size() + self.export_format.heap_size()
+    }
+}
+
+impl HeapSize for ParseWarning {
+    fn heap_size(&self) -> usize {
+        match self {
+            ParseWarning::UnknownPropertyType {
+                property,
+                type_name,
+            } => property.heap_size() + type_name.heap_size(),
+            ParseWarning::UnresolvedTileGid { layer_name, .. } => layer_name.heap_size(),
+            ParseWarning::TileCountMismatch { tileset_name, .. } => tileset_name.heap_size(),
+            ParseWarning::DuplicateProperty { name, path, .. }
+            | ParseWarning::DuplicateLayerName { name, path, .. } => {
+                name.heap_size() + path.heap_size()
+            }
+            // The error is left out, since it's shared with any clone of the warning and its
+            // source can't be measured
+            ParseWarning::MissingTileset { path, .. } => path.heap_size(),
+            ParseWarning::UnknownLayerType {
+                tag_name,
+                layer_name,
+            } => tag_name.heap_size() + layer_name.heap_size(),
+        }
+    }
+}
@@ -3,10 +3,10 @@ use std::sync::Arc;
 
 use quick_xml::events::Event;
 
-use crate::parse::xml::{Parser, ReadFrom, Reader};
+use crate::parse::xml::{LoadContext, Parser, ReadFrom, Reader};
 use crate::{
-    util::*, EmbeddedParseResultType, Error, MapTilesetGid, ObjectData, ResourceCache, Result,
-    Tileset,
+    util::*, EmbeddedParseResultType, Error, LoadProgress, MapTilesetGid, ObjectData,
+    ResourceCache, Result, Tileset,
 };
 
 /// A template, consisting of an object and a tileset
@@ -17,6 +17,9 @@ use crate::{
 pub struct Template {
     /// The tileset this template contains a reference to
     pub tileset: Option<Arc<Tileset>>,
+    /// The `source` attribute of the template's tileset exactly as written in the template file,
+    /// or [`None`] if it has no tileset or it is embedded.
+    pub tileset_raw_source: Option<String>,
     /// The object data for this template
     pub object: ObjectData,
 }
@@ -26,32 +29,56 @@ impl Template {
         path: &Path,
         read_from: &mut impl ReadFrom,
         cache: &mut impl ResourceCache,
+        context: &mut LoadContext,
     ) -> Result<Arc<Template>> {
+        #[cfg(feature = "json")]
+        if crate::parse::json::is_json_path(path) {
+            return crate::parse::json::parse_template(path, read_from, cache, context).await;
+        }
         // Open the template file
-        let mut file =
-            read_from
-                .read_from(path)
-                .await
-                .map_err(|err| Error::ResourceLoadingError {
-                    path: path.to_owned(),
-                    err: Box::new(err),
-                })?;
+        let file = read_from
+            .read_from(path)
+            .await
+            .map_err(|err| Error::ResourceLoadingError {
+                path: path.to_owned(),
+                err,
+            })?;
+        Self::parse_template_from(file, path, read_from, cache, context).await
+    }
+
+    /// Parses a template from an already opened reader. `path` is only used to resolve the
+    /// external files the template refers to.
+    pub(crate) async fn parse_template_from<R: Reader>(
+        mut file: R,
+        path: &Path,
+        read_from: &mut impl ReadFrom,
+        cache: &mut impl ResourceCache,
+        context: &mut LoadContext,
+    ) -> Result<Arc<Template>> {
         let mut buffer = Vec::new();
         loop {
+            let position = file.buffer_position();
             let next = file
                 .read_event_into(&mut buffer)
                 .await
-                .map_err(Error::XmlDecodingError)?;
+                .map_err(|err| Error::XmlDecodingError(err).at_position(path, position))?;
             match next {
                 Event::Start(start) if start.local_name().into_inner() == b"template" => {
-                    let template = Self::parse_external_template(
-                        &mut Parser::with_reader(file),
-                        path,
-                        read_from,
-                        cache,
-                    )
-                    .await?;
-                    return Ok(template);
+                    let mut parser = Parser::with_reader(file);
+                    parser.last_event_position = position;
+                    parser.context = std::mem::take(context);
+                    parser.path = path.to_owned();
+                    let template =
+                        Self::parse_external_template(&mut parser, path, read_from, cache)
+                            .await
+                            .map_err(|err| err.at_position(path, parser.last_event_position));
+                    *context = parser.context;
+                    if template.is_ok() {
+                        context
+                            .settings
+                            .report_progress(LoadProgress::TemplateLoaded(path));
+                    }
+                    return template;
                 }
                 Event::Eof => {
                     return Err(Error::PrematureEnd(
@@ -71,6 +98,7 @@ impl Template {
     ) -> Result<Arc<Template>> {
         let mut object = Option::None;
         let mut tileset = None;
+        let mut tileset_raw_source = None;
         let mut tileset_gid: Vec<MapTilesetGid> = vec![];
 
         let mut buffer = Vec::new();
@@ -90,17 +118,19 @@ impl Template {
             "tileset" => for attrs {
                 let res = Tileset::parse_xml_in_map(parser, &attrs, template_path, read_from, cache).await?;
                 match res.result_type {
-                    EmbeddedParseResultType::ExternalReference { tileset_path } => {
+                    EmbeddedParseResultType::ExternalReference { tileset_path, raw_source } => {
+                        tileset_raw_source = Some(raw_source);
                         tileset = Some(if let Some(ts) = cache.get_tileset(&tileset_path) {
                             ts
                         } else {
-                            let tileset = Arc::new(crate::parse::xml::parse_tileset(&tileset_path, read_from, cache).await?);
+                            let tileset = Arc::new(crate::parse::parse_tileset(&tileset_path, read_from, cache, &mut parser.context).await?);
                             cache.insert_tileset(tileset_path.clone(), tileset.clone());
                             tileset
                         });
                     }
                     EmbeddedParseResultType::Embedded { tileset: embedded_tileset } => {
                         tileset = Some(Arc::new(embedded_tileset));
+                        tileset_raw_source = None;
                     },
                 };
                 tileset_gid.push(MapTilesetGid {
@@ -113,6 +143,10 @@ impl Template {
 
         let object = object.ok_or(Error::TemplateHasNoObject)?;
 
-        Ok(Arc::new(Template { tileset, object }))
+        Ok(Arc::new(Template {
+            tileset,
+            tileset_raw_source,
+            object,
+        }))
     }
 }
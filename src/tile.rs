@@ -1,12 +1,17 @@
-use std::{collections::HashMap, path::Path};
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, OnceLock},
+};
 
 use quick_xml::events::attributes::Attribute;
 
 use crate::{
-    animation::{parse_animation, Frame},
+    animation::{self, parse_animation, Frame},
     error::Error,
     image::Image,
     layers::ObjectLayerData,
+    objects::{ObjectData, ObjectShape},
     parse::xml::{Parser, ReadFrom, Reader},
     properties::{parse_properties, Properties},
     util::{get_attrs, parse_tag},
@@ -17,10 +22,13 @@ use crate::{
 pub type TileId = u32;
 
 /// Raw data belonging to a tile.
-#[derive(Debug, PartialEq, Clone, Default)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TileData {
     /// The image of the tile. Only set when the tile is part of an "image collection" tileset.
-    pub image: Option<Image>,
+    ///
+    /// Tiles that refer to the same image share it, see [`Image`].
+    pub image: Option<Arc<Image>>,
     /// The custom properties of this tile.
     pub properties: Properties,
     /// The collision shapes of this tile.
@@ -33,22 +41,58 @@ pub struct TileData {
     pub probability: f32,
 }
 
+impl Default for TileData {
+    fn default() -> Self {
+        Self {
+            image: None,
+            properties: Properties::default(),
+            collision: None,
+            animation: None,
+            user_type: None,
+            probability: 1.0,
+        }
+    }
+}
+
 /// Points to a tile belonging to a tileset.
 #[derive(Debug)]
 pub struct Tile<'tileset> {
     pub(crate) tileset: &'tileset Tileset,
     pub(crate) data: &'tileset TileData,
+    defined: bool,
 }
 
 impl<'tileset> Tile<'tileset> {
     pub(crate) fn new(tileset: &'tileset Tileset, data: &'tileset TileData) -> Self {
-        Self { tileset, data }
+        Self {
+            tileset,
+            data,
+            defined: true,
+        }
+    }
+
+    /// Creates a tile that has no `<tile>` element of its own, using the default tile data.
+    pub(crate) fn implicit(tileset: &'tileset Tileset) -> Self {
+        static DEFAULT_DATA: OnceLock<TileData> = OnceLock::new();
+        Self {
+            tileset,
+            data: DEFAULT_DATA.get_or_init(TileData::default),
+            defined: false,
+        }
     }
 
     /// Get the tileset this tile is from.
     pub fn tileset(&self) -> &'tileset Tileset {
         self.tileset
     }
+
+    /// Whether this tile was explicitly defined in its tileset through a `<tile>` element.
+    /// Tiles that weren't only exist implicitly as part of the tileset image and always have the
+    /// default [`TileData`].
+    #[inline]
+    pub fn is_defined(&self) -> bool {
+        self.defined
+    }
 }
 
 impl<'tileset> std::ops::Deref for Tile<'tileset> {
@@ -60,6 +104,83 @@ impl<'tileset> std::ops::Deref for Tile<'tileset> {
     }
 }
 
+impl TileData {
+    /// Returns an iterator over the collision shapes of this tile. Their positions are relative to
+    /// the top-left corner of the tile.
+    ///
+    /// Unlike going through [`ObjectLayer`](crate::ObjectLayer), this doesn't need a map, so it
+    /// can be used on tilesets loaded on their own.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let tileset = tiled::Loader::new().load_tsx_tileset("assets/tilesheet_collision.tsx")?;
+    /// let tile = tileset.get_tile(0).unwrap();
+    ///
+    /// assert_eq!(tile.collision_objects().count(), 5);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn collision_objects(&self) -> impl Iterator<Item = &ObjectData> {
+        self.collision
+            .iter()
+            .flat_map(|collision| collision.object_data())
+    }
+
+    /// Returns an iterator over the rectangular collision shapes of this tile, as `(x, y, width,
+    /// height)` tuples relative to the top-left corner of the tile.
+    ///
+    /// Rotated rectangles and other shapes are skipped; Use [`TileData::collision_objects`] to
+    /// access them.
+    pub fn collision_rects(&self) -> impl Iterator<Item = (f32, f32, f32, f32)> + '_ {
+        self.collision_objects()
+            .filter(|object| object.rotation == 0.0)
+            .filter_map(|object| match object.shape {
+                ObjectShape::Rect { width, height } => Some((object.x, object.y, width, height)),
+                _ => None,
+            })
+    }
+
+    /// Returns how long a whole loop of this tile's animation lasts, in milliseconds, or 0 if the
+    /// tile isn't animated.
+    pub fn animation_total_duration(&self) -> u32 {
+        self.animation
+            .as_deref()
+            .map_or(0, animation::total_duration)
+    }
+
+    /// Returns the animation frame to display once `elapsed_ms` milliseconds have passed since the
+    /// animation started, along with how many milliseconds into that frame this is. The animation
+    /// loops, so `elapsed_ms` can be larger than [`TileData::animation_total_duration`].
+    ///
+    /// Frames lasting zero milliseconds are never returned, unless all of them do, in which case
+    /// the animation is considered static and the first frame is always returned. Returns [`None`]
+    /// if the tile isn't animated.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let tileset = tiled::Loader::new().load_tsx_tileset("assets/tilesheet_sparse.tsx")?;
+    /// let tile = tileset.get_tile(17).unwrap();
+    ///
+    /// // Each of the two frames lasts 100ms
+    /// let (frame, into_frame) = tile.frame_at(250).unwrap();
+    /// assert_eq!(frame.tile_id, 17);
+    /// assert_eq!(into_frame, 50);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn frame_at(&self, elapsed_ms: u32) -> Option<(&Frame, u32)> {
+        animation::frame_at(self.animation.as_deref()?, elapsed_ms)
+    }
+
+    /// Returns the ID of the tile to display once `elapsed_ms` milliseconds have passed since the
+    /// animation started. See [`TileData::frame_at`] for details.
+    pub fn frame_tile_id_at(&self, elapsed_ms: u32) -> Option<TileId> {
+        self.frame_at(elapsed_ms).map(|(frame, _)| frame.tile_id)
+    }
+}
+
 impl TileData {
     pub(crate) async fn new<R: Reader>(
         parser: &mut Parser<R>,
@@ -69,7 +190,7 @@ impl TileData {
         cache: &mut impl ResourceCache,
     ) -> Result<(TileId, TileData)> {
         let ((user_type, user_class, probability), id) = get_attrs!(
-            for v in attrs {
+            for v in attrs of "tile" {
                 Some("type") => user_type ?= v.parse(),
                 Some("class") => user_class ?= v.parse(),
                 Some("probability") => probability ?= v.parse(),
@@ -86,7 +207,7 @@ impl TileData {
         let mut buffer = Vec::new();
         parse_tag!(parser => &mut buffer, "tile", {
             "image" => for attrs {
-                image = Some(Image::new(parser, attrs, path_relative_to).await?);
+                image = Some(Image::new(parser, attrs, path_relative_to, read_from).await?);
                 Ok(())
             },
             "properties" => {
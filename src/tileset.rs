@@ -1,5 +1,8 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
 
 use quick_xml::events::attributes::Attribute;
 
@@ -7,8 +10,12 @@ use crate::error::{Error, Result};
 use crate::image::Image;
 use crate::parse::xml::{Parser, ReadFrom, Reader};
 use crate::properties::{parse_properties, Properties};
+use crate::stats::{HeapSize, TilesetStats};
 use crate::tile::TileData;
-use crate::{util::*, Gid, InvalidTilesetError, ResourceCache, Tile, TileId};
+use crate::{
+    util::*, Gid, InvalidTilesetError, Orientation, ParseWarning, ResourceCache, Strictness, Tile,
+    TileId,
+};
 
 mod wangset;
 pub use wangset::*;
@@ -17,6 +24,7 @@ pub use wangset::*;
 ///
 /// Also see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#tileset).
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Tileset {
     /// The name of the tileset, set by the user.
     pub name: String,
@@ -38,15 +46,26 @@ pub struct Tileset {
     pub margin: u32,
     /// The number of tiles in this tileset. Note that tile IDs don't always have a connection with
     /// the tile count, and as such there may be tiles with an ID bigger than the tile count.
+    ///
+    /// If the `tilecount` attribute is missing, this is derived from the [image](Self::image)
+    /// dimensions, or is the number of tiles of [image collection](Self::is_image_collection)
+    /// tilesets.
     pub tilecount: u32,
     /// The number of tile columns in the tileset. Editable for image collection tilesets, otherwise
     /// calculated using [image](Self::image) width, [tile width](Self::tile_width),
     /// [spacing](Self::spacing) and [margin](Self::margin).
+    ///
+    /// This is usually 0 for [image collection](Self::is_image_collection) tilesets, so make sure
+    /// to check before dividing by it.
     pub columns: u32,
     /// The x-offset to be used when drawing tiles of this tileset.
     pub offset_x: i32,
     /// The y-offset to be used when drawing tiles of this tileset.
     pub offset_y: i32,
+    /// The size to use when rendering tiles from this tileset on a tile layer.
+    pub tile_render_size: TileRenderSize,
+    /// How tiles are rendered when their size differs from the size they're rendered at.
+    pub fill_mode: FillMode,
 
     /// A tileset can either:
     /// * have a single spritesheet `image` in `tileset` ("regular" tileset);
@@ -55,9 +74,9 @@ pub struct Tileset {
     /// --------
     /// - Source: [tiled issue #2117](https://github.com/mapeditor/tiled/issues/2117)
     /// - Source: [`columns` documentation](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#tileset)
-    pub image: Option<Image>,
+    pub image: Option<Arc<Image>>,
 
-    /// All the tiles present in this tileset, indexed by their local IDs.
+    /// The tiles explicitly defined in this tileset, indexed by their local IDs.
     tiles: HashMap<TileId, TileData>,
 
     /// All the wangsets present in this tileset.
@@ -68,13 +87,180 @@ pub struct Tileset {
 
     /// The custom tileset type, arbitrarily set by the user.
     pub user_type: Option<String>,
+
+    /// The ways in which tiles from this tileset can be transformed, if specified.
+    pub transformations: Option<Transformations>,
+
+    /// How the tile images of this tileset are aligned to the map grid, if specified. Mostly
+    /// useful for isometric [image collection](Self::is_image_collection) tilesets.
+    pub grid: Option<Grid>,
+
+    /// The path of the external TSX file this tileset was loaded from, or [`None`] if it was
+    /// embedded in a map or template.
+    pub source: Option<PathBuf>,
+
+    /// The TMX format version the external TSX file this tileset was loaded from was saved to, if
+    /// any. [`None`] for embedded tilesets, which share the version of the file containing them.
+    pub version: Option<String>,
+
+    /// The version of Tiled the external TSX file this tileset was loaded from was saved with, if
+    /// present. [`None`] for embedded tilesets.
+    pub tiled_version: Option<String>,
+
+    #[cfg_attr(feature = "serde", serde(default))]
+    placeholder: bool,
+}
+
+/// A rectangle in pixels, such as the area of a tile inside of a [tileset image](Tileset::image).
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rect {
+    /// The x coordinate of the left edge of the rectangle.
+    pub x: u32,
+    /// The y coordinate of the top edge of the rectangle.
+    pub y: u32,
+    /// The width of the rectangle.
+    pub width: u32,
+    /// The height of the rectangle.
+    pub height: u32,
+}
+
+/// Describes which transformations can be applied to the tiles of a [`Tileset`] (e.g. when
+/// filling an area using Wang sets).
+///
+/// Also see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#transformations).
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transformations {
+    /// Whether tiles can be flipped horizontally.
+    pub hflip: bool,
+    /// Whether tiles can be flipped vertically.
+    pub vflip: bool,
+    /// Whether tiles can be rotated in 90-degree increments.
+    pub rotate: bool,
+    /// Whether untransformed tiles remain preferred, otherwise transformed tiles are used to
+    /// produce more variations.
+    pub prefer_untransformed: bool,
+}
+
+/// The grid used to align the tiles of a [`Tileset`], e.g. when placing tile objects or tiles of an
+/// isometric image collection tileset.
+///
+/// Also see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#grid).
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Grid {
+    /// The orientation of the grid. Only [`Orientation::Orthogonal`] and
+    /// [`Orientation::Isometric`] are used by Tiled.
+    pub orientation: Orientation,
+    /// The width of a grid cell, in pixels.
+    pub width: u32,
+    /// The height of a grid cell, in pixels.
+    pub height: u32,
+}
+
+/// The size tiles from a [`Tileset`] are rendered at on tile layers.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TileRenderSize {
+    /// Tiles are rendered at their own size.
+    #[default]
+    Tile,
+    /// Tiles are rendered at the tile grid size of the map.
+    Grid,
+}
+
+#[derive(Debug)]
+/// An error arising from trying to parse a [`TileRenderSize`] that is not valid.
+pub struct TileRenderSizeError {
+    /// The invalid string found.
+    pub str_found: String,
+}
+
+impl fmt::Display for TileRenderSizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to parse tile render size, valid options are `tile` and `grid` but got `{}` \
+            instead",
+            self.str_found
+        )
+    }
+}
+
+impl std::error::Error for TileRenderSizeError {}
+
+impl FromStr for TileRenderSize {
+    type Err = TileRenderSizeError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "tile" => Ok(TileRenderSize::Tile),
+            "grid" => Ok(TileRenderSize::Grid),
+            _ => Err(TileRenderSizeError {
+                str_found: s.to_owned(),
+            }),
+        }
+    }
+}
+
+/// How tiles from a [`Tileset`] fill the area they're rendered at when its size differs from the
+/// size of the tiles, e.g. when [`TileRenderSize::Grid`] is used.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FillMode {
+    /// Tiles are stretched to fill the whole area.
+    #[default]
+    Stretch,
+    /// Tiles are scaled as much as possible while keeping their aspect ratio.
+    PreserveAspectFit,
+}
+
+#[derive(Debug)]
+/// An error arising from trying to parse a [`FillMode`] that is not valid.
+pub struct FillModeError {
+    /// The invalid string found.
+    pub str_found: String,
+}
+
+impl fmt::Display for FillModeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to parse fill mode, valid options are `stretch` and `preserve-aspect-fit` but \
+            got `{}` instead",
+            self.str_found
+        )
+    }
+}
+
+impl std::error::Error for FillModeError {}
+
+impl FromStr for FillMode {
+    type Err = FillModeError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "stretch" => Ok(FillMode::Stretch),
+            "preserve-aspect-fit" => Ok(FillMode::PreserveAspectFit),
+            _ => Err(FillModeError {
+                str_found: s.to_owned(),
+            }),
+        }
+    }
 }
 
 // FIXME: box large enum variant?
 #[allow(clippy::large_enum_variant)]
 pub(crate) enum EmbeddedParseResultType {
-    ExternalReference { tileset_path: PathBuf },
-    Embedded { tileset: Tileset },
+    ExternalReference {
+        tileset_path: PathBuf,
+        /// The `source` attribute of the reference, as written in the file.
+        raw_source: String,
+    },
+    Embedded {
+        tileset: Tileset,
+    },
 }
 
 pub(crate) struct EmbeddedParseResult {
@@ -86,7 +272,7 @@ pub(crate) struct EmbeddedParseResult {
 struct TilesetProperties {
     spacing: Option<u32>,
     margin: Option<u32>,
-    tilecount: u32,
+    tilecount: Option<u32>,
     columns: Option<u32>,
     name: String,
     user_type: Option<String>,
@@ -94,22 +280,230 @@ struct TilesetProperties {
     tile_height: u32,
     /// The root all non-absolute paths contained within the tileset are relative to.
     root_path: PathBuf,
+    /// The path of the external tileset file, if any.
+    source: Option<PathBuf>,
+    tile_render_size: Option<TileRenderSize>,
+    fill_mode: Option<FillMode>,
+    /// The `version` attribute of the external tileset file, if any.
+    version: Option<String>,
+    /// The `tiledversion` attribute of the external tileset file, if any.
+    tiled_version: Option<String>,
 }
 
 impl Tileset {
     /// Gets the tile with the specified ID from the tileset.
+    ///
+    /// For tilesets with a [tileset image](Self::image), this returns a tile for every ID below
+    /// [`tilecount`](Self::tilecount), even if it wasn't [defined](Tile::is_defined) explicitly.
     #[inline]
     pub fn get_tile(&self, id: TileId) -> Option<Tile> {
-        self.tiles.get(&id).map(|data| Tile::new(self, data))
+        match self.tiles.get(&id) {
+            Some(data) => Some(Tile::new(self, data)),
+            None if !self.is_image_collection() && id < self.tilecount => {
+                Some(Tile::implicit(self))
+            }
+            None => None,
+        }
     }
 
-    /// Iterates through the tiles from this tileset.
-    #[inline]
+    /// Iterates through all the tiles from this tileset in ascending ID order.
+    ///
+    /// For tilesets with a [tileset image](Self::image), this includes every ID below
+    /// [`tilecount`](Self::tilecount), whether it was [defined](Tile::is_defined) explicitly or
+    /// not. [Image collection](Self::is_image_collection) tilesets only contain the tiles that were
+    /// defined. Use [`Tileset::defined_tiles`] to only iterate through explicitly defined tiles.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// # fn main() {
+    /// let tileset = Loader::new()
+    ///     .load_tsx_tileset("assets/tilesheet.tsx")
+    ///     .unwrap();
+    ///
+    /// assert_eq!(tileset.tiles().len(), tileset.tilecount as usize);
+    /// # }
+    /// ```
     pub fn tiles(&self) -> impl ExactSizeIterator<Item = (TileId, Tile)> {
+        let implicit_ids = if self.is_image_collection() {
+            0..0
+        } else {
+            0..self.tilecount
+        };
+        let mut ids: Vec<TileId> = implicit_ids
+            .chain(
+                self.tiles
+                    .keys()
+                    .copied()
+                    .filter(|id| self.is_image_collection() || *id >= self.tilecount),
+            )
+            .collect();
+        ids.sort_unstable();
+        ids.into_iter().map(move |id| {
+            let tile = match self.tiles.get(&id) {
+                Some(data) => Tile::new(self, data),
+                None => Tile::implicit(self),
+            };
+            (id, tile)
+        })
+    }
+
+    /// Iterates through the tiles that were explicitly [defined](Tile::is_defined) in this
+    /// tileset, in no particular order.
+    #[inline]
+    pub fn defined_tiles(&self) -> impl ExactSizeIterator<Item = (TileId, Tile<'_>)> {
         self.tiles
             .iter()
             .map(move |(id, data)| (*id, Tile::new(self, data)))
     }
+
+    /// Returns whether this is an "image collection" tileset, in which each tile has its own image
+    /// instead of being part of a single [tileset image](Self::image).
+    ///
+    /// The [tile width](Self::tile_width) and [tile height](Self::tile_height) of these tilesets
+    /// only hold the maximum dimensions of their tiles; Use the width and height of each
+    /// [tile image](crate::TileData::image) instead.
+    #[inline]
+    pub fn is_image_collection(&self) -> bool {
+        self.image.is_none()
+    }
+
+    /// Whether this tileset is an empty stand-in for an external tileset that could not be
+    /// read. See [`LoadSettings::placeholder_missing_tilesets`](crate::LoadSettings::placeholder_missing_tilesets).
+    #[inline]
+    pub fn is_placeholder(&self) -> bool {
+        self.placeholder
+    }
+
+    /// Whether a tile with the given ID is part of this tileset.
+    ///
+    /// For regular tilesets, that is any ID lower than [`tilecount`](Self::tilecount). Image
+    /// collection tilesets can have gaps between IDs, so only the tiles they define are part of
+    /// them.
+    pub fn contains_tile(&self, id: TileId) -> bool {
+        if self.is_image_collection() {
+            self.tiles.contains_key(&id)
+        } else {
+            id < self.tilecount
+        }
+    }
+
+    /// Returns the area of the [tileset image](Self::image) the tile with the given ID is drawn
+    /// from, taking the [margin](Self::margin) and [spacing](Self::spacing) into account.
+    ///
+    /// If [`columns`](Self::columns) is 0, it is derived from the width of the tileset image. Returns [`None`] for [image collection](Self::is_image_collection) tilesets and
+    /// for IDs that are not below [`tilecount`](Self::tilecount).
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::{Loader, Rect};
+    /// # fn main() {
+    /// let tileset = Loader::new()
+    ///     .load_tsx_tileset("assets/tilesheet.tsx")
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     tileset.image_rect_for_tile(15),
+    ///     Some(Rect { x: 32, y: 32, width: 32, height: 32 })
+    /// );
+    /// # }
+    /// ```
+    pub fn image_rect_for_tile(&self, id: TileId) -> Option<Rect> {
+        let image = self.image.as_ref()?;
+        if id >= self.tilecount {
+            return None;
+        }
+        let columns = match self.columns {
+            0 => Self::calculate_columns(image, self.tile_width, self.margin, self.spacing),
+            columns => columns,
+        };
+        if columns == 0 {
+            return None;
+        }
+        Some(Rect {
+            x: self.margin + (id % columns) * (self.tile_width + self.spacing),
+            y: self.margin + (id / columns) * (self.tile_height + self.spacing),
+            width: self.tile_width,
+            height: self.tile_height,
+        })
+    }
+
+    /// Returns the first Wang set of this tileset with the given name, if any.
+    pub fn wang_set(&self, name: &str) -> Option<&WangSet> {
+        self.wang_sets.iter().find(|wang_set| wang_set.name == name)
+    }
+
+    /// Iterates through the tiles that have their own image, alongside said image. This is only
+    /// the case for [image collection](Self::is_image_collection) tilesets.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// # fn main() {
+    /// let tileset = Loader::new()
+    ///     .load_tsx_tileset("assets/tilesheet_image_collection.tsx")
+    ///     .unwrap();
+    ///
+    /// for (id, image) in tileset.image_collection_tiles() {
+    ///     println!("Tile {id} is {}x{}", image.width, image.height);
+    /// }
+    /// # }
+    /// ```
+    pub fn image_collection_tiles(&self) -> impl Iterator<Item = (TileId, &Image)> {
+        self.tiles
+            .iter()
+            .filter_map(|(id, data)| Some((*id, &**data.image.as_ref()?)))
+    }
+
+    /// Counts the contents of this tileset and estimates the memory it uses.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// # fn main() {
+    /// let tileset = Loader::new()
+    ///     .load_tsx_tileset("assets/tilesheet.tsx")
+    ///     .unwrap();
+    /// let stats = tileset.statistics();
+    ///
+    /// println!(
+    ///     "{} tiles with data, about {} bytes",
+    ///     stats.tiles, stats.heap_size
+    /// );
+    /// # }
+    /// ```
+    pub fn statistics(&self) -> TilesetStats {
+        let mut stats = TilesetStats {
+            tiles: self.tiles.len(),
+            wang_sets: self.wang_sets.len(),
+            heap_size: self.heap_size(),
+            ..Default::default()
+        };
+        for data in self.tiles.values() {
+            if let Some(frames) = &data.animation {
+                stats.animated_tiles += 1;
+                stats.animation_frames += frames.len();
+            }
+            if let Some(collision) = &data.collision {
+                stats.collision_objects += collision.object_data().len();
+            }
+        }
+        stats
+    }
+}
+
+impl HeapSize for Tileset {
+    fn heap_size(&self) -> usize {
+        self.name.heap_size()
+            + self.image.heap_size()
+            + self.tiles.heap_size()
+            + self.wang_sets.heap_size()
+            + self.properties.heap_size()
+            + self.user_type.heap_size()
+            + self.source.heap_size()
+            + self.version.heap_size()
+            + self.tiled_version.heap_size()
+    }
 }
 
 impl Tileset {
@@ -120,15 +514,16 @@ impl Tileset {
         read_from: &mut impl ReadFrom,
         cache: &mut impl ResourceCache,
     ) -> Result<EmbeddedParseResult> {
-        Tileset::parse_xml_embedded(parser, attrs, path, read_from, cache)
-            .await
-            .or_else(|err| {
-                if matches!(err, Error::MalformedAttributes(_)) {
-                    Tileset::parse_xml_reference(attrs, path)
-                } else {
-                    Err(err)
-                }
-            })
+        // External tilesets are only referenced through their source, while embedded ones have
+        // their contents right there.
+        if attrs
+            .iter()
+            .any(|attr| attr.key.local_name().into_inner() == b"source")
+        {
+            Tileset::parse_xml_reference(parser, attrs, path)
+        } else {
+            Tileset::parse_xml_embedded(parser, attrs, path, read_from, cache).await
+        }
     }
 
     async fn parse_xml_embedded<R: Reader>(
@@ -140,22 +535,25 @@ impl Tileset {
     ) -> Result<EmbeddedParseResult> {
         let (
             (spacing, margin, columns, name, user_type, user_class),
-            (tilecount, first_gid, tile_width, tile_height),
+            (tile_render_size, fill_mode, tilecount),
+            (first_gid, tile_width, tile_height),
         ) = get_attrs!(
-           for v in attrs {
+           for v in attrs of "tileset" {
+            Some("tilerendersize") => tile_render_size ?= v.parse::<TileRenderSize>(),
+            Some("fillmode") => fill_mode ?= v.parse::<FillMode>(),
             Some("spacing") => spacing ?= v.parse(),
             Some("margin") => margin ?= v.parse(),
             Some("columns") => columns ?= v.parse(),
+            Some("tilecount") => tilecount ?= v.parse::<u32>(),
             Some("name") => name = v,
             Some("type") => user_type ?= v.parse(),
             Some("class") => user_class ?= v.parse(),
 
-            "tilecount" => tilecount ?= v.parse::<u32>(),
             "firstgid" => first_gid ?= v.parse::<u32>().map(Gid),
             "tilewidth" => tile_width ?= v.parse::<u32>(),
             "tileheight" => tile_height ?= v.parse::<u32>(),
            }
-           ((spacing, margin, columns, name, user_type, user_class), (tilecount, first_gid, tile_width, tile_height))
+           ((spacing, margin, columns, name, user_type, user_class), (tile_render_size, fill_mode, tilecount), (first_gid, tile_width, tile_height))
         );
 
         let root_path = path.parent().ok_or(Error::PathIsNotFile)?.to_owned();
@@ -168,10 +566,15 @@ impl Tileset {
                 name: name.unwrap_or_default().to_string(),
                 user_type: user_type.or(user_class),
                 root_path,
+                source: None,
                 columns,
                 tilecount,
                 tile_height,
                 tile_width,
+                tile_render_size,
+                fill_mode,
+                version: None,
+                tiled_version: None,
             },
             read_from,
             cache,
@@ -183,20 +586,28 @@ impl Tileset {
         })
     }
 
-    fn parse_xml_reference(attrs: &[Attribute], map_path: &Path) -> Result<EmbeddedParseResult> {
+    fn parse_xml_reference<R: Reader>(
+        parser: &Parser<R>,
+        attrs: &[Attribute],
+        map_path: &Path,
+    ) -> Result<EmbeddedParseResult> {
         let (first_gid, source) = get_attrs!(
-            for v in attrs {
+            for v in attrs of "tileset" {
                 "firstgid" => first_gid ?= v.parse::<u32>().map(Gid),
                 "source" => source = v,
             }
             (first_gid, source)
         );
 
-        let tileset_path = map_path.parent().ok_or(Error::PathIsNotFile)?.join(source);
+        let directory = map_path.parent().ok_or(Error::PathIsNotFile)?;
+        let tileset_path = normalize_path(&parser.context.settings.resolve_path(directory, source));
 
         Ok(EmbeddedParseResult {
             first_gid,
-            result_type: EmbeddedParseResultType::ExternalReference { tileset_path },
+            result_type: EmbeddedParseResultType::ExternalReference {
+                tileset_path,
+                raw_source: source.to_owned(),
+            },
         })
     }
 
@@ -209,21 +620,26 @@ impl Tileset {
     ) -> Result<Tileset> {
         let (
             (spacing, margin, columns, name, user_type, user_class),
-            (tilecount, tile_width, tile_height),
+            (tile_render_size, fill_mode, version, tiled_version, tilecount),
+            (tile_width, tile_height),
         ) = get_attrs!(
-            for v in attrs {
+            for v in attrs of "tileset" {
+                Some("version") => version = v.to_owned(),
+                Some("tiledversion") => tiled_version = v.to_owned(),
+                Some("tilerendersize") => tile_render_size ?= v.parse::<TileRenderSize>(),
+                Some("fillmode") => fill_mode ?= v.parse::<FillMode>(),
                 Some("spacing") => spacing ?= v.parse(),
                 Some("margin") => margin ?= v.parse(),
                 Some("columns") => columns ?= v.parse(),
+                Some("tilecount") => tilecount ?= v.parse::<u32>(),
                 Some("name") => name = v,
                 Some("type") => user_type ?= v.parse(),
                 Some("class") => user_class ?= v.parse(),
 
-                "tilecount" => tilecount ?= v.parse::<u32>(),
                 "tilewidth" => tile_width ?= v.parse::<u32>(),
                 "tileheight" => tile_height ?= v.parse::<u32>(),
             }
-            ((spacing, margin, columns, name, user_type, user_class), (tilecount, tile_width, tile_height))
+            ((spacing, margin, columns, name, user_type, user_class), (tile_render_size, fill_mode, version, tiled_version, tilecount), (tile_width, tile_height))
         );
 
         let root_path = path.parent().ok_or(Error::PathIsNotFile)?.to_owned();
@@ -236,10 +652,15 @@ impl Tileset {
                 name: name.unwrap_or_default().to_string(),
                 user_type: user_type.or(user_class),
                 root_path,
+                source: Some(path.to_owned()),
                 columns,
                 tilecount,
                 tile_height,
                 tile_width,
+                tile_render_size,
+                fill_mode,
+                version,
+                tiled_version,
             },
             reader,
             cache,
@@ -254,21 +675,31 @@ impl Tileset {
         cache: &mut impl ResourceCache,
     ) -> Result<Tileset> {
         let mut image = Option::None;
-        let mut tiles = HashMap::with_capacity(prop.tilecount as usize);
+        let mut tiles = HashMap::new();
         let mut properties = HashMap::new();
         let mut wang_sets = Vec::new();
         let mut offset = (0i32, 0i32);
+        let mut transformations = None;
+        let mut grid = None;
 
         let mut buffer = Vec::new();
         parse_tag!(parser => &mut buffer, "tileset", {
             "image" => for attrs {
-                image = Some(Image::new(parser, attrs, &prop.root_path).await?);
+                image = Some(Image::new(parser, attrs, &prop.root_path, read_from).await?);
                 Ok(())
             },
             "tileoffset" => for attrs {
                 offset = parse_tileoffset(attrs)?;
                 Ok(())
             },
+            "transformations" => for attrs {
+                transformations = Some(parse_transformations(attrs)?);
+                Ok(())
+            },
+            "grid" => for attrs {
+                grid = Some(parse_grid(attrs)?);
+                Ok(())
+            },
             "properties" => {
                 properties = parse_properties(parser).await?;
                 Ok(())
@@ -288,24 +719,47 @@ impl Tileset {
         // A tileset is considered an image collection tileset if there is no image attribute (because its tiles do).
         let is_image_collection_tileset = image.is_none();
 
-        if !is_image_collection_tileset {
-            if prop.tile_width == 0 || prop.tile_height == 0 {
-                return Err(Error::InvalidTileset(
-                    InvalidTilesetError::InvalidTileDimensions,
-                ));
-            }
-
-            for tile_id in 0..prop.tilecount {
-                tiles.entry(tile_id).or_default();
-            }
+        if !is_image_collection_tileset && (prop.tile_width == 0 || prop.tile_height == 0) {
+            return Err(Error::InvalidTileset(
+                InvalidTilesetError::InvalidTileDimensions,
+            ));
         }
 
         let margin = prop.margin.unwrap_or(0);
         let spacing = prop.spacing.unwrap_or(0);
-        let columns = prop
-            .columns
-            .map(Ok)
-            .unwrap_or_else(|| Self::calculate_columns(&image, prop.tile_width, margin, spacing))?;
+        // The number of columns and rows of tiles that fit in the tileset image, if its dimensions
+        // are known
+        let grid_size = image
+            .as_deref()
+            .filter(|image| image.width > 0 && image.height > 0)
+            .map(|image| {
+                (
+                    Self::calculate_columns(image, prop.tile_width, margin, spacing),
+                    Self::calculate_rows(image, prop.tile_height, margin, spacing),
+                )
+            });
+        let (columns, tilecount) = match (&image, grid_size) {
+            // Image collection tilesets have no columns to speak of, and as many tiles as they
+            // define
+            (None, _) => (
+                prop.columns.unwrap_or(0),
+                prop.tilecount.unwrap_or(tiles.len() as u32),
+            ),
+            (Some(_), Some((columns, rows))) => {
+                let derived = columns * rows;
+                if let Some(declared) = prop.tilecount.filter(|&declared| declared != derived) {
+                    Self::report_tile_count_mismatch(parser, &prop.name, declared, derived)?;
+                }
+                (
+                    prop.columns.unwrap_or(columns),
+                    prop.tilecount.unwrap_or(derived),
+                )
+            }
+            (Some(_), None) => match (prop.columns, prop.tilecount) {
+                (Some(columns), Some(tilecount)) => (columns, tilecount),
+                _ => return Err(Error::InvalidTileset(InvalidTilesetError::MissingTileCount)),
+            },
+        };
 
         Ok(Tileset {
             name: prop.name,
@@ -317,38 +771,145 @@ impl Tileset {
             columns,
             offset_x: offset.0,
             offset_y: offset.1,
-            tilecount: prop.tilecount,
+            tile_render_size: prop.tile_render_size.unwrap_or_default(),
+            fill_mode: prop.fill_mode.unwrap_or_default(),
+            tilecount,
             image,
             tiles,
             wang_sets,
             properties,
+            transformations,
+            grid,
+            source: prop.source,
+            version: prop.version,
+            tiled_version: prop.tiled_version,
+            placeholder: false,
         })
     }
 
-    fn calculate_columns(
-        image: &Option<Image>,
-        tile_width: u32,
-        margin: u32,
-        spacing: u32,
-    ) -> Result<u32> {
-        image
-            .as_ref()
-            .map(|image| (image.width as u32 - margin + spacing) / (tile_width + spacing))
-            .ok_or_else(|| {
-                Error::MalformedAttributes(
-                    "No <image> nor columns attribute in <tileset>".to_string(),
-                )
-            })
+    /// Creates an empty tileset to stand in for an external tileset that could not be loaded.
+    pub(crate) fn placeholder(path: &Path, tile_width: u32, tile_height: u32) -> Tileset {
+        Tileset {
+            name: path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            tile_width,
+            tile_height,
+            spacing: 0,
+            margin: 0,
+            tilecount: 0,
+            columns: 0,
+            offset_x: 0,
+            offset_y: 0,
+            tile_render_size: TileRenderSize::default(),
+            fill_mode: FillMode::default(),
+            image: None,
+            tiles: HashMap::new(),
+            wang_sets: Vec::new(),
+            properties: HashMap::new(),
+            user_type: None,
+            transformations: None,
+            grid: None,
+            source: Some(path.to_owned()),
+            version: None,
+            tiled_version: None,
+            placeholder: true,
+        }
+    }
+
+    /// Fails or records a warning because the declared tile count of a tileset disagrees with
+    /// the one derived from its image, depending on the strictness of the parser. Tiled itself
+    /// tolerates this, so it only fails with [`Strictness::Strict`].
+    fn report_tile_count_mismatch<R>(
+        parser: &mut Parser<R>,
+        tileset_name: &str,
+        declared: u32,
+        derived: u32,
+    ) -> Result<()> {
+        let tileset_name = tileset_name.to_owned();
+        match parser.context.settings.strictness {
+            Strictness::Strict => {
+                return Err(Error::TileCountMismatch {
+                    tileset_name,
+                    declared,
+                    derived,
+                })
+            }
+            Strictness::Error | Strictness::Warn => {
+                parser
+                    .context
+                    .state
+                    .warnings
+                    .push(ParseWarning::TileCountMismatch {
+                        tileset_name,
+                        declared,
+                        derived,
+                    })
+            }
+            Strictness::Ignore => {}
+        }
+        Ok(())
+    }
+
+    fn calculate_columns(image: &Image, tile_width: u32, margin: u32, spacing: u32) -> u32 {
+        (image.width as u32)
+            .saturating_sub(2 * margin)
+            .saturating_add(spacing)
+            / (tile_width + spacing)
+    }
+
+    fn calculate_rows(image: &Image, tile_height: u32, margin: u32, spacing: u32) -> u32 {
+        (image.height as u32)
+            .saturating_sub(2 * margin)
+            .saturating_add(spacing)
+            / (tile_height + spacing)
     }
 }
 
 /// Parse the optional <tileoffset x=... y=.../> tag.
 fn parse_tileoffset(attrs: Vec<Attribute>) -> Result<(i32, i32)> {
     Ok(get_attrs!(
-        for v in attrs {
+        for v in attrs of "tileoffset" {
             "x" => offset_x ?= v.parse::<i32>(),
             "y" => offset_y ?= v.parse::<i32>(),
         }
         (offset_x, offset_y)
     ))
 }
+
+/// Parse the optional <transformations hflip=... vflip=... rotate=... preferuntransformed=.../> tag.
+fn parse_transformations(attrs: Vec<Attribute>) -> Result<Transformations> {
+    let (hflip, vflip, rotate, prefer_untransformed) = get_attrs!(
+        for v in attrs of "transformations" {
+            Some("hflip") => hflip ?= v.parse().map(|x: i32| x == 1),
+            Some("vflip") => vflip ?= v.parse().map(|x: i32| x == 1),
+            Some("rotate") => rotate ?= v.parse().map(|x: i32| x == 1),
+            Some("preferuntransformed") => prefer_untransformed ?= v.parse().map(|x: i32| x == 1),
+        }
+        (hflip, vflip, rotate, prefer_untransformed)
+    );
+    Ok(Transformations {
+        hflip: hflip.unwrap_or(false),
+        vflip: vflip.unwrap_or(false),
+        rotate: rotate.unwrap_or(false),
+        prefer_untransformed: prefer_untransformed.unwrap_or(false),
+    })
+}
+
+/// Parse the optional <grid orientation=... width=... height=.../> tag.
+fn parse_grid(attrs: Vec<Attribute>) -> Result<Grid> {
+    let (orientation, width, height) = get_attrs!(
+        for v in attrs of "grid" {
+            Some("orientation") => orientation ?= v.parse::<Orientation>(),
+            "width" => width ?= v.parse::<u32>(),
+            "height" => height ?= v.parse::<u32>(),
+        }
+        (orientation, width, height)
+    );
+    Ok(Grid {
+        orientation: orientation.unwrap_or(Orientation::Orthogonal),
+        width,
+        height,
+    })
+}
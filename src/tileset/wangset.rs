@@ -16,6 +16,7 @@ pub use wang_tile::*;
 
 /// Wang set's terrain brush connection type.
 #[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum WangSetType {
     Corner,
@@ -24,8 +25,23 @@ pub enum WangSetType {
     Mixed,
 }
 
+impl WangSetType {
+    /// Whether the [corners](WangId::corner) of the Wang IDs of this type of set are used.
+    #[inline]
+    pub fn uses_corners(&self) -> bool {
+        matches!(self, WangSetType::Corner | WangSetType::Mixed)
+    }
+
+    /// Whether the [edges](WangId::edge) of the Wang IDs of this type of set are used.
+    #[inline]
+    pub fn uses_edges(&self) -> bool {
+        matches!(self, WangSetType::Edge | WangSetType::Mixed)
+    }
+}
+
 /// Raw data belonging to a WangSet.
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WangSet {
     /// The name of the Wang set.
     pub name: String,
@@ -39,9 +55,71 @@ pub struct WangSet {
     pub wang_tiles: HashMap<TileId, WangTile>,
     /// The custom properties of this Wang set.
     pub properties: Properties,
+    /// The class of this Wang set, arbitrarily set by the user.
+    pub user_type: Option<String>,
 }
 
 impl WangSet {
+    /// Returns the first color of this set with the given name, alongside its index in the
+    /// [Wang IDs](WangId) of the set's tiles, which starts at 1 since 0 means "no color".
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// # fn main() {
+    /// # let tileset = Loader::new()
+    /// #     .load_tsx_tileset("assets/tilesheet_wangsets.tsx")
+    /// #     .unwrap();
+    /// let wall = tileset.wang_set("Wall").unwrap();
+    /// let (dark, _) = wall.color("Dark").unwrap();
+    ///
+    /// for (id, _) in wall.tiles_matching(|wang_id| wang_id.top_right() == dark) {
+    ///     println!("{id}");
+    /// }
+    /// # }
+    /// ```
+    pub fn color(&self, name: &str) -> Option<(u8, &WangColor)> {
+        self.wang_colors
+            .iter()
+            .enumerate()
+            .find(|(_, color)| color.name == name)
+            .map(|(index, color)| (index as u8 + 1, color))
+    }
+
+    /// Returns the Wang ID of the tile with the given ID, if it is part of this Wang set.
+    #[inline]
+    pub fn wang_id_of(&self, tile_id: TileId) -> Option<WangId> {
+        self.wang_tiles.get(&tile_id).map(|tile| tile.wang_id)
+    }
+
+    /// Iterates through the Wang tiles of this set whose Wang ID matches the given predicate, in
+    /// no particular order.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// # fn main() {
+    /// # let map = Loader::new()
+    /// #     .load_tmx_map("assets/tiled_csv_wangsets.tmx")
+    /// #     .unwrap();
+    /// let wang_set = &map.tilesets()[0].wang_sets[1];
+    ///
+    /// // Tiles that have the second color on their top right corner
+    /// for (id, _) in wang_set.tiles_matching(|wang_id| wang_id.top_right() == 2) {
+    ///     println!("{id}");
+    /// }
+    /// # }
+    /// ```
+    pub fn tiles_matching(
+        &self,
+        pattern: impl Fn(&WangId) -> bool,
+    ) -> impl Iterator<Item = (TileId, &WangTile)> {
+        self.wang_tiles
+            .iter()
+            .filter(move |(_, tile)| pattern(&tile.wang_id))
+            .map(|(id, tile)| (*id, tile))
+    }
+
     /// Reads data from XML parser to create a WangSet.
     // FIXME: was public before
     pub(crate) async fn new<R: Reader>(
@@ -49,13 +127,14 @@ impl WangSet {
         attrs: Vec<Attribute<'_>>,
     ) -> Result<WangSet> {
         // Get common data
-        let (name, wang_set_type, tile) = get_attrs!(
-            for v in attrs {
+        let (user_type, (name, wang_set_type, tile)) = get_attrs!(
+            for v in attrs of "wangset" {
+                Some("class") => user_type ?= v.parse::<String>(),
                 "name" => name ?= v.parse::<String>(),
                 "type" => wang_set_type ?= v.parse::<String>(),
                 "tile" => tile ?= v.parse::<i64>(),
             }
-            (name, wang_set_type, tile)
+            (user_type, (name, wang_set_type, tile))
         );
 
         let wang_set_type = match wang_set_type.as_str() {
@@ -95,6 +174,7 @@ impl WangSet {
             wang_colors,
             wang_tiles,
             properties,
+            user_type,
         })
     }
 }
@@ -12,6 +12,7 @@ use crate::{
 
 /// Stores the data of the Wang color.
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WangColor {
     /// The name of this color.
     pub name: String,
@@ -23,6 +24,8 @@ pub struct WangColor {
     pub probability: f32,
     /// The custom properties of this color.
     pub properties: Properties,
+    /// The class of this color, arbitrarily set by the user.
+    pub user_type: Option<String>,
 }
 
 impl WangColor {
@@ -33,14 +36,15 @@ impl WangColor {
         attrs: Vec<Attribute<'_>>,
     ) -> Result<WangColor> {
         // Get common data
-        let (name, color, tile, probability) = get_attrs!(
-            for v in attrs {
+        let (user_type, (name, color, tile, probability)) = get_attrs!(
+            for v in attrs of "wangcolor" {
+                Some("class") => user_type ?= v.parse::<String>(),
                 "name" => name ?= v.parse::<String>(),
                 "color" => color ?= v.parse(),
                 "tile" => tile ?= v.parse::<i64>(),
                 "probability" => probability ?= v.parse::<f32>(),
             }
-            (name, color, tile, probability)
+            (user_type, (name, color, tile, probability))
         );
 
         let tile = if tile >= 0 { Some(tile as u32) } else { None };
@@ -60,6 +64,7 @@ impl WangColor {
             tile,
             probability,
             properties,
+            user_type,
         })
     }
 }
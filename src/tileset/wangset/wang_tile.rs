@@ -5,9 +5,127 @@ use quick_xml::events::attributes::Attribute;
 use crate::{error::Error, util::get_attrs, Result, TileId};
 
 /// The Wang ID, stored as an array of 8 u8 values.
-#[derive(Debug, PartialEq, Clone, Copy)]
+///
+/// Each value is the index of a [`WangColor`](crate::WangColor) in the Wang set, starting from 1,
+/// or 0 if the position has no color. The positions are ordered clockwise starting from the top
+/// edge, so edges have even indices and corners have odd ones:
+///
+/// | Index | Position     | Edge or corner index |
+/// |-------|--------------|----------------------|
+/// | 0     | Top          | Edge 0               |
+/// | 1     | Top right    | Corner 0             |
+/// | 2     | Right        | Edge 1               |
+/// | 3     | Bottom right | Corner 1             |
+/// | 4     | Bottom       | Edge 2               |
+/// | 5     | Bottom left  | Corner 2             |
+/// | 6     | Left         | Edge 3               |
+/// | 7     | Top left     | Corner 3             |
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WangId(pub [u8; 8]);
 
+impl WangId {
+    /// Returns whether the given position index is a corner rather than an edge.
+    #[inline]
+    pub fn is_corner_index(index: usize) -> bool {
+        index % 2 == 1
+    }
+
+    /// The color of the given corner, clockwise starting from the top right one (0 to 3).
+    ///
+    /// ## Panics
+    /// Panics if `index` is 4 or greater.
+    #[inline]
+    pub fn corner(&self, index: usize) -> u8 {
+        assert!(index < 4, "corner index out of range: {}", index);
+        self.0[index * 2 + 1]
+    }
+
+    /// The color of the given edge, clockwise starting from the top one (0 to 3).
+    ///
+    /// ## Panics
+    /// Panics if `index` is 4 or greater.
+    #[inline]
+    pub fn edge(&self, index: usize) -> u8 {
+        assert!(index < 4, "edge index out of range: {}", index);
+        self.0[index * 2]
+    }
+
+    /// The color of the top edge.
+    #[inline]
+    pub fn top(&self) -> u8 {
+        self.0[0]
+    }
+
+    /// The color of the top right corner.
+    #[inline]
+    pub fn top_right(&self) -> u8 {
+        self.0[1]
+    }
+
+    /// The color of the right edge.
+    #[inline]
+    pub fn right(&self) -> u8 {
+        self.0[2]
+    }
+
+    /// The color of the bottom right corner.
+    #[inline]
+    pub fn bottom_right(&self) -> u8 {
+        self.0[3]
+    }
+
+    /// The color of the bottom edge.
+    #[inline]
+    pub fn bottom(&self) -> u8 {
+        self.0[4]
+    }
+
+    /// The color of the bottom left corner.
+    #[inline]
+    pub fn bottom_left(&self) -> u8 {
+        self.0[5]
+    }
+
+    /// The color of the left edge.
+    #[inline]
+    pub fn left(&self) -> u8 {
+        self.0[6]
+    }
+
+    /// The color of the top left corner.
+    #[inline]
+    pub fn top_left(&self) -> u8 {
+        self.0[7]
+    }
+
+    /// Returns the Wang ID of a tile rotated clockwise by the given number of 90 degree steps.
+    /// Negative values rotate counterclockwise.
+    pub fn rotated(&self, rotations: i32) -> WangId {
+        let shift = rotations.rem_euclid(4) as usize * 2;
+        let mut ret = [0u8; 8];
+        for (i, color) in self.0.iter().enumerate() {
+            ret[(i + shift) % 8] = *color;
+        }
+        WangId(ret)
+    }
+
+    /// Returns the Wang ID of a tile flipped horizontally, i.e. mirrored along the vertical axis.
+    pub fn flipped_horizontally(&self) -> WangId {
+        let mut ret = [0u8; 8];
+        for (i, color) in self.0.iter().enumerate() {
+            ret[(8 - i) % 8] = *color;
+        }
+        WangId(ret)
+    }
+
+    /// Returns the Wang ID of a tile flipped vertically, i.e. mirrored along the horizontal axis.
+    pub fn flipped_vertically(&self) -> WangId {
+        // Same as Tiled, which flips horizontally and then rotates by 180 degrees
+        self.flipped_horizontally().rotated(2)
+    }
+}
+
 impl FromStr for WangId {
     type Err = Error;
 
@@ -32,24 +150,51 @@ impl FromStr for WangId {
 }
 
 /// Stores the Wang ID.
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WangTile {
     #[allow(missing_docs)]
     pub wang_id: WangId,
+    /// Whether this tile is flipped horizontally. Only set if the Wang set allows flipping.
+    pub hflip: bool,
+    /// Whether this tile is flipped vertically. Only set if the Wang set allows flipping.
+    pub vflip: bool,
+    /// Whether this tile is flipped anti-diagonally. Only set if the Wang set allows flipping.
+    pub dflip: bool,
 }
 
 impl WangTile {
     /// Reads data from XML parser to create a WangTile.
     pub(crate) fn new(attrs: Vec<Attribute>) -> Result<(TileId, WangTile)> {
         // Get common data
-        let (tile_id, wang_id) = get_attrs!(
-            for v in attrs {
+        let ((hflip, vflip, dflip), (tile_id, wang_id)) = get_attrs!(
+            for v in attrs of "wangtile" {
+                Some("hflip") => hflip ?= parse_flip(v),
+                Some("vflip") => vflip ?= parse_flip(v),
+                Some("dflip") => dflip ?= parse_flip(v),
                 "tileid" => tile_id ?= v.parse::<u32>(),
                 "wangid" => wang_id ?= v.parse(),
             }
-            (tile_id, wang_id)
+            ((hflip, vflip, dflip), (tile_id, wang_id))
         );
 
-        Ok((tile_id, WangTile { wang_id }))
+        Ok((
+            tile_id,
+            WangTile {
+                wang_id,
+                hflip: hflip.unwrap_or(false),
+                vflip: vflip.unwrap_or(false),
+                dflip: dflip.unwrap_or(false),
+            },
+        ))
+    }
+}
+
+/// Parses a flip flag, which older versions of Tiled wrote as `true`/`false` instead of `1`/`0`.
+fn parse_flip(v: &str) -> std::result::Result<bool, ()> {
+    match v {
+        "1" | "true" => Ok(true),
+        "0" | "false" => Ok(false),
+        _ => Err(()),
     }
 }
@@ -4,14 +4,16 @@
 /// The syntax is:
 /// ```ignore
 /// get_attrs!(
-///     for $attr in $attributes {
+///     for $attr in $attributes of $element {
 ///         $($branch),*
 ///     }
 ///     $expression_to_return
 /// )
 /// ```
 /// Where `$attributes` is anything that implements `Iterator<Item = OwnedAttribute>`,
-/// and `$attr` is the value of the attribute (a String) going to be used in each branch.
+/// `$attr` is the value of the attribute (a String) going to be used in each branch and
+/// `$element` is the name of the element the attributes belong to (a `&'static str`), which is
+/// reported in errors.
 ///
 /// Each branch indicates a variable to be set once a certain attribute is found.
 /// Its syntax is as follows:
@@ -32,10 +34,13 @@
 /// ```ignore
 /// Some("name") => name = v,
 /// ```
+/// Attributes that aren't optional are required, and an
+/// [`Error::MissingAttribute`](crate::Error::MissingAttribute) is returned if one is missing.
 ///
 /// Finally, branches can also use `?=` instead of `=`, which will make them accept a `Result<T, E>`
-/// instead. If the expression results in an Err, the error will be handled internally and the
-/// iteration will return early with a `Result<T, crate::Error>`.
+/// instead. If the expression results in an Err, the iteration will return early with an
+/// [`Error::MalformedAttribute`](crate::Error::MalformedAttribute). The error type must implement
+/// [`AttrParseError`].
 ///
 /// Here are some examples of valid branches:
 /// ```ignore
@@ -55,7 +60,7 @@
 /// ## Example
 /// ```ignore
 /// let ((c, infinite), (v, o, w, h, tw, th)) = get_attrs!(
-///     for v in attrs {
+///     for v in attrs of "map" {
 ///         Some("backgroundcolor") => colour ?= v.parse(),
 ///         Some("infinite") => infinite = v == "1",
 ///         "version" => version = v,
@@ -70,7 +75,7 @@
 /// ```
 macro_rules! get_attrs {
     (
-        for $attr:ident in $attrs:ident {
+        for $attr:ident in $attrs:ident of $element:tt {
             $($branches:tt)*
         }
         $ret_expr:expr
@@ -82,10 +87,10 @@ macro_rules! get_attrs {
                 let $attr = std::str::from_utf8(&attr.value).map_err(|err| {
                     $crate::error::Error::XmlDecodingError(quick_xml::Error::NonDecodable(Some(err)))
                 })?;
-                $crate::util::process_attr_branches!(attr; $($branches)*);
+                $crate::util::process_attr_branches!(attr, $attr, $element; $($branches)*);
             }
 
-            $crate::util::handle_attr_branches!($($branches)*);
+            $crate::util::handle_attr_branches!($element; $($branches)*);
 
             $ret_expr
         }
@@ -109,49 +114,51 @@ macro_rules! let_attr_branches {
 pub(crate) use let_attr_branches;
 
 macro_rules! process_attr_branches {
-    ($attr:ident; ) => {};
+    ($attr:ident, $value:ident, $element:tt; ) => {};
 
-    ($attr:ident; Some($attr_pat_opt:literal) => $opt_var:ident = $opt_expr:expr $(, $($tail:tt)*)?) => {
+    ($attr:ident, $value:ident, $element:tt; Some($attr_pat_opt:literal) => $opt_var:ident = $opt_expr:expr $(, $($tail:tt)*)?) => {
         if($attr.key.local_name().into_inner() == $attr_pat_opt.as_bytes()) {
             $opt_var = Some($opt_expr);
         }
         else {
-            $crate::util::process_attr_branches!($attr; $($($tail)*)?);
+            $crate::util::process_attr_branches!($attr, $value, $element; $($($tail)*)?);
         }
     };
 
-    ($attr:ident; Some($attr_pat_opt:literal) => $opt_var:ident ?= $opt_expr:expr $(, $($tail:tt)*)?) => {
+    ($attr:ident, $value:ident, $element:tt; Some($attr_pat_opt:literal) => $opt_var:ident ?= $opt_expr:expr $(, $($tail:tt)*)?) => {
         if($attr.key.local_name().into_inner() == $attr_pat_opt.as_bytes()) {
-            $opt_var = Some($opt_expr.map_err(|_|
-                $crate::Error::MalformedAttributes(
-                    concat!("Error parsing optional attribute '", $attr_pat_opt, "'").to_owned()
-                )
-            )?);
+            $opt_var = Some($opt_expr.map_err(|err| $crate::Error::MalformedAttribute {
+                element: $element,
+                attribute: $attr_pat_opt,
+                value: $value.to_owned(),
+                source: $crate::util::AttrParseError::into_source(err),
+            })?);
         }
         else {
-            $crate::util::process_attr_branches!($attr; $($($tail)*)?);
+            $crate::util::process_attr_branches!($attr, $value, $element; $($($tail)*)?);
         }
     };
 
-    ($attr:ident; $attr_pat_opt:literal => $opt_var:ident = $opt_expr:expr $(, $($tail:tt)*)?) => {
+    ($attr:ident, $value:ident, $element:tt; $attr_pat_opt:literal => $opt_var:ident = $opt_expr:expr $(, $($tail:tt)*)?) => {
         if($attr.key.local_name().into_inner() == $attr_pat_opt.as_bytes()) {
             $opt_var = Some($opt_expr);
         }
         else {
-            $crate::util::process_attr_branches!($attr; $($($tail)*)?);
+            $crate::util::process_attr_branches!($attr, $value, $element; $($($tail)*)?);
         }
     };
 
-    ($attr:ident; $attr_pat_opt:literal => $opt_var:ident ?= $opt_expr:expr $(, $($tail:tt)*)?) => {
+    ($attr:ident, $value:ident, $element:tt; $attr_pat_opt:literal => $opt_var:ident ?= $opt_expr:expr $(, $($tail:tt)*)?) => {
         if($attr.key.local_name().into_inner() == $attr_pat_opt.as_bytes()) {
-            $opt_var = Some($opt_expr.map_err(|_|
-                $crate::Error::MalformedAttributes(
-                    concat!("Error parsing attribute '", $attr_pat_opt, "'").to_owned()
-                )
-            )?);
+            $opt_var = Some($opt_expr.map_err(|err| $crate::Error::MalformedAttribute {
+                element: $element,
+                attribute: $attr_pat_opt,
+                value: $value.to_owned(),
+                source: $crate::util::AttrParseError::into_source(err),
+            })?);
         }
         else {
-            $crate::util::process_attr_branches!($attr; $($($tail)*)?);
+            $crate::util::process_attr_branches!($attr, $value, $element; $($($tail)*)?);
         }
     }
 }
@@ -159,44 +166,83 @@ macro_rules! process_attr_branches {
 pub(crate) use process_attr_branches;
 
 macro_rules! handle_attr_branches {
-    () => {};
+    ($element:tt; ) => {};
 
-    (Some($attr_pat_opt:literal) => $opt_var:ident $(?)?= $opt_expr:expr $(, $($tail:tt)*)?) => {
-        $crate::util::handle_attr_branches!($($($tail)*)?);
+    ($element:tt; Some($attr_pat_opt:literal) => $opt_var:ident $(?)?= $opt_expr:expr $(, $($tail:tt)*)?) => {
+        $crate::util::handle_attr_branches!($element; $($($tail)*)?);
     };
 
-    ($attr_pat_opt:literal => $opt_var:ident $(?)?= $opt_expr:expr $(, $($tail:tt)*)?) => {
+    ($element:tt; $attr_pat_opt:literal => $opt_var:ident $(?)?= $opt_expr:expr $(, $($tail:tt)*)?) => {
         let $opt_var = $opt_var
-            .ok_or_else(||
-                Error::MalformedAttributes(
-                    concat!("Missing attribute: ", $attr_pat_opt).to_owned()
-                )
-            )?;
+            .ok_or($crate::Error::MissingAttribute {
+                element: $element,
+                attribute: $attr_pat_opt,
+            })?;
 
-        $crate::util::handle_attr_branches!($($($tail)*)?);
+        $crate::util::handle_attr_branches!($element; $($($tail)*)?);
     };
 }
 
 pub(crate) use handle_attr_branches;
 
+/// An error returned by the expression of a `?=` branch of [`get_attrs`], which becomes the
+/// source of the resulting [`Error::MalformedAttribute`](crate::Error::MalformedAttribute).
+pub(crate) trait AttrParseError {
+    fn into_source(self) -> Option<Box<dyn std::error::Error + Send + Sync + 'static>>;
+}
+
+impl AttrParseError for () {
+    fn into_source(self) -> Option<Box<dyn std::error::Error + Send + Sync + 'static>> {
+        None
+    }
+}
+
+macro_rules! impl_attr_parse_error {
+    ($($ty:ty),*) => {
+        $(
+            impl AttrParseError for $ty {
+                fn into_source(self) -> Option<Box<dyn std::error::Error + Send + Sync + 'static>> {
+                    Some(Box::new(self))
+                }
+            }
+        )*
+    };
+}
+
+impl_attr_parse_error!(
+    std::convert::Infallible,
+    std::num::ParseIntError,
+    std::num::ParseFloatError,
+    crate::Error,
+    crate::DrawOrderError,
+    crate::FillModeError,
+    crate::OrientationParseError,
+    crate::StaggerAxisError,
+    crate::StaggerIndexError,
+    crate::TileRenderSizeError
+);
+
 /// Goes through the children of the tag and will call the correct function for
 /// that child. Closes the tag.
 macro_rules! parse_tag {
-    (@match_next $next:expr, $close_tag:expr, {$($open_tag:expr => $( for $attrs:ident )? $body:block),* $(,)*}) => {
+    (@match_next $next:expr, $close_tag:expr, {$($open_tag:expr => $( for $attrs:ident )? $body:block),* $(,)*} $(, else $other:ident => $else_body:block)?) => {
         match $next {
             #[allow(unused_variables)]
             quick_xml::events::Event::Start(start) | quick_xml::events::Event::Empty(start) => {
                 $(
                     if start.local_name().into_inner() == $open_tag.as_bytes() {
                         $(
-                            let $attrs = start
-                                .attributes()
-                                .collect::<std::result::Result<std::vec::Vec<_>, _>>()
-                                .map_err(|err| $crate::Error::XmlDecodingError(err.into()))?;
+                            let $attrs = $crate::util::collect_attributes(&start)?;
                         )?
                         $body?
-                    }
+                    } else
                 )*
+                {
+                    $(
+                        let $other = &start;
+                        $else_body?;
+                    )?
+                }
             }
 
             quick_xml::events::Event::End(end) if end.local_name().into_inner() == $close_tag.as_bytes() => {
@@ -227,6 +273,16 @@ macro_rules! parse_tag {
                 parse_tag!(@match_next next, $close_tag, { $($open_tag => $( for $attrs )? $body, )? })
             }
         }
+    };
+
+    // Also calls the `else` body with the start of every other element
+    ($parser:expr => $buf:expr, $close_tag:expr, {$($open_tag:expr => $( for $attrs:ident )? $body:block),* $(,)*}, else $other:ident => $else_body:block) => {
+        if !$parser.last_event_was_empty {
+            loop {
+                let next: quick_xml::events::Event = $parser.read_event_into($buf).await.map_err(Error::XmlDecodingError)?;
+                parse_tag!(@match_next next, $close_tag, { $($open_tag => $( for $attrs )? $body, )* }, else $other => $else_body)
+            }
+        }
     }
 }
 
@@ -268,27 +324,168 @@ pub(crate) use get_attrs;
 pub(crate) use map_wrapper;
 pub(crate) use parse_tag;
 
-use crate::{Gid, MapTilesetGid};
+use std::borrow::Cow;
+use std::path::{Component, Path, PathBuf};
+
+use quick_xml::events::{attributes::Attribute, BytesStart};
+
+/// Collects the attributes of an element, replacing the entity and character references in their
+/// values by the characters they stand for.
+///
+/// Values with references that can't be resolved are kept as they are written in the file.
+pub(crate) fn collect_attributes<'a>(start: &'a BytesStart) -> crate::Result<Vec<Attribute<'a>>> {
+    start
+        .attributes()
+        .map(|attr| {
+            let attr = attr.map_err(|err| crate::Error::XmlDecodingError(err.into()))?;
+            if !attr.value.contains(&b'&') {
+                return Ok(attr);
+            }
+            let unescaped = std::str::from_utf8(&attr.value)
+                .ok()
+                .and_then(|value| quick_xml::escape::unescape(value).ok())
+                .map(|value| value.into_owned().into_bytes());
+            let value = match unescaped {
+                Some(value) => Cow::Owned(value),
+                None => attr.value,
+            };
+            Ok(Attribute {
+                key: attr.key,
+                value,
+            })
+        })
+        .collect()
+}
+
+/// Lexically normalizes a path by resolving its `.` and `..` components without touching the
+/// filesystem, so that the same resource referenced through different relative paths is only
+/// cached once.
+///
+/// Leading `..` components of relative paths are kept as is, since there is nothing to resolve
+/// them against.
+pub(crate) fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                // `..` at the root is the root itself
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => {}
+                _ => normalized.push(component),
+            },
+            _ => normalized.push(component),
+        }
+    }
+    normalized
+}
 
-/// Returns both the tileset and its index
-pub(crate) fn get_tileset_for_gid(
-    tilesets: &[MapTilesetGid],
-    gid: Gid,
-) -> Option<(usize, &MapTilesetGid)> {
-    tilesets
+/// Turns the `source` attribute of a reference found in a file inside `directory` into the path of
+/// the file it refers to.
+///
+/// Relative sources are joined to `directory`, while absolute ones, including `file://` URIs, are
+/// used as is unless they start with one of the `absolute_prefixes`, which is then replaced.
+/// Backslashes are treated as separators, since Tiled on Windows may write them.
+pub(crate) fn source_to_path(
+    directory: &Path,
+    source: &str,
+    absolute_prefixes: &[(String, PathBuf)],
+) -> PathBuf {
+    let source = match source.strip_prefix("file://") {
+        Some(uri_path) => file_uri_to_path(uri_path),
+        None => source.to_owned(),
+    };
+    let source = if cfg!(windows) {
+        source
+    } else {
+        source.replace('\\', "/")
+    };
+
+    if !is_absolute_source(&source) {
+        return directory.join(source);
+    }
+    absolute_prefixes
         .iter()
-        .enumerate()
-        .rev()
-        .find(|(_idx, ts)| ts.first_gid <= gid)
+        .find_map(|(from, to)| strip_source_prefix(&source, from).map(|rest| to.join(rest)))
+        .unwrap_or_else(|| PathBuf::from(source))
 }
 
-pub fn floor_div(a: i32, b: i32) -> i32 {
-    let d = a / b;
-    let r = a % b;
+/// Converts the part of a `file://` URI after the scheme into a path.
+fn file_uri_to_path(uri_path: &str) -> String {
+    let uri_path = uri_path.strip_prefix("localhost").unwrap_or(uri_path);
+    let path = percent_decode(uri_path);
+    let bytes = path.as_bytes();
+    if bytes.len() >= 3 && bytes[0] == b'/' && is_drive(&path[1..]) {
+        // `file:///C:/...`
+        path[1..].to_owned()
+    } else if path.starts_with('/') {
+        path
+    } else {
+        // `file://server/share/...`
+        format!("//{}", path)
+    }
+}
+
+fn percent_decode(s: &str) -> String {
+    let mut decoded = Vec::with_capacity(s.len());
+    let mut bytes = s.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte == b'%' {
+            let hex = bytes.clone().take(2).collect::<Vec<_>>();
+            let value = std::str::from_utf8(&hex)
+                .ok()
+                .filter(|hex| hex.len() == 2)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            if let Some(value) = value {
+                decoded.push(value);
+                bytes.nth(1);
+                continue;
+            }
+        }
+        decoded.push(byte);
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
 
-    if r == 0 {
-        d
+/// Whether a path starts with a Windows drive, such as `C:`.
+fn is_drive(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && matches!(bytes.get(2), None | Some(b'/') | Some(b'\\'))
+}
+
+/// Whether a source is an absolute path, on any platform.
+fn is_absolute_source(source: &str) -> bool {
+    source.starts_with('/') || source.starts_with('\\') || is_drive(source)
+}
+
+/// Returns the rest of `source` if it starts with the path `prefix`, comparing whole components.
+/// Drive letters are compared case-insensitively.
+fn strip_source_prefix<'a>(source: &'a str, prefix: &str) -> Option<&'a str> {
+    let is_separator = |c: char| c == '/' || c == '\\';
+    let prefix = prefix.trim_end_matches(is_separator);
+    let mut source_chars = source.char_indices();
+    for (index, prefix_char) in prefix.chars().enumerate() {
+        let (_, source_char) = source_chars.next()?;
+        let matches = if is_separator(prefix_char) {
+            is_separator(source_char)
+        } else if index == 0 && is_drive(prefix) {
+            source_char.eq_ignore_ascii_case(&prefix_char)
+        } else {
+            source_char == prefix_char
+        };
+        if !matches {
+            return None;
+        }
+    }
+    let rest = &source[prefix.len()..];
+    if rest.is_empty() || rest.starts_with(is_separator) {
+        Some(rest.trim_start_matches(is_separator))
     } else {
-        d - ((a < 0) ^ (b < 0)) as i32
+        None
     }
 }
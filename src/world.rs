@@ -0,0 +1,232 @@
+//! Structures related to Tiled world files.
+
+use std::{
+    convert::TryFrom,
+    path::{Path, PathBuf},
+};
+
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::{util::normalize_path, Error, Loader, Map, ResourceCache, ResourceReader, Result};
+
+/// A Tiled world, which places several maps relative to each other.
+///
+/// Worlds are loaded from `.world` files through [`Loader::load_world`]. Their maps aren't loaded
+/// along with them; Use [`World::load_maps`] for that.
+///
+/// Also see the [TMX docs](https://doc.mapeditor.org/en/stable/manual/worlds/).
+#[derive(Debug, Clone)]
+pub struct World {
+    /// The path the world was loaded from.
+    pub source: PathBuf,
+    /// The maps in this world, including the ones found by matching its [`patterns`] against the
+    /// files next to the world file.
+    ///
+    /// [`patterns`]: Self::patterns
+    pub maps: Vec<WorldMapRef>,
+    /// The patterns used to automatically place maps in this world based on their file names.
+    pub patterns: Vec<WorldPattern>,
+    /// Whether Tiled only shows the maps adjacent to the current one when editing this world.
+    pub only_show_adjacent_maps: bool,
+}
+
+/// A reference to a map in a [`World`], along with where it is placed.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WorldMapRef {
+    /// The path to the map file, relative to the working directory rather than to the world file.
+    pub path: PathBuf,
+    /// The X position of the map in the world, in pixels.
+    pub x: i32,
+    /// The Y position of the map in the world, in pixels.
+    pub y: i32,
+    /// The width of the map in pixels, if specified by the world.
+    pub width: Option<u32>,
+    /// The height of the map in pixels, if specified by the world.
+    pub height: Option<u32>,
+}
+
+/// A pattern that places every map whose file name matches it in a [`World`].
+///
+/// The regular expression must contain two capture groups, which are parsed as the X and Y
+/// coordinates of the map in the pattern's grid.
+#[derive(Debug, Clone)]
+pub struct WorldPattern {
+    /// The regular expression file names are matched against.
+    pub regexp: Regex,
+    /// The horizontal distance in pixels between two consecutive X coordinates.
+    pub multiplier_x: u32,
+    /// The vertical distance in pixels between two consecutive Y coordinates.
+    pub multiplier_y: u32,
+    /// The horizontal offset in pixels applied to every map position.
+    pub offset_x: i32,
+    /// The vertical offset in pixels applied to every map position.
+    pub offset_y: i32,
+    /// The width of the maps in pixels, if specified by the world.
+    pub map_width: Option<u32>,
+    /// The height of the maps in pixels, if specified by the world.
+    pub map_height: Option<u32>,
+}
+
+impl WorldPattern {
+    /// Matches the file name of `path` against this pattern, returning where the map it
+    /// points to would be placed if it matches.
+    ///
+    /// Returns `None` as well if the map's position doesn't fit in an [`i32`].
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let world = tiled::Loader::new().load_world("assets/world/world_pattern.world")?;
+    /// let map = world.patterns[0].match_path("map-x01-y02.tmx").unwrap();
+    ///
+    /// assert_eq!((map.x, map.y), (64, 2 * 64 + 32));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn match_path(&self, path: impl AsRef<Path>) -> Option<WorldMapRef> {
+        let path = path.as_ref();
+        let file_name = path.file_name()?.to_str()?;
+        let captures = self.regexp.captures(file_name)?;
+        let x = captures.get(1)?.as_str().parse::<i32>().ok()?;
+        let y = captures.get(2)?.as_str().parse::<i32>().ok()?;
+
+        let place = |coord: i32, multiplier: u32, offset: i32| {
+            coord
+                .checked_mul(i32::try_from(multiplier).ok()?)?
+                .checked_add(offset)
+        };
+
+        Some(WorldMapRef {
+            path: path.to_owned(),
+            x: place(x, self.multiplier_x, self.offset_x)?,
+            y: place(y, self.multiplier_y, self.offset_y)?,
+            width: self.map_width,
+            height: self.map_height,
+        })
+    }
+}
+
+impl World {
+    /// Loads every map in this world through `loader`, returning them along with their
+    /// placement.
+    ///
+    /// Tilesets and templates shared between maps are only loaded once, through the loader's
+    /// [`ResourceCache`].
+    pub fn load_maps<R: ResourceReader, C: ResourceCache>(
+        &self,
+        loader: &mut Loader<R, C>,
+    ) -> Result<Vec<(WorldMapRef, Map)>> {
+        self.maps
+            .iter()
+            .map(|map_ref| Ok((map_ref.clone(), loader.load_tmx_map(&map_ref.path)?)))
+            .collect()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawWorld {
+    #[serde(default)]
+    maps: Vec<RawWorldMap>,
+    #[serde(default)]
+    patterns: Vec<RawWorldPattern>,
+    #[serde(default)]
+    only_show_adjacent_maps: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawWorldMap {
+    file_name: String,
+    x: i32,
+    y: i32,
+    width: Option<u32>,
+    height: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawWorldPattern {
+    regexp: String,
+    multiplier_x: u32,
+    multiplier_y: u32,
+    #[serde(default)]
+    offset_x: i32,
+    #[serde(default)]
+    offset_y: i32,
+    map_width: Option<u32>,
+    map_height: Option<u32>,
+}
+
+pub(crate) fn parse_world(path: &Path, reader: &mut impl ResourceReader) -> Result<World> {
+    let resource = reader
+        .read_from(path)
+        .map_err(|err| Error::ResourceLoadingError {
+            path: path.to_owned(),
+            err: Box::new(err),
+        })?;
+    let raw: RawWorld = serde_json::from_reader(resource).map_err(Error::WorldDecodingError)?;
+    let world_dir = path.parent().ok_or(Error::PathIsNotFile)?;
+
+    let patterns = raw
+        .patterns
+        .into_iter()
+        .map(|pattern| {
+            Ok(WorldPattern {
+                regexp: Regex::new(&pattern.regexp).map_err(Error::InvalidWorldPattern)?,
+                multiplier_x: pattern.multiplier_x,
+                multiplier_y: pattern.multiplier_y,
+                offset_x: pattern.offset_x,
+                offset_y: pattern.offset_y,
+                map_width: pattern.map_width,
+                map_height: pattern.map_height,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut maps: Vec<WorldMapRef> = raw
+        .maps
+        .into_iter()
+        .map(|map| WorldMapRef {
+            path: normalize_path(&world_dir.join(map.file_name)),
+            x: map.x,
+            y: map.y,
+            width: map.width,
+            height: map.height,
+        })
+        .collect();
+
+    if !patterns.is_empty() {
+        let mut files =
+            reader
+                .list_files(world_dir)
+                .map_err(|err| Error::ResourceLoadingError {
+                    path: world_dir.to_owned(),
+                    err: Box::new(err),
+                })?;
+        files.sort();
+        // Maps listed explicitly take precedence over the ones placed by patterns.
+        let matched: Vec<WorldMapRef> = files
+            .iter()
+            .filter_map(|file| {
+                let path = normalize_path(&world_dir.join(file.file_name()?));
+                if maps.iter().any(|map| map.path == path) {
+                    return None;
+                }
+                patterns
+                    .iter()
+                    .find_map(|pattern| pattern.match_path(&path))
+            })
+            .collect();
+        maps.extend(matched);
+    }
+
+    Ok(World {
+        source: path.to_owned(),
+        maps,
+        patterns,
+        only_show_adjacent_maps: raw.only_show_adjacent_maps,
+    })
+}
@@ -0,0 +1,1100 @@
+//! Writing maps and tilesets back into TMX and TSX files.
+
+use std::{
+    borrow::Cow,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use base64::Engine;
+
+use crate::{
+    Color, DrawOrder, EditorSettings, Error, FillMode, Frame, Gid, GroupLayer, HorizontalAlignment,
+    Image, ImageLayer, Layer, LayerType, Map, ObjectData, ObjectLayerData, ObjectShape,
+    Orientation, Properties, PropertyValue, Result, StaggerAxis, StaggerIndex, TileBounds,
+    TileData, TileId, TileLayer, TileRenderSize, Tileset, TilesetLocation, VerticalAlignment,
+    WangSet, WangSetType,
+};
+
+/// The TMX format version written when the map or tileset doesn't have one of its own.
+const FORMAT_VERSION: &str = "1.10";
+
+/// How [`Map::write_to`] encodes the tiles of tile layers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DataEncoding {
+    /// Comma separated GIDs. This is what Tiled uses by default.
+    #[default]
+    Csv,
+    /// Little-endian GIDs encoded in base64, without compression.
+    Base64,
+    /// Little-endian GIDs compressed with zlib and encoded in base64.
+    Base64Zlib,
+    /// Little-endian GIDs compressed with gzip and encoded in base64.
+    Base64Gzip,
+    /// Little-endian GIDs compressed with Zstandard and encoded in base64. Writing fails with
+    /// [`Error::InvalidEncodingFormat`] unless the `zstd` feature is enabled.
+    Base64Zstd,
+}
+
+impl DataEncoding {
+    /// The `encoding` and `compression` attributes of `<data>` elements using this encoding.
+    fn attributes(self) -> (&'static str, Option<&'static str>) {
+        match self {
+            DataEncoding::Csv => ("csv", None),
+            DataEncoding::Base64 => ("base64", None),
+            DataEncoding::Base64Zlib => ("base64", Some("zlib")),
+            DataEncoding::Base64Gzip => ("base64", Some("gzip")),
+            DataEncoding::Base64Zstd => ("base64", Some("zstd")),
+        }
+    }
+}
+
+/// How [`Map::write_to`] writes the tilesets of a map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TilesetStorage {
+    /// Tilesets loaded from TSX files are referenced through their source, as it was written in
+    /// the map (see [`Map::tileset_raw_sources`]) or else their [`Tileset::source`]. The TSX files
+    /// themselves aren't written. Tilesets that were embedded in the map stay embedded.
+    #[default]
+    External,
+    /// Every tileset is embedded in the map, including the ones loaded from TSX files.
+    ///
+    /// The sources of the images of tilesets that were loaded from TSX files are rewritten to be
+    /// relative to the map, using the tileset's raw source. If it isn't known, their resolved
+    /// [`Image::source`] is written instead.
+    Embedded,
+}
+
+/// Options for [`Map::write_to`] and [`Tileset::write_to`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WriteOptions {
+    /// How the tiles of tile layers are encoded.
+    pub data_encoding: DataEncoding,
+    /// Whether tilesets are referenced or embedded.
+    pub tilesets: TilesetStorage,
+    /// The maximum number of decimals written for floating point values, e.g. object positions.
+    /// Trailing zeros are left out. If [`None`], values are written with as many digits as needed
+    /// to read them back exactly.
+    pub float_precision: Option<usize>,
+}
+
+impl Map {
+    /// Writes the map in the TMX format, which Tiled and this crate can load back.
+    ///
+    /// The map is written with its layers, objects and properties, and its tilesets referenced or
+    /// embedded depending on [`WriteOptions::tilesets`]. External tileset and template files
+    /// aren't written; Use [`Tileset::write_to`] to write tilesets on their own.
+    ///
+    /// Some information isn't kept when loading maps, so it can't be written either:
+    /// - The contents of layers whose [contents were skipped](crate::LayerData::contents_skipped).
+    /// - Properties of custom enum types, which are written as the string value they're named by.
+    /// - The render order, which is always written as `right-down`.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// use tiled::{DataEncoding, Loader, WriteOptions};
+    ///
+    /// let mut loader = Loader::new();
+    /// let map = loader.load_tmx_map("assets/tiled_base64_zlib.tmx")?;
+    ///
+    /// let mut tmx = Vec::new();
+    /// let options = WriteOptions {
+    ///     data_encoding: DataEncoding::Csv,
+    ///     ..Default::default()
+    /// };
+    /// map.write_to(&mut tmx, options)?;
+    ///
+    /// let reloaded = loader.load_tmx_map_from(&tmx[..], "assets/tiled_base64_zlib.tmx")?;
+    /// assert_eq!(reloaded, map);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_to(&self, writer: impl Write, options: WriteOptions) -> Result<()> {
+        let mut w = XmlWriter::new(writer, options);
+        w.declaration()?;
+        write_map(&mut w, self)?;
+        w.finish()
+    }
+}
+
+impl Tileset {
+    /// Writes the tileset in the TSX format, which Tiled and this crate can load back.
+    ///
+    /// Like Tiled, attributes and elements that are at their default value, such as a tile
+    /// probability of 1 or empty properties, are left out. Only [`WriteOptions::float_precision`]
+    /// applies to tilesets; The other options are ignored.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// use tiled::{Loader, WriteOptions};
+    ///
+    /// let mut loader = Loader::new();
+    /// let tileset = loader.load_tsx_tileset("assets/tilesheet_wangsets.tsx")?;
+    ///
+    /// let mut tsx = Vec::new();
+    /// tileset.write_to(&mut tsx, WriteOptions::default())?;
+    ///
+    /// let reloaded = loader.load_tsx_tileset_from(&tsx[..], "assets/tilesheet_wangsets.tsx")?;
+    /// assert_eq!(reloaded, tileset);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_to(&self, writer: impl Write, options: WriteOptions) -> Result<()> {
+        let mut w = XmlWriter::new(writer, options);
+        w.declaration()?;
+        write_tileset(&mut w, self, None, ImageSources::Raw)?;
+        w.finish()
+    }
+}
+
+/// An XML element's name and attributes, in the order they're written in.
+///
+/// Names are usually static, but the ones of elements that are written back as they were read,
+/// such as unknown layers, aren't.
+pub(crate) struct Element {
+    name: Cow<'static, str>,
+    attributes: Vec<(Cow<'static, str>, String)>,
+}
+
+impl Element {
+    pub(crate) fn new(name: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            name: name.into(),
+            attributes: Vec::new(),
+        }
+    }
+
+    pub(crate) fn attr(mut self, name: impl Into<Cow<'static, str>>, value: impl ToString) -> Self {
+        self.attributes.push((name.into(), value.to_string()));
+        self
+    }
+
+    pub(crate) fn attr_if(self, condition: bool, name: &'static str, value: impl ToString) -> Self {
+        if condition {
+            self.attr(name, value)
+        } else {
+            self
+        }
+    }
+
+    pub(crate) fn attr_opt(self, name: &'static str, value: Option<impl ToString>) -> Self {
+        match value {
+            Some(value) => self.attr(name, value),
+            None => self,
+        }
+    }
+}
+
+/// A minimal XML writer producing the same layout as Tiled: One element per line, indented by
+/// one space per level.
+pub(crate) struct XmlWriter<W: Write> {
+    out: BufWriter<W>,
+    depth: usize,
+    options: WriteOptions,
+    /// The number of bytes written so far.
+    written: u64,
+}
+
+impl<W: Write> XmlWriter<W> {
+    pub(crate) fn new(writer: W, options: WriteOptions) -> Self {
+        Self {
+            out: BufWriter::new(writer),
+            depth: 0,
+            options,
+            written: 0,
+        }
+    }
+
+    pub(crate) fn finish(mut self) -> Result<()> {
+        self.out.flush().map_err(Error::WritingError)
+    }
+
+    fn write(&mut self, text: &str) -> Result<()> {
+        self.written += text.len() as u64;
+        self.out
+            .write_all(text.as_bytes())
+            .map_err(Error::WritingError)
+    }
+
+    /// The offset in bytes at which the next element will be written.
+    #[cfg(feature = "json")]
+    pub(crate) fn position(&self) -> u64 {
+        self.written
+    }
+
+    pub(crate) fn declaration(&mut self) -> Result<()> {
+        self.write("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")
+    }
+
+    fn open_tag(&mut self, element: &Element) -> Result<()> {
+        let mut tag = " ".repeat(self.depth);
+        tag.push('<');
+        tag.push_str(&element.name);
+        for (name, value) in &element.attributes {
+            tag.push(' ');
+            tag.push_str(name);
+            tag.push_str("=\"");
+            tag.push_str(&escape(value, true));
+            tag.push('"');
+        }
+        self.write(&tag)
+    }
+
+    /// Writes an element without contents.
+    pub(crate) fn empty(&mut self, element: Element) -> Result<()> {
+        self.open_tag(&element)?;
+        self.write("/>\n")
+    }
+
+    /// Writes the start of an element whose children are written next, until [`Self::end`].
+    pub(crate) fn start(&mut self, element: Element) -> Result<()> {
+        self.open_tag(&element)?;
+        self.write(">\n")?;
+        self.depth += 1;
+        Ok(())
+    }
+
+    pub(crate) fn end(&mut self, name: &str) -> Result<()> {
+        self.depth -= 1;
+        let tag = format!("{}</{}>\n", " ".repeat(self.depth), name);
+        self.write(&tag)
+    }
+
+    /// Writes an element containing only text, which is escaped.
+    pub(crate) fn text(&mut self, element: Element, text: &str) -> Result<()> {
+        self.open_tag(&element)?;
+        self.write(">")?;
+        self.write(&escape(text, false))?;
+        let tag = format!("</{}>\n", element.name);
+        self.write(&tag)
+    }
+
+    /// Formats a floating point value according to [`WriteOptions::float_precision`].
+    fn float(&self, value: f32) -> String {
+        match self.options.float_precision {
+            None => value.to_string(),
+            Some(precision) => {
+                let mut text = format!("{:.*}", precision, value);
+                if text.contains('.') {
+                    text.truncate(text.trim_end_matches('0').trim_end_matches('.').len());
+                }
+                if text == "-0" {
+                    text.remove(0);
+                }
+                text
+            }
+        }
+    }
+}
+
+/// Escapes text for use in XML attributes or element contents. Line breaks and tabs in attributes
+/// are written as character references, as they would be normalized to spaces otherwise.
+fn escape(text: &str, attribute: bool) -> Cow<'_, str> {
+    let needs_escaping = |c: char| match c {
+        '&' | '<' | '>' | '\r' => true,
+        '"' | '\n' | '\t' => attribute,
+        _ => false,
+    };
+    if !text.contains(needs_escaping) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len() + 8);
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '\r' => escaped.push_str("&#13;"),
+            '"' if attribute => escaped.push_str("&quot;"),
+            '\n' if attribute => escaped.push_str("&#10;"),
+            '\t' if attribute => escaped.push_str("&#9;"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+fn flag(value: bool) -> &'static str {
+    if value {
+        "1"
+    } else {
+        "0"
+    }
+}
+
+/// Formats a color the way Tiled does, leaving out the alpha component if it's opaque.
+fn color(color: Color) -> String {
+    if color.alpha == 255 {
+        format!("#{:02x}{:02x}{:02x}", color.red, color.green, color.blue)
+    } else {
+        color.to_string()
+    }
+}
+
+fn write_map(w: &mut XmlWriter<impl Write>, map: &Map) -> Result<()> {
+    let staggered = matches!(
+        map.orientation,
+        Orientation::Staggered | Orientation::Hexagonal
+    );
+    let hexagonal = map.orientation == Orientation::Hexagonal;
+    w.start(
+        Element::new("map")
+            .attr("version", map.version())
+            .attr_opt("tiledversion", map.tiled_version())
+            .attr_opt("class", map.user_type.as_deref())
+            .attr("orientation", map.orientation)
+            .attr("renderorder", "right-down")
+            .attr("width", map.width)
+            .attr("height", map.height)
+            .attr("tilewidth", map.tile_width)
+            .attr("tileheight", map.tile_height)
+            .attr_if(
+                hexagonal || map.hex_side_length != 0,
+                "hexsidelength",
+                map.hex_side_length,
+            )
+            .attr_if(
+                staggered || map.stagger_axis != StaggerAxis::default(),
+                "staggeraxis",
+                match map.stagger_axis {
+                    StaggerAxis::X => "x",
+                    StaggerAxis::Y => "y",
+                },
+            )
+            .attr_if(
+                staggered || map.stagger_index != StaggerIndex::default(),
+                "staggerindex",
+                match map.stagger_index {
+                    StaggerIndex::Even => "even",
+                    StaggerIndex::Odd => "odd",
+                },
+            )
+            .attr_opt("backgroundcolor", map.background_color.map(color))
+            .attr("infinite", flag(map.infinite()))
+            .attr_opt("nextlayerid", map.next_layer_id)
+            .attr_opt("nextobjectid", map.next_object_id),
+    )?;
+    if let Some(settings) = &map.editor_settings {
+        write_editor_settings(w, settings)?;
+    }
+    write_properties(w, &map.properties)?;
+
+    for (index, (first_gid, tileset)) in map.tilesets_with_first_gids().enumerate() {
+        let raw_source = map
+            .tileset_raw_sources()
+            .get(index)
+            .and_then(Option::as_deref);
+        let source = match w.options.tilesets {
+            TilesetStorage::External => raw_source.map(str::to_owned).or_else(|| {
+                tileset
+                    .source
+                    .as_ref()
+                    .map(|source| source.to_string_lossy().into_owned())
+            }),
+            TilesetStorage::Embedded => None,
+        };
+        match source {
+            Some(source) => w.empty(
+                Element::new("tileset")
+                    .attr("firstgid", first_gid.0)
+                    .attr("source", source),
+            )?,
+            None => {
+                let image_sources = match (&tileset.source, raw_source) {
+                    (None, _) => ImageSources::Raw,
+                    (Some(_), Some(raw_source)) => ImageSources::FromTileset(raw_source),
+                    (Some(_), None) => ImageSources::Resolved,
+                };
+                write_tileset(w, tileset, Some(first_gid), image_sources)?
+            }
+        }
+    }
+
+    for layer in map.layers() {
+        write_layer(w, map, layer)?;
+    }
+    w.end("map")
+}
+
+fn write_editor_settings(w: &mut XmlWriter<impl Write>, settings: &EditorSettings) -> Result<()> {
+    w.start(Element::new("editorsettings"))?;
+    if let Some((width, height)) = settings.chunk_size {
+        w.empty(
+            Element::new("chunksize")
+                .attr("width", width)
+                .attr("height", height),
+        )?;
+    }
+    if settings.export_target.is_some() || settings.export_format.is_some() {
+        w.empty(
+            Element::new("export")
+                .attr_opt("target", settings.export_target.as_deref())
+                .attr_opt("format", settings.export_format.as_deref()),
+        )?;
+    }
+    w.end("editorsettings")
+}
+
+/// How the sources of the images of a tileset are written.
+#[derive(Clone, Copy)]
+enum ImageSources<'a> {
+    /// As they were written in the file the tileset was loaded from.
+    Raw,
+    /// Relative to the directory of the given raw tileset source, for tilesets loaded from a TSX
+    /// file that are embedded in a map.
+    FromTileset(&'a str),
+    /// As their resolved path.
+    Resolved,
+}
+
+/// Writes a `<tileset>` element with all of its contents. `first_gid` is only given for tilesets
+/// embedded in a map.
+fn write_tileset(
+    w: &mut XmlWriter<impl Write>,
+    tileset: &Tileset,
+    first_gid: Option<Gid>,
+    image_sources: ImageSources,
+) -> Result<()> {
+    let mut element = Element::new("tileset");
+    element = match first_gid {
+        Some(first_gid) => element.attr("firstgid", first_gid.0),
+        None => element
+            .attr(
+                "version",
+                tileset.version.as_deref().unwrap_or(FORMAT_VERSION),
+            )
+            .attr_opt("tiledversion", tileset.tiled_version.as_deref()),
+    };
+    w.start(
+        element
+            .attr("name", &tileset.name)
+            .attr_opt("class", tileset.user_type.as_deref())
+            .attr("tilewidth", tileset.tile_width)
+            .attr("tileheight", tileset.tile_height)
+            .attr_if(tileset.spacing != 0, "spacing", tileset.spacing)
+            .attr_if(tileset.margin != 0, "margin", tileset.margin)
+            .attr("tilecount", tileset.tilecount)
+            .attr("columns", tileset.columns)
+            .attr_if(
+                tileset.tile_render_size == TileRenderSize::Grid,
+                "tilerendersize",
+                "grid",
+            )
+            .attr_if(
+                tileset.fill_mode == FillMode::PreserveAspectFit,
+                "fillmode",
+                "preserve-aspect-fit",
+            ),
+    )?;
+
+    if tileset.offset_x != 0 || tileset.offset_y != 0 {
+        w.empty(
+            Element::new("tileoffset")
+                .attr("x", tileset.offset_x)
+                .attr("y", tileset.offset_y),
+        )?;
+    }
+    if let Some(grid) = &tileset.grid {
+        w.empty(
+            Element::new("grid")
+                .attr("orientation", grid.orientation)
+                .attr("width", grid.width)
+                .attr("height", grid.height),
+        )?;
+    }
+    write_properties(w, &tileset.properties)?;
+    if let Some(image) = &tileset.image {
+        write_image(w, image, image_sources)?;
+    }
+    if let Some(transformations) = &tileset.transformations {
+        w.empty(
+            Element::new("transformations")
+                .attr("hflip", flag(transformations.hflip))
+                .attr("vflip", flag(transformations.vflip))
+                .attr("rotate", flag(transformations.rotate))
+                .attr(
+                    "preferuntransformed",
+                    flag(transformations.prefer_untransformed),
+                ),
+        )?;
+    }
+
+    let mut tiles: Vec<_> = tileset.defined_tiles().collect();
+    tiles.sort_by_key(|(id, _)| *id);
+    for (id, tile) in tiles {
+        write_tile(w, id, &tile, image_sources)?;
+    }
+
+    if !tileset.wang_sets.is_empty() {
+        w.start(Element::new("wangsets"))?;
+        for wang_set in &tileset.wang_sets {
+            write_wang_set(w, wang_set)?;
+        }
+        w.end("wangsets")?;
+    }
+    w.end("tileset")
+}
+
+fn write_tile(
+    w: &mut XmlWriter<impl Write>,
+    id: TileId,
+    tile: &TileData,
+    image_sources: ImageSources,
+) -> Result<()> {
+    let element = Element::new("tile")
+        .attr("id", id)
+        .attr_opt("type", tile.user_type.as_deref())
+        .attr_if(
+            tile.probability != 1.0,
+            "probability",
+            w.float(tile.probability),
+        );
+    if tile.properties.is_empty()
+        && tile.image.is_none()
+        && tile.collision.is_none()
+        && tile.animation.is_none()
+    {
+        return w.empty(element);
+    }
+    w.start(element)?;
+    write_properties(w, &tile.properties)?;
+    if let Some(image) = &tile.image {
+        write_image(w, image, image_sources)?;
+    }
+    if let Some(collision) = &tile.collision {
+        w.start(object_group_attributes(
+            Element::new("objectgroup"),
+            collision,
+        ))?;
+        for object in collision.object_data() {
+            write_object(w, None, object)?;
+        }
+        w.end("objectgroup")?;
+    }
+    if let Some(frames) = &tile.animation {
+        w.start(Element::new("animation"))?;
+        for Frame { tile_id, duration } in frames {
+            w.empty(
+                Element::new("frame")
+                    .attr("tileid", tile_id)
+                    .attr("duration", duration),
+            )?;
+        }
+        w.end("animation")?;
+    }
+    w.end("tile")
+}
+
+fn write_wang_set(w: &mut XmlWriter<impl Write>, wang_set: &WangSet) -> Result<()> {
+    let tile = wang_set.tile.map_or(-1, i64::from);
+    w.start(
+        Element::new("wangset")
+            .attr("name", &wang_set.name)
+            .attr_opt("class", wang_set.user_type.as_deref())
+            .attr(
+                "type",
+                match wang_set.wang_set_type {
+                    WangSetType::Corner => "corner",
+                    WangSetType::Edge => "edge",
+                    WangSetType::Mixed => "mixed",
+                },
+            )
+            .attr("tile", tile),
+    )?;
+    write_properties(w, &wang_set.properties)?;
+    for wang_color in &wang_set.wang_colors {
+        let element = Element::new("wangcolor")
+            .attr("name", &wang_color.name)
+            .attr_opt("class", wang_color.user_type.as_deref())
+            .attr("color", color(wang_color.color))
+            .attr("tile", wang_color.tile.map_or(-1, i64::from))
+            .attr("probability", w.float(wang_color.probability));
+        if wang_color.properties.is_empty() {
+            w.empty(element)?;
+        } else {
+            w.start(element)?;
+            write_properties(w, &wang_color.properties)?;
+            w.end("wangcolor")?;
+        }
+    }
+    let mut wang_tiles: Vec<_> = wang_set.wang_tiles.iter().collect();
+    wang_tiles.sort_by_key(|(id, _)| **id);
+    for (id, wang_tile) in wang_tiles {
+        let wang_id = wang_tile.wang_id.0.map(|color| color.to_string()).join(",");
+        w.empty(
+            Element::new("wangtile")
+                .attr("tileid", id)
+                .attr("wangid", wang_id)
+                .attr_if(wang_tile.hflip, "hflip", "1")
+                .attr_if(wang_tile.vflip, "vflip", "1")
+                .attr_if(wang_tile.dflip, "dflip", "1"),
+        )?;
+    }
+    w.end("wangset")
+}
+
+fn write_image(
+    w: &mut XmlWriter<impl Write>,
+    image: &Image,
+    image_sources: ImageSources,
+) -> Result<()> {
+    let source = match (&image.embedded_data, &image.raw_source, image_sources) {
+        (Some(_), _, _) => None,
+        (None, Some(raw_source), ImageSources::Raw) => Some(raw_source.clone()),
+        (None, Some(raw_source), ImageSources::FromTileset(tileset_source)) => {
+            let absolute = Path::new(raw_source).is_absolute() || raw_source.starts_with("file:");
+            match tileset_source.rfind(['/', '\\']) {
+                Some(end) if !absolute => {
+                    Some(format!("{}/{}", &tileset_source[..end], raw_source))
+                }
+                _ => Some(raw_source.clone()),
+            }
+        }
+        (None, _, _) => Some(image.source.to_string_lossy().into_owned()),
+    };
+    let element = Element::new("image")
+        .attr_opt("format", image.format.as_deref())
+        .attr_opt("source", source)
+        .attr_opt(
+            "trans",
+            image
+                .transparent_colour
+                .map(|c| format!("{:02x}{:02x}{:02x}", c.red, c.green, c.blue)),
+        )
+        .attr_if(image.width != 0, "width", image.width)
+        .attr_if(image.height != 0, "height", image.height);
+    match &image.embedded_data {
+        Some(data) => {
+            w.start(element)?;
+            let encoded = base64::engine::general_purpose::STANDARD.encode(data);
+            w.text(Element::new("data").attr("encoding", "base64"), &encoded)?;
+            w.end("image")
+        }
+        None => w.empty(element),
+    }
+}
+
+fn write_layer(w: &mut XmlWriter<impl Write>, map: &Map, layer: Layer) -> Result<()> {
+    let element = |name: &'static str, size: Option<(u32, u32)>| {
+        let mut element = Element::new(name)
+            .attr_if(layer.id() != 0, "id", layer.id())
+            .attr("name", &layer.name)
+            .attr_opt("class", layer.user_type.as_deref());
+        if let Some((width, height)) = size {
+            element = element.attr("width", width).attr("height", height);
+        }
+        element
+            .attr_if(!layer.visible, "visible", "0")
+            .attr_if(layer.locked, "locked", "1")
+            .attr_if(layer.opacity != 1.0, "opacity", w.float(layer.opacity))
+            .attr_opt("tintcolor", layer.tint_color.map(color))
+            .attr_if(layer.offset_x != 0.0, "offsetx", w.float(layer.offset_x))
+            .attr_if(layer.offset_y != 0.0, "offsety", w.float(layer.offset_y))
+            .attr_if(
+                layer.parallax_x != 1.0,
+                "parallaxx",
+                w.float(layer.parallax_x),
+            )
+            .attr_if(
+                layer.parallax_y != 1.0,
+                "parallaxy",
+                w.float(layer.parallax_y),
+            )
+    };
+
+    match layer.layer_type() {
+        LayerType::Tiles(tile_layer) => {
+            let size = match &tile_layer {
+                TileLayer::Finite(finite) => (finite.width(), finite.height()),
+                TileLayer::Infinite(_) => (map.width, map.height),
+            };
+            let element = element("layer", Some(size));
+            w.start(element)?;
+            write_properties(w, &layer.properties)?;
+            write_tile_data(w, map, &tile_layer)?;
+            w.end("layer")
+        }
+        LayerType::Objects(object_layer) => {
+            let element = object_group_attributes(element("objectgroup", None), &object_layer);
+            w.start(element)?;
+            write_properties(w, &layer.properties)?;
+            for object in object_layer.object_data() {
+                write_object(w, Some(map.first_gids()), object)?;
+            }
+            w.end("objectgroup")
+        }
+        LayerType::Image(image_layer) => {
+            let element = image_layer_attributes(element("imagelayer", None), &image_layer);
+            w.start(element)?;
+            write_properties(w, &layer.properties)?;
+            if let Some(image) = &image_layer.image {
+                write_image(w, image, ImageSources::Raw)?;
+            }
+            w.end("imagelayer")
+        }
+        LayerType::Group(group_layer) => {
+            w.start(element("group", None))?;
+            write_properties(w, &layer.properties)?;
+            write_group_children(w, map, &group_layer)?;
+            w.end("group")
+        }
+        // Only the attributes of unknown layers are known, which are written back unchanged
+        LayerType::Unknown {
+            tag_name,
+            attributes,
+        } => {
+            let mut attributes: Vec<_> = attributes.iter().collect();
+            attributes.sort();
+            let element = attributes.into_iter().fold(
+                Element::new(tag_name.to_owned()),
+                |element, (name, value)| element.attr(name.clone(), value),
+            );
+            w.empty(element)
+        }
+    }
+}
+
+fn write_group_children(
+    w: &mut XmlWriter<impl Write>,
+    map: &Map,
+    group_layer: &GroupLayer,
+) -> Result<()> {
+    for child in group_layer.layers() {
+        write_layer(w, map, child)?;
+    }
+    Ok(())
+}
+
+fn object_group_attributes(element: Element, layer: &ObjectLayerData) -> Element {
+    element.attr_opt("color", layer.colour.map(color)).attr_if(
+        layer.draw_order == DrawOrder::Index,
+        "draworder",
+        "index",
+    )
+}
+
+fn image_layer_attributes(element: Element, layer: &ImageLayer) -> Element {
+    element
+        .attr_if(layer.repeat_x, "repeatx", "1")
+        .attr_if(layer.repeat_y, "repeaty", "1")
+}
+
+fn write_tile_data(w: &mut XmlWriter<impl Write>, map: &Map, layer: &TileLayer) -> Result<()> {
+    let first_gids = map.first_gids();
+    let gids = |bounds: TileBounds| {
+        let mut gids = Vec::with_capacity(bounds.width as usize * bounds.height as usize);
+        for y in bounds.y..bounds.y + bounds.height as i32 {
+            for x in bounds.x..bounds.x + bounds.width as i32 {
+                let tile = match layer {
+                    TileLayer::Finite(finite) => finite.get_tile_data(x, y),
+                    TileLayer::Infinite(infinite) => infinite.get_tile_data(x, y),
+                };
+                gids.push(tile.map_or(0, |tile| {
+                    tile.to_bits_with_first_gid(first_gids[tile.tileset_index()])
+                }));
+            }
+        }
+        gids
+    };
+
+    let encoding = w.options.data_encoding;
+    let (encoding_name, compression) = encoding.attributes();
+    let data = Element::new("data")
+        .attr("encoding", encoding_name)
+        .attr_opt("compression", compression);
+    match layer {
+        TileLayer::Finite(finite) => {
+            let bounds = TileBounds {
+                x: 0,
+                y: 0,
+                width: finite.width(),
+                height: finite.height(),
+            };
+            let text = encode_gids(w, &gids(bounds), bounds.width)?;
+            w.text(data, &text)
+        }
+        TileLayer::Infinite(infinite) => {
+            let chunks = if infinite.file_chunks().is_empty() {
+                let mut positions: Vec<_> = infinite.chunk_data().map(|(pos, _)| pos).collect();
+                positions.sort_by_key(|&(x, y)| (y, x));
+                positions
+                    .into_iter()
+                    .map(|(x, y)| TileBounds {
+                        x: x * crate::ChunkData::WIDTH as i32,
+                        y: y * crate::ChunkData::HEIGHT as i32,
+                        width: crate::ChunkData::WIDTH,
+                        height: crate::ChunkData::HEIGHT,
+                    })
+                    .collect()
+            } else {
+                infinite.file_chunks().to_vec()
+            };
+            w.start(data)?;
+            for bounds in chunks {
+                let text = encode_gids(w, &gids(bounds), bounds.width)?;
+                w.text(
+                    Element::new("chunk")
+                        .attr("x", bounds.x)
+                        .attr("y", bounds.y)
+                        .attr("width", bounds.width)
+                        .attr("height", bounds.height),
+                    &text,
+                )?;
+            }
+            w.end("data")
+        }
+    }
+}
+
+/// Encodes GIDs as the text contents of a `<data>` or `<chunk>` element, laid out like Tiled does.
+fn encode_gids(w: &XmlWriter<impl Write>, gids: &[u32], width: u32) -> Result<String> {
+    let bytes: Vec<u8> = gids.iter().flat_map(|gid| gid.to_le_bytes()).collect();
+    let compressed = match w.options.data_encoding {
+        DataEncoding::Csv => {
+            let rows: Vec<String> = gids
+                .chunks(width.max(1) as usize)
+                .map(|row| {
+                    row.iter()
+                        .map(|gid| gid.to_string())
+                        .collect::<Vec<_>>()
+                        .join(",")
+                })
+                .collect();
+            return Ok(format!("\n{}\n{}", rows.join(",\n"), " ".repeat(w.depth)));
+        }
+        DataEncoding::Base64 => bytes,
+        DataEncoding::Base64Zlib => {
+            let mut encoder =
+                flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&bytes).map_err(Error::WritingError)?;
+            encoder.finish().map_err(Error::WritingError)?
+        }
+        DataEncoding::Base64Gzip => {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&bytes).map_err(Error::WritingError)?;
+            encoder.finish().map_err(Error::WritingError)?
+        }
+        DataEncoding::Base64Zstd => {
+            #[cfg(feature = "zstd")]
+            {
+                zstd::stream::encode_all(&bytes[..], 0).map_err(Error::WritingError)?
+            }
+            #[cfg(not(feature = "zstd"))]
+            {
+                return Err(Error::InvalidEncodingFormat {
+                    encoding: Some("base64".to_owned()),
+                    compression: Some("zstd".to_owned()),
+                });
+            }
+        }
+    };
+    Ok(format!(
+        "\n{}{}\n{}",
+        " ".repeat(w.depth + 1),
+        base64::engine::general_purpose::STANDARD.encode(compressed),
+        " ".repeat(w.depth)
+    ))
+}
+
+/// Writes an object. `first_gids` are the first GIDs of the map's tilesets, or [`None`] for
+/// tile collision objects, which can't be tile objects.
+fn write_object(
+    w: &mut XmlWriter<impl Write>,
+    first_gids: Option<&[Gid]>,
+    object: &ObjectData,
+) -> Result<()> {
+    // Instances of templates are written with all their attributes, as leaving one out would
+    // make it take the template's value instead
+    let templated = object.template_source.is_some();
+    let template = object.template_raw_source.clone().or_else(|| {
+        object
+            .template_source
+            .as_ref()
+            .map(|source| source.to_string_lossy().into_owned())
+    });
+    let gid = object
+        .tile_data()
+        .and_then(|tile| match (tile.tileset_location(), first_gids) {
+            (TilesetLocation::Map(index), Some(first_gids)) => {
+                Some(tile.to_bits_with_first_gid(first_gids[*index]))
+            }
+            _ => None,
+        });
+    let (width, height) = match object.shape {
+        ObjectShape::Rect { width, height }
+        | ObjectShape::Ellipse { width, height }
+        | ObjectShape::Text { width, height, .. } => (width, height),
+        _ => (0.0, 0.0),
+    };
+    let element = Element::new("object")
+        .attr_if(object.id() != 0, "id", object.id())
+        .attr_opt("template", template)
+        .attr_if(templated || !object.name.is_empty(), "name", &object.name)
+        .attr_if(
+            templated || !object.user_type.is_empty(),
+            "type",
+            &object.user_type,
+        )
+        .attr_opt("gid", gid)
+        .attr("x", w.float(object.x))
+        .attr("y", w.float(object.y))
+        .attr_if(templated || width != 0.0, "width", w.float(width))
+        .attr_if(templated || height != 0.0, "height", w.float(height))
+        .attr_if(
+            templated || object.rotation != 0.0,
+            "rotation",
+            w.float(object.rotation),
+        )
+        .attr_if(
+            templated || !object.visible,
+            "visible",
+            flag(object.visible),
+        )
+        .attr_if(object.locked, "locked", "1");
+
+    if matches!(object.shape, ObjectShape::Rect { .. }) && object.properties.is_empty() {
+        return w.empty(element);
+    }
+    w.start(element)?;
+    write_properties(w, &object.properties)?;
+    let points = |points: &[(f32, f32)]| {
+        points
+            .iter()
+            .map(|(x, y)| format!("{},{}", w.float(*x), w.float(*y)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    match &object.shape {
+        ObjectShape::Rect { .. } => {}
+        ObjectShape::Ellipse { .. } => w.empty(Element::new("ellipse"))?,
+        ObjectShape::Point(..) => w.empty(Element::new("point"))?,
+        ObjectShape::Polygon { points: vertices } => {
+            let points = points(vertices);
+            w.empty(Element::new("polygon").attr("points", points))?
+        }
+        ObjectShape::Polyline { points: vertices } => {
+            let points = points(vertices);
+            w.empty(Element::new("polyline").attr("points", points))?
+        }
+        ObjectShape::Text {
+            font_family,
+            pixel_size,
+            wrap,
+            color: text_color,
+            bold,
+            italic,
+            underline,
+            strikeout,
+            kerning,
+            halign,
+            valign,
+            text,
+            ..
+        } => {
+            let default_color = Color {
+                alpha: 255,
+                red: 0,
+                green: 0,
+                blue: 0,
+            };
+            let element = Element::new("text")
+                .attr_if(font_family != "sans-serif", "fontfamily", font_family)
+                .attr_if(*pixel_size != 16, "pixelsize", pixel_size)
+                .attr_if(*wrap, "wrap", "1")
+                .attr_if(*text_color != default_color, "color", color(*text_color))
+                .attr_if(*bold, "bold", "1")
+                .attr_if(*italic, "italic", "1")
+                .attr_if(*underline, "underline", "1")
+                .attr_if(*strikeout, "strikeout", "1")
+                .attr_if(!*kerning, "kerning", "0")
+                .attr_if(
+                    *halign != HorizontalAlignment::Left,
+                    "halign",
+                    match halign {
+                        HorizontalAlignment::Left => "left",
+                        HorizontalAlignment::Center => "center",
+                        HorizontalAlignment::Right => "right",
+                        HorizontalAlignment::Justify => "justify",
+                    },
+                )
+                .attr_if(
+                    *valign != VerticalAlignment::Top,
+                    "valign",
+                    match valign {
+                        VerticalAlignment::Top => "top",
+                        VerticalAlignment::Center => "center",
+                        VerticalAlignment::Bottom => "bottom",
+                    },
+                );
+            w.text(element, text)?
+        }
+    }
+    w.end("object")
+}
+
+/// Writes a `<properties>` element, unless there are no properties. They're sorted by name so
+/// that the output doesn't depend on the order of the map they're stored in.
+fn write_properties(w: &mut XmlWriter<impl Write>, properties: &Properties) -> Result<()> {
+    if properties.is_empty() {
+        return Ok(());
+    }
+    let mut properties: Vec<_> = properties.iter().collect();
+    properties.sort_by_key(|(name, _)| *name);
+    w.start(Element::new("properties"))?;
+    for (name, value) in properties {
+        let element = Element::new("property").attr("name", name);
+        match value {
+            PropertyValue::BoolValue(value) => {
+                w.empty(element.attr("type", "bool").attr("value", value))?
+            }
+            PropertyValue::FloatValue(value) => {
+                let value = w.float(*value);
+                w.empty(element.attr("type", "float").attr("value", value))?
+            }
+            PropertyValue::IntValue(value) => {
+                w.empty(element.attr("type", "int").attr("value", value))?
+            }
+            PropertyValue::ColorValue(value) => {
+                w.empty(element.attr("type", "color").attr("value", value))?
+            }
+            // Tiled writes multiline strings as the contents of the element
+            PropertyValue::StringValue(value) if value.contains('\n') => w.text(element, value)?,
+            PropertyValue::StringValue(value) => w.empty(element.attr("value", value))?,
+            PropertyValue::FileValue(value) => {
+                w.empty(element.attr("type", "file").attr("value", value))?
+            }
+            PropertyValue::ObjectValue(value) => {
+                w.empty(element.attr("type", "object").attr("value", value))?
+            }
+            PropertyValue::ClassValue {
+                property_type,
+                properties,
+            } => {
+                let element = element
+                    .attr("type", "class")
+                    .attr("propertytype", property_type);
+                if properties.is_empty() {
+                    w.empty(element)?
+                } else {
+                    w.start(element)?;
+                    write_properties(w, properties)?;
+                    w.end("property")?
+                }
+            }
+            PropertyValue::EnumValue { name, value } => w.empty(
+                element
+                    .attr("type", "string")
+                    .attr("propertytype", name)
+                    .attr("value", value),
+            )?,
+            PropertyValue::Unknown {
+                type_name,
+                raw_value,
+            } => w.empty(element.attr("type", type_name).attr("value", raw_value))?,
+        }
+    }
+    w.end("properties")
+}
@@ -1,8 +1,26 @@
-use std::path::PathBuf;
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
+#[cfg(feature = "project")]
+use tiled::EnumStorage;
+#[cfg(feature = "world")]
+use tiled::WorldMapRef;
 use tiled::{
-    Color, FiniteTileLayer, HorizontalAlignment, LayerType, Loader, Map, ObjectShape,
-    PropertyValue, ResourceCache, TileLayer, TilesetLocation, VerticalAlignment, WangId,
+    floor_div, floor_div_i64, floor_mod, floor_mod_i64, get_tileset_for_gid, ChainedReader,
+    ChainedReaderError, ChunkData, Color, DataEncoding, DefaultResourceCache, DrawOrder,
+    DuplicateHandling, EditorSettings, EffectiveLayerAttributes, Error, ErrorKind, FillMode,
+    FiniteTileLayer, Frame, Gid, Grid, GroupLayer, HasProperties, HorizontalAlignment,
+    LayerNameFilter, LayerTileData, LayerType, LoadProgress, LoadSettings, Loader, Map,
+    MapTilesetGid, MemoryResourceError, MemoryResourceReader, ObjectAction, ObjectCallback,
+    ObjectData, ObjectLayerData, ObjectShape, Orientation, OwnedLayerTile, OwnedObject,
+    ParseWarning, PathResolver, ProgressHook, PropertiesExt, PropertyValue, Rect, ResourceCache,
+    Strictness, TileBounds, TileData, TileLayer, TileLocation, TileRenderSize, TiledVersion,
+    Tileset, TilesetLocation, TilesetStats, TilesetStorage, Transformations, ValidationIssue,
+    VerticalAlignment, WangId, WriteOptions,
 };
 
 fn as_finite<'map>(data: TileLayer<'map>) -> FiniteTileLayer<'map> {
@@ -57,6 +75,31 @@ fn test_gzip_and_zlib_encoded_and_raw_are_the_same() {
     assert!((0..99).map(|x| layer.get_tile(x, 99)).all(|t| t.is_none()));
 }
 
+#[test]
+fn test_xml_tile_elements_are_the_same_as_encoded() {
+    let mut loader = Loader::new();
+    let xml = loader.load_tmx_map("assets/tiled_xml.tmx").unwrap();
+    let csv = loader.load_tmx_map("assets/tiled_xml_csv.tmx").unwrap();
+    compare_everything_but_tileset_sources(&xml, &csv);
+    assert_eq!(*xml.get_layer(0).unwrap(), *csv.get_layer(0).unwrap());
+
+    let layer = as_finite(xml.get_layer(0).unwrap().as_tile_layer().unwrap());
+    assert_eq!(layer.get_tile(0, 0).unwrap().id(), 29);
+    assert!(layer.get_tile(10, 0).is_none());
+
+    let xml = loader
+        .load_tmx_map("assets/tiled_xml_infinite.tmx")
+        .unwrap();
+    let zlib = loader
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    compare_everything_but_tileset_sources(&xml, &zlib);
+    assert_eq!(xml.layers().len(), zlib.layers().len());
+    for (xml, zlib) in xml.layers().zip(zlib.layers()) {
+        assert_eq!(*xml, *zlib);
+    }
+}
+
 #[test]
 fn test_external_tileset() {
     let mut loader = Loader::new();
@@ -134,6 +177,147 @@ fn test_infinite_map() {
     }
 }
 
+#[test]
+fn test_infinite_map_chunks() {
+    let r = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+
+    if let TileLayer::Infinite(inf) = &r.get_layer(0).unwrap().as_tile_layer().unwrap() {
+        // Only chunks that contain tiles are stored.
+        for (_, chunk) in inf.chunks() {
+            assert!((0..ChunkData::WIDTH as i32)
+                .flat_map(|x| (0..ChunkData::HEIGHT as i32).map(move |y| (x, y)))
+                .any(|(x, y)| chunk.get_tile(x, y).is_some()));
+        }
+
+        let chunk_pos = ChunkData::tile_to_chunk_pos(-16, 0);
+        assert_eq!(chunk_pos, (-1, 0));
+        let chunk = inf.get_chunk(chunk_pos.0, chunk_pos.1).unwrap();
+        assert_eq!(chunk.get_tile(0, 0).unwrap().id(), 17);
+        assert!(inf.get_chunk(-100, -100).is_none());
+    } else {
+        panic!("It is wrongly recognised as a finite map");
+    }
+}
+
+#[test]
+fn test_tile_data_debug_is_summarized() {
+    let map = Loader::new().load_tmx_map("assets/tiled_csv.tmx").unwrap();
+    let layer = as_finite(map.get_layer(0).unwrap().as_tile_layer().unwrap());
+    let non_empty = (0..100)
+        .flat_map(|x| (0..100).map(move |y| (x, y)))
+        .filter(|&(x, y)| layer.get_tile(x, y).is_some())
+        .count();
+    assert_eq!(
+        format!("{:?}", *layer),
+        format!(
+            "FiniteTileLayerData {{ width: 100, height: 100, tiles: [10000 tiles, {}% non-empty] }}",
+            non_empty * 100 / 10000
+        )
+    );
+    assert!(!format!("{:?}", map).contains("LayerTileData {"));
+    let full = format!("{:?}", layer.debug_full());
+    assert_eq!(full.matches("LayerTileData {").count(), non_empty);
+    assert_eq!(full.matches("None").count(), 10000 - non_empty);
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    let layer = match map.get_layer(0).unwrap().as_tile_layer().unwrap() {
+        TileLayer::Infinite(layer) => layer,
+        TileLayer::Finite(_) => panic!("Not an infinite tile layer"),
+    };
+    let chunk_count = layer.chunks().count();
+    assert_eq!(
+        format!("{:?}", *layer),
+        format!(
+            "InfiniteTileLayerData {{ chunks: {}, tiles: [{} tiles, 100% non-empty] }}",
+            chunk_count,
+            chunk_count * ChunkData::TILE_COUNT
+        )
+    );
+    let chunk = layer.get_chunk(-1, 0).unwrap();
+    assert_eq!(
+        format!("{:?}", *chunk),
+        "ChunkData { tiles: [256 tiles, 100% non-empty] }"
+    );
+    assert_eq!(
+        format!("{:?}", chunk.debug_full())
+            .matches("LayerTileData {")
+            .count(),
+        ChunkData::TILE_COUNT
+    );
+    assert!(format!("{:?}", layer.debug_full())
+        .starts_with("InfiniteTileLayerData { chunks: {(-1, 0): ChunkData { tiles: [Some("));
+
+    // Small value types can be used as keys
+    let mut gids = std::collections::HashSet::new();
+    for (_, chunk) in layer.chunks() {
+        gids.extend((0..ChunkData::TILE_COUNT as i32).filter_map(|i| {
+            chunk
+                .get_tile(i % ChunkData::WIDTH as i32, i / ChunkData::WIDTH as i32)
+                .map(|tile| *tile)
+        }));
+    }
+    assert!(!gids.is_empty());
+}
+
+#[test]
+fn test_tile_layer_iteration() {
+    let r = Loader::new().load_tmx_map("assets/tiled_csv.tmx").unwrap();
+    let layer = as_finite(r.get_layer(0).unwrap().as_tile_layer().unwrap());
+
+    // Rows include empty tiles, while the sparse iterator skips them.
+    let rows: Vec<Vec<_>> = layer.rows().map(|row| row.collect()).collect();
+    assert_eq!(rows.len(), layer.height() as usize);
+    assert!(rows.iter().all(|row| row.len() == layer.width() as usize));
+    let non_empty: Vec<_> = rows
+        .iter()
+        .enumerate()
+        .flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter_map(move |(x, tile)| tile.map(|tile| ((x as i32, y as i32), tile)))
+        })
+        .collect();
+    assert!(non_empty.len() < rows.len() * rows[0].len());
+    assert_eq!(layer.tiles().collect::<Vec<_>>(), non_empty);
+
+    let bounds = layer.bounds().unwrap();
+    assert!(non_empty.iter().all(|((x, y), _)| bounds.x <= *x
+        && *x < bounds.x + bounds.width as i32
+        && bounds.y <= *y
+        && *y < bounds.y + bounds.height as i32));
+
+    let r = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    let layer = r.get_layer(0).unwrap().as_tile_layer().unwrap();
+    assert_eq!(
+        layer.bounds(),
+        Some(TileBounds {
+            x: -16,
+            y: 0,
+            width: 48,
+            height: 48
+        })
+    );
+    let tiles: Vec<_> = layer.tiles().collect();
+    // Row-major order, across chunks
+    assert!(tiles
+        .windows(2)
+        .all(|pair| (pair[0].0 .1, pair[0].0 .0) < (pair[1].0 .1, pair[1].0 .0)));
+    for ((x, y), tile) in &tiles {
+        assert_eq!(layer.get_tile(*x, *y), Some(*tile));
+    }
+    let expected = (-16..32)
+        .flat_map(|x| (0..48).map(move |y| (x, y)))
+        .filter(|(x, y)| layer.get_tile(*x, *y).is_some())
+        .count();
+    assert_eq!(tiles.len(), expected);
+}
+
 #[test]
 fn test_image_layers() {
     let r = Loader::new()
@@ -170,6 +354,21 @@ fn test_image_layers() {
     }
 }
 
+#[test]
+fn test_image_layer_repeat() {
+    let r = Loader::new()
+        .load_tmx_map("assets/tiled_image_layers_repeat.tmx")
+        .unwrap();
+    let repeats: Vec<_> = r
+        .layers()
+        .map(|layer| {
+            let image_layer = layer.as_image_layer().unwrap();
+            (image_layer.repeat_x, image_layer.repeat_y)
+        })
+        .collect();
+    assert_eq!(repeats, [(true, true), (true, false), (false, false)]);
+}
+
 #[test]
 fn test_tile_property() {
     let r = Loader::new()
@@ -264,6 +463,70 @@ fn test_flipped() {
     assert!(!t4.flip_v);
 }
 
+#[test]
+fn test_flip_rotation_and_flip() {
+    let r = Loader::new()
+        .load_tmx_map("assets/tiled_flipped_all.tmx")
+        .unwrap();
+    let layer = r.get_layer(0).unwrap().as_tile_layer().unwrap();
+
+    // (x, y, flip_h, flip_v, flip_d, rotation, flipped)
+    let expected = [
+        (0, 0, false, false, false, 0, false),
+        (1, 0, true, false, false, 0, true),
+        (2, 0, false, true, false, 180, true),
+        (3, 0, true, true, false, 180, false),
+        (0, 1, false, false, true, 270, true),
+        (1, 1, true, false, true, 90, false),
+        (2, 1, false, true, true, 270, false),
+        (3, 1, true, true, true, 90, true),
+    ];
+    for (x, y, flip_h, flip_v, flip_d, rotation, flipped) in expected {
+        let tile = layer.get_tile(x, y).unwrap();
+        assert_eq!(tile.id(), 2);
+        assert_eq!(
+            (tile.flip_h(), tile.flip_v(), tile.flip_d()),
+            (flip_h, flip_v, flip_d)
+        );
+        assert!(!tile.rotate_hex_120);
+        assert_eq!(
+            tile.rotation_and_flip(),
+            (rotation, flipped),
+            "tile at {x},{y}"
+        );
+    }
+}
+
+#[test]
+fn test_hexagonal_rotation_and_flip() {
+    let r = Loader::new()
+        .load_tmx_map("assets/tiled_flipped_hexagonal.tmx")
+        .unwrap();
+    let layer = r.get_layer(0).unwrap().as_tile_layer().unwrap();
+
+    // (x, y, rotation, flipped)
+    let expected = [
+        (0, 0, 0, false),
+        (1, 0, 60, false),
+        (2, 0, 120, false),
+        (3, 0, 180, false),
+        (0, 1, 0, true),
+        (1, 1, 180, true),
+        (2, 1, 240, true),
+    ];
+    for (x, y, rotation, flipped) in expected {
+        let tile = layer.get_tile(x, y).unwrap();
+        assert_eq!(tile.id(), 2);
+        assert_eq!(
+            tile.rotation_and_flip(),
+            (rotation, flipped),
+            "tile at {x},{y}"
+        );
+    }
+    assert!(layer.get_tile(2, 0).unwrap().rotate_hex_120);
+    assert!(layer.get_tile(3, 0).unwrap().rotate_hex_120);
+}
+
 #[test]
 fn test_ldk_export() {
     let r = Loader::new()
@@ -353,6 +616,46 @@ fn test_class_property() {
     };
 }
 
+#[test]
+fn test_typed_property_accessors() {
+    let r = Loader::new()
+        .load_tmx_map("assets/tiled_class_property.tmx")
+        .unwrap();
+    let object = r
+        .get_layer(1)
+        .unwrap()
+        .as_object_layer()
+        .unwrap()
+        .get_object(0)
+        .unwrap();
+    let (property_type, members) = object.properties.get_class("class property").unwrap();
+    assert_eq!(property_type, "test_type");
+    assert_eq!(members.get_int("test_property_1"), Some(3));
+    assert_eq!(members.get_as::<i32>("test_property_1"), Some(3));
+
+    // Wrong types and missing keys return `None`
+    assert_eq!(members.get_float("test_property_1"), None);
+    assert_eq!(members.get_string("test_property_1"), None);
+    assert_eq!(members.get_as::<bool>("test_property_1"), None);
+    assert_eq!(members.get_int("missing"), None);
+    assert!(object.properties.get_class("missing").is_none());
+    assert_eq!(object.properties.get_bool("class property"), None);
+
+    let r = Loader::new()
+        .load_tmx_map("assets/tiled_base64.tmx")
+        .unwrap();
+    let properties = &r.get_layer(0).unwrap().properties;
+    assert_eq!(properties.get_string("prop2"), Some("some text"));
+    assert_eq!(
+        properties.get_as::<String>("prop2").as_deref(),
+        Some("some text")
+    );
+    assert_eq!(properties.get_int("prop1"), None);
+    assert_eq!(properties.get_file("prop2"), None);
+    assert_eq!(properties.get_object("prop2"), None);
+    assert_eq!(properties.get_color("prop2"), None);
+}
+
 #[test]
 fn test_tint_color() {
     let r = Loader::new()
@@ -478,6 +781,66 @@ fn test_object_template_property() {
     assert_eq!(object_nt.get_tile().unwrap().id(), 44);
 }
 
+#[test]
+fn test_object_template_overrides() {
+    let map = Loader::new()
+        .load_tmx_map("assets/templates/overrides.tmx")
+        .unwrap();
+    let layer = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    let object = |index| layer.get_object(index).unwrap();
+
+    // Only the position is set on the object, everything else comes from the template
+    let moved = object(0);
+    assert_eq!((moved.x, moved.y), (10.0, 20.0));
+    assert_eq!(&*moved.name, "enemy");
+    assert_eq!(&*moved.user_type, "npc");
+    assert_eq!(moved.rotation, 45.0);
+    assert_eq!(
+        moved.shape,
+        ObjectShape::Ellipse {
+            width: 16.0,
+            height: 24.0
+        }
+    );
+    assert_eq!(moved.properties.get_int("hp"), Some(10));
+    assert_eq!(moved.properties.get_string("faction"), Some("orcs"));
+
+    // Overridden properties and attributes take precedence, even when empty
+    let overridden = object(1);
+    assert_eq!(&*overridden.name, "");
+    assert_eq!(overridden.properties.get_int("hp"), Some(25));
+    assert_eq!(overridden.properties.get_string("faction"), Some("orcs"));
+
+    // New properties are added to the template's, and resizing applies to its shape
+    let added = object(2);
+    assert_eq!(added.properties.len(), 3);
+    assert_eq!(added.properties.get_string("loot"), Some("gold"));
+    assert_eq!(added.properties.get_int("hp"), Some(10));
+    assert_eq!(
+        added.shape,
+        ObjectShape::Ellipse {
+            width: 32.0,
+            height: 48.0
+        }
+    );
+
+    // Shapes declared by the object replace the template's one entirely
+    let replaced = object(3);
+    assert_eq!(&*replaced.user_type, "boss");
+    assert_eq!(replaced.rotation, 0.0);
+    assert_eq!(
+        replaced.shape,
+        ObjectShape::Polygon {
+            points: vec![(0.0, 0.0), (16.0, 0.0), (16.0, 16.0)]
+        }
+    );
+
+    // Points are placed where the object is, not where the template's object was
+    let spawn = object(4);
+    assert_eq!(&*spawn.name, "spawn");
+    assert_eq!(spawn.shape, ObjectShape::Point(64.0, 96.0));
+}
+
 #[test]
 fn test_templates() {
     let mut loader = Loader::new();
@@ -525,6 +888,168 @@ fn test_reading_wang_sets() {
     assert_eq!(readed_damage, damage_value);
 }
 
+#[test]
+fn test_wang_id_accessors() {
+    let wang_id = WangId([1, 2, 3, 4, 5, 6, 7, 8]);
+    assert_eq!(
+        [
+            wang_id.top(),
+            wang_id.right(),
+            wang_id.bottom(),
+            wang_id.left()
+        ],
+        [1, 3, 5, 7]
+    );
+    assert_eq!(
+        [
+            wang_id.top_right(),
+            wang_id.bottom_right(),
+            wang_id.bottom_left(),
+            wang_id.top_left()
+        ],
+        [2, 4, 6, 8]
+    );
+    assert_eq!(
+        (0..4).map(|i| wang_id.edge(i)).collect::<Vec<_>>(),
+        [1, 3, 5, 7]
+    );
+    assert_eq!(
+        (0..4).map(|i| wang_id.corner(i)).collect::<Vec<_>>(),
+        [2, 4, 6, 8]
+    );
+    assert_eq!(
+        (0..8).map(WangId::is_corner_index).collect::<Vec<_>>(),
+        [false, true, false, true, false, true, false, true]
+    );
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_csv_wangsets.tmx")
+        .unwrap();
+    let wang_set = &map.tilesets()[0].wang_sets[1];
+    assert_eq!(
+        wang_set.wang_id_of(10),
+        Some(WangId([2, 2, 0, 2, 0, 2, 2, 2]))
+    );
+    assert!(wang_set
+        .tiles_matching(|wang_id| wang_id.top() == 2)
+        .all(|(id, tile)| tile.wang_id.top() == 2 && wang_set.wang_id_of(id).is_some()));
+    assert!(wang_set
+        .tiles_matching(|wang_id| *wang_id == WangId([2, 2, 0, 2, 0, 2, 2, 2]))
+        .any(|(id, _)| id == 10));
+}
+
+#[test]
+fn test_wang_id_transformations() {
+    // Where the color at each position ends up, as in Tiled's `WangId::rotate`,
+    // `WangId::flipHorizontally` and `WangId::flipVertically`
+    let rotated_once = [2, 3, 4, 5, 6, 7, 0, 1];
+    let flipped_horizontally = [0, 7, 6, 5, 4, 3, 2, 1];
+    let flipped_vertically = [4, 3, 2, 1, 0, 7, 6, 5];
+
+    for position in 0..8 {
+        let mut colors = [0; 8];
+        colors[position] = 1;
+        let wang_id = WangId(colors);
+        let moved_to = |transformed: WangId| transformed.0.iter().position(|&c| c == 1).unwrap();
+
+        assert_eq!(moved_to(wang_id.rotated(0)), position);
+        assert_eq!(moved_to(wang_id.rotated(1)), rotated_once[position]);
+        assert_eq!(moved_to(wang_id.rotated(2)), (position + 4) % 8);
+        assert_eq!(moved_to(wang_id.rotated(3)), (position + 6) % 8);
+        assert_eq!(moved_to(wang_id.rotated(4)), position);
+        assert_eq!(moved_to(wang_id.rotated(-1)), (position + 6) % 8);
+        assert_eq!(
+            moved_to(wang_id.flipped_horizontally()),
+            flipped_horizontally[position]
+        );
+        assert_eq!(
+            moved_to(wang_id.flipped_vertically()),
+            flipped_vertically[position]
+        );
+        // Flipping both ways is the same as rotating by 180 degrees
+        assert_eq!(
+            wang_id.flipped_horizontally().flipped_vertically(),
+            wang_id.rotated(2)
+        );
+    }
+}
+
+#[test]
+fn test_wang_tile_flip_flags() {
+    let mut loader = Loader::new();
+    let tileset = loader
+        .load_tsx_tileset("assets/tilesheet_wangsets_flipped.tsx")
+        .unwrap();
+
+    let wangset = &tileset.wang_sets[0];
+    assert_eq!(
+        wangset.properties.get("flip probability"),
+        Some(&PropertyValue::FloatValue(0.5))
+    );
+
+    let flags = |id| {
+        let tile = wangset.wang_tiles.get(&id).unwrap();
+        (tile.hflip, tile.vflip, tile.dflip)
+    };
+    assert_eq!(flags(0), (false, false, false));
+    assert_eq!(flags(1), (true, false, false));
+    assert_eq!(flags(2), (false, true, true));
+}
+
+#[test]
+fn test_wang_set_lookup() {
+    let mut loader = Loader::new();
+    let map = loader
+        .load_tmx_map("assets/tiled_embedded_wangsets.tmx")
+        .unwrap();
+
+    let (tileset, cliffs) = map.find_wang_set("cliffs").unwrap();
+    assert!(Arc::ptr_eq(tileset, &map.tilesets()[1]));
+    assert_eq!(
+        cliffs.properties.get("height"),
+        Some(&PropertyValue::IntValue(3))
+    );
+    let (grass, color) = cliffs.color("grass").unwrap();
+    assert_eq!(grass, 2);
+    assert_eq!(color.probability, 0.5);
+    assert!(cliffs.color("dirt").is_none());
+    assert_eq!(cliffs.wang_tiles.len(), 3);
+    assert!(cliffs.wang_tiles[&3].hflip);
+
+    // Tilesets are searched in order, external ones included
+    let (tileset, wall) = map.find_wang_set("Wall").unwrap();
+    assert!(Arc::ptr_eq(tileset, &map.tilesets()[0]));
+    assert_eq!(wall.color("Dark").unwrap().0, 2);
+    assert!(map.find_wang_set("missing").is_none());
+
+    // Embedded Wang sets are parsed exactly like the ones of external tilesets
+    let external = loader
+        .load_tsx_tileset("assets/tilesheet_terrain.tsx")
+        .unwrap();
+    assert_eq!(map.tilesets()[1].wang_sets, external.wang_sets);
+    assert_eq!(external.wang_set("paths").unwrap().tile, Some(2));
+}
+
+#[test]
+fn test_tileset_transformations() {
+    let mut loader = Loader::new();
+    let tileset = loader
+        .load_tsx_tileset("assets/tilesheet_wangsets_flipped.tsx")
+        .unwrap();
+    assert_eq!(
+        tileset.transformations,
+        Some(Transformations {
+            hflip: true,
+            vflip: false,
+            rotate: true,
+            prefer_untransformed: false,
+        })
+    );
+
+    let tileset = loader.load_tsx_tileset("assets/tilesheet.tsx").unwrap();
+    assert_eq!(tileset.transformations, None);
+}
+
 #[test]
 fn test_text_object() {
     let mut loader = Loader::new();
@@ -574,3 +1099,4779 @@ fn test_text_object() {
         _ => panic!(),
     };
 }
+
+#[test]
+fn test_map_from_reader() {
+    let mut loader = Loader::new();
+    let from_reader = loader
+        .load_tmx_map_from(
+            std::io::Cursor::new(include_bytes!("../assets/tiled_base64_external.tmx")),
+            "assets/does_not_exist.tmx",
+        )
+        .unwrap();
+    let from_path = loader
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    compare_everything_but_tileset_sources(&from_reader, &from_path);
+    assert_eq!(from_reader.tilesets(), from_path.tilesets());
+
+    // Maps with embedded tilesets only don't need to resolve anything relative to the path.
+    let embedded = loader
+        .load_tmx_map_from(
+            std::io::Cursor::new(include_bytes!("../assets/tiled_base64.tmx")),
+            "dummy.tmx",
+        )
+        .unwrap();
+    assert_eq!(embedded.tilesets().len(), 1);
+}
+
+#[test]
+fn test_class_attributes() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_class_attributes.tmx")
+        .unwrap();
+    assert_eq!(map.user_type.as_deref(), Some("map_class"));
+
+    let tileset = &map.tilesets()[0];
+    assert_eq!(tileset.user_type.as_deref(), Some("tileset_class"));
+    let wang_set = &tileset.wang_sets[0];
+    assert_eq!(wang_set.user_type.as_deref(), Some("wangset_class"));
+    assert_eq!(
+        wang_set.wang_colors[0].user_type.as_deref(),
+        Some("wangcolor_class")
+    );
+
+    let layer_classes: Vec<_> = map.layers().map(|l| l.user_type.clone()).collect();
+    assert_eq!(
+        layer_classes,
+        [
+            "tile_layer_class",
+            "object_layer_class",
+            "image_layer_class",
+            "group_layer_class"
+        ]
+        .map(|c| Some(c.to_owned()))
+    );
+    let group = map.get_layer(3).unwrap().as_group_layer().unwrap();
+    assert_eq!(
+        group.get_layer(0).unwrap().user_type.as_deref(),
+        Some("nested_layer_class")
+    );
+
+    let objects = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    assert_eq!(
+        objects.get_object(0).unwrap().user_type.as_ref(),
+        "object_class"
+    );
+    assert_eq!(
+        objects.get_object(1).unwrap().user_type.as_ref(),
+        "object_type"
+    );
+}
+
+#[test]
+fn test_object_world_vertices() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_rotation.tmx")
+        .unwrap();
+    let layer = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    let vertices = |idx| layer.get_object(idx).unwrap().world_vertices();
+    let assert_close = |actual: Vec<(f32, f32)>, expected: &[(f32, f32)]| {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!(
+                (a.0 - e.0).abs() < 1e-3 && (a.1 - e.1).abs() < 1e-3,
+                "{:?} != {:?}",
+                actual,
+                expected
+            );
+        }
+    };
+
+    // Rectangles rotate around their top-left corner
+    assert_close(
+        vertices(0),
+        &[(100., 100.), (100., 120.), (90., 120.), (90., 100.)],
+    );
+    // Tile objects rotate around their bottom-left corner
+    assert_close(
+        vertices(1),
+        &[(82., 80.), (82., 112.), (50., 112.), (50., 80.)],
+    );
+    assert_close(vertices(2), &[(200., 200.), (190., 200.), (200., 190.)]);
+    assert_close(vertices(3), &[(12., 34.)]);
+    assert_close(
+        vertices(5),
+        &[(10., 20.), (40., 20.), (40., 60.), (10., 60.)],
+    );
+
+    let ellipse = layer.get_object(4).unwrap();
+    assert_eq!(
+        ellipse.world_vertices().len(),
+        tiled::ObjectData::DEFAULT_ELLIPSE_SEGMENTS
+    );
+    assert_close(
+        ellipse.world_ellipse(4).unwrap(),
+        &[(20., 5.), (10., 10.), (0., 5.), (10., 0.)],
+    );
+    assert!(layer.get_object(0).unwrap().world_ellipse(4).is_none());
+}
+
+#[test]
+fn test_cache_eviction() {
+    use std::{cell::Cell, rc::Rc};
+
+    // Simulates editing the tileset file on disk between loads.
+    let tileset_name = Rc::new(Cell::new("tilesheet"));
+    let reader_tileset_name = tileset_name.clone();
+    let mut loader = Loader::with_reader(move |path: &std::path::Path| -> std::io::Result<_> {
+        let mut contents = std::fs::read_to_string(path)?;
+        if path.ends_with("tilesheet.tsx") {
+            contents = contents.replace(
+                "name=\"tilesheet\"",
+                &format!("name=\"{}\"", reader_tileset_name.get()),
+            );
+        }
+        Ok(std::io::Cursor::new(contents))
+    });
+
+    let map = loader
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    assert_eq!(map.tilesets()[0].name, "tilesheet");
+    assert_eq!(loader.cache().tilesets().count(), 1);
+    assert_eq!(loader.cache().templates().count(), 0);
+
+    tileset_name.set("edited");
+    let map = loader
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    assert_eq!(map.tilesets()[0].name, "tilesheet");
+
+    let evicted = loader
+        .cache_mut()
+        .remove_tileset("assets/tilesheet.tsx")
+        .unwrap();
+    assert_eq!(evicted.name, "tilesheet");
+    assert!(loader
+        .cache_mut()
+        .remove_tileset("assets/tilesheet.tsx")
+        .is_none());
+
+    let map = loader
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    assert_eq!(map.tilesets()[0].name, "edited");
+
+    loader.cache_mut().clear();
+    assert_eq!(loader.cache().tilesets().count(), 0);
+}
+
+#[test]
+fn test_effective_layer_attributes() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_nested_parallax.tmx")
+        .unwrap();
+
+    let top = map.get_layer(0).unwrap().effective_attributes();
+    assert_eq!(
+        top,
+        EffectiveLayerAttributes {
+            offset_x: 3.0,
+            offset_y: 4.0,
+            parallax_x: 0.25,
+            parallax_y: 1.0,
+            tint_color: Some(Color {
+                alpha: 0xff,
+                red: 0x12,
+                green: 0x34,
+                blue: 0x56
+            }),
+        }
+    );
+
+    let outer = map.get_layer(1).unwrap().as_group_layer().unwrap();
+    let inner = outer.get_layer(0).unwrap();
+    let nested = inner.as_group_layer().unwrap().get_layer(0).unwrap();
+    assert_eq!(nested.name, "nested");
+    assert_eq!(
+        nested.effective_attributes(),
+        EffectiveLayerAttributes {
+            offset_x: 111.0,
+            offset_y: 7.0,
+            parallax_x: 0.5,
+            parallax_y: 2.0,
+            tint_color: Some(Color {
+                alpha: 0x80,
+                red: 0x80,
+                green: 0x40,
+                blue: 0x00
+            }),
+        }
+    );
+}
+
+#[test]
+fn test_effective_opacity_and_visibility() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+
+    let top = map.get_layer(0).unwrap();
+    assert!(top.parent().is_none());
+    assert_eq!(top.effective_opacity(), 1.0);
+    assert!(top.effective_visibility());
+    assert_eq!(top.effective_tint(), None);
+
+    // group-2 (50%, hidden) > group-3 (50%, tinted) > tile-3 (80%, tinted)
+    let nested = map.find_layer(|layer| layer.name == "tile-3").unwrap();
+    let ancestors: Vec<_> = nested.ancestors().map(|layer| layer.id()).collect();
+    assert_eq!(ancestors, [8, 6]);
+    assert!(nested.visible);
+    assert!(!nested.effective_visibility());
+    assert!((nested.effective_opacity() - 0.2).abs() < f32::EPSILON);
+    assert_eq!(
+        nested.effective_tint(),
+        Some(Color {
+            alpha: 0xff,
+            red: 0x80,
+            green: 0x80,
+            blue: 0x40
+        })
+    );
+}
+
+#[test]
+fn test_image_rect_for_tile() {
+    let mut tileset = Loader::new()
+        .load_tsx_tileset("assets/tilesheet.tsx")
+        .unwrap();
+    let rect = |x, y| {
+        Some(Rect {
+            x,
+            y,
+            width: 32,
+            height: 32,
+        })
+    };
+
+    assert_eq!(tileset.image_rect_for_tile(0), rect(0, 0));
+    assert_eq!(tileset.image_rect_for_tile(13), rect(416, 0));
+    assert_eq!(tileset.image_rect_for_tile(15), rect(32, 32));
+    assert_eq!(tileset.image_rect_for_tile(83), rect(416, 160));
+    assert_eq!(tileset.image_rect_for_tile(84), None);
+
+    tileset.margin = 2;
+    assert_eq!(tileset.image_rect_for_tile(15), rect(34, 34));
+    tileset.spacing = 1;
+    assert_eq!(tileset.image_rect_for_tile(15), rect(35, 35));
+    tileset.margin = 0;
+    assert_eq!(tileset.image_rect_for_tile(29), rect(33, 66));
+
+    // Without columns, they are derived from the image: (448 - 2 * 4 + 2) / (32 + 2) = 13
+    tileset.columns = 0;
+    tileset.margin = 4;
+    tileset.spacing = 2;
+    assert_eq!(tileset.image_rect_for_tile(13), rect(4, 38));
+
+    let collection = Loader::new()
+        .load_tsx_tileset("assets/tilesheet_image_collection.tsx")
+        .unwrap();
+    assert_eq!(collection.image_rect_for_tile(0), None);
+}
+
+#[test]
+fn test_tileset_from_reader() {
+    let mut loader = Loader::new();
+    let from_reader = loader
+        .load_tsx_tileset_from(
+            std::io::Cursor::new(include_bytes!("../assets/tilesheet.tsx")),
+            "assets/tilesheet.tsx",
+        )
+        .unwrap();
+    let from_path = loader.load_tsx_tileset("assets/tilesheet.tsx").unwrap();
+    assert_eq!(from_reader, from_path);
+    assert_eq!(
+        from_reader.image.as_ref().unwrap().source,
+        PathBuf::from("assets/tilesheet.png")
+    );
+    assert!(loader.cache().get_tileset("assets/tilesheet.tsx").is_none());
+}
+
+#[test]
+fn test_per_load_cache() {
+    let mut loader = Loader::new();
+    let mut editor_cache = DefaultResourceCache::new();
+    let mut runtime_cache = DefaultResourceCache::new();
+
+    let editor_map = loader
+        .load_tmx_map_with_cache("assets/tiled_object_template.tmx", &mut editor_cache)
+        .unwrap();
+    let runtime_map = loader
+        .load_tmx_map_with_cache("assets/tiled_object_template.tmx", &mut runtime_cache)
+        .unwrap();
+    compare_everything_but_tileset_sources(&editor_map, &runtime_map);
+
+    for cache in [&editor_cache, &runtime_cache] {
+        assert!(cache.get_tileset("assets/tilesheet.tsx").is_some());
+        assert!(cache
+            .get_template("assets/tiled_object_template.tx")
+            .is_some());
+    }
+    // Each cache loaded its own copy of the tileset.
+    assert!(!std::sync::Arc::ptr_eq(
+        &editor_cache.get_tileset("assets/tilesheet.tsx").unwrap(),
+        &runtime_cache.get_tileset("assets/tilesheet.tsx").unwrap()
+    ));
+    assert_eq!(loader.cache().tilesets().count(), 0);
+    assert_eq!(loader.cache().templates().count(), 0);
+}
+
+#[test]
+fn test_tileset_offset() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_tileset_offset.tmx")
+        .unwrap();
+    let offsets: Vec<_> = map
+        .tilesets()
+        .iter()
+        .map(|tileset| (tileset.offset_x, tileset.offset_y))
+        .collect();
+    // External, embedded and missing <tileoffset> respectively
+    assert_eq!(offsets, [(-4, 16), (8, -2), (0, 0)]);
+}
+
+#[test]
+fn test_tile_attributes() {
+    let tileset = Loader::new()
+        .load_tsx_tileset("assets/tilesheet_tile_attributes.tsx")
+        .unwrap();
+    let tile = |id| tileset.get_tile(id).unwrap();
+
+    assert_eq!(tile(0).user_type.as_deref(), Some("grass"));
+    assert_eq!(tile(0).probability, 0.25);
+    // Older files use `type` instead of `class`
+    assert_eq!(tile(1).user_type.as_deref(), Some("water"));
+    assert_eq!(tile(1).probability, 0.0);
+    assert_eq!(tile(2).user_type, None);
+    assert_eq!(tile(2).probability, 3.5);
+    assert_eq!(tile(2).properties.get_bool("walkable"), Some(true));
+    // Tiles without a <tile> element use the defaults
+    assert_eq!(tile(3).user_type, None);
+    assert_eq!(tile(3).probability, 1.0);
+}
+
+#[test]
+fn test_load_free_functions() {
+    let loaded = Loader::new()
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    let map = tiled::load_map("assets/tiled_base64_external.tmx").unwrap();
+    compare_everything_but_tileset_sources(&map, &loaded);
+    let tileset = tiled::load_tileset("assets/tilesheet.tsx").unwrap();
+    assert_eq!(*map.tilesets()[0], tileset);
+
+    #[cfg(feature = "json")]
+    assert_eq!(
+        tiled::load_map("assets/tiled_json.tmj").unwrap(),
+        Loader::new().load_tmj_map("assets/tiled_json.tmj").unwrap()
+    );
+}
+
+#[cfg(feature = "async-tokio")]
+#[tokio::test]
+async fn test_load_free_functions_async() {
+    fn assert_send<T: Send>(value: T) -> T {
+        value
+    }
+
+    let loaded = Loader::new()
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    let map = assert_send(tiled::load_map_async("assets/tiled_base64_external.tmx"))
+        .await
+        .unwrap();
+    compare_everything_but_tileset_sources(&map, &loaded);
+    let tileset = assert_send(tiled::load_tileset_async("assets/tilesheet.tsx"))
+        .await
+        .unwrap();
+    assert_eq!(*map.tilesets()[0], tileset);
+
+    #[cfg(feature = "json")]
+    assert_eq!(
+        assert_send(tiled::load_map_async("assets/tiled_json.tmj"))
+            .await
+            .unwrap(),
+        Loader::new().load_tmj_map("assets/tiled_json.tmj").unwrap()
+    );
+}
+
+#[test]
+fn test_compressed_documents() {
+    let plain = Loader::new()
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    let gzip = Loader::new()
+        .load_tmx_map("assets/tiled_base64_external.tmx.gz")
+        .unwrap();
+    assert_eq!(gzip, plain);
+
+    let mut tileset = Loader::new()
+        .load_tsx_tileset("assets/tilesheet.tsx.gz")
+        .unwrap();
+    assert_eq!(
+        tileset.source,
+        Some(PathBuf::from("assets/tilesheet.tsx.gz"))
+    );
+    tileset.source = plain.tilesets()[0].source.clone();
+    assert_eq!(tileset, *plain.tilesets()[0]);
+
+    #[cfg(feature = "zstd")]
+    {
+        let zstd = Loader::new()
+            .load_tmx_map("assets/tiled_base64_external.tmx.zst")
+            .unwrap();
+        assert_eq!(zstd, plain);
+    }
+}
+
+#[cfg(feature = "async-tokio")]
+#[tokio::test]
+async fn test_compressed_documents_async() {
+    let plain = Loader::new()
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    let gzip = Loader::new()
+        .load_tmx_map_async("assets/tiled_base64_external.tmx.gz")
+        .await
+        .unwrap();
+    assert_eq!(gzip, plain);
+
+    #[cfg(feature = "zstd")]
+    {
+        let zstd = Loader::new()
+            .load_tmx_map_async("assets/tiled_base64_external.tmx.zst")
+            .await
+            .unwrap();
+        assert_eq!(zstd, plain);
+    }
+
+    /// Hands out its contents a byte at a time, and only every other time it is polled.
+    struct Trickle {
+        contents: Vec<u8>,
+        pos: usize,
+        ready: bool,
+    }
+
+    impl tokio::io::AsyncRead for Trickle {
+        fn poll_read(
+            mut self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+            buf: &mut tokio::io::ReadBuf<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            use tokio::io::AsyncBufRead;
+            let byte = match self.as_mut().poll_fill_buf(cx) {
+                std::task::Poll::Ready(Ok(bytes)) => bytes.to_vec(),
+                std::task::Poll::Ready(Err(err)) => return std::task::Poll::Ready(Err(err)),
+                std::task::Poll::Pending => return std::task::Poll::Pending,
+            };
+            buf.put_slice(&byte);
+            self.consume(byte.len());
+            std::task::Poll::Ready(Ok(()))
+        }
+    }
+
+    impl tokio::io::AsyncBufRead for Trickle {
+        fn poll_fill_buf(
+            self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<std::io::Result<&[u8]>> {
+            let this = self.get_mut();
+            this.ready = !this.ready;
+            if !this.ready {
+                cx.waker().wake_by_ref();
+                return std::task::Poll::Pending;
+            }
+            let end = this.contents.len().min(this.pos + 1);
+            std::task::Poll::Ready(Ok(&this.contents[this.pos..end]))
+        }
+
+        fn consume(self: std::pin::Pin<&mut Self>, amt: usize) {
+            self.get_mut().pos += amt;
+        }
+    }
+
+    struct TrickleReader;
+
+    impl tiled::AsyncResourceReader for TrickleReader {
+        type Resource = Trickle;
+        type Error = std::io::Error;
+
+        async fn read_from(&mut self, path: &Path) -> std::io::Result<Self::Resource> {
+            Ok(Trickle {
+                contents: std::fs::read(path)?,
+                pos: 0,
+                ready: false,
+            })
+        }
+    }
+
+    let trickled = Loader::with_reader(TrickleReader)
+        .load_tmx_map_async("assets/tiled_base64_external.tmx.gz")
+        .await
+        .unwrap();
+    assert_eq!(trickled, plain);
+}
+
+#[test]
+fn test_shared_template_is_read_once() {
+    let reads = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let reader = {
+        let reads = reads.clone();
+        move |path: &std::path::Path| {
+            reads.borrow_mut().push(path.to_owned());
+            std::fs::File::open(path).map(std::io::BufReader::new)
+        }
+    };
+    let mut loader = Loader::with_reader(reader);
+    let first = loader
+        .load_tmx_map("assets/sibling_maps/first/map.tmx")
+        .unwrap();
+    let second = loader
+        .load_tmx_map("assets/sibling_maps/second/map.tmx")
+        .unwrap();
+
+    let count = |name: &str| {
+        reads
+            .borrow()
+            .iter()
+            .filter(|path| path.file_name().unwrap() == name)
+            .count()
+    };
+    assert_eq!(count("simple_figure.tx"), 1);
+    assert_eq!(count("simple_figure.tsx"), 1);
+    assert_eq!(count("tilesheet.tsx"), 1);
+    assert!(std::sync::Arc::ptr_eq(
+        &first.tilesets()[0],
+        &second.tilesets()[0]
+    ));
+
+    let template_path = std::path::Path::new("assets/templates/simple_figure.tx");
+    assert!(loader.cache().get_template(template_path).is_some());
+    let objects: Vec<_> = second
+        .get_layer(1)
+        .unwrap()
+        .as_object_layer()
+        .unwrap()
+        .objects()
+        .map(|object| object.name.to_string())
+        .collect();
+    assert_eq!(objects, ["simple_figure", "simple_figure"]);
+}
+
+#[test]
+fn test_layer_search() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+
+    assert_eq!(map.get_layer_by_name("group-2").unwrap().id(), 6);
+    // Only top-level layers are searched by name
+    assert!(map.get_layer_by_name("tile-3").is_none());
+    assert!(map.get_layer_by_name("missing").is_none());
+
+    let layers: Vec<_> = map
+        .layers_recursive()
+        .map(|(layer, depth)| (layer.id(), depth))
+        .collect();
+    // tile-1, group-1, tile-2, group-2, group-3, tile-3
+    assert_eq!(layers, [(1, 0), (3, 0), (5, 1), (6, 0), (8, 1), (9, 2)]);
+
+    let nested = map.find_layer(|layer| layer.name == "tile-3").unwrap();
+    assert_eq!(nested.id(), 9);
+    let group = map
+        .find_layer(|layer| layer.as_group_layer().is_some() && layer.id() > 3)
+        .unwrap();
+    assert_eq!(group.name, "group-2");
+    assert!(map.find_layer(|layer| layer.name == "missing").is_none());
+}
+
+#[test]
+fn test_wrapped_text_object() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_text_object_wrapped.tmx")
+        .unwrap();
+
+    let group = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    let mut objects = group.objects();
+    match &objects.next().unwrap().shape {
+        ObjectShape::Text {
+            font_family,
+            pixel_size,
+            wrap,
+            color,
+            bold,
+            italic,
+            underline,
+            strikeout,
+            kerning,
+            halign,
+            valign,
+            text,
+            width,
+            height,
+        } => {
+            assert_eq!(font_family.as_str(), "Noto Serif");
+            assert_eq!(*pixel_size, 12);
+            assert!(*wrap);
+            assert_eq!(
+                *color,
+                Color {
+                    red: 32,
+                    green: 64,
+                    blue: 128,
+                    alpha: 255
+                }
+            );
+            assert!(!*bold);
+            assert!(!*italic);
+            assert!(!*underline);
+            assert!(!*strikeout);
+            assert!(!*kerning);
+            assert_eq!(*halign, HorizontalAlignment::Justify);
+            assert_eq!(*valign, VerticalAlignment::Center);
+            assert_eq!(text.replace("\r\n", "\n"), "Fish & chips\ncost <5> coins");
+            assert_eq!(*width, 48.);
+            assert_eq!(*height, 40.);
+        }
+        _ => panic!(),
+    };
+    match &objects.next().unwrap().shape {
+        ObjectShape::Text { text, .. } => assert_eq!(text, ""),
+        _ => panic!(),
+    };
+}
+
+#[test]
+fn test_identical_images_are_shared() {
+    let image = |tileset: &tiled::Tileset, id| tileset.get_tile(id).unwrap().image.clone().unwrap();
+
+    let tileset = Loader::new()
+        .load_tsx_tileset("assets/tilesheet_shared_images.tsx")
+        .unwrap();
+    assert!(Arc::ptr_eq(&image(&tileset, 0), &image(&tileset, 1)));
+    // Images of the same file that differ otherwise can't be shared
+    assert!(!Arc::ptr_eq(&image(&tileset, 0), &image(&tileset, 2)));
+    assert_eq!(image(&tileset, 0).source, image(&tileset, 2).source);
+
+    // Images are also shared between the tilesets and the layers of a map
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_shared_images.tmx")
+        .unwrap();
+    let layer_image = |index| match map.get_layer(index).unwrap().layer_type() {
+        LayerType::Image(layer) => layer.image.clone().unwrap(),
+        _ => panic!("Not an image layer"),
+    };
+    assert!(Arc::ptr_eq(&layer_image(0), &layer_image(1)));
+    assert!(Arc::ptr_eq(&layer_image(0), &image(&map.tilesets()[0], 0)));
+}
+
+#[test]
+fn test_image_collection_tileset() {
+    let tileset = Loader::new()
+        .load_tsx_tileset("assets/tilesheet_image_collection.tsx")
+        .unwrap();
+
+    assert!(tileset.is_image_collection());
+    assert_eq!(tileset.columns, 0);
+    assert_eq!(tileset.tilecount, 2);
+
+    let mut tiles: Vec<_> = tileset.image_collection_tiles().collect();
+    tiles.sort_by_key(|(id, _)| *id);
+    assert_eq!(tiles.len(), 2);
+
+    let (id, image) = tiles[0];
+    assert_eq!(id, 0);
+    assert_eq!(image.source, PathBuf::from("assets/tilesheet.png"));
+    assert_eq!((image.width, image.height), (448, 192));
+    assert_eq!(image.transparent_colour, None);
+
+    let (id, image) = tiles[1];
+    assert_eq!(id, 3);
+    assert_eq!(image.source, PathBuf::from("assets/folder/small.png"));
+    assert_eq!((image.width, image.height), (16, 24));
+    assert_eq!(
+        image.transparent_colour,
+        Some(Color {
+            red: 255,
+            green: 0,
+            blue: 255,
+            alpha: 255
+        })
+    );
+    assert_eq!(tileset.get_tile(3).unwrap().image.as_deref(), Some(image));
+
+    let atlas = Loader::new()
+        .load_tsx_tileset("assets/tilesheet.tsx")
+        .unwrap();
+    assert!(!atlas.is_image_collection());
+    assert_eq!(atlas.image_collection_tiles().count(), 0);
+}
+
+#[test]
+fn test_parse_error_position() {
+    let path = "assets/tiled_malformed_layer.tmx";
+    let err = Loader::new().load_tmx_map(path).unwrap_err();
+
+    let contents = std::fs::read_to_string(path).unwrap();
+    let expected_position = contents.find("<layer id=\"2\"").unwrap() as u64;
+    match &err {
+        tiled::Error::ParseError {
+            path: err_path,
+            position,
+            json_path: None,
+            err: inner,
+        } => {
+            assert_eq!(err_path, &PathBuf::from(path));
+            assert_eq!(*position, expected_position);
+            assert!(matches!(**inner, tiled::Error::MalformedAttribute { .. }));
+        }
+        _ => panic!("expected a parse error, got {:?}", err),
+    }
+    assert!(matches!(
+        err.inner(),
+        tiled::Error::MalformedAttribute { .. }
+    ));
+    let message = err.to_string();
+    assert!(message.contains(path));
+    assert!(message.contains(&format!("at byte offset {}", expected_position)));
+}
+
+/// Settings that replace the tilesets that can't be read by placeholders.
+fn placeholder_settings() -> LoadSettings {
+    LoadSettings {
+        placeholder_missing_tilesets: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_placeholder_missing_tileset() {
+    let mut loader = Loader::new();
+    assert!(loader
+        .load_tmx_map("assets/tiled_missing_tileset.tmx")
+        .is_err());
+
+    let map = loader
+        .load_tmx_map_with_settings("assets/tiled_missing_tileset.tmx", placeholder_settings())
+        .unwrap();
+    let missing_path = PathBuf::from("assets/missing_tileset.tsx");
+    match map.warnings() {
+        [ParseWarning::MissingTileset { path, error }] => {
+            assert_eq!(path, &missing_path);
+            assert_eq!(error.kind(), ErrorKind::ResourceLoadingError);
+            assert!(matches!(
+                &**error,
+                Error::ResourceLoadingError { path, .. } if path == &missing_path
+            ));
+        }
+        warnings => panic!("Unexpected warnings: {:?}", warnings),
+    }
+
+    assert_eq!(map.tilesets().len(), 2);
+    assert_eq!(
+        map.tilesets()[0].source,
+        Some(PathBuf::from("assets/tilesheet.tsx"))
+    );
+    let placeholder = &map.tilesets()[1];
+    assert_eq!(placeholder.source, Some(missing_path.clone()));
+    assert_eq!(placeholder.name, "missing_tileset");
+    assert_eq!(placeholder.tilecount, 0);
+    assert_eq!(placeholder.tiles().len(), 0);
+    // Placeholders are not cached
+    assert!(loader.cache().get_tileset(&missing_path).is_none());
+
+    let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    let present = layer.get_tile(0, 0).unwrap();
+    assert_eq!(present.tileset_index(), 0);
+    assert!(present.get_tile().is_some());
+    let missing_tile = layer.get_tile(1, 0).unwrap();
+    assert_eq!((missing_tile.tileset_index(), missing_tile.id()), (1, 1));
+    assert!(missing_tile.get_tile().is_none());
+    assert!(layer.get_tile(0, 1).is_none());
+    let flipped = layer.get_tile(1, 1).unwrap();
+    assert_eq!((flipped.tileset_index(), flipped.id()), (1, 0));
+    assert!(flipped.flip_h());
+}
+
+#[test]
+fn test_placeholder_malformed_tileset() {
+    let mut reader = MemoryResourceReader::new();
+    reader.insert_str(
+        "map.tmx",
+        r#"<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32">
+ <tileset firstgid="1" source="broken.tsx"/>
+</map>"#,
+    );
+    reader.insert_str("broken.tsx", r#"<tileset name="broken" tilewidth="32">"#);
+    // Tilesets that were read but can't be parsed are not replaced
+    let err = Loader::with_reader(reader)
+        .load_tmx_map_with_settings("map.tmx", placeholder_settings())
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        tiled::Error::ParseError { ref path, .. } if path == Path::new("broken.tsx")
+    ));
+    assert_ne!(err.kind(), ErrorKind::ResourceLoadingError);
+}
+
+#[test]
+fn test_editor_settings() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_editor_settings.tmx")
+        .unwrap();
+    assert_eq!(
+        map.editor_settings,
+        Some(EditorSettings {
+            chunk_size: Some((32, 8)),
+            export_target: Some("../export/level.json".to_owned()),
+            export_format: Some("json".to_owned()),
+        })
+    );
+    let layer = match map.get_layer(0).unwrap().as_tile_layer().unwrap() {
+        TileLayer::Infinite(layer) => layer,
+        _ => panic!("expected an infinite layer"),
+    };
+    assert_eq!(layer.get_tile(1, 1).unwrap().id(), 3);
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64.tmx")
+        .unwrap();
+    assert_eq!(map.editor_settings, None);
+}
+
+/// A cache that never stores anything, so that every lookup misses.
+struct ForgetfulCache;
+
+impl ResourceCache for ForgetfulCache {
+    fn get_tileset(
+        &self,
+        _path: impl AsRef<tiled::ResourcePath>,
+    ) -> Option<std::sync::Arc<tiled::Tileset>> {
+        None
+    }
+    fn insert_tileset(
+        &mut self,
+        _path: impl AsRef<tiled::ResourcePath>,
+        _tileset: std::sync::Arc<tiled::Tileset>,
+    ) {
+    }
+    fn get_template(
+        &self,
+        _path: impl AsRef<tiled::ResourcePath>,
+    ) -> Option<std::sync::Arc<tiled::Template>> {
+        None
+    }
+    fn insert_template(
+        &mut self,
+        _path: impl AsRef<tiled::ResourcePath>,
+        _template: std::sync::Arc<tiled::Template>,
+    ) {
+    }
+    fn remove_tileset(
+        &mut self,
+        _path: impl AsRef<tiled::ResourcePath>,
+    ) -> Option<std::sync::Arc<tiled::Tileset>> {
+        None
+    }
+    fn remove_template(
+        &mut self,
+        _path: impl AsRef<tiled::ResourcePath>,
+    ) -> Option<std::sync::Arc<tiled::Template>> {
+        None
+    }
+    fn clear(&mut self) {}
+    fn tilesets(
+        &self,
+    ) -> impl Iterator<Item = (&tiled::ResourcePath, &std::sync::Arc<tiled::Tileset>)> {
+        std::iter::empty()
+    }
+    fn templates(
+        &self,
+    ) -> impl Iterator<Item = (&tiled::ResourcePath, &std::sync::Arc<tiled::Template>)> {
+        std::iter::empty()
+    }
+}
+
+#[test]
+fn test_duplicate_tileset_is_read_once() {
+    let reads = std::rc::Rc::new(std::cell::Cell::new(0));
+    let reader = {
+        let reads = reads.clone();
+        move |path: &std::path::Path| {
+            if path.file_name().unwrap() == "tilesheet.tsx" {
+                reads.set(reads.get() + 1);
+            }
+            std::fs::File::open(path).map(std::io::BufReader::new)
+        }
+    };
+    let mut loader = Loader::with_cache_and_reader(ForgetfulCache, reader);
+    let map = loader
+        .load_tmx_map("assets/tiled_duplicate_tileset.tmx")
+        .unwrap();
+
+    assert_eq!(reads.get(), 1);
+    assert_eq!(map.tilesets().len(), 2);
+    assert!(std::sync::Arc::ptr_eq(
+        &map.tilesets()[0],
+        &map.tilesets()[1]
+    ));
+
+    let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    let tile = layer.get_tile(1, 0).unwrap();
+    assert_eq!((tile.tileset_index(), tile.id()), (1, 1));
+    let tile = layer.get_tile(1, 1).unwrap();
+    assert_eq!((tile.tileset_index(), tile.id()), (1, 0));
+}
+
+#[test]
+fn test_map_id_attributes() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_ids.tmx")
+        .unwrap();
+    assert_eq!(map.next_layer_id, Some(4));
+    assert_eq!(map.next_object_id, Some(5));
+    assert_eq!(
+        map.background_color,
+        Some(Color {
+            alpha: 0x80,
+            red: 0x10,
+            green: 0x20,
+            blue: 0x30
+        })
+    );
+    // The nested object has an ID that Tiled would give out again
+    assert_eq!(map.max_used_object_id(), Some(7));
+    assert!(map.max_used_object_id() >= map.next_object_id);
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_flipped.tmx")
+        .unwrap();
+    assert_eq!(map.max_used_object_id(), None);
+}
+
+#[test]
+fn test_object_tiles() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_template_gid.tmx")
+        .unwrap();
+    let layer = map.get_layer(0).unwrap().as_object_layer().unwrap();
+
+    // The template's tileset starts at GID 10, so GID 55 is its 46th tile
+    let templated = layer.get_object(0).unwrap().get_tile().unwrap();
+    assert!(matches!(
+        templated.tileset_location(),
+        TilesetLocation::Template(..)
+    ));
+    assert_eq!(templated.get_tileset().name, "tilesheet_template");
+    assert_eq!(templated.id(), 45);
+    assert!(templated.get_tile().is_some());
+    assert_eq!(
+        (templated.flip_h(), templated.flip_v(), templated.flip_d()),
+        (true, false, false)
+    );
+
+    let tile = layer.get_object(1).unwrap().get_tile().unwrap();
+    assert_eq!(tile.tileset_location(), &TilesetLocation::Map(0));
+    assert_eq!(tile.get_tileset().name, "tilesheet");
+    assert_eq!(tile.id(), 45);
+    assert!(tile.get_tile().is_some());
+    assert_eq!(
+        (tile.flip_h(), tile.flip_v(), tile.flip_d()),
+        (false, true, false)
+    );
+}
+
+#[test]
+fn test_template_tilesets() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_template_tileset.tmx")
+        .unwrap();
+    let template_tilesets = map.template_tilesets();
+    let names: Vec<_> = template_tilesets
+        .iter()
+        .map(|tileset| tileset.name.as_str())
+        .collect();
+    assert_eq!(names, ["template_only", "tilesheet_template"]);
+    assert_eq!(map.tilesets().len(), 1);
+
+    // The tile of an object is drawable from the tileset of its template
+    let object = map.get_object_by_id(1).unwrap();
+    let tile = object.get_tile().unwrap();
+    assert!(std::ptr::eq(tile.get_tileset(), &**template_tilesets[0]));
+    assert_eq!(tile.id(), 5);
+    assert!(tile.get_tile().is_some());
+    assert_eq!(
+        tile.get_tileset().image.as_ref().unwrap().source,
+        PathBuf::from("assets/tilesheet.png")
+    );
+
+    let reloaded = write_and_reload(
+        &map,
+        "assets/tiled_template_tileset.tmx",
+        WriteOptions::default(),
+    );
+    let reloaded_names: Vec<_> = reloaded
+        .template_tilesets()
+        .iter()
+        .map(|tileset| tileset.name.clone())
+        .collect();
+    assert_eq!(reloaded_names, names);
+    let tile = reloaded.get_object_by_id(3).unwrap().get_tile().unwrap();
+    assert_eq!(tile.get_tileset().name, "template_only");
+    assert_eq!(tile.id(), 5);
+}
+
+#[test]
+fn test_tileset_lookups() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_tile_render_size.tmx")
+        .unwrap();
+    let index_of = |found: Option<(usize, &Arc<Tileset>)>| {
+        let (index, tileset) = found?;
+        assert!(Arc::ptr_eq(tileset, &map.tilesets()[index]));
+        Some(index)
+    };
+
+    assert_eq!(
+        index_of(map.tileset_by_source("assets/tilesheet.tsx")),
+        Some(2)
+    );
+    assert_eq!(
+        index_of(map.tileset_by_source("./assets/folder/../tilesheet_render_size.tsx")),
+        Some(0)
+    );
+    assert_eq!(index_of(map.tileset_by_source("tilesheet.tsx")), None);
+
+    assert_eq!(index_of(map.tileset_by_name("embedded")), Some(1));
+    assert_eq!(index_of(map.tileset_by_name("tilesheet")), Some(2));
+    assert_eq!(index_of(map.tileset_by_name("missing")), None);
+
+    for (index, tileset) in map.tilesets().iter().enumerate() {
+        assert_eq!(map.tileset_index_of(tileset), Some(index));
+    }
+    // Equal tilesets that aren't the map's own aren't found
+    let copy = Arc::new(Tileset::clone(&map.tilesets()[0]));
+    assert_eq!(map.tileset_index_of(&copy), None);
+}
+
+#[test]
+fn test_tile_children_in_any_order() {
+    // Each file holds the permutations of a tile's children as nested elements, then again with
+    // self-closing `<objectgroup/>` and `<properties/>` elements
+    for path in [
+        "assets/tilesheet_tile_children_1_4.tsx",
+        "assets/tilesheet_tile_children_1_8.tsx",
+        "assets/tilesheet_tile_children_1_10.tsx",
+    ] {
+        let tileset = Loader::new().load_tsx_tileset(path).unwrap();
+        for id in 0..4 {
+            let tile = tileset.get_tile(id).unwrap();
+            assert_eq!(
+                tile.animation.as_deref(),
+                Some(
+                    &[
+                        Frame {
+                            tile_id: id,
+                            duration: 100
+                        },
+                        Frame {
+                            tile_id: id + 1,
+                            duration: 200
+                        },
+                    ][..]
+                ),
+                "animation of tile {} in {}",
+                id,
+                path
+            );
+            let collision = tile.collision.as_ref().unwrap();
+            if id < 2 {
+                assert_eq!(collision.object_data().len(), 1, "tile {} in {}", id, path);
+                assert_eq!(tile.properties.get_int("tile"), Some(id as i32));
+            } else {
+                assert!(
+                    collision.object_data().is_empty(),
+                    "tile {} in {}",
+                    id,
+                    path
+                );
+                assert!(tile.properties.is_empty(), "tile {} in {}", id, path);
+            }
+        }
+        assert!(tileset.get_tile(4).unwrap().animation.is_none());
+    }
+}
+
+#[test]
+fn test_tile_render_size_and_fill_mode() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_tile_render_size.tmx")
+        .unwrap();
+    let tilesets = map.tilesets();
+
+    assert_eq!(tilesets[0].tile_render_size, TileRenderSize::Grid);
+    assert_eq!(tilesets[0].fill_mode, FillMode::PreserveAspectFit);
+    assert_eq!(tilesets[1].tile_render_size, TileRenderSize::Grid);
+    assert_eq!(tilesets[1].fill_mode, FillMode::Stretch);
+    assert_eq!(tilesets[2].tile_render_size, TileRenderSize::Tile);
+    assert_eq!(tilesets[2].fill_mode, FillMode::Stretch);
+
+    let err = Loader::new()
+        .load_tmx_map("assets/tiled_invalid_fill_mode.tmx")
+        .unwrap_err();
+    match err.inner() {
+        tiled::Error::MalformedAttribute {
+            element, attribute, ..
+        } => assert_eq!((*element, *attribute), ("tileset", "fillmode")),
+        err => panic!("unexpected error: {:?}", err),
+    }
+}
+
+#[test]
+fn test_load_settings_skip_layer_contents() {
+    let full = Loader::new()
+        .load_tmx_map("assets/tiled_large_base64.tmx")
+        .unwrap();
+    let ground = full.get_layer(0).unwrap();
+    assert!(!ground.contents_skipped());
+    let ground = as_finite(ground.as_tile_layer().unwrap());
+    assert_eq!(ground.get_tile(1023, 1023).unwrap().id(), 1023 * 2 % 84);
+
+    let start = std::time::Instant::now();
+    let map = Loader::new()
+        .load_tmx_map_with_settings(
+            "assets/tiled_large_base64.tmx",
+            LoadSettings {
+                skip_tile_layer_data: true,
+                layer_name_filter: Some(LayerNameFilter::new(|name| name != "decoration")),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    println!(
+        "Loaded with skipped layer contents in {:?}",
+        start.elapsed()
+    );
+
+    assert_eq!(map.layers().len(), 4);
+
+    let ground = map.get_layer(0).unwrap();
+    assert!(ground.contents_skipped());
+    assert_eq!(ground.name, "ground");
+    assert_eq!(ground.id(), 1);
+    assert_eq!(
+        ground.properties.get("biome"),
+        Some(&PropertyValue::StringValue("plains".to_string()))
+    );
+    let ground = as_finite(ground.as_tile_layer().unwrap());
+    assert_eq!((ground.width(), ground.height()), (1024, 1024));
+    assert!(ground.get_tile(0, 0).is_none());
+
+    let spawns = map.get_layer(1).unwrap();
+    assert!(!spawns.contents_skipped());
+    assert_eq!(spawns.as_object_layer().unwrap().objects().len(), 1);
+
+    let decoration = map.get_layer(2).unwrap();
+    assert!(decoration.contents_skipped());
+    assert_eq!(
+        decoration.properties.get("animated"),
+        Some(&PropertyValue::BoolValue(true))
+    );
+    assert_eq!(decoration.as_object_layer().unwrap().objects().len(), 0);
+
+    let overlay = map.get_layer_by_name("overlays").unwrap();
+    assert!(!overlay.contents_skipped());
+    let overlay = overlay.as_group_layer().unwrap().get_layer(0).unwrap();
+    assert!(overlay.contents_skipped());
+    assert!(overlay.as_tile_layer().unwrap().get_tile(0, 0).is_none());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_round_trip() {
+    for path in [
+        "assets/tiled_base64_external.tmx",
+        "assets/tiled_base64_zlib_infinite.tmx",
+        "assets/tiled_class_property.tmx",
+        "assets/tiled_csv_wangsets.tmx",
+        "assets/tiled_editor_settings.tmx",
+        "assets/tiled_group_layers.tmx",
+        "assets/tiled_image_layers.tmx",
+        "assets/tiled_object_template.tmx",
+        "assets/tiled_text_object.tmx",
+        "assets/tiled_tile_render_size.tmx",
+    ] {
+        let map = Loader::new().load_tmx_map(path).unwrap();
+        let json = serde_json::to_string(&map).unwrap();
+        let deserialized: Map = serde_json::from_str(&json).unwrap();
+        assert_eq!(map, deserialized, "{} changed after a round trip", path);
+    }
+
+    // The errors of missing tilesets keep their kind and message
+    let map = Loader::new()
+        .load_tmx_map_with_settings(
+            "assets/tiled_missing_tileset.tmx",
+            LoadSettings {
+                placeholder_missing_tilesets: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let json = serde_json::to_string(map.warnings()).unwrap();
+    let deserialized: Vec<ParseWarning> = serde_json::from_str(&json).unwrap();
+    assert_eq!(map.warnings(), deserialized);
+}
+
+#[test]
+fn test_tileset_grid() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_isometric_collection.tmx")
+        .unwrap();
+    let tilesets = map.tilesets();
+
+    assert_eq!(
+        tilesets[0].grid,
+        Some(Grid {
+            orientation: Orientation::Isometric,
+            width: 64,
+            height: 32,
+        })
+    );
+    assert!(tilesets[0].is_image_collection());
+    assert_eq!(
+        tilesets[1].grid,
+        Some(Grid {
+            orientation: Orientation::Isometric,
+            width: 32,
+            height: 16,
+        })
+    );
+    assert_eq!(tilesets[2].grid, None);
+
+    let tileset = Loader::new()
+        .load_tsx_tileset("assets/tilesheet_image_collection.tsx")
+        .unwrap();
+    assert_eq!(
+        tileset.grid,
+        Some(Grid {
+            orientation: Orientation::Orthogonal,
+            width: 1,
+            height: 1,
+        })
+    );
+}
+
+#[cfg(feature = "async-tokio")]
+#[test]
+fn test_async_load_future_size() {
+    // Loading futures are often spawned on tasks with small stacks
+    let mut loader = Loader::new();
+    let future = loader.load_tmx_map_async("assets/tiled_group_layers.tmx");
+    let size = std::mem::size_of_val(&future);
+    assert!(size < 4096, "the map loading future is {} bytes", size);
+}
+
+#[cfg(feature = "async-tokio")]
+#[tokio::test]
+async fn test_async_loading_is_send() {
+    fn assert_send<T: Send>(_: &T) {}
+
+    let mut loader = Loader::new();
+    let mut cache = DefaultResourceCache::new();
+    assert_send(&loader.load_tmx_map_async("assets/tiled_group_layers.tmx"));
+    assert_send(&loader.load_tmx_map_with_cache_async("assets/tiled_group_layers.tmx", &mut cache));
+    assert_send(&loader.load_tmx_map_with_settings_async(
+        "assets/tiled_group_layers.tmx",
+        LoadSettings::default(),
+    ));
+    assert_send(&loader.load_tmx_map_from_async(
+        include_bytes!("../assets/tiled_object_template.tmx").as_slice(),
+        "assets/tiled_object_template.tmx",
+    ));
+    assert_send(&loader.load_tsx_tileset_async("assets/tilesheet.tsx"));
+
+    // Maps with templates and group layers go through recursive futures.
+    let handle = tokio::spawn(async {
+        let mut loader = Loader::new();
+        let templated = loader
+            .load_tmx_map_async("assets/tiled_object_template.tmx")
+            .await?;
+        let grouped = loader
+            .load_tmx_map_async("assets/tiled_group_layers.tmx")
+            .await?;
+        tiled::Result::Ok((templated, grouped))
+    });
+    let (templated, grouped) = handle.await.unwrap().unwrap();
+    assert_eq!(
+        templated,
+        Loader::new()
+            .load_tmx_map("assets/tiled_object_template.tmx")
+            .unwrap()
+    );
+    assert_eq!(
+        grouped,
+        Loader::new()
+            .load_tmx_map("assets/tiled_group_layers.tmx")
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_sparse_tileset_tiles() {
+    let tileset = Loader::new()
+        .load_tsx_tileset("assets/tilesheet_sparse.tsx")
+        .unwrap();
+
+    let tiles: Vec<_> = tileset.tiles().collect();
+    // Every ID below the tile count, plus the tile defined past it
+    assert_eq!(tiles.len(), 257);
+    assert!(tiles.iter().map(|(id, _)| *id).eq((0..256).chain([300])));
+    let defined_ids: Vec<_> = tiles
+        .iter()
+        .filter(|(_, tile)| tile.is_defined())
+        .map(|(id, _)| *id)
+        .collect();
+    assert_eq!(defined_ids, [3, 17, 255, 300]);
+
+    let implicit = tileset.get_tile(0).unwrap();
+    assert!(!implicit.is_defined());
+    assert!(implicit.properties.is_empty());
+    assert_eq!(implicit.probability, 1.0);
+    assert!(tileset.get_tile(256).is_none());
+
+    let solid = tileset.get_tile(3).unwrap();
+    assert!(solid.is_defined());
+    assert_eq!(solid.properties.get_bool("solid"), Some(true));
+    assert_eq!(
+        tileset
+            .get_tile(17)
+            .unwrap()
+            .animation
+            .as_ref()
+            .unwrap()
+            .len(),
+        2
+    );
+    assert_eq!(tileset.get_tile(300).unwrap().probability, 0.5);
+
+    let mut defined: Vec<_> = tileset.defined_tiles().map(|(id, _)| id).collect();
+    defined.sort_unstable();
+    assert_eq!(defined, [3, 17, 255, 300]);
+
+    // Image collections have no implicit tiles
+    let collection = Loader::new()
+        .load_tsx_tileset("assets/tilesheet_image_collection.tsx")
+        .unwrap();
+    let ids: Vec<_> = collection.tiles().map(|(id, _)| id).collect();
+    assert_eq!(ids, [0, 3]);
+    assert!(collection.get_tile(1).is_none());
+}
+
+#[test]
+fn test_embedded_image() {
+    let tileset = Loader::new()
+        .load_tsx_tileset("assets/tilesheet_embedded_image.tsx")
+        .unwrap();
+    let image = tileset.image.unwrap();
+
+    let original = std::fs::read("assets/embedded_image.png").unwrap();
+    assert_eq!(image.embedded_data.as_ref().unwrap().len(), original.len());
+    assert_eq!(image.embedded_data.as_ref().unwrap(), &original);
+    assert_eq!(image.format.as_deref(), Some("png"));
+    assert_eq!(image.source, PathBuf::new());
+    assert_eq!((image.width, image.height), (8, 8));
+    assert_eq!(
+        image.transparent_colour,
+        Some(Color {
+            alpha: 0xff,
+            red: 0xff,
+            green: 0x00,
+            blue: 0xff,
+        })
+    );
+    assert_eq!(tileset.columns, 2);
+
+    let regular = Loader::new()
+        .load_tsx_tileset("assets/tilesheet.tsx")
+        .unwrap()
+        .image
+        .unwrap();
+    assert_eq!(regular.embedded_data, None);
+    assert_eq!(regular.format, None);
+}
+
+#[test]
+fn test_unknown_property_type_strictness() {
+    let path = "assets/tiled_unknown_property_type.tmx";
+    let load = |strictness| {
+        Loader::new().load_tmx_map_with_settings(
+            path,
+            LoadSettings {
+                strictness,
+                ..Default::default()
+            },
+        )
+    };
+
+    let err = Loader::new().load_tmx_map(path).unwrap_err();
+    assert!(matches!(
+        err.inner(),
+        tiled::Error::UnknownPropertyType { type_name } if type_name == "enum"
+    ));
+    assert!(load(Strictness::Error).is_err());
+
+    let map = load(Strictness::Warn).unwrap();
+    assert_eq!(
+        map.properties.get("difficulty"),
+        Some(&PropertyValue::Unknown {
+            type_name: "enum".to_owned(),
+            raw_value: "hard".to_owned(),
+        })
+    );
+    assert_eq!(map.properties.get_string("name"), Some("Unknown types"));
+    let tile = map.tilesets()[0].get_tile(0).unwrap();
+    assert_eq!(
+        tile.properties.get("terrain"),
+        Some(&PropertyValue::Unknown {
+            type_name: "enum".to_owned(),
+            raw_value: "grass".to_owned(),
+        })
+    );
+    let mut properties: Vec<_> = map
+        .warnings()
+        .iter()
+        .map(|warning| match warning {
+            ParseWarning::UnknownPropertyType {
+                property,
+                type_name,
+            } => {
+                assert_eq!(type_name, "enum");
+                property.as_str()
+            }
+            warning => panic!("unexpected warning: {}", warning),
+        })
+        .collect();
+    properties.sort_unstable();
+    assert_eq!(properties, ["difficulty", "terrain"]);
+
+    let ignored = load(Strictness::Ignore).unwrap();
+    assert!(ignored.warnings().is_empty());
+    assert_eq!(ignored.properties, map.properties);
+    assert_eq!(ignored.tilesets(), map.tilesets());
+}
+
+/// Returns the outline of the tile at the given position, in pixels.
+fn tile_outline(map: &Map, x: i32, y: i32) -> Vec<(f32, f32)> {
+    let (left, top) = map.tile_to_pixel(x, y);
+    let (w, h) = (map.tile_width as f32, map.tile_height as f32);
+    let side = map.hex_side_length as f32;
+    let outline = match map.orientation {
+        Orientation::Orthogonal => vec![(0.0, 0.0), (w, 0.0), (w, h), (0.0, h)],
+        Orientation::Isometric | Orientation::Staggered => {
+            vec![(w / 2.0, 0.0), (w, h / 2.0), (w / 2.0, h), (0.0, h / 2.0)]
+        }
+        Orientation::Hexagonal if map.stagger_axis == tiled::StaggerAxis::X => {
+            let offset = (w - side) / 2.0;
+            vec![
+                (offset, 0.0),
+                (offset + side, 0.0),
+                (w, h / 2.0),
+                (offset + side, h),
+                (offset, h),
+                (0.0, h / 2.0),
+            ]
+        }
+        Orientation::Hexagonal => {
+            let offset = (h - side) / 2.0;
+            vec![
+                (w / 2.0, 0.0),
+                (w, offset),
+                (w, offset + side),
+                (w / 2.0, h),
+                (0.0, offset + side),
+                (0.0, offset),
+            ]
+        }
+    };
+    outline
+        .into_iter()
+        .map(|(x, y)| (left + x, top + y))
+        .collect()
+}
+
+fn outline_contains(outline: &[(f32, f32)], (px, py): (f32, f32)) -> bool {
+    let mut inside = false;
+    for (i, &(x1, y1)) in outline.iter().enumerate() {
+        let (x2, y2) = outline[(i + 1) % outline.len()];
+        if (y1 > py) != (y2 > py) && px < x1 + (py - y1) / (y2 - y1) * (x2 - x1) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+#[test]
+fn test_self_closing_elements() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_self_closing.tmx")
+        .unwrap();
+
+    assert_eq!(map.tilesets().len(), 2);
+    let tileset = &map.tilesets()[0];
+    assert!(tileset.get_tile(0).unwrap().is_defined());
+    assert!(tileset.wang_sets[0].wang_tiles.is_empty());
+    let empty = &map.tilesets()[1];
+    assert_eq!(empty.name, "empty");
+    assert_eq!(empty.tiles().len(), 0);
+
+    for index in 0..3 {
+        let layer = map.get_layer(index).unwrap();
+        let tiles = as_finite(layer.as_tile_layer().unwrap());
+        assert_eq!((tiles.width(), tiles.height()), (10, 10), "{}", layer.name);
+        assert!(tiles.get_tile(0, 0).is_none());
+    }
+
+    let no_objects = map.get_layer(3).unwrap().as_object_layer().unwrap();
+    assert!(no_objects.object_data().is_empty());
+    let objects = map.get_layer(4).unwrap().as_object_layer().unwrap();
+    assert_eq!(objects.object_data().len(), 2);
+    let properties = &objects.get_object(1).unwrap().properties;
+    assert_eq!(properties.get_string("note"), Some(""));
+    assert_eq!(
+        properties.get_class("settings"),
+        Some(("Settings", &Default::default()))
+    );
+
+    let no_image = map.get_layer(5).unwrap().as_image_layer().unwrap();
+    assert!(no_image.image.is_none());
+    let empty_group = map.get_layer(6).unwrap().as_group_layer().unwrap();
+    assert_eq!(empty_group.layers().len(), 0);
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_self_closing_infinite.tmx")
+        .unwrap();
+    for layer in map.layers() {
+        match layer.as_tile_layer().unwrap() {
+            TileLayer::Infinite(tiles) => assert_eq!(tiles.chunks().len(), 0),
+            TileLayer::Finite(_) => panic!("{} should be infinite", layer.name),
+        }
+    }
+}
+
+#[test]
+fn test_object_callback() {
+    use std::sync::{Arc, Mutex};
+
+    let load = |action| {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let collected = seen.clone();
+        let map = Loader::new()
+            .load_tmx_map_with_settings(
+                "assets/tiled_nested_objects.tmx",
+                LoadSettings {
+                    on_object: Some(ObjectCallback::new(move |object, layer| {
+                        let groups: Vec<_> = layer.group_path().map(|(name, _)| name).collect();
+                        collected.lock().unwrap().push((
+                            object.id(),
+                            layer.name().to_owned(),
+                            layer.id(),
+                            groups.join("/"),
+                        ));
+                        action
+                    })),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let seen = seen.lock().unwrap().clone();
+        (map, seen)
+    };
+
+    // Objects that are part of tiles are not passed to the callback
+    let (map, seen) = load(ObjectAction::Keep);
+    assert_eq!(
+        seen,
+        [
+            (1, "spawns".to_owned(), 1, String::new()),
+            (2, "spawns".to_owned(), 1, String::new()),
+            (3, "enemies".to_owned(), 4, "level/dungeon".to_owned()),
+            (4, "enemies".to_owned(), 4, "level/dungeon".to_owned()),
+            (5, "enemies".to_owned(), 4, "level/dungeon".to_owned()),
+        ]
+    );
+    let spawns = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    assert_eq!(spawns.object_data().len(), 2);
+    let collision = map.tilesets()[0].get_tile(0).unwrap().collision.clone();
+    assert_eq!(collision.unwrap().object_data().len(), 1);
+
+    let (map, seen) = load(ObjectAction::Discard);
+    assert_eq!(seen.len(), 5);
+    for (layer, _) in map.layers_recursive() {
+        if let Some(objects) = layer.as_object_layer() {
+            assert!(objects.object_data().is_empty());
+        }
+    }
+}
+
+#[test]
+fn test_tile_pixel_conversion() {
+    let hexagonal = Loader::new()
+        .load_tmx_map("assets/tiled_flipped_hexagonal.tmx")
+        .unwrap();
+    assert_eq!(hexagonal.hex_side_length, 16);
+    assert_eq!(
+        Loader::new()
+            .load_tmx_map("assets/tiled_base64.tmx")
+            .unwrap()
+            .hex_side_length,
+        0
+    );
+
+    let mut maps = Vec::new();
+    for orientation in [
+        Orientation::Orthogonal,
+        Orientation::Isometric,
+        Orientation::Staggered,
+        Orientation::Hexagonal,
+    ] {
+        for axis in [tiled::StaggerAxis::X, tiled::StaggerAxis::Y] {
+            for index in [tiled::StaggerIndex::Odd, tiled::StaggerIndex::Even] {
+                let mut map = hexagonal.clone();
+                map.orientation = orientation;
+                map.stagger_axis = axis;
+                map.stagger_index = index;
+                // Hexagons are sized so that their outline is made of the points closest to
+                // their center, which is what Tiled assumes when converting pixels to tiles.
+                let (width, height, side) = match (orientation, axis) {
+                    (Orientation::Hexagonal, tiled::StaggerAxis::X) => (26, 24, 10),
+                    (Orientation::Hexagonal, tiled::StaggerAxis::Y) => (24, 26, 10),
+                    _ => (64, 32, 0),
+                };
+                map.tile_width = width;
+                map.tile_height = height;
+                map.hex_side_length = side;
+                maps.push(map);
+            }
+        }
+    }
+
+    for map in &maps {
+        let describe = format!(
+            "{:?} map staggered along {:?} ({:?})",
+            map.orientation, map.stagger_axis, map.stagger_index
+        );
+        // Tile positions survive a round trip through the center of their bounding rectangle,
+        // including negative ones
+        for x in -5..5 {
+            for y in -5..5 {
+                let (px, py) = map.tile_to_pixel(x, y);
+                let center = (
+                    px + map.tile_width as f32 / 2.0,
+                    py + map.tile_height as f32 / 2.0,
+                );
+                assert_eq!(
+                    map.pixel_to_tile(center.0, center.1),
+                    (x, y),
+                    "{}",
+                    describe
+                );
+            }
+        }
+        // Any pixel belongs to the tile it is converted to. The fractional parts are chosen so
+        // that no pixel lies on the edge between two tiles.
+        for j in -100..100 {
+            for i in -100..100 {
+                let (px, py) = (i as f32 * 3.0 + 0.37, j as f32 * 2.0 + 0.53);
+                let (x, y) = map.pixel_to_tile(px, py);
+                assert!(
+                    outline_contains(&tile_outline(map, x, y), (px, py)),
+                    "({}, {}) is not inside tile ({}, {}) of {}",
+                    px,
+                    py,
+                    x,
+                    y,
+                    describe
+                );
+            }
+        }
+    }
+
+    let orthogonal = &maps[0];
+    assert_eq!(orthogonal.tile_to_pixel(-2, 3), (-128.0, 96.0));
+    let isometric = &maps[4];
+    // The map is 2 tiles high, so tile (0, 1) touches its left edge
+    assert_eq!(isometric.tile_to_pixel(0, 1), (0.0, 16.0));
+    assert_eq!(isometric.tile_to_pixel(0, 0), (32.0, 0.0));
+}
+
+#[test]
+fn test_remap_tilesets() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    let source = map.tilesets()[0].source.clone().unwrap();
+
+    let err = map.remap_tilesets(&HashMap::new()).unwrap_err();
+    match err {
+        Error::UnmatchedTilesets { paths } => assert_eq!(paths, vec![source.clone()]),
+        err => panic!("Unexpected error: {}", err),
+    }
+
+    // A tileset loaded by another loader isn't shared with the map
+    let tileset = Arc::new(Loader::new().load_tsx_tileset(&source).unwrap());
+    assert!(!Arc::ptr_eq(&map.tilesets()[0], &tileset));
+    let mapping = HashMap::from([(source, tileset.clone())]);
+    map.remap_tilesets(&mapping).unwrap();
+    assert!(Arc::ptr_eq(&map.tilesets()[0], &tileset));
+
+    let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    let tile = layer.get_tile(0, 0).unwrap();
+    assert!(std::ptr::eq(tile.get_tileset(), &*tileset));
+}
+
+#[test]
+fn test_gid_resolution() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    let tileset = map.tilesets()[0].clone();
+    let tilesets = [
+        MapTilesetGid {
+            first_gid: Gid(1),
+            tileset: tileset.clone(),
+        },
+        MapTilesetGid {
+            first_gid: Gid(1 + tileset.tilecount),
+            tileset: tileset.clone(),
+        },
+    ];
+
+    assert!(get_tileset_for_gid(&tilesets, Gid::EMPTY).is_none());
+    let (index, found) = get_tileset_for_gid(&tilesets, Gid(tileset.tilecount + 3)).unwrap();
+    assert_eq!(index, 1);
+    assert_eq!(found.first_gid, Gid(1 + tileset.tilecount));
+
+    assert!(LayerTileData::from_bits(0, &tilesets).is_none());
+    let data = LayerTileData::from_bits(0x80000000 | 5, &tilesets).unwrap();
+    assert_eq!(data.tileset_index(), 0);
+    assert_eq!(data.id(), 4);
+    assert!(data.flip_h());
+    assert!(!data.flip_v());
+}
+
+#[cfg(feature = "world")]
+#[test]
+fn test_world_list() {
+    let mut loader = Loader::new();
+    let world = loader.load_world("assets/world/world_list.world").unwrap();
+    assert!(world.patterns.is_empty());
+    assert!(!world.only_show_adjacent_maps);
+    assert_eq!(
+        world.maps,
+        vec![
+            WorldMapRef {
+                path: PathBuf::from("assets/world/map-x00-y00.tmx"),
+                x: 0,
+                y: 0,
+                width: Some(64),
+                height: Some(64),
+            },
+            WorldMapRef {
+                path: PathBuf::from("assets/world/map-x01-y00.tmx"),
+                x: 64,
+                y: -32,
+                width: Some(64),
+                height: Some(64),
+            },
+        ]
+    );
+
+    let maps = world.load_maps(&mut loader).unwrap();
+    assert_eq!(maps.len(), 2);
+    assert_eq!(maps[1].0, world.maps[1]);
+    // Both maps share the same tileset through the loader's cache
+    assert!(Arc::ptr_eq(
+        &maps[0].1.tilesets()[0],
+        &maps[1].1.tilesets()[0]
+    ));
+}
+
+#[cfg(feature = "world")]
+#[test]
+fn test_world_pattern() {
+    let world = Loader::new()
+        .load_world("assets/world/world_pattern.world")
+        .unwrap();
+    assert!(world.only_show_adjacent_maps);
+    assert_eq!(world.patterns.len(), 1);
+    let positions: Vec<_> = world
+        .maps
+        .iter()
+        .map(|map| (map.path.clone(), map.x, map.y))
+        .collect();
+    assert_eq!(
+        positions,
+        vec![
+            (PathBuf::from("assets/world/map-x00-y00.tmx"), 0, 32),
+            (PathBuf::from("assets/world/map-x00-y01.tmx"), 0, 96),
+            (PathBuf::from("assets/world/map-x01-y00.tmx"), 64, 32),
+        ]
+    );
+
+    // Positions that don't fit in an i32 don't match
+    let mut pattern = world.patterns[0].clone();
+    assert!(pattern.match_path("map-x99999999-y00.tmx").is_none());
+    pattern.multiplier_x = u32::MAX;
+    assert!(pattern.match_path("map-x01-y00.tmx").is_none());
+
+    // Readers that can't list files leave pattern maps unresolved
+    let mut loader = Loader::with_reader(|path: &std::path::Path| {
+        std::fs::File::open(path).map(std::io::BufReader::new)
+    });
+    let world = loader
+        .load_world("assets/world/world_pattern.world")
+        .unwrap();
+    assert!(world.maps.is_empty());
+    assert_eq!(world.patterns.len(), 1);
+}
+
+#[test]
+fn test_lookup_by_id() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_nested_objects.tmx")
+        .unwrap();
+    let enemies = map.get_layer_by_id(4).unwrap();
+    assert_eq!(enemies.name, "enemies");
+    assert_eq!(enemies.parent().unwrap().id(), 3);
+    assert!(map.get_layer_by_id(5).is_none());
+    assert!(map.get_layer_by_id(0).is_none());
+
+    let enemies = enemies.as_object_layer().unwrap();
+    assert_eq!(&*enemies.get_object_by_id(4).unwrap().name, "goblin");
+    assert_eq!(enemies.get_object_by_id(4).unwrap().x, 128.0);
+    assert!(enemies.get_object_by_id(1).is_none());
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_duplicate_ids.tmx")
+        .unwrap();
+    assert_eq!(map.get_layer_by_id(3).unwrap().name, "nested");
+    let first = map.get_layer_by_id(1).unwrap().as_object_layer().unwrap();
+    assert_eq!(&*first.get_object_by_id(2).unwrap().name, "first");
+}
+
+#[test]
+fn test_object_references() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_references.tmx")
+        .unwrap();
+
+    let switch = map.get_object_by_id(1).unwrap();
+    assert_eq!(&*switch.name, "switch");
+    // Objects nested in group layers are found as well
+    let door = map.get_object_by_id(2).unwrap();
+    assert_eq!(&*door.name, "door");
+    assert_eq!(door.x, 96.0);
+    assert!(map.get_object_by_id(4).is_none());
+    assert!(map.get_object_by_id(0).is_none());
+
+    assert_eq!(switch.resolve_object_property("target").unwrap(), door);
+    // Unset, dangling, missing and non-object properties don't resolve to anything
+    assert!(switch.resolve_object_property("unset").is_none());
+    assert!(switch.resolve_object_property("missing").is_none());
+    assert!(switch.resolve_object_property("none").is_none());
+    assert!(switch.resolve_object_property("name").is_none());
+
+    let layer = map.get_layer(0).unwrap();
+    assert_eq!(
+        layer.resolve_object_property("first_switch").unwrap(),
+        switch
+    );
+}
+
+#[test]
+fn test_validation() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_invalid_references.tmx")
+        .unwrap();
+    let issues = map.validate();
+    assert_eq!(
+        issues,
+        vec![
+            ValidationIssue::TileOutOfRange {
+                layer_id: 1,
+                layer_name: "tiles".to_owned(),
+                location: TileLocation::Layer { x: 1, y: 0 },
+                tileset_name: "small".to_owned(),
+                tile_id: 6,
+            },
+            ValidationIssue::DuplicateObjectId {
+                layer_id: 3,
+                layer_name: "objects".to_owned(),
+                object_id: 1,
+            },
+            ValidationIssue::InvalidObjectCoordinates {
+                layer_id: 3,
+                layer_name: "objects".to_owned(),
+                object_id: 1,
+            },
+            ValidationIssue::TileOutOfRange {
+                layer_id: 3,
+                layer_name: "objects".to_owned(),
+                location: TileLocation::Object { object_id: 2 },
+                tileset_name: "small".to_owned(),
+                tile_id: 4,
+            },
+        ]
+    );
+
+    let map = Loader::new()
+        .load_tmx_map_with_settings("assets/tiled_missing_tileset.tmx", placeholder_settings())
+        .unwrap();
+    assert!(!map.tilesets()[0].is_placeholder());
+    assert!(map.tilesets()[1].is_placeholder());
+    let missing: Vec<_> = map
+        .validate()
+        .into_iter()
+        .map(|issue| match issue {
+            ValidationIssue::MissingTileset {
+                location,
+                tileset_source,
+                ..
+            } => {
+                assert_eq!(
+                    tileset_source,
+                    Some(PathBuf::from("assets/missing_tileset.tsx"))
+                );
+                location
+            }
+            issue => panic!("Unexpected issue: {}", issue),
+        })
+        .collect();
+    assert_eq!(
+        missing,
+        vec![
+            TileLocation::Layer { x: 1, y: 0 },
+            TileLocation::Layer { x: 1, y: 1 }
+        ]
+    );
+
+    for path in [
+        "assets/tiled_base64_external.tmx",
+        "assets/tiled_image_layers.tmx",
+        "assets/tiled_object_groups.tmx",
+        "assets/tiled_base64_zlib_infinite.tmx",
+    ] {
+        let map = Loader::new().load_tmx_map(path).unwrap();
+        assert_eq!(map.validate(), vec![], "{}", path);
+    }
+
+    let settings = || LoadSettings {
+        validate: true,
+        ..Default::default()
+    };
+    let err = Loader::new()
+        .load_tmx_map_with_settings("assets/tiled_invalid_references.tmx", settings())
+        .unwrap_err();
+    match err {
+        Error::ValidationFailed(issue) => assert_eq!(issue, issues[0]),
+        err => panic!("Unexpected error: {}", err),
+    }
+    Loader::new()
+        .load_tmx_map_with_settings("assets/tiled_base64_external.tmx", settings())
+        .unwrap();
+}
+
+#[test]
+fn test_versions() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    let version: TiledVersion = map.version().parse().unwrap();
+    assert!(version >= TiledVersion::new(1, 0, 0));
+    assert!(map.tiled_version().is_some());
+    let tileset = &map.tilesets()[0];
+    assert_eq!(tileset.version.as_deref(), Some("1.4"));
+    assert_eq!(tileset.tiled_version.as_deref(), Some("1.4.0"));
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_no_tiled_version.tmx")
+        .unwrap();
+    assert_eq!(map.version(), "1.0");
+    assert_eq!(map.tiled_version(), None);
+    let map = Loader::new().load_tmx_map("assets/tiled_csv.tmx").unwrap();
+    // Embedded tilesets share the version of their map
+    assert_eq!(map.tilesets()[0].version, None);
+    assert_eq!(map.tilesets()[0].tiled_version, None);
+
+    assert_eq!("1.10.2".parse(), Ok(TiledVersion::new(1, 10, 2)));
+    assert_eq!("1.0".parse(), Ok(TiledVersion::new(1, 0, 0)));
+    assert_eq!(" 1.11.0-beta ".parse(), Ok(TiledVersion::new(1, 11, 0)));
+    assert!("1".parse::<TiledVersion>().is_err());
+    assert!("".parse::<TiledVersion>().is_err());
+    assert!("1..2".parse::<TiledVersion>().is_err());
+    assert!("one.two".parse::<TiledVersion>().is_err());
+
+    assert!(TiledVersion::new(1, 10, 0) > TiledVersion::new(1, 9, 2));
+    assert!(TiledVersion::new(2, 0, 0) > TiledVersion::new(1, 10, 0));
+    assert!(TiledVersion::new(1, 9, 0) < TiledVersion::new(1, 9, 1));
+    assert_eq!(TiledVersion::new(1, 9, 1).to_string(), "1.9.1");
+}
+
+#[test]
+fn test_declared_encodings() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_latin1.tmx")
+        .unwrap();
+    assert_eq!(map.properties.get_string("déjà vu"), Some("café crème"));
+    assert_eq!(map.get_layer(0).unwrap().name, "Château");
+    let group = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    let object = group.objects().next().unwrap();
+    assert_eq!(&*object.name, "Épée");
+    match &object.shape {
+        ObjectShape::Text { text, .. } => assert_eq!(text, "Façade & cæur"),
+        _ => panic!(),
+    }
+    let tileset = &map.tilesets()[0];
+    assert_eq!(tileset.name, "Tuiles à l'été");
+    assert_eq!(tileset.properties.get_string("propriété"), Some("über"));
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_utf8_bom.tmx")
+        .unwrap();
+    assert_eq!(map.get_layer(0).unwrap().name, "Château");
+    assert_eq!(map.tilesets()[0].name, "tilesheet");
+
+    // Any ASCII-compatible encoding is decoded, not only Latin-1
+    let mut map_file = br#"<?xml version="1.0" encoding="windows-1251"?>
+<map version="1.10" orientation="orthogonal" renderorder="right-down" width="1" height="1" tilewidth="32" tileheight="32" infinite="0" nextlayerid="2" nextobjectid="1">
+ <layer id="1" name=""#
+        .to_vec();
+    // "Замок", in Windows-1251
+    map_file.extend_from_slice(&[0xC7, 0xE0, 0xEC, 0xEE, 0xEA]);
+    map_file.extend_from_slice(
+        br#"" width="1" height="1">
+  <data encoding="csv">0</data>
+ </layer>
+</map>"#,
+    );
+    let map = Loader::new()
+        .load_tmx_map_from(&map_file[..], "cyrillic.tmx")
+        .unwrap();
+    assert_eq!(map.get_layer(0).unwrap().name, "Замок");
+}
+
+#[test]
+fn test_tile_collision() {
+    let tileset = Loader::new()
+        .load_tsx_tileset("assets/tilesheet_collision.tsx")
+        .unwrap();
+    let tile = tileset.get_tile(0).unwrap();
+    let objects: Vec<_> = tile.collision_objects().collect();
+    assert_eq!(objects.len(), 5);
+    assert!(matches!(objects[1].shape, ObjectShape::Ellipse { .. }));
+    assert!(matches!(objects[2].shape, ObjectShape::Polygon { .. }));
+    assert_eq!(objects[3].shape, ObjectShape::Point(2.0, 30.0));
+    assert_eq!(objects[4].rotation, 45.0);
+    // Only the unrotated rectangle is returned
+    assert_eq!(
+        tile.collision_rects().collect::<Vec<_>>(),
+        vec![(0.0, 16.0, 32.0, 16.0)]
+    );
+
+    let tile = tileset.get_tile(1).unwrap();
+    assert_eq!(tile.collision_objects().count(), 0);
+    assert_eq!(tile.collision_rects().count(), 0);
+    let tile = tileset.get_tile(2).unwrap();
+    assert_eq!(tile.collision_objects().count(), 0);
+}
+
+#[test]
+fn test_tile_data_size_check() {
+    let err = Loader::new()
+        .load_tmx_map("assets/tiled_base64_truncated.tmx")
+        .unwrap_err();
+    assert!(matches!(
+        err.inner(),
+        Error::InvalidTileDataSize {
+            expected: 16,
+            found: 12
+        }
+    ));
+    assert!(err.to_string().contains("truncated"));
+
+    let err = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib_oversized.tmx")
+        .unwrap_err();
+    assert!(matches!(
+        err.inner(),
+        Error::DecompressedTileDataTooLarge { limit: 16 }
+    ));
+}
+
+#[test]
+fn test_text_tile_data_size_check() {
+    let map = |width: u32, data: &str| {
+        format!(
+            r#"<map version="1.10" orientation="orthogonal" width="{0}" height="1" tilewidth="32" tileheight="32">
+ <tileset firstgid="1" name="tiles" tilewidth="32" tileheight="32" tilecount="4" columns="2"/>
+ <layer id="1" name="Tiles" width="{0}" height="1">
+  {1}
+ </layer>
+</map>"#,
+            width, data
+        )
+    };
+    let load = |width: u32, data: &str| {
+        Loader::new().load_tmx_map_from(map(width, data).as_bytes(), "map.tmx")
+    };
+
+    // Layers without any width used to load their tiles, and then panic when iterating over them
+    let err = load(0, r#"<data encoding="csv">1,2,3</data>"#).unwrap_err();
+    assert!(matches!(
+        err.inner(),
+        Error::InvalidTileDataSize {
+            expected: 0,
+            found: 12
+        }
+    ));
+    let err = load(2, r#"<data encoding="csv">1,2,3</data>"#).unwrap_err();
+    assert!(matches!(
+        err.inner(),
+        Error::InvalidTileDataSize {
+            expected: 8,
+            found: 12
+        }
+    ));
+    let err = load(2, r#"<data><tile gid="1"/></data>"#).unwrap_err();
+    assert!(matches!(
+        err.inner(),
+        Error::InvalidTileDataSize {
+            expected: 8,
+            found: 4
+        }
+    ));
+
+    let map = load(2, r#"<data><tile gid="1"/><tile gid="2"/></data>"#).unwrap();
+    let layer = match map.get_layer(0).unwrap().as_tile_layer().unwrap() {
+        tiled::TileLayer::Finite(layer) => layer,
+        _ => unreachable!(),
+    };
+    assert_eq!(layer.tiles().count(), 2);
+
+    let map = load(0, r#"<data encoding="csv"></data>"#).unwrap();
+    let layer = match map.get_layer(0).unwrap().as_tile_layer().unwrap() {
+        tiled::TileLayer::Finite(layer) => layer,
+        _ => unreachable!(),
+    };
+    assert_eq!(layer.tiles().count(), 0);
+}
+
+#[test]
+fn test_huge_declared_layer_size() {
+    // Small files that declare huge layers mustn't make room for all of their tiles beforehand
+    let huge_map = |size: &str| {
+        format!(
+            r#"<map version="1.10" orientation="orthogonal" width="{0}" height="{0}" tilewidth="32" tileheight="32">
+ <layer id="1" name="Huge" width="{0}" height="{0}">
+  <data encoding="base64" compression="zlib">eJxjZGBgYETCAAA4AAU=</data>
+ </layer>
+</map>"#,
+            size
+        )
+    };
+
+    let err = Loader::new()
+        .load_tmx_map_from(huge_map("100000").as_bytes(), "huge.tmx")
+        .unwrap_err();
+    assert!(matches!(
+        err.inner(),
+        Error::DecompressedTileDataTooLarge { .. } | Error::TileLayerTooLarge { .. }
+    ));
+    // Even without a limit on their size
+    let mut reader = MemoryResourceReader::new();
+    reader.insert_str("huge.tmx", &huge_map("100000"));
+    let err = Loader::with_reader(reader)
+        .load_tmx_map_with_settings(
+            "huge.tmx",
+            LoadSettings {
+                max_decompressed_layer_bytes: None,
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.inner(),
+        Error::InvalidTileDataSize { found: 16, .. } | Error::TileLayerTooLarge { .. }
+    ));
+
+    let err = Loader::new()
+        .load_tmx_map_from(huge_map("4000000000").as_bytes(), "huge.tmx")
+        .unwrap_err();
+    assert!(matches!(
+        err.inner(),
+        Error::TileLayerTooLarge {
+            width: 4000000000,
+            height: 4000000000
+        }
+    ));
+}
+
+#[test]
+fn test_decompression_limits() {
+    // A 2x2 layer whose data inflates to 16 MiB
+    let err = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib_bomb.tmx")
+        .unwrap_err();
+    assert!(matches!(
+        err.inner(),
+        Error::DecompressedTileDataTooLarge { limit: 16 }
+    ));
+
+    // The same data in a layer that declares room for it is still rejected by the default limit,
+    // since declared dimensions come from the file too
+    let bomb = std::fs::read_to_string("assets/tiled_base64_zlib_bomb.tmx")
+        .unwrap()
+        .replace(
+            r#"width="2" height="2""#,
+            r#"width="100000" height="100000""#,
+        );
+    let err = Loader::new()
+        .load_tmx_map_from(bomb.as_bytes(), "assets/tiled_base64_zlib_bomb.tmx")
+        .unwrap_err();
+    assert!(matches!(
+        err.inner(),
+        Error::DecompressedTileDataTooLarge { limit }
+            if *limit == LoadSettings::DEFAULT_MAX_DECOMPRESSED_LAYER_BYTES
+    ));
+
+    let settings = || LoadSettings {
+        max_decompressed_layer_bytes: Some(1024),
+        ..Default::default()
+    };
+    for path in [
+        "assets/tiled_base64_zlib.tmx",
+        "assets/tiled_base64_gzip.tmx",
+        "assets/tiled_base64.tmx",
+        "assets/tiled_csv.tmx",
+    ] {
+        let err = Loader::new()
+            .load_tmx_map_with_settings(path, settings())
+            .unwrap_err();
+        assert!(matches!(
+            err.inner(),
+            Error::DecompressedTileDataTooLarge { limit: 1024 }
+        ));
+    }
+    for limit in [Some(100 * 100 * 4), None] {
+        Loader::new()
+            .load_tmx_map_with_settings(
+                "assets/tiled_base64_zlib.tmx",
+                LoadSettings {
+                    max_decompressed_layer_bytes: limit,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+    }
+}
+
+#[test]
+fn test_group_layer_navigation() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    let names: Vec<_> = map
+        .layers()
+        .flat_map(|layer| layer.descendants_and_self())
+        .map(|layer| layer.name.clone())
+        .collect();
+    assert_eq!(
+        names,
+        ["tile-1", "group-1", "tile-2", "group-2", "group-3", "tile-3"]
+    );
+
+    let group = map.get_layer(2).unwrap().as_group_layer().unwrap();
+    assert_eq!(group.layers().len(), 1);
+    assert_eq!(group.get_layer(0).unwrap().name, "group-3");
+    let descendants: Vec<_> = group.descendants().collect();
+    assert_eq!(descendants.len(), 2);
+    // Descendants still know about their parents
+    assert_eq!(descendants[1].parent().unwrap().name, "group-3");
+    assert_eq!(descendants[1].ancestors().count(), 2);
+
+    let layer = group.as_layer();
+    assert_eq!(layer.name, "group-2");
+    assert_eq!(layer.id(), 6);
+    assert!(!layer.visible);
+    assert_eq!(
+        group.properties().get("key"),
+        Some(&PropertyValue::StringValue("value5".to_string()))
+    );
+    let nested = group.get_layer(0).unwrap().as_group_layer().unwrap();
+    assert_eq!(nested.as_layer().name, "group-3");
+    assert_eq!(nested.as_layer().parent().unwrap().name, "group-2");
+}
+
+#[test]
+fn test_typed_layer_iterators() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    let filtered: Vec<_> = map
+        .layers()
+        .filter_map(|layer| match layer.layer_type() {
+            LayerType::Tiles(layer) => Some(as_finite(layer)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        map.tile_layers().map(as_finite).collect::<Vec<_>>(),
+        filtered
+    );
+    assert_eq!(filtered.len(), 1);
+
+    let filtered: Vec<_> = map
+        .layers_recursive()
+        .filter_map(|(layer, _)| match layer.layer_type() {
+            LayerType::Tiles(layer) => Some(as_finite(layer)),
+            _ => None,
+        })
+        .collect();
+    let recursive: Vec<_> = map.tile_layers_recursive().map(as_finite).collect();
+    assert_eq!(recursive, filtered);
+    assert_eq!(recursive.len(), 3);
+    assert_eq!(map.object_layers_recursive().count(), 0);
+    assert_eq!(map.image_layers_recursive().count(), 0);
+
+    let group = map.get_layer(1).unwrap();
+    assert!(GroupLayer::try_from(&group).is_ok());
+    assert_eq!(TileLayer::try_from(&group).unwrap_err(), group);
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_image_layers.tmx")
+        .unwrap();
+    assert_eq!(map.image_layers().count(), 2);
+    assert_eq!(map.tile_layers().count(), 0);
+}
+
+#[test]
+fn test_path_resolver() {
+    let mut loader = Loader::new();
+    let err = loader
+        .load_tmx_map("assets/tiled_relocated_tileset.tmx")
+        .unwrap_err();
+    assert!(matches!(
+        err.inner(),
+        Error::ResourceLoadingError { path, .. } if path == &PathBuf::from("art/tilesheet.tsx")
+    ));
+
+    let sources = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let seen = sources.clone();
+    loader.settings_mut().path_resolver = Some(PathResolver::new(
+        move |directory: &std::path::Path, source: &str| {
+            seen.lock().unwrap().push(source.to_owned());
+            match source {
+                "../art/tilesheet.tsx" => PathBuf::from("assets/tilesheet.tsx"),
+                _ => directory.join(source),
+            }
+        },
+    ));
+    let map = loader
+        .load_tmx_map("assets/tiled_relocated_tileset.tmx")
+        .unwrap();
+    let tileset = &map.tilesets()[0];
+    assert_eq!(tileset.source, Some(PathBuf::from("assets/tilesheet.tsx")));
+    // The tileset's own references are resolved relative to where it was actually found
+    assert_eq!(
+        tileset.image.as_ref().unwrap().source,
+        PathBuf::from("assets/tilesheet.png")
+    );
+    assert_eq!(
+        *sources.lock().unwrap(),
+        ["../art/tilesheet.tsx", "tilesheet.png"]
+    );
+
+    // Misconfigured resolvers are reported with the path they returned
+    loader.settings_mut().path_resolver =
+        Some(PathResolver::new(|_: &std::path::Path, source: &str| {
+            PathBuf::from("nowhere").join(std::path::Path::new(source).file_name().unwrap())
+        }));
+    let err = loader
+        .load_tmx_map_with_cache(
+            "assets/tiled_relocated_tileset.tmx",
+            &mut DefaultResourceCache::new(),
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.inner(),
+        Error::ResourceLoadingError { path, .. } if path == &PathBuf::from("nowhere/tilesheet.tsx")
+    ));
+
+    loader.settings_mut().path_resolver = None;
+    let map = loader
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    assert_eq!(
+        map.tilesets()[0].source,
+        Some(PathBuf::from("assets/tilesheet.tsx"))
+    );
+}
+
+#[test]
+fn test_animation_playback() {
+    let animated = |durations: &[u32]| TileData {
+        animation: Some(
+            durations
+                .iter()
+                .enumerate()
+                .map(|(tile_id, &duration)| Frame {
+                    tile_id: tile_id as u32,
+                    duration,
+                })
+                .collect(),
+        ),
+        ..Default::default()
+    };
+
+    let tile = animated(&[100, 0, 50]);
+    assert_eq!(tile.animation_total_duration(), 150);
+    assert_eq!(tile.frame_tile_id_at(0), Some(0));
+    assert_eq!(tile.frame_tile_id_at(99), Some(0));
+    // Frame boundaries belong to the next frame, skipping zero duration ones
+    let (frame, into_frame) = tile.frame_at(100).unwrap();
+    assert_eq!((frame.tile_id, into_frame), (2, 0));
+    assert_eq!(tile.frame_at(149).map(|(_, ms)| ms), Some(49));
+    // Elapsed time wraps around the total duration
+    assert_eq!(tile.frame_tile_id_at(150), Some(0));
+    assert_eq!(tile.frame_tile_id_at(1_000_170), Some(2));
+
+    // Animations where no time passes are static
+    let tile = animated(&[0, 0]);
+    assert_eq!(tile.animation_total_duration(), 0);
+    assert_eq!(
+        tile.frame_at(1234).map(|(frame, ms)| (frame.tile_id, ms)),
+        Some((0, 0))
+    );
+
+    let tile = animated(&[40]);
+    assert_eq!(
+        tile.frame_at(130).map(|(frame, ms)| (frame.tile_id, ms)),
+        Some((0, 10))
+    );
+
+    let tile = animated(&[u32::MAX, 10]);
+    assert_eq!(tile.animation_total_duration(), u32::MAX);
+    assert_eq!(tile.frame_tile_id_at(u32::MAX - 1), Some(0));
+
+    assert_eq!(animated(&[]).frame_at(10), None);
+    assert_eq!(TileData::default().animation_total_duration(), 0);
+    assert_eq!(TileData::default().frame_tile_id_at(10), None);
+}
+
+#[test]
+fn test_locked_and_visible_flags() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_locked_layers.tmx")
+        .unwrap();
+    let flags: Vec<_> = map
+        .layers_recursive()
+        .map(|(layer, _)| (layer.name.clone(), layer.visible, layer.locked))
+        .collect();
+    assert_eq!(
+        flags
+            .iter()
+            .map(|(name, visible, locked)| (name.as_str(), *visible, *locked))
+            .collect::<Vec<_>>(),
+        [
+            ("ground", true, true),
+            ("hidden", false, true),
+            ("markers", true, false),
+            ("backdrop", false, false),
+            ("unlocked", true, false),
+        ]
+    );
+    let markers = map.find_layer(|layer| layer.name == "markers").unwrap();
+    assert!(!markers.effective_visibility());
+
+    let object = map.get_object_by_id(1).unwrap();
+    assert!(object.locked);
+    assert!(object.visible);
+    let object = map.get_object_by_id(2).unwrap();
+    assert!(!object.locked);
+    assert!(!object.visible);
+
+    // Collision shapes are allowed to have an ID of 0
+    let tile = map.tilesets()[0].get_tile(0).unwrap();
+    let shape = tile.collision_objects().next().unwrap();
+    assert_eq!(shape.id(), 0);
+    assert!(shape.visible);
+    assert!(!shape.locked);
+}
+
+#[test]
+fn test_object_builder() {
+    let mut properties = HashMap::new();
+    properties.insert(Arc::from("solid"), PropertyValue::BoolValue(true));
+    let object = ObjectData::builder()
+        .id(7)
+        .name("wall")
+        .user_type("collider")
+        .position(10.0, 20.0)
+        .size(30.0, 40.0)
+        .rotation(90.0)
+        .locked(true)
+        .properties(properties)
+        .build()
+        .unwrap();
+    assert_eq!(object.id(), 7);
+    assert_eq!(&*object.name, "wall");
+    assert_eq!(&*object.user_type, "collider");
+    assert_eq!((object.x, object.y, object.rotation), (10.0, 20.0, 90.0));
+    assert!(object.visible && object.locked);
+    assert_eq!(
+        object.shape,
+        ObjectShape::Rect {
+            width: 30.0,
+            height: 40.0
+        }
+    );
+    assert_eq!(object.properties.get_bool("solid"), Some(true));
+    assert!(object.tile_data().is_none());
+
+    let default = ObjectData::builder().build().unwrap();
+    assert_eq!(default.id(), 0);
+    assert!(default.name.is_empty());
+    assert!(default.visible && !default.locked);
+
+    // Explicit shapes win over the size
+    let point = ObjectData::builder()
+        .size(5.0, 5.0)
+        .shape(ObjectShape::Point(1.0, 2.0))
+        .build()
+        .unwrap();
+    assert_eq!(point.shape, ObjectShape::Point(1.0, 2.0));
+
+    let polygon = |points: Vec<(f32, f32)>| {
+        ObjectData::builder()
+            .shape(ObjectShape::Polygon { points })
+            .build()
+    };
+    let polyline = |points: Vec<(f32, f32)>| {
+        ObjectData::builder()
+            .shape(ObjectShape::Polyline { points })
+            .build()
+    };
+    assert!(polygon(vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)]).is_ok());
+    assert!(matches!(
+        polygon(vec![(0.0, 0.0), (1.0, 0.0)]),
+        Err(Error::InvalidObjectData { .. })
+    ));
+    assert!(polyline(vec![(0.0, 0.0), (1.0, 0.0)]).is_ok());
+    assert!(matches!(
+        polyline(vec![(0.0, 0.0)]),
+        Err(Error::InvalidObjectData { .. })
+    ));
+
+    // Built objects can be mixed with parsed ones
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_groups.tmx")
+        .unwrap();
+    let parsed = map.object_layers_recursive().next().unwrap();
+    let mut objects = parsed.object_data().to_vec();
+    objects.push(object.clone());
+    let layer = ObjectLayerData::from_objects(objects, Some("#ff0000".parse().unwrap()));
+    assert_eq!(layer.object_data().len(), parsed.object_data().len() + 1);
+    assert_eq!(layer.object_data().last(), Some(&object));
+    assert_eq!(layer.colour.unwrap().red, 0xff);
+}
+
+/// Extracts the numbers of every CSV block found between `start` and `end` in a map file.
+fn csv_blocks(path: &str, start: &str, end: &str) -> Vec<Vec<u32>> {
+    let text = std::fs::read_to_string(path).unwrap();
+    text.split(start)
+        .skip(1)
+        .map(|block| {
+            let block = &block[block.find('>').unwrap() + 1..block.find(end).unwrap()];
+            block
+                .split(',')
+                .map(|number| number.trim().parse().unwrap())
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn test_raw_gid_data() {
+    for path in [
+        "assets/tiled_csv.tmx",
+        "assets/tiled_flipped_all.tmx",
+        "assets/tiled_flipped_hexagonal.tmx",
+    ] {
+        let map = Loader::new().load_tmx_map(path).unwrap();
+        let layer = as_finite(map.get_layer(0).unwrap().as_tile_layer().unwrap());
+        let expected = csv_blocks(path, "<data", "</data>").remove(0);
+        assert_eq!(layer.raw_gid_data(), expected, "{}", path);
+
+        let tilesets: Vec<_> = map
+            .tilesets()
+            .iter()
+            .zip(map.first_gids())
+            .map(|(tileset, &first_gid)| MapTilesetGid {
+                first_gid,
+                tileset: tileset.clone(),
+            })
+            .collect();
+        for (x, y, tile) in layer
+            .rows()
+            .enumerate()
+            .flat_map(|(y, row)| row.enumerate().map(move |(x, tile)| (x, y, tile)))
+        {
+            let bits = tile.map_or(0, |tile| tile.to_bits(&tilesets));
+            assert_eq!(bits, expected[x + y * layer.width() as usize]);
+        }
+    }
+
+    let path = "assets/tiled_csv_infinite_flipped.tmx";
+    let map = Loader::new().load_tmx_map(path).unwrap();
+    let layer = match map.get_layer(0).unwrap().as_tile_layer().unwrap() {
+        TileLayer::Infinite(layer) => layer,
+        _ => panic!("expected an infinite layer"),
+    };
+    let expected = csv_blocks(path, "<chunk", "</chunk>");
+    assert_eq!(layer.get_chunk(0, 0).unwrap().raw_gid_data(), expected[0]);
+    assert_eq!(layer.get_chunk(-1, 1).unwrap().raw_gid_data(), expected[1]);
+    // The GIDs of the second tileset keep its first GID
+    assert_eq!(
+        layer.get_chunk(0, 0).unwrap().raw_gid_data()[18],
+        0x40000000 | 200
+    );
+}
+
+/// Records the events of a [`LoadSettings::progress_hook`] as strings.
+fn record_progress(loader: &mut Loader) -> Arc<std::sync::Mutex<Vec<String>>> {
+    let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorded = events.clone();
+    loader.settings_mut().progress_hook = Some(ProgressHook::new(move |event: LoadProgress| {
+        let event = match event {
+            LoadProgress::ExternalTilesetStarted(path) => {
+                format!("tileset started {}", path.display())
+            }
+            LoadProgress::ExternalTilesetFinished(path) => {
+                format!("tileset finished {}", path.display())
+            }
+            LoadProgress::LayerParsed {
+                name,
+                index,
+                total_hint,
+            } => format!("layer {} {} {:?}", index, name, total_hint),
+            LoadProgress::TemplateLoaded(path) => format!("template {}", path.display()),
+            _ => unreachable!(),
+        };
+        recorded.lock().unwrap().push(event);
+    }));
+    events
+}
+
+const LOAD_PROGRESS_EVENTS: [&str; 10] = [
+    "tileset started assets/tilesheet.tsx",
+    "tileset finished assets/tilesheet.tsx",
+    "tileset started assets/tilesheet_collision.tsx",
+    "tileset finished assets/tilesheet_collision.tsx",
+    "layer 0 Ground Some(4)",
+    "tileset started assets/tilesheet_template.tsx",
+    "tileset finished assets/tilesheet_template.tsx",
+    "template assets/tiled_object_template.tx",
+    "layer 1 Objects Some(4)",
+    "layer 2 Group Some(4)",
+];
+
+#[test]
+fn test_progress_hook() {
+    let mut loader = Loader::new();
+    let events = record_progress(&mut loader);
+    loader
+        .load_tmx_map("assets/tiled_load_progress.tmx")
+        .unwrap();
+    assert_eq!(*events.lock().unwrap(), LOAD_PROGRESS_EVENTS);
+
+    // Cached tilesets and templates aren't read again
+    events.lock().unwrap().clear();
+    loader
+        .load_tmx_map("assets/tiled_load_progress.tmx")
+        .unwrap();
+    assert_eq!(
+        *events.lock().unwrap(),
+        [
+            "layer 0 Ground Some(4)",
+            "layer 1 Objects Some(4)",
+            "layer 2 Group Some(4)"
+        ]
+    );
+
+    loader.settings_mut().progress_hook = None;
+    events.lock().unwrap().clear();
+    loader
+        .load_tmx_map_with_cache(
+            "assets/tiled_load_progress.tmx",
+            &mut DefaultResourceCache::new(),
+        )
+        .unwrap();
+    assert!(events.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_progress_hook_with_unsync_state() {
+    // Hooks only need to be `Send`, so they can keep state in a `Cell` and report through a channel
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let count = std::cell::Cell::new(0);
+    let mut loader = Loader::new();
+    loader.settings_mut().progress_hook = Some(ProgressHook::new(move |_: LoadProgress| {
+        count.set(count.get() + 1);
+        sender.send(count.get()).unwrap();
+    }));
+    loader
+        .load_tmx_map("assets/tiled_load_progress.tmx")
+        .unwrap();
+    assert_eq!(receiver.try_iter().last(), Some(LOAD_PROGRESS_EVENTS.len()));
+}
+
+#[cfg(feature = "async-tokio")]
+#[tokio::test]
+async fn test_progress_hook_async() {
+    let mut loader = Loader::new();
+    let events = record_progress(&mut loader);
+    loader
+        .load_tmx_map_async("assets/tiled_load_progress.tmx")
+        .await
+        .unwrap();
+    assert_eq!(*events.lock().unwrap(), LOAD_PROGRESS_EVENTS);
+}
+
+#[test]
+fn test_text_entities_and_cdata() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_text_entities.tmx")
+        .unwrap();
+    let string = |name: &str| match map.properties.get(name) {
+        Some(PropertyValue::StringValue(value)) => value.as_str(),
+        value => panic!("Unexpected value for {}: {:?}", name, value),
+    };
+    assert_eq!(string("ampersand"), "Fish & chips");
+    assert_eq!(string("numeric"), "Tab\tand\nnewline \u{263A}");
+    assert_eq!(string("cdata"), "<b>bold</b> & raw");
+    assert_eq!(string("mixed"), "before < & > after");
+    assert_eq!(string("multiline"), "Line 1\r\nLine 2 & more\r\n  Line 3");
+
+    let layer = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    match &layer.get_object(0).unwrap().shape {
+        ObjectShape::Text { text, .. } => assert_eq!(text, "Caf\u{e9} <menu> &\r\ndrinks"),
+        shape => panic!("Unexpected shape: {:?}", shape),
+    }
+}
+
+#[test]
+fn test_statistics() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_statistics.tmx")
+        .unwrap();
+    let stats = map.statistics();
+    assert_eq!(stats.tile_layers, 2);
+    assert_eq!(stats.object_layers, 2);
+    assert_eq!(stats.image_layers, 1);
+    assert_eq!(stats.group_layers, 1);
+    assert_eq!(stats.tile_cells, 4 * 3 + 2 * 2);
+    assert_eq!(stats.objects, 4);
+    assert_eq!(stats.tilesets, 2);
+    assert_eq!(stats.animated_tiles, 1);
+
+    let sparse = map.tilesets()[0].statistics();
+    assert_eq!(
+        sparse,
+        TilesetStats {
+            tiles: 4,
+            animated_tiles: 1,
+            animation_frames: 2,
+            collision_objects: 0,
+            wang_sets: 0,
+            heap_size: sparse.heap_size,
+        }
+    );
+    let collision = map.tilesets()[1].statistics();
+    assert_eq!(collision.tiles, 2);
+    assert_eq!(collision.collision_objects, 5);
+    let wang_sets = Loader::new()
+        .load_tsx_tileset("assets/tilesheet_wangsets.tsx")
+        .unwrap()
+        .statistics();
+    assert_eq!(wang_sets.wang_sets, 3);
+
+    // The estimate covers the tile cells and the tilesets, but stays in the right ballpark
+    assert!(stats.heap_size > sparse.heap_size + collision.heap_size);
+    assert!(stats.heap_size > stats.tile_cells * std::mem::size_of::<Option<LayerTileData>>());
+    assert!(stats.heap_size < 64 * 1024);
+
+    // It grows along with the tile layers of the map
+    let large = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib.tmx")
+        .unwrap()
+        .statistics();
+    assert_eq!(large.tile_cells, 100 * 100 * large.tile_layers);
+    assert!(large.heap_size > large.tile_cells * std::mem::size_of::<Option<LayerTileData>>());
+    let infinite = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    let chunks: usize = infinite
+        .tile_layers_recursive()
+        .map(|layer| match layer {
+            TileLayer::Infinite(layer) => layer.chunks().len(),
+            TileLayer::Finite(_) => panic!("Not an infinite tile layer"),
+        })
+        .sum();
+    assert_eq!(
+        infinite.statistics().tile_cells,
+        chunks * ChunkData::TILE_COUNT
+    );
+}
+
+#[test]
+fn test_unordered_tilesets() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_unordered_tilesets.tmx")
+        .unwrap();
+    let tilesets: Vec<_> = map
+        .tilesets_with_first_gids()
+        .map(|(first_gid, tileset)| (first_gid, tileset.name.as_str()))
+        .collect();
+    assert_eq!(
+        tilesets,
+        [(Gid(85), "tilesheet_collision"), (Gid(1), "tilesheet")]
+    );
+    assert_eq!(map.gid_range_of(0), 85..169);
+    assert_eq!(map.gid_range_of(1), 1..85);
+
+    let layer = as_finite(map.get_layer(0).unwrap().as_tile_layer().unwrap());
+    let tile = |x, y| {
+        let tile = layer.get_tile(x, y).unwrap();
+        (tile.tileset_index(), tile.id())
+    };
+    assert_eq!(tile(0, 0), (1, 0));
+    assert_eq!(tile(1, 0), (1, 83));
+    assert_eq!(tile(2, 0), (0, 0));
+    assert_eq!(tile(0, 1), (0, 1));
+    assert_eq!(tile(1, 1), (0, 83));
+    assert_eq!(tile(2, 1), (0, 0));
+    assert!(layer.get_tile(2, 1).unwrap().flip_h);
+    assert_eq!(layer.raw_gid_data(), [1, 84, 85, 86, 168, 2147483733]);
+
+    let objects = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    let object = objects.get_object(0).unwrap();
+    let tile = object.get_tile().unwrap();
+    assert_eq!(tile.id(), 1);
+    assert_eq!(tile.get_tileset().name, "tilesheet_collision");
+}
+
+#[test]
+fn test_image_transparent_colors() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_transparent_colors.tmx")
+        .unwrap();
+    let magenta = Some(Color {
+        alpha: 0xff,
+        red: 0xff,
+        green: 0x00,
+        blue: 0xff,
+    });
+
+    let atlas = &map.tilesets()[0];
+    assert_eq!(atlas.image.as_ref().unwrap().transparent_colour, magenta);
+
+    // Both with and without a `#` prefix
+    let collection = &map.tilesets()[1];
+    for id in 0..2 {
+        let tile = collection.get_tile(id).unwrap();
+        assert_eq!(tile.image.as_ref().unwrap().transparent_colour, magenta);
+    }
+    for layer in map.image_layers() {
+        assert_eq!(layer.image.as_ref().unwrap().transparent_colour, magenta);
+    }
+    assert_eq!(map.image_layers().count(), 2);
+}
+
+/// Reads files from the filesystem, except for those whose path ends with the given suffix.
+fn reader_without(
+    suffix: &'static str,
+) -> impl Fn(&std::path::Path) -> std::io::Result<std::io::BufReader<std::fs::File>> {
+    move |path| {
+        if path.to_string_lossy().ends_with(suffix) {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("{} is hidden", path.display()),
+            ))
+        } else {
+            Ok(std::io::BufReader::new(std::fs::File::open(path)?))
+        }
+    }
+}
+
+#[test]
+fn test_chained_reader() {
+    // The tileset only exists in the fallback reader
+    let mut loader = Loader::with_reader(ChainedReader::new(
+        reader_without(".tsx"),
+        reader_without(".tmx"),
+    ));
+    let map = loader
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    assert_eq!(map.tilesets()[0].name, "tilesheet");
+    assert!(loader.cache().get_tileset("assets/tilesheet.tsx").is_some());
+
+    // Both errors are kept when neither reader has the file
+    loader.reader_mut().primary = reader_without(".tmx");
+    let err = loader.load_tmx_map("assets/tiled_csv.tmx").unwrap_err();
+    let err = match err {
+        Error::ResourceLoadingError { path, err } => {
+            assert_eq!(path, PathBuf::from("assets/tiled_csv.tmx"));
+            err
+        }
+        err => panic!("Unexpected error: {:?}", err),
+    };
+    let err = err
+        .downcast_ref::<ChainedReaderError<std::io::Error, std::io::Error>>()
+        .unwrap();
+    assert_eq!(err.primary.to_string(), "assets/tiled_csv.tmx is hidden");
+    assert_eq!(err.fallback.to_string(), "assets/tiled_csv.tmx is hidden");
+    assert!(std::error::Error::source(err)
+        .unwrap()
+        .to_string()
+        .contains("hidden"));
+}
+
+#[cfg(feature = "async-tokio")]
+#[tokio::test]
+async fn test_chained_reader_async() {
+    /// Reads nothing at all.
+    struct EmptyReader;
+
+    impl tiled::AsyncResourceReader for EmptyReader {
+        type Resource = tokio::io::BufReader<tokio::fs::File>;
+        type Error = std::io::Error;
+
+        async fn read_from(&mut self, _path: &std::path::Path) -> std::io::Result<Self::Resource> {
+            Err(std::io::ErrorKind::NotFound.into())
+        }
+    }
+
+    let mut loader = Loader::with_reader(ChainedReader::new(
+        EmptyReader,
+        tiled::FilesystemResourceReader,
+    ));
+    let map = loader
+        .load_tmx_map_async("assets/tiled_base64_external.tmx")
+        .await
+        .unwrap();
+    assert_eq!(map.tilesets()[0].name, "tilesheet");
+
+    let mut loader = Loader::with_reader(ChainedReader::new(EmptyReader, EmptyReader));
+    let err = loader
+        .load_tmx_map_async("assets/tiled_base64_external.tmx")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::ResourceLoadingError { .. }));
+}
+
+#[cfg(feature = "async-tokio")]
+#[tokio::test]
+async fn test_external_tilesets_are_read_concurrently() {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    };
+
+    /// Takes a while to open files, and counts how often each file is opened and how many files
+    /// are being opened at once.
+    #[derive(Default)]
+    struct SlowReader {
+        reads: Mutex<HashMap<PathBuf, usize>>,
+        opening: AtomicUsize,
+        max_opening: AtomicUsize,
+    }
+
+    impl SlowReader {
+        async fn open(&self, path: &Path) -> std::io::Result<std::io::Cursor<Vec<u8>>> {
+            *self
+                .reads
+                .lock()
+                .unwrap()
+                .entry(path.to_owned())
+                .or_default() += 1;
+            let opening = self.opening.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_opening.fetch_max(opening, Ordering::SeqCst);
+            // Other files opened concurrently start being opened meanwhile
+            for _ in 0..8 {
+                tokio::task::yield_now().await;
+            }
+            self.opening.fetch_sub(1, Ordering::SeqCst);
+            Ok(std::io::Cursor::new(std::fs::read(path)?))
+        }
+    }
+
+    impl tiled::AsyncResourceReader for SlowReader {
+        type Resource = std::io::Cursor<Vec<u8>>;
+        type Error = std::io::Error;
+
+        async fn read_from(&mut self, path: &Path) -> std::io::Result<Self::Resource> {
+            self.open(path).await
+        }
+
+        async fn read_from_many(
+            &mut self,
+            paths: &[PathBuf],
+        ) -> Vec<std::io::Result<Self::Resource>> {
+            let reader = &*self;
+            futures::future::join_all(paths.iter().map(|path| reader.open(path))).await
+        }
+    }
+
+    let mut loader = Loader::with_reader(SlowReader::default());
+    let map = loader
+        .load_tmx_map_async("assets/tiled_many_tilesets.tmx")
+        .await
+        .unwrap();
+
+    let names: Vec<_> = map.tilesets().iter().map(|ts| ts.name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "tilesheet",
+            "tilesheet_wangsets",
+            "terrain",
+            "tilesheet_collision",
+            "tilesheet"
+        ]
+    );
+    assert!(Arc::ptr_eq(&map.tilesets()[0], &map.tilesets()[4]));
+    let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    assert_eq!(
+        layer.get_tile(1, 1).unwrap().get_tileset().name,
+        "tilesheet"
+    );
+
+    // Four distinct tilesets were read, each once, and all opened at once
+    let tileset_reads: Vec<_> = loader
+        .reader()
+        .reads
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(path, _)| path.extension().unwrap() == "tsx")
+        .map(|(_, reads)| *reads)
+        .collect();
+    assert_eq!(tileset_reads, [1, 1, 1, 1]);
+    assert_eq!(loader.reader().max_opening.load(Ordering::SeqCst), 4);
+}
+
+#[test]
+fn test_object_draw_order() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_draw_order.tmx")
+        .unwrap();
+    let names = |layer: &tiled::ObjectLayer| -> Vec<String> {
+        layer
+            .objects_in_draw_order()
+            .map(|object| object.name.to_string())
+            .collect()
+    };
+
+    let top_down = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    assert_eq!(top_down.draw_order, DrawOrder::TopDown);
+    assert_eq!(names(&top_down), ["point", "tile", "low rect", "tied rect"]);
+
+    let index = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    assert_eq!(index.draw_order, DrawOrder::Index);
+    assert_eq!(names(&index), ["low rect", "tile", "point", "tied rect"]);
+
+    // Tile collisions are written with the index order
+    let tileset = Loader::new()
+        .load_tsx_tileset("assets/tilesheet_collision.tsx")
+        .unwrap();
+    let tile = tileset.get_tile(0).unwrap();
+    assert_eq!(
+        tile.collision.as_ref().unwrap().draw_order,
+        DrawOrder::Index
+    );
+    assert_eq!("index".parse::<DrawOrder>().unwrap(), DrawOrder::Index);
+    assert!("bottomup".parse::<DrawOrder>().is_err());
+}
+
+#[test]
+fn test_malformed_attribute() {
+    let err = Loader::new()
+        .load_tmx_map("assets/tiled_invalid_width.tmx")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::MalformedAttribute);
+    match err.inner() {
+        Error::MalformedAttribute {
+            element,
+            attribute,
+            value,
+            source,
+        } => {
+            assert_eq!(*element, "map");
+            assert_eq!(*attribute, "width");
+            assert_eq!(value, "abc");
+            let source = source
+                .as_ref()
+                .expect("integer parse errors should be kept");
+            assert!(source.is::<std::num::ParseIntError>());
+        }
+        err => panic!("unexpected error: {:?}", err),
+    }
+    let message = err.to_string();
+    assert!(message.contains("Error parsing attribute 'width' of element 'map' with value \"abc\""));
+    assert!(std::error::Error::source(err.inner()).is_some());
+
+    let err = Loader::new()
+        .load_tmx_map_from(
+            &br#"<map version="1.10" orientation="orthogonal" width="2" height="2" tilewidth="32"></map>"#[..],
+            "missing.tmx",
+        )
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::MissingAttribute);
+    assert!(matches!(
+        err.inner(),
+        Error::MissingAttribute {
+            element: "map",
+            attribute: "tileheight"
+        }
+    ));
+    assert!(err
+        .to_string()
+        .contains("Missing attribute 'tileheight' of element 'map'"));
+
+    let err = Loader::new()
+        .load_tmx_map("assets/tiled_does_not_exist.tmx")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ResourceLoadingError);
+}
+
+const PROBED_IMAGE_DIMENSIONS: [(&str, (i32, i32)); 6] = [
+    ("gif", (3, 2)),
+    ("bmp", (5, 4)),
+    ("jpeg", (16, 8)),
+    ("embedded", (3, 2)),
+    ("partial", (100, 192)),
+    ("unknown", (0, 0)),
+];
+
+fn image_dimensions(map: &Map) -> Vec<(String, (i32, i32))> {
+    map.layers()
+        .map(|layer| {
+            let image_layer = layer.as_image_layer().unwrap();
+            let image = image_layer.image.as_ref().unwrap();
+            (layer.name.clone(), (image.width, image.height))
+        })
+        .collect()
+}
+
+fn assert_probed_image_dimensions(map: &Map) {
+    let expected: Vec<_> = PROBED_IMAGE_DIMENSIONS
+        .iter()
+        .map(|(name, size)| (name.to_string(), *size))
+        .collect();
+    assert_eq!(image_dimensions(map), expected);
+    let image = map.tilesets()[0].image.as_ref().unwrap();
+    assert_eq!((image.width, image.height), (448, 192));
+}
+
+#[test]
+fn test_probe_image_dimensions() {
+    let mut loader = Loader::new();
+    assert!(!loader.settings().probe_image_dimensions);
+    let tileset = loader
+        .load_tsx_tileset("assets/tilesheet_no_image_size.tsx")
+        .unwrap();
+    let image = tileset.image.as_ref().unwrap();
+    assert_eq!((image.width, image.height), (0, 0));
+    let map = loader
+        .load_tmx_map("assets/tiled_image_dimensions.tmx")
+        .unwrap();
+    assert_eq!(image_dimensions(&map)[4], ("partial".to_owned(), (100, 0)));
+
+    let mut loader = Loader::new();
+    loader.settings_mut().probe_image_dimensions = true;
+    let tileset = loader
+        .load_tsx_tileset("assets/tilesheet_no_image_size.tsx")
+        .unwrap();
+    let image = tileset.image.as_ref().unwrap();
+    assert_eq!((image.width, image.height), (448, 192));
+    let map = loader
+        .load_tmx_map("assets/tiled_image_dimensions.tmx")
+        .unwrap();
+    assert_probed_image_dimensions(&map);
+
+    // Images with dimensions are never read
+    let mut loader = Loader::with_reader(|path: &std::path::Path| {
+        if path.extension().is_some_and(|ext| ext == "png") {
+            Err(std::io::Error::from(std::io::ErrorKind::NotFound))
+        } else {
+            std::fs::File::open(path).map(std::io::BufReader::new)
+        }
+    });
+    loader.settings_mut().probe_image_dimensions = true;
+    loader.load_tmx_map("assets/tiled_base64.tmx").unwrap();
+    let err = loader
+        .load_tsx_tileset("assets/tilesheet_no_image_size.tsx")
+        .unwrap_err();
+    match err.inner() {
+        Error::ResourceLoadingError { path, .. } => {
+            assert_eq!(path, &PathBuf::from("assets/tilesheet.png"))
+        }
+        err => panic!("unexpected error: {:?}", err),
+    }
+}
+
+#[cfg(feature = "async-tokio")]
+#[tokio::test]
+async fn test_probe_image_dimensions_async() {
+    let mut loader = Loader::new();
+    loader.settings_mut().probe_image_dimensions = true;
+    let map = loader
+        .load_tmx_map_async("assets/tiled_image_dimensions.tmx")
+        .await
+        .unwrap();
+    assert_probed_image_dimensions(&map);
+}
+
+/// A [`MemoryResourceReader`] holding a map, its tileset and the tileset's image, inserted
+/// through paths that are spelled differently than the map refers to them.
+fn memory_reader() -> MemoryResourceReader {
+    let mut reader = MemoryResourceReader::new();
+    reader.insert(
+        "./game/maps/map.tmx",
+        std::fs::read("assets/tiled_base64_external.tmx").unwrap(),
+    );
+    let tileset = std::fs::read_to_string("assets/tilesheet_no_image_size.tsx").unwrap();
+    reader.insert_str("game/maps//tilesheet.tsx", &tileset);
+    reader.insert(
+        "game/images/../maps/tilesheet.png",
+        std::fs::read("assets/tilesheet.png").unwrap(),
+    );
+    reader
+}
+
+#[test]
+fn test_memory_resource_reader() {
+    let plain = Loader::new()
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+
+    let mut loader = Loader::with_reader(memory_reader());
+    loader.settings_mut().probe_image_dimensions = true;
+    let map = loader.load_tmx_map("game/maps/../maps/map.tmx").unwrap();
+    compare_everything_but_tileset_sources(&map, &plain);
+    let tileset = &map.tilesets()[0];
+    assert_eq!(
+        tileset.source,
+        Some(PathBuf::from("game/maps/tilesheet.tsx"))
+    );
+    // The image dimensions could only be probed by reading the image from memory
+    let image = tileset.image.as_ref().unwrap();
+    assert_eq!(image.source, PathBuf::from("game/maps/tilesheet.png"));
+    assert_eq!((image.width, image.height), (448, 192));
+
+    let reader = memory_reader();
+    assert_eq!(reader.paths().len(), 3);
+    assert!(reader.get("game/maps/tilesheet.tsx").is_some());
+    assert!(reader.get("game/tilesheet.tsx").is_none());
+
+    let err = Loader::with_reader(reader)
+        .load_tmx_map("game/maps/mao.tmx")
+        .unwrap_err();
+    let err = match err {
+        Error::ResourceLoadingError { err, .. } => err,
+        err => panic!("unexpected error: {:?}", err),
+    };
+    let err = err.downcast::<MemoryResourceError>().unwrap();
+    assert_eq!(err.near_misses, [PathBuf::from("game/maps/map.tmx")]);
+    assert_eq!(
+        err.to_string(),
+        "'game/maps/mao.tmx' is not in memory; Did you mean 'game/maps/map.tmx'"
+    );
+}
+
+#[cfg(feature = "async-tokio")]
+#[tokio::test]
+async fn test_memory_resource_reader_async() {
+    let mut loader = Loader::with_reader(memory_reader());
+    loader.settings_mut().probe_image_dimensions = true;
+    let map = loader
+        .load_tmx_map_async("./game/maps/map.tmx")
+        .await
+        .unwrap();
+    let image = map.tilesets()[0].image.as_ref().unwrap();
+    assert_eq!((image.width, image.height), (448, 192));
+}
+
+#[test]
+fn test_has_properties() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_has_properties.tmx")
+        .unwrap();
+    let tileset = &map.tilesets()[0];
+    let tile = tileset.get_tile(0).unwrap();
+    let wang_set = &tileset.wang_sets[0];
+    let tile_layer = map.get_layer(0).unwrap();
+    let group = match map.get_layer(1).unwrap().layer_type() {
+        LayerType::Group(group) => group,
+        _ => panic!("expected a group layer"),
+    };
+    let object_layer = group.get_layer(0).unwrap();
+    let object = object_layer
+        .as_object_layer()
+        .unwrap()
+        .get_object(0)
+        .unwrap();
+
+    let entities: Vec<(&dyn HasProperties, &str)> = vec![
+        (&map, "map"),
+        (&**tileset, "tileset"),
+        (&tile, "tile"),
+        (&*tile, "tile"),
+        (wang_set, "wangset"),
+        (&wang_set.wang_colors[0], "wangcolor"),
+        (&tile_layer, "tile layer"),
+        (&*tile_layer, "tile layer"),
+        (&group, "group layer"),
+        (&object_layer, "object layer"),
+        (&object, "object"),
+        (&*object, "object"),
+    ];
+    for (entity, kind) in entities {
+        assert_eq!(
+            entity.get_property("kind"),
+            Some(&PropertyValue::StringValue(kind.to_owned()))
+        );
+        assert_eq!(entity.properties().get_string("kind"), Some(kind));
+        assert_eq!(entity.get_property("missing"), None);
+    }
+}
+
+#[test]
+fn test_mixed_chunk_sizes() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_mixed_chunk_sizes.tmx")
+        .unwrap();
+    assert_eq!(
+        map.editor_settings.as_ref().unwrap().chunk_size,
+        Some((32, 16))
+    );
+    let layer = match map.get_layer(0).unwrap().as_tile_layer().unwrap() {
+        TileLayer::Infinite(layer) => layer,
+        TileLayer::Finite(_) => panic!("expected an infinite layer"),
+    };
+
+    let bounds = |x, y, width, height| TileBounds {
+        x,
+        y,
+        width,
+        height,
+    };
+    assert_eq!(
+        layer.file_chunks(),
+        [
+            bounds(0, 0, 32, 16),
+            bounds(32, 0, 16, 16),
+            bounds(-8, -4, 8, 4)
+        ]
+    );
+
+    let expected_tiles = [
+        ((-8, -4), 5),
+        ((-1, -1), 6),
+        ((0, 0), 0),
+        ((32, 0), 3),
+        ((20, 3), 2),
+        ((31, 15), 1),
+        ((47, 15), 4),
+    ];
+    let tiles: Vec<_> = layer.tiles().map(|(pos, tile)| (pos, tile.id())).collect();
+    assert_eq!(tiles, expected_tiles);
+    assert_eq!(layer.bounds(), Some(bounds(-8, -4, 56, 20)));
+
+    let normalized = layer.normalize_chunks(32, 16);
+    let positions: Vec<_> = normalized.iter().map(|(pos, _)| *pos).collect();
+    assert_eq!(positions, [(-1, -1), (0, 0), (1, 0)]);
+    let tile_id = |chunk: usize, x: usize, y: usize| {
+        normalized[chunk].1[x + y * 32]
+            .as_ref()
+            .map(|tile| tile.id())
+    };
+    assert_eq!(tile_id(0, 24, 12), Some(5));
+    assert_eq!(tile_id(0, 31, 15), Some(6));
+    assert_eq!(tile_id(1, 0, 0), Some(0));
+    assert_eq!(tile_id(1, 20, 3), Some(2));
+    assert_eq!(tile_id(1, 31, 15), Some(1));
+    assert_eq!(tile_id(2, 0, 0), Some(3));
+    assert_eq!(tile_id(2, 15, 15), Some(4));
+    assert_eq!(tile_id(2, 16, 0), None);
+
+    for (width, height) in [(1, 1), (7, 3), (16, 16), (64, 64)] {
+        let normalized = layer.normalize_chunks(width, height);
+        let mut tiles = Vec::new();
+        for ((chunk_x, chunk_y), chunk) in normalized {
+            assert_eq!(chunk.len(), (width * height) as usize);
+            for (index, tile) in chunk.iter().enumerate() {
+                if let Some(tile) = tile {
+                    let x = chunk_x * width as i32 + (index % width as usize) as i32;
+                    let y = chunk_y * height as i32 + (index / width as usize) as i32;
+                    assert_eq!(layer.get_tile(x, y).unwrap().id(), tile.id());
+                    tiles.push(((x, y), tile.id()));
+                }
+            }
+        }
+        tiles.sort_by_key(|((x, y), _)| (*y, *x));
+        assert_eq!(tiles, expected_tiles);
+    }
+}
+
+/// The tiles of `tiled_infinite_plain_data.tmx` and `tiled_finite_chunked_data.tmx`, whose layers
+/// all store them differently.
+const PLAIN_AND_CHUNKED_GIDS: [u32; 24] = [
+    1, 2, 3, 4, 5, 6, //
+    7, 8, 0, 0, 9, 10, //
+    0, 0, 11, 12, 0, 0, //
+    13, 0, 0, 0, 0, 14, //
+];
+
+#[test]
+fn test_infinite_map_with_plain_layer_data() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_infinite_plain_data.tmx")
+        .unwrap();
+    assert!(map.infinite());
+    assert_eq!(map.layers().len(), 3);
+    for layer in map.layers() {
+        let name = &layer.name;
+        let layer = match layer.as_tile_layer().unwrap() {
+            TileLayer::Infinite(layer) => layer,
+            TileLayer::Finite(_) => panic!("expected an infinite layer"),
+        };
+        assert_eq!(
+            layer.file_chunks(),
+            [TileBounds {
+                x: 0,
+                y: 0,
+                width: 6,
+                height: 4
+            }],
+            "layer '{}'",
+            name
+        );
+        for (index, gid) in PLAIN_AND_CHUNKED_GIDS.iter().enumerate() {
+            let (x, y) = ((index % 6) as i32, (index / 6) as i32);
+            let id = layer.get_tile(x, y).map(|tile| tile.id() + 1);
+            assert_eq!(id.unwrap_or(0), *gid, "layer '{}' at {:?}", name, (x, y));
+        }
+        assert_eq!(layer.get_tile(6, 0), None);
+        assert_eq!(layer.get_tile(0, 4), None);
+    }
+}
+
+#[test]
+fn test_finite_map_with_chunked_layer_data() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_finite_chunked_data.tmx")
+        .unwrap();
+    assert!(!map.infinite());
+    assert_eq!(map.layers().len(), 3);
+    for layer in map.layers() {
+        let name = &layer.name;
+        let layer = match layer.as_tile_layer().unwrap() {
+            TileLayer::Finite(layer) => layer,
+            TileLayer::Infinite(_) => panic!("expected a finite layer"),
+        };
+        assert_eq!((layer.width(), layer.height()), (6, 4));
+        // The parts of chunks past the layer's bounds are empty, so they are dropped
+        assert_eq!(
+            layer.raw_gid_data(),
+            PLAIN_AND_CHUNKED_GIDS,
+            "layer '{}'",
+            name
+        );
+    }
+
+    let err = Loader::new()
+        .load_tmx_map("assets/tiled_finite_chunk_out_of_bounds.tmx")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ChunkOutOfBounds);
+    assert!(matches!(
+        err.inner(),
+        Error::ChunkOutOfBounds {
+            chunk: TileBounds {
+                x: 4,
+                y: 0,
+                width: 4,
+                height: 1
+            },
+            width: 6,
+            height: 4
+        }
+    ));
+}
+
+#[test]
+fn test_floor_div_and_mod() {
+    for a in -50i32..=50 {
+        for b in (-10..=10).filter(|b| *b != 0) {
+            let (div, rem) = (floor_div(a, b), floor_mod(a, b));
+            assert_eq!(div * b + rem, a, "{} / {}", a, b);
+            assert!(rem == 0 || (rem < 0) == (b < 0), "{} % {}", a, b);
+            assert!(rem.abs() < b.abs(), "{} % {}", a, b);
+            assert_eq!(div as f64, (a as f64 / b as f64).floor(), "{} / {}", a, b);
+
+            let (a, b) = (a as i64, b as i64);
+            assert_eq!(floor_div_i64(a, b), div as i64);
+            assert_eq!(floor_mod_i64(a, b), rem as i64);
+        }
+    }
+
+    // All sign combinations, with and without a remainder
+    assert_eq!((floor_div(7, 3), floor_mod(7, 3)), (2, 1));
+    assert_eq!((floor_div(-7, 3), floor_mod(-7, 3)), (-3, 2));
+    assert_eq!((floor_div(7, -3), floor_mod(7, -3)), (-3, -2));
+    assert_eq!((floor_div(-7, -3), floor_mod(-7, -3)), (2, -1));
+    assert_eq!((floor_div(-6, 3), floor_mod(-6, 3)), (-2, 0));
+    assert_eq!((floor_div(6, -3), floor_mod(6, -3)), (-2, 0));
+
+    let big = i64::from(i32::MAX) * 4 + 1;
+    assert_eq!(floor_div_i64(-big, 4), -i64::from(i32::MAX) - 1);
+    assert_eq!(floor_mod_i64(-big, 4), 3);
+    assert_eq!(floor_div(i32::MIN, 16), i32::MIN / 16);
+    assert_eq!(floor_mod(i32::MIN, 16), 0);
+    assert_eq!(floor_mod(i32::MAX, -16), -1);
+}
+
+/// Creates a loader that reads files from memory, as if they were at the given virtual paths.
+fn virtual_file_loader(
+    files: &[(&str, &str)],
+) -> Loader<impl Fn(&std::path::Path) -> std::io::Result<std::io::Cursor<Vec<u8>>>> {
+    let files: HashMap<PathBuf, Vec<u8>> = files
+        .iter()
+        .map(|(path, asset)| (PathBuf::from(path), std::fs::read(asset).unwrap()))
+        .collect();
+    Loader::with_reader(move |path: &std::path::Path| {
+        files
+            .get(path)
+            .map(|contents| std::io::Cursor::new(contents.clone()))
+            .ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::NotFound, path.display().to_string())
+            })
+    })
+}
+
+#[test]
+fn test_absolute_sources() {
+    let map_path = "game/maps/level.tmx";
+    let map_file = (map_path, "assets/tiled_absolute_sources.tmx");
+    let template_file = "assets/tiled_object_template.tx";
+    let template_tileset_file = "assets/tilesheet_template.tsx";
+
+    let mut loader = virtual_file_loader(&[
+        map_file,
+        ("C:/assets/tiles/tilesheet.tsx", "assets/tilesheet.tsx"),
+        ("/srv/art/My Tiles/tilesheet.tsx", "assets/tilesheet.tsx"),
+        ("game/shared/tilesheet.tsx", "assets/tilesheet.tsx"),
+        ("/srv/art/templates/object.tx", template_file),
+        (
+            "/srv/art/templates/tilesheet_template.tsx",
+            template_tileset_file,
+        ),
+    ]);
+    let map = loader.load_tmx_map(map_path).unwrap();
+    let sources: Vec<_> = map
+        .tilesets()
+        .iter()
+        .map(|tileset| tileset.source.clone().unwrap())
+        .collect();
+    assert_eq!(
+        sources,
+        [
+            PathBuf::from("C:/assets/tiles/tilesheet.tsx"),
+            PathBuf::from("/srv/art/My Tiles/tilesheet.tsx"),
+            PathBuf::from("game/shared/tilesheet.tsx"),
+        ]
+    );
+    let image_layer = map.get_layer(1).unwrap().as_image_layer().unwrap();
+    assert_eq!(
+        image_layer.image.as_ref().unwrap().source,
+        PathBuf::from("c:/assets/images/background.png")
+    );
+    let object_layer = map.get_layer(2).unwrap().as_object_layer().unwrap();
+    let object = object_layer.get_object(0).unwrap();
+    // Relative to the template
+    assert_eq!(
+        object.get_tile().unwrap().get_tileset().source.as_deref(),
+        Some(std::path::Path::new(
+            "/srv/art/templates/tilesheet_template.tsx"
+        ))
+    );
+
+    let mut loader = virtual_file_loader(&[
+        map_file,
+        ("/mnt/assets/tiles/tilesheet.tsx", "assets/tilesheet.tsx"),
+        ("art/My Tiles/tilesheet.tsx", "assets/tilesheet.tsx"),
+        ("game/shared/tilesheet.tsx", "assets/tilesheet.tsx"),
+        ("art/templates/object.tx", template_file),
+        (
+            "art/templates/tilesheet_template.tsx",
+            template_tileset_file,
+        ),
+    ]);
+    // Only whole components are matched
+    loader.settings_mut().absolute_prefixes = vec![
+        ("/srv/ar".into(), "wrong".into()),
+        ("/srv/art/".into(), "art".into()),
+        ("C:\\assets".into(), "/mnt/assets".into()),
+    ];
+    let map = loader.load_tmx_map(map_path).unwrap();
+    assert_eq!(
+        map.tilesets()[0].source.as_deref(),
+        Some(std::path::Path::new("/mnt/assets/tiles/tilesheet.tsx"))
+    );
+    assert_eq!(
+        map.tilesets()[1].source.as_deref(),
+        Some(std::path::Path::new("art/My Tiles/tilesheet.tsx"))
+    );
+    let image_layer = map.get_layer(1).unwrap().as_image_layer().unwrap();
+    assert_eq!(
+        image_layer.image.as_ref().unwrap().source,
+        PathBuf::from("/mnt/assets/images/background.png")
+    );
+
+    loader.settings_mut().absolute_prefixes.clear();
+    let err = loader.load_tmx_map(map_path).unwrap_err();
+    match err.inner() {
+        Error::ResourceLoadingError { path, .. } => {
+            assert_eq!(path, &PathBuf::from("C:/assets/tiles/tilesheet.tsx"))
+        }
+        err => panic!("unexpected error: {:?}", err),
+    }
+}
+
+#[test]
+fn test_object_contains_point_and_aabb() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_hit_tests.tmx")
+        .unwrap();
+    let layer = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    let object = |idx| layer.get_object(idx).unwrap();
+    let assert_aabb = |idx, expected: (f32, f32, f32, f32)| {
+        let actual = object(idx).aabb();
+        let close = [
+            (actual.0, expected.0),
+            (actual.1, expected.1),
+            (actual.2, expected.2),
+            (actual.3, expected.3),
+        ]
+        .iter()
+        .all(|(a, e)| (a - e).abs() < 1e-2);
+        assert!(close, "{:?} != {:?}", actual, expected);
+    };
+
+    // Rotated rectangle
+    let rect = object(0);
+    assert!(rect.contains_point(103.5, 110.6));
+    assert!(rect.contains_point(100., 100.));
+    assert!(!rect.contains_point(119., 100.));
+    // Inside the bounding box, but outside of the rotated shape
+    assert!(!rect.contains_point(112., 102.));
+    assert_aabb(0, (92.93, 100., 114.14, 121.21));
+
+    // Concave polygon
+    let concave = object(1);
+    assert!(concave.contains_point(5., 220.));
+    assert!(concave.contains_point(15., 205.));
+    assert!(concave.contains_point(25., 225.));
+    assert!(!concave.contains_point(15., 220.));
+    assert!(!concave.contains_point(35., 205.));
+    assert_aabb(1, (0., 200., 30., 230.));
+
+    // Rotated concave polygon
+    let rotated_concave = object(2);
+    assert!(rotated_concave.contains_point(295., 280.));
+    assert!(!rotated_concave.contains_point(285., 280.));
+    assert!(!rotated_concave.contains_point(305., 280.));
+    assert_aabb(2, (270., 270., 300., 300.));
+
+    // Degenerate shapes
+    let empty_rect = object(3);
+    assert!(empty_rect.contains_point(50., 50.));
+    assert!(!empty_rect.contains_point(51., 50.));
+    assert_aabb(3, (50., 50., 50., 50.));
+    let two_point_polygon = object(4);
+    assert!(!two_point_polygon.contains_point(15., 15.));
+    assert_aabb(4, (10., 10., 20., 20.));
+
+    // Rotated ellipse, whose bounding box is exact
+    let ellipse = object(5);
+    assert!(ellipse.contains_point(-5., 18.));
+    assert!(!ellipse.contains_point(-9., 1.));
+    assert_aabb(5, (-10., 0., 0., 20.));
+
+    let point = object(6);
+    assert!(point.contains_point(12., 34.));
+    assert!(!point.contains_point(12.1, 34.));
+    assert_aabb(6, (12., 34., 12., 34.));
+
+    let polyline = object(7);
+    assert!(!polyline.contains_point(5., 0.));
+    assert!(!polyline.contains_point(10., 5.));
+    assert_aabb(7, (0., 0., 10., 10.));
+
+    // Tile objects are anchored at their bottom-left corner
+    let tile = object(8);
+    assert!(tile.contains_point(60., 60.));
+    assert!(!tile.contains_point(60., 90.));
+    assert_aabb(8, (50., 48., 82., 80.));
+}
+
+#[test]
+fn test_raw_sources() {
+    let map = Loader::new()
+        .load_tmx_map("assets/folder/tiled_raw_sources.tmx")
+        .unwrap();
+
+    // Raw sources are kept verbatim, whichever separators they use
+    assert_eq!(
+        map.tileset_raw_sources(),
+        &[
+            Some("..\\tilesheet.tsx".to_owned()),
+            Some("../tilesheet_template.tsx".to_owned()),
+            None,
+        ]
+    );
+    // While the resolved ones point to the files that were loaded
+    let tilesets = map.tilesets();
+    assert_eq!(
+        tilesets[0].source.as_deref(),
+        Some(Path::new("assets/tilesheet.tsx"))
+    );
+    assert_eq!(
+        tilesets[1].source.as_deref(),
+        Some(Path::new("assets/tilesheet_template.tsx"))
+    );
+    assert_eq!(tilesets[2].source, None);
+
+    let tileset_image = tilesets[2].image.as_ref().unwrap();
+    assert_eq!(
+        tileset_image.raw_source.as_deref(),
+        Some("..\\tilesheet.png")
+    );
+
+    let image_layer = match map.get_layer(0).unwrap().layer_type() {
+        LayerType::Image(layer) => layer,
+        _ => panic!("expected an image layer"),
+    };
+    let image = image_layer.image.as_ref().unwrap();
+    assert_eq!(image.raw_source.as_deref(), Some("../tilesheet.png"));
+    assert_eq!(image.source, Path::new("assets/folder/../tilesheet.png"));
+
+    let objects = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    let templated = objects.get_object(0).unwrap();
+    assert_eq!(
+        templated.template_raw_source.as_deref(),
+        Some("..\\tiled_object_template.tx")
+    );
+    assert_eq!(
+        templated.template_source.as_deref(),
+        Some(Path::new("assets/tiled_object_template.tx"))
+    );
+    let plain = objects.get_object(1).unwrap();
+    assert_eq!(plain.template_raw_source, None);
+    assert_eq!(plain.template_source, None);
+
+    let mut loader = Loader::new();
+    loader
+        .load_tmx_map("assets/folder/tiled_raw_sources.tmx")
+        .unwrap();
+    let template = loader
+        .cache()
+        .get_template(Path::new("assets/tiled_object_template.tx"))
+        .unwrap();
+    assert_eq!(
+        template.tileset_raw_source.as_deref(),
+        Some("tilesheet_template.tsx")
+    );
+}
+
+#[test]
+fn test_color_conversions() {
+    let color = Color {
+        alpha: 0x12,
+        red: 0xab,
+        green: 0x00,
+        blue: 0xff,
+    };
+    assert_eq!(color.to_string(), "#12ab00ff");
+    assert_eq!("#12ab00ff".parse(), Ok(color));
+    assert_eq!("#12AB00FF".parse(), Ok(color));
+    // Colors without alpha are opaque, and are always formatted with it
+    let opaque: Color = "#ab00ff".parse().unwrap();
+    assert_eq!(opaque.alpha, 0xff);
+    assert_eq!(opaque.to_string(), "#ffab00ff");
+    assert_eq!(opaque.to_string().parse(), Ok(opaque));
+    // Shorthand forms repeat each digit
+    assert_eq!("#f0a".parse(), "#ff00aa".parse::<Color>());
+    assert_eq!("#8f0a".parse(), "#88ff00aa".parse::<Color>());
+    assert_eq!("f0a".parse(), "#ff00aa".parse::<Color>());
+    for invalid in [
+        "",
+        "#",
+        "#12",
+        "#12345",
+        "#1234567",
+        "#123456789",
+        "#ggg",
+        "#éé",
+    ] {
+        assert_eq!(invalid.parse::<Color>(), Err(()), "{}", invalid);
+    }
+
+    assert_eq!(<[u8; 4]>::from(color), [0xab, 0x00, 0xff, 0x12]);
+    assert_eq!(Color::from([0xab, 0x00, 0xff, 0x12]), color);
+    assert_eq!(
+        Color::from_rgba_f32(color.to_rgba_f32()),
+        color,
+        "conversions to floats should round-trip"
+    );
+    assert_eq!(opaque.to_rgba_f32(), [0xab as f32 / 255.0, 0.0, 1.0, 1.0]);
+    assert_eq!(
+        Color::from_rgba_f32([2.0, -1.0, 0.5, 1.0]),
+        Color {
+            alpha: 0xff,
+            red: 0xff,
+            green: 0x00,
+            blue: 0x80,
+        }
+    );
+}
+
+#[test]
+fn test_stagger_attributes() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_hexagonal_stagger_x.tmx")
+        .unwrap();
+    assert_eq!(map.orientation, Orientation::Hexagonal);
+    assert_eq!(map.stagger_axis, tiled::StaggerAxis::X);
+    assert_eq!(map.stagger_index, tiled::StaggerIndex::Even);
+    assert_eq!(map.hex_side_length, 14);
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_flipped_hexagonal.tmx")
+        .unwrap();
+    assert_eq!(map.stagger_axis, tiled::StaggerAxis::Y);
+    assert_eq!(map.stagger_index, tiled::StaggerIndex::Odd);
+    assert_eq!(map.hex_side_length, 16);
+
+    // Orthogonal maps don't have any, so they get the defaults
+    let map = Loader::new().load_tmx_map("assets/tiled_csv.tmx").unwrap();
+    assert_eq!(map.stagger_axis, tiled::StaggerAxis::Y);
+    assert_eq!(map.stagger_index, tiled::StaggerIndex::Odd);
+    assert_eq!(map.hex_side_length, 0);
+
+    let err = Loader::new()
+        .load_tmx_map("assets/tiled_invalid_stagger_axis.tmx")
+        .unwrap_err();
+    match err.inner() {
+        Error::MalformedAttribute {
+            element, attribute, ..
+        } => assert_eq!((*element, *attribute), ("map", "staggeraxis")),
+        err => panic!("unexpected error: {:?}", err),
+    }
+}
+
+#[test]
+fn test_map_without_tilesets() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_no_tilesets.tmx")
+        .unwrap();
+    assert!(map.tilesets().is_empty());
+    assert_eq!(map.layers().len(), 3);
+    for layer in map.layers() {
+        let layer = as_finite(layer.as_tile_layer().unwrap());
+        for y in 0..2 {
+            for x in 0..3 {
+                assert!(layer.get_tile(x, y).is_none());
+            }
+        }
+    }
+    assert!(map.warnings().is_empty());
+
+    let load = |strictness| {
+        Loader::new().load_tmx_map_with_settings(
+            "assets/tiled_no_tilesets_painted.tmx",
+            LoadSettings {
+                strictness,
+                ..Default::default()
+            },
+        )
+    };
+    let err = load(Strictness::Error).unwrap_err();
+    assert!(matches!(
+        err.inner(),
+        Error::UnresolvedTileGid { layer_name, gid: 5 } if layer_name == "painted"
+    ));
+    let map = load(Strictness::Warn).unwrap();
+    // Only the first unresolved tile of a layer is reported
+    assert_eq!(
+        map.warnings(),
+        [ParseWarning::UnresolvedTileGid {
+            layer_name: "painted".to_owned(),
+            gid: 5
+        }]
+    );
+    let layer = as_finite(map.get_layer(0).unwrap().as_tile_layer().unwrap());
+    assert!(layer.get_tile(1, 0).is_none());
+    assert!(layer.get_tile(0, 1).is_none());
+    assert!(load(Strictness::Ignore).unwrap().warnings().is_empty());
+}
+
+#[test]
+fn test_derived_tile_count() {
+    // (448 - 2 * 1 + 2) / (32 + 2) = 13 columns and (192 - 2 * 1 + 2) / (32 + 2) = 5 rows
+    let tileset = Loader::new()
+        .load_tsx_tileset("assets/tilesheet_derived_count.tsx")
+        .unwrap();
+    assert_eq!(tileset.columns, 13);
+    assert_eq!(tileset.tilecount, 65);
+    assert_eq!(tileset.tiles().len(), 65);
+
+    // Image collections have as many tiles as they define
+    let collection = Loader::new()
+        .load_tsx_tileset_from(
+            &br#"<tileset name="collection" tilewidth="32" tileheight="32">
+                <tile id="0"><image source="a.png" width="32" height="32"/></tile>
+                <tile id="4"><image source="b.png" width="32" height="32"/></tile>
+            </tileset>"#[..],
+            "assets/collection.tsx",
+        )
+        .unwrap();
+    assert_eq!(collection.tilecount, 2);
+    assert_eq!(collection.columns, 0);
+
+    // Nothing to derive them from without the dimensions of the image
+    let err = Loader::new()
+        .load_tsx_tileset_from(
+            &br#"<tileset name="unknown" tilewidth="32" tileheight="32">
+                <image source="tilesheet.png"/>
+            </tileset>"#[..],
+            "assets/unknown.tsx",
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.inner(),
+        Error::InvalidTileset(tiled::InvalidTilesetError::MissingTileCount)
+    ));
+}
+
+#[test]
+fn test_tile_count_mismatch() {
+    let mismatch = ParseWarning::TileCountMismatch {
+        tileset_name: "wrong count".to_owned(),
+        declared: 90,
+        derived: 84,
+    };
+    // Tiled tolerates this, so it's only warned about by default and the declared tile count is
+    // kept
+    let tileset = Loader::new()
+        .load_tsx_tileset("assets/tilesheet_wrong_count.tsx")
+        .unwrap();
+    assert_eq!(tileset.tilecount, 90);
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_wrong_tile_count.tmx")
+        .unwrap();
+    assert_eq!(map.warnings(), std::slice::from_ref(&mismatch));
+    assert_eq!(map.tilesets()[0].tilecount, 90);
+
+    let load = |strictness| {
+        Loader::new().load_tmx_map_with_settings(
+            "assets/tiled_wrong_tile_count.tmx",
+            LoadSettings {
+                strictness,
+                ..Default::default()
+            },
+        )
+    };
+    let err = load(Strictness::Strict).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::TileCountMismatch);
+    assert_eq!(err.inner().to_string(), mismatch.to_string());
+    for strictness in [Strictness::Error, Strictness::Warn] {
+        assert_eq!(
+            load(strictness).unwrap().warnings(),
+            std::slice::from_ref(&mismatch)
+        );
+    }
+    assert!(load(Strictness::Ignore).unwrap().warnings().is_empty());
+}
+
+#[test]
+fn test_unknown_layer_types() {
+    let load = |strictness| {
+        Loader::new().load_tmx_map_with_settings(
+            "assets/tiled_unknown_layers.tmx",
+            LoadSettings {
+                strictness,
+                ..Default::default()
+            },
+        )
+    };
+    let err = load(Strictness::Strict).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnknownLayerType);
+    assert!(matches!(
+        err.inner(),
+        Error::UnknownLayerType { tag_name } if tag_name == "voxellayer"
+    ));
+
+    // Like Tiled, the default is to keep loading, only warning about them
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_unknown_layers.tmx")
+        .unwrap();
+    let warning = |tag_name: &str, layer_name: &str| ParseWarning::UnknownLayerType {
+        tag_name: tag_name.to_owned(),
+        layer_name: layer_name.to_owned(),
+    };
+    let warnings = [
+        warning("voxellayer", "voxels"),
+        warning("voxellayer", "nested voxels"),
+    ];
+    assert_eq!(map.warnings(), warnings);
+    assert_eq!(load(Strictness::Error).unwrap().warnings(), warnings);
+    assert_eq!(load(Strictness::Warn).unwrap(), map);
+
+    // Unknown layers keep their place, and the layers inside of them are skipped
+    let names: Vec<_> = map
+        .layers_recursive()
+        .map(|(layer, _)| layer.name.clone())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "ground",
+            "voxels",
+            "objects",
+            "group",
+            "nested voxels",
+            "image"
+        ]
+    );
+    let voxels = map.get_layer(1).unwrap();
+    assert_eq!(voxels.id(), 2);
+    assert!(!voxels.visible);
+    assert_eq!(voxels.parallax_x, 0.5);
+    assert_eq!(voxels.user_type.as_deref(), Some("terrain"));
+    assert!(voxels.properties.is_empty());
+    match voxels.layer_type() {
+        LayerType::Unknown {
+            tag_name,
+            attributes,
+        } => {
+            assert_eq!(tag_name, "voxellayer");
+            assert_eq!(attributes.len(), 6);
+            assert_eq!(attributes["depth"], "8");
+            assert_eq!(attributes["name"], "voxels");
+        }
+        other => panic!("expected an unknown layer, got {:?}", other),
+    }
+    assert!(map.get_layer(2).unwrap().as_object_layer().is_some());
+    let group = map.get_layer(3).unwrap().as_group_layer().unwrap();
+    assert!(matches!(
+        group.get_layer(0).unwrap().layer_type(),
+        LayerType::Unknown {
+            tag_name: "voxellayer",
+            ..
+        }
+    ));
+    assert!(group.get_layer(1).unwrap().as_image_layer().is_some());
+    assert_eq!(map.statistics().unknown_layers, 2);
+
+    let ignored = load(Strictness::Ignore).unwrap();
+    assert!(ignored.warnings().is_empty());
+    assert_eq!(ignored.layers().len(), 4);
+
+    // Unknown layers are written back with their attributes
+    let mut tmx = Vec::new();
+    map.write_to(&mut tmx, WriteOptions::default()).unwrap();
+    let mut reader = MemoryResourceReader::new();
+    reader.insert("map.tmx", tmx);
+    let reloaded = Loader::with_reader(reader)
+        .load_tmx_map_with_settings(
+            "map.tmx",
+            LoadSettings {
+                strictness: Strictness::Ignore,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let unknown_layer = |map: &Map| match map.get_layer(1).unwrap().layer_type() {
+        LayerType::Unknown {
+            tag_name,
+            attributes,
+        } => (tag_name.to_owned(), attributes.clone()),
+        other => panic!("expected an unknown layer, got {:?}", other),
+    };
+    assert_eq!(unknown_layer(&reloaded), unknown_layer(&map));
+    assert_eq!(reloaded.layers().len(), 4);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_unknown_layer_types_json() {
+    let load = |strictness| {
+        let mut loader = Loader::new();
+        loader.settings_mut().strictness = strictness;
+        loader.load_tmj_map("assets/tiled_unknown_layers.tmj")
+    };
+    let err = load(Strictness::Strict).unwrap_err();
+    assert!(matches!(
+        err.inner(),
+        Error::UnknownLayerType { tag_name } if tag_name == "voxellayer"
+    ));
+    assert!(err.to_string().contains("$.layers[1]"));
+
+    // Unknown layers of JSON maps are kept and warned about like those of TMX maps
+    let tmx = Loader::new()
+        .load_tmx_map("assets/tiled_unknown_layers.tmx")
+        .unwrap();
+    let map = Loader::new()
+        .load_tmj_map("assets/tiled_unknown_layers.tmj")
+        .unwrap();
+    assert_eq!(map.warnings(), tmx.warnings());
+    assert_eq!(load(Strictness::Warn).unwrap(), map);
+    assert!(load(Strictness::Ignore).unwrap().warnings().is_empty());
+
+    let layers = |map: &Map| {
+        map.layers_recursive()
+            .map(|(layer, _)| (layer.name.clone(), layer.id(), layer.layer_type()))
+            .map(|(name, id, layer_type)| match layer_type {
+                LayerType::Unknown {
+                    tag_name,
+                    attributes,
+                } => (name, id, Some((tag_name.to_owned(), attributes.clone()))),
+                _ => (name, id, None),
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(layers(&map), layers(&tmx));
+    let voxels = map.get_layer(1).unwrap();
+    assert!(!voxels.visible);
+    assert_eq!(voxels.parallax_x, 0.5);
+    assert_eq!(voxels.user_type.as_deref(), Some("terrain"));
+    assert!(voxels.properties.is_empty());
+}
+
+#[test]
+fn test_duplicate_names() {
+    let path = "assets/tiled_duplicates.tmx";
+    let tmx = std::fs::read_to_string(path).unwrap();
+    let position = |needle: &str| tmx.rfind(needle).unwrap() as u64;
+    let warnings = [
+        ParseWarning::DuplicateProperty {
+            name: "speed".to_owned(),
+            path: PathBuf::from(path),
+            position: position("<property name=\"speed\""),
+        },
+        // Layers with the same name in different parents are fine
+        ParseWarning::DuplicateLayerName {
+            name: "ground".to_owned(),
+            path: PathBuf::from(path),
+            position: position("<objectgroup id=\"4\""),
+        },
+    ];
+    let load = |duplicates| {
+        Loader::new().load_tmx_map_with_settings(
+            path,
+            LoadSettings {
+                duplicates,
+                ..Default::default()
+            },
+        )
+    };
+
+    // The last property wins by default
+    let map = Loader::new().load_tmx_map(path).unwrap();
+    assert_eq!(map.warnings(), warnings);
+    assert_eq!(map.properties.get_int("speed"), Some(2));
+    assert_eq!(map.properties.len(), 2);
+    assert_eq!(
+        map.layers().filter(|layer| layer.name == "ground").count(),
+        2
+    );
+    assert_eq!(
+        warnings[0].to_string(),
+        format!(
+            "Property 'speed' is defined more than once (in '{}' at byte offset {})",
+            path,
+            position("<property name=\"speed\"")
+        )
+    );
+
+    let map = load(DuplicateHandling::FirstWins).unwrap();
+    assert_eq!(map.warnings(), warnings);
+    assert_eq!(map.properties.get_int("speed"), Some(1));
+
+    let err = load(DuplicateHandling::Error).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::DuplicateProperty);
+    assert!(matches!(
+        err,
+        Error::ParseError { position: at, .. } if at == position("<property name=\"speed\"")
+    ));
+}
+
+#[test]
+fn test_inherited_properties() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_inherited_properties.tmx")
+        .unwrap();
+
+    let layer = as_finite(map.get_layer(0).unwrap().as_tile_layer().unwrap());
+    let properties = layer.get_tile(0, 0).unwrap().properties();
+    assert_eq!(properties.get_int("health"), Some(10));
+    assert_eq!(properties.get_bool("solid"), Some(true));
+    assert!(layer.get_tile(1, 0).unwrap().properties().is_empty());
+
+    let object = map.get_object_by_id(1).unwrap();
+    let properties = object.effective_properties();
+    // The object's own values win, and the others are inherited from its tile
+    assert_eq!(properties.get_int("health"), Some(5));
+    assert_eq!(properties.get_bool("solid"), Some(true));
+    let (property_type, stats) = properties.get_class("stats").unwrap();
+    assert_eq!(property_type, "Stats");
+    assert_eq!(stats.get_int("armor"), Some(7));
+    assert_eq!(stats.get_int("speed"), Some(1));
+    // The object's own properties are left untouched
+    assert_eq!(object.properties.len(), 2);
+    assert_eq!(object.properties.get_bool("solid"), None);
+
+    let object = map.get_object_by_id(2).unwrap();
+    assert!(object.effective_properties().is_empty());
+    let object = map.get_object_by_id(3).unwrap();
+    assert_eq!(*object.effective_properties(), object.properties);
+}
+
+#[test]
+fn test_owned_wrappers() {
+    /// A component that must be `'static`, like the ones of an ECS.
+    #[derive(Clone)]
+    struct Component {
+        object: OwnedObject,
+        tile: OwnedLayerTile,
+    }
+    fn assert_static<T: Clone + Send + Sync + 'static>(value: T) -> T {
+        value
+    }
+
+    let map = Arc::new(
+        Loader::new()
+            .load_tmx_map("assets/tiled_inherited_properties.tmx")
+            .unwrap(),
+    );
+    let object = map.get_object_by_id(1).unwrap().to_owned_object(&map);
+    let tile = map
+        .get_layer(0)
+        .unwrap()
+        .to_owned_layer(&map)
+        .get_tile(0, 0)
+        .unwrap();
+    let component = assert_static(Component { object, tile });
+    drop(map);
+
+    let Component { object, tile } = component.clone();
+    assert!(Arc::ptr_eq(object.map(), component.object.map()));
+    assert_eq!(object.id(), 1);
+    assert_eq!(object.index(), 0);
+    assert_eq!(object.layer().name, "Object Layer 1");
+    assert_eq!(object.layer().index_path(), [1]);
+    assert!(matches!(
+        object.shape,
+        ObjectShape::Rect { width, height } if width == 32.0 && height == 32.0
+    ));
+    assert_eq!(object.properties.get_int("health"), Some(5));
+    assert_eq!(
+        object.get().effective_properties().get_bool("solid"),
+        Some(true)
+    );
+
+    assert_eq!(tile.position(), (0, 0));
+    assert_eq!(tile.id(), 0);
+    let properties = &tile.get().get_tile().unwrap().properties;
+    assert_eq!(properties.get_int("health"), Some(10));
+    assert!(tile.layer().get_tile(7, 7).is_none());
+
+    // Layers nested inside groups are found again through their path
+    let map = Arc::new(
+        Loader::new()
+            .load_tmx_map("assets/tiled_group_layers.tmx")
+            .unwrap(),
+    );
+    let nested = map
+        .layers_recursive()
+        .find(|(layer, _)| layer.name == "tile-3")
+        .unwrap()
+        .0
+        .to_owned_layer(&map);
+    assert_eq!(nested.index_path(), [2, 0, 0]);
+    assert_eq!(nested.id(), 9);
+    assert_eq!(nested.get().parent().unwrap().name, "group-3");
+
+    // Wrappers can only be converted with the map they are from
+    let other = Arc::new((*map).clone());
+    let wrong_map = std::panic::catch_unwind(|| map.get_layer(0).unwrap().to_owned_layer(&other));
+    assert!(wrong_map.is_err());
+}
+
+#[cfg(feature = "project")]
+#[test]
+fn test_project() {
+    let mut loader = Loader::new();
+    let project = loader
+        .load_project("assets/project/project.tiled-project")
+        .unwrap();
+    assert_eq!(project.classes.len(), 2);
+    let stats = project.get_class("Stats").unwrap();
+    assert_eq!(stats.use_as, ["property", "object"]);
+    assert_eq!(
+        stats.members,
+        vec![
+            ("armor".to_owned(), PropertyValue::IntValue(2)),
+            (
+                "kind".to_owned(),
+                PropertyValue::EnumValue {
+                    name: "Kind".to_owned(),
+                    value: "Goblin".to_owned(),
+                }
+            ),
+            ("speed".to_owned(), PropertyValue::FloatValue(1.5)),
+        ]
+    );
+    let abilities = project.get_enum("Abilities").unwrap();
+    assert_eq!(abilities.storage, EnumStorage::Int);
+    assert_eq!(abilities.value_name("3").as_deref(), Some("Fly,Swim"));
+    assert_eq!(abilities.value_name("8"), None);
+
+    // Without a project, only the members that are set are loaded
+    let map = loader
+        .load_tmx_map("assets/project/project_map.tmx")
+        .unwrap();
+    let (_, members) = map.properties.get_class("stats").unwrap();
+    assert_eq!(members.len(), 1);
+    assert_eq!(
+        map.properties.get("kind"),
+        Some(&PropertyValue::StringValue("Orc".to_owned()))
+    );
+
+    loader.settings_mut().project = Some(Arc::new(project));
+    let map = loader
+        .load_tmx_map("assets/project/project_map.tmx")
+        .unwrap();
+    let (class, members) = map.properties.get_class("stats").unwrap();
+    assert_eq!(class, "Stats");
+    assert_eq!(members.get_int("armor"), Some(7));
+    assert_eq!(members.get_float("speed"), Some(1.5));
+    assert_eq!(
+        map.properties.get("kind"),
+        Some(&PropertyValue::EnumValue {
+            name: "Kind".to_owned(),
+            value: "Orc".to_owned(),
+        })
+    );
+    assert_eq!(
+        map.properties.get("abilities"),
+        Some(&PropertyValue::EnumValue {
+            name: "Abilities".to_owned(),
+            value: "Fly,Burrow".to_owned(),
+        })
+    );
+    // Nested class members fall back to the defaults of the class containing them
+    let (_, boss) = map.properties.get_class("boss").unwrap();
+    assert_eq!(boss.get_string("name"), Some("Unnamed"));
+    let (_, boss_stats) = boss.get_class("stats").unwrap();
+    assert_eq!(boss_stats.get_int("armor"), Some(4));
+    assert_eq!(boss_stats.get_float("speed"), Some(0.5));
+    assert!(matches!(
+        boss_stats.get("kind"),
+        Some(PropertyValue::EnumValue { .. })
+    ));
+}
+
+/// Writes the map with the given options and loads it back as if it was the file at `path`.
+fn write_and_reload(map: &Map, path: &str, options: WriteOptions) -> Map {
+    let mut tmx = Vec::new();
+    map.write_to(&mut tmx, options).unwrap();
+    Loader::new().load_tmx_map_from(&tmx[..], path).unwrap()
+}
+
+#[test]
+fn test_write_round_trip() {
+    let fixtures = [
+        "assets/tiled_base64.tmx",
+        "assets/tiled_base64_zlib_infinite.tmx",
+        "assets/tiled_csv_wangsets.tmx",
+        "assets/tiled_flipped_all.tmx",
+        "assets/tiled_group_layers.tmx",
+        "assets/tiled_hexagonal_stagger_x.tmx",
+        "assets/tiled_image_layers.tmx",
+        "assets/tiled_object_groups.tmx",
+        "assets/tiled_object_template.tmx",
+        "assets/tiled_parallax.tmx",
+        "assets/tiled_text_entities.tmx",
+        "assets/tiled_text_object.tmx",
+        "assets/tiled_tileset_offset.tmx",
+        "assets/folder/tiled_raw_sources.tmx",
+    ];
+    let encodings = [
+        DataEncoding::Csv,
+        DataEncoding::Base64,
+        DataEncoding::Base64Zlib,
+        DataEncoding::Base64Gzip,
+    ];
+    for path in fixtures {
+        let map = Loader::new().load_tmx_map(path).unwrap();
+        for data_encoding in encodings {
+            for tilesets in [TilesetStorage::External, TilesetStorage::Embedded] {
+                let options = WriteOptions {
+                    data_encoding,
+                    tilesets,
+                    ..Default::default()
+                };
+                let reloaded = write_and_reload(&map, path, options);
+                if tilesets == TilesetStorage::External {
+                    assert_eq!(reloaded, map, "{} with {:?}", path, options);
+                } else {
+                    // Embedded tilesets lose their source, but everything else should be kept
+                    assert_eq!(reloaded.tilesets().len(), map.tilesets().len());
+                    for (r, e) in reloaded.tilesets().iter().zip(map.tilesets()) {
+                        assert_eq!(r.source, None);
+                        assert_eq!(r.name, e.name);
+                        assert_eq!(r.tilecount, e.tilecount);
+                        // Image paths are rewritten relative to the map, so they may be spelt
+                        // differently while pointing to the same file
+                        let image = |tileset: &tiled::Tileset| {
+                            tileset
+                                .image
+                                .as_ref()
+                                .map(|image| std::fs::canonicalize(&image.source).unwrap())
+                        };
+                        assert_eq!(image(r), image(e), "{} with {:?}", path, options);
+                    }
+                    assert_eq!(
+                        reloaded.layers().count(),
+                        map.layers().count(),
+                        "{} with {:?}",
+                        path,
+                        options
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_write_flip_bits_and_escaping() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_flipped_all.tmx")
+        .unwrap();
+    let mut tmx = Vec::new();
+    map.write_to(&mut tmx, WriteOptions::default()).unwrap();
+    let tmx = String::from_utf8(tmx).unwrap();
+    // The same tile with every combination of flipping flags, in the same order as the fixture
+    assert!(tmx.contains("3,2147483651,1073741827,3221225475,\n"));
+    assert!(tmx.contains("536870915,2684354563,1610612739,3758096387\n"));
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_text_entities.tmx")
+        .unwrap();
+    let mut tmx = Vec::new();
+    map.write_to(&mut tmx, WriteOptions::default()).unwrap();
+    let tmx = String::from_utf8(tmx).unwrap();
+    assert!(tmx.contains(r#"value="Fish &amp; chips""#));
+    assert!(tmx.contains(r#"value="&lt;b&gt;bold&lt;/b&gt; &amp; raw""#));
+    let reloaded = write_and_reload(&map, "assets/tiled_text_entities.tmx", Default::default());
+    assert_eq!(reloaded, map);
+}
+
+#[test]
+fn test_write_float_precision() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_groups.tmx")
+        .unwrap();
+    let options = WriteOptions {
+        float_precision: Some(0),
+        ..Default::default()
+    };
+    let mut tmx = Vec::new();
+    map.write_to(&mut tmx, options).unwrap();
+    let reloaded = Loader::new()
+        .load_tmx_map_from(&tmx[..], "assets/tiled_object_groups.tmx")
+        .unwrap();
+
+    let objects = |map: &Map| -> Vec<(f32, f32)> {
+        map.layers()
+            .filter_map(|layer| layer.as_object_layer())
+            .flat_map(|layer| {
+                layer
+                    .objects()
+                    .map(|object| (object.x, object.y))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    };
+    let rounded: Vec<_> = objects(&map)
+        .into_iter()
+        .map(|(x, y)| (x.round(), y.round()))
+        .collect();
+    assert_eq!(objects(&reloaded), rounded);
+}
+
+#[cfg(not(feature = "zstd"))]
+#[test]
+fn test_write_zstd_without_feature() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64.tmx")
+        .unwrap();
+    let options = WriteOptions {
+        data_encoding: DataEncoding::Base64Zstd,
+        ..Default::default()
+    };
+    let err = map.write_to(Vec::new(), options).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidEncodingFormat);
+}
+
+#[test]
+fn test_write_tileset_round_trip() {
+    let fixtures = [
+        "assets/tilesheet.tsx",
+        "assets/tilesheet_collision.tsx",
+        "assets/tilesheet_embedded_image.tsx",
+        "assets/tilesheet_image_collection.tsx",
+        "assets/tilesheet_render_size.tsx",
+        "assets/tilesheet_sparse.tsx",
+        "assets/tilesheet_tile_attributes.tsx",
+        "assets/tilesheet_wangsets.tsx",
+        "assets/tilesheet_wangsets_flipped.tsx",
+        "assets/tilesheet_written.tsx",
+    ];
+    for path in fixtures {
+        let tileset = Loader::new().load_tsx_tileset(path).unwrap();
+        let mut tsx = Vec::new();
+        tileset.write_to(&mut tsx, WriteOptions::default()).unwrap();
+        let reloaded = Loader::new().load_tsx_tileset_from(&tsx[..], path).unwrap();
+        assert_eq!(reloaded, tileset, "{}", path);
+    }
+}
+
+#[test]
+fn test_write_tileset_golden() {
+    // The fixture is laid out exactly like the writer does, so writing it must reproduce it
+    let path = "assets/tilesheet_written.tsx";
+    let tileset = Loader::new().load_tsx_tileset(path).unwrap();
+    let mut tsx = Vec::new();
+    tileset.write_to(&mut tsx, WriteOptions::default()).unwrap();
+    // Fixtures are checked out with CRLF line endings
+    let expected = std::fs::read_to_string(path).unwrap().replace("\r\n", "\n");
+    assert_eq!(String::from_utf8(tsx).unwrap(), expected);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_json_maps_are_the_same_as_xml() {
+    for (tmj, tmx) in [
+        ("assets/tiled_json.tmj", "assets/tiled_json.tmx"),
+        (
+            "assets/tiled_json_infinite.tmj",
+            "assets/tiled_json_infinite.tmx",
+        ),
+    ] {
+        let json = Loader::new().load_tmj_map(tmj).unwrap();
+        let xml = Loader::new().load_tmx_map(tmx).unwrap();
+        assert_eq!(json, xml, "{}", tmj);
+    }
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_json_tilesets_and_templates() {
+    let mut loader = Loader::new();
+    let mut tileset = loader.load_tsj_tileset("assets/tilesheet.tsj").unwrap();
+    assert_eq!(tileset.source, Some(PathBuf::from("assets/tilesheet.tsj")));
+    tileset.source = Some(PathBuf::from("assets/tilesheet.tsx"));
+    assert_eq!(
+        tileset,
+        loader.load_tsx_tileset("assets/tilesheet.tsx").unwrap()
+    );
+
+    let json = loader
+        .load_tmj_map("assets/tiled_json_references.tmj")
+        .unwrap();
+    let xml = loader
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    assert_eq!(json.tilesets()[0].name, xml.tilesets()[0].name);
+    assert_eq!(json.tilesets()[0].properties, xml.tilesets()[0].properties);
+    let objects = |map: &Map| {
+        let layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+        layer
+            .objects()
+            .map(|object| {
+                let tile = object.get_tile().map(|tile| tile.id());
+                (object.shape.clone(), object.properties.clone(), tile)
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(objects(&json), objects(&xml));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_load_map_detects_format() {
+    let mut loader = Loader::new();
+    let json = loader.load_map("assets/tiled_json.tmj").unwrap();
+    let xml = loader.load_map("assets/tiled_json.tmx").unwrap();
+    assert_eq!(json, xml);
+
+    let err = loader.load_tmj_map("assets/tiled_json.tmx").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::JsonDecodingError);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_json_error_paths() {
+    let map = |layers: &str| {
+        format!(
+            r#"{{"type": "map", "version": "1.10", "orientation": "orthogonal",
+                "renderorder": "right-down", "width": 1, "height": 1, "tilewidth": 32,
+                "tileheight": 32, "infinite": false, "nextlayerid": 3, "nextobjectid": 2,
+                "tilesets": [], "layers": [
+                    {{"type": "tilelayer", "id": 1, "name": "Ground", "width": 1, "height": 1,
+                      "data": [0]}},
+                    {}
+                ]}}"#,
+            layers
+        )
+    };
+    let json_path = |layers: &str| {
+        let mut reader = MemoryResourceReader::new();
+        reader.insert_str("map.tmj", &map(layers));
+        let err = Loader::with_reader(reader)
+            .load_tmj_map("map.tmj")
+            .unwrap_err();
+        match &err {
+            Error::ParseError {
+                path,
+                position: 0,
+                json_path: Some(json_path),
+                ..
+            } => {
+                assert_eq!(path, &PathBuf::from("map.tmj"));
+                assert!(err.to_string().contains(json_path.as_str()), "{}", err);
+                (json_path.clone(), err.kind())
+            }
+            _ => panic!("expected a parse error with a JSON path, got {:?}", err),
+        }
+    };
+
+    // Errors found while parsing the converted document
+    assert_eq!(
+        json_path(
+            r#"{"type": "objectgroup", "id": 2, "name": "Objects", "objects": [
+                {"id": 1, "x": 0, "y": 0, "properties": [
+                    {"name": "speed", "type": "float", "value": 1.5},
+                    {"name": "count", "type": "int", "value": "many"}
+                ]}
+            ]}"#
+        ),
+        (
+            "$.layers[1].objects[0].properties[1]".to_owned(),
+            ErrorKind::InvalidPropertyValue
+        )
+    );
+    assert_eq!(
+        json_path(r#"{"type": "imagelayer", "id": 2, "name": "Image", "opacity": "half"}"#),
+        ("$.layers[1]".to_owned(), ErrorKind::MalformedAttribute)
+    );
+    // Errors found while converting it
+    assert_eq!(
+        json_path(r#"{"type": "voxel layer", "id": 2, "name": "Unknown"}"#),
+        ("$.layers[1]".to_owned(), ErrorKind::MalformedAttribute)
+    );
+    assert_eq!(
+        json_path(
+            r#"{"type": "objectgroup", "id": 2, "name": "Objects", "objects": [
+                {"id": 1, "x": 0, "y": 0, "polygon": [{"x": 0}]}
+            ]}"#
+        ),
+        (
+            "$.layers[1].objects[0].polygon".to_owned(),
+            ErrorKind::MissingAttribute
+        )
+    );
+    assert_eq!(
+        json_path(
+            r#"{"type": "tilelayer", "id": 2, "name": "Upper", "width": 1, "height": 1,
+                "data": [-1]}"#
+        ),
+        ("$.layers[1]".to_owned(), ErrorKind::MalformedAttribute)
+    );
+
+    // Errors found while converting name the element and attribute they're about
+    let mut reader = MemoryResourceReader::new();
+    reader.insert_str(
+        "map.tmj",
+        &map(r#"{"type": "objectgroup", "id": 2, "name": "Objects", "objects": {}}"#),
+    );
+    reader.insert_str("array.tmj", "[]");
+    let mut loader = Loader::with_reader(reader);
+    let err = loader.load_tmj_map("map.tmj").unwrap_err();
+    match err.inner() {
+        Error::MalformedAttribute {
+            element,
+            attribute,
+            value,
+            ..
+        } => assert_eq!(
+            (*element, *attribute, value.as_str()),
+            ("objectgroup", "objects", "{}")
+        ),
+        _ => panic!("expected a malformed attribute, got {:?}", err),
+    }
+    let err = loader.load_tmj_map("array.tmj").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::JsonDecodingError);
+}
+
+#[cfg(all(feature = "json", feature = "async-tokio"))]
+#[tokio::test]
+async fn test_json_loading_async() {
+    let mut loader = Loader::new();
+    let map = loader
+        .load_map_async("assets/tiled_json.tmj")
+        .await
+        .unwrap();
+    assert_eq!(map, loader.load_tmx_map("assets/tiled_json.tmx").unwrap());
+    let tileset = loader
+        .load_tsj_tileset_async("assets/tilesheet.tsj")
+        .await
+        .unwrap();
+    assert_eq!(tileset.name, "tilesheet");
+}